@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::data_ingestion::{AccountId, ClientData, ClientDataCompact};
+
+/// Per-account totals, an account can be on both sides of a transaction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct AccountStats {
+    pub total_sent: f64,
+    pub total_received: f64,
+    pub sent_count: usize,
+    pub received_count: usize,
+}
+
+impl AccountStats {
+    pub fn transaction_count(&self) -> usize {
+        self.sent_count + self.received_count
+    }
+
+    /// received minus sent
+    pub fn net(&self) -> f64 {
+        self.total_received - self.total_sent
+    }
+}
+
+/// Group-by account: totals and counts of what every account sent and received
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn aggregate_by_account(records: &[ClientData]) -> HashMap<String, AccountStats> {
+    let mut accounts: HashMap<String, AccountStats> = HashMap::new();
+
+    for record in records {
+        // get_mut first so we only allocate a key for accounts we haven't seen yet
+        match accounts.get_mut(record.from_id.as_str()) {
+            Some(stats) => {
+                stats.total_sent += record.amount;
+                stats.sent_count += 1;
+            }
+            None => {
+                accounts.insert(
+                    record.from_id.clone(),
+                    AccountStats {
+                        total_sent: record.amount,
+                        sent_count: 1,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+
+        match accounts.get_mut(record.to_id.as_str()) {
+            Some(stats) => {
+                stats.total_received += record.amount;
+                stats.received_count += 1;
+            }
+            None => {
+                accounts.insert(
+                    record.to_id.clone(),
+                    AccountStats {
+                        total_received: record.amount,
+                        received_count: 1,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+
+    accounts
+}
+
+/// [`aggregate_by_account`] over the compact schema, the keys are plain `u32`s
+/// so there is nothing to allocate and hashing is cheap
+pub fn aggregate_by_account_compact(
+    records: &[ClientDataCompact],
+) -> HashMap<AccountId, AccountStats> {
+    let mut accounts: HashMap<AccountId, AccountStats> = HashMap::new();
+
+    for record in records {
+        let sender = accounts.entry(record.from_id).or_default();
+        sender.total_sent += record.amount;
+        sender.sent_count += 1;
+
+        let receiver = accounts.entry(record.to_id).or_default();
+        receiver.total_received += record.amount;
+        receiver.received_count += 1;
+    }
+
+    accounts
+}
+
+/// The `n` accounts that sent the most money, biggest first
+pub fn top_n_senders(
+    accounts: &HashMap<String, AccountStats>,
+    n: usize,
+) -> Vec<(&str, &AccountStats)> {
+    top_n_by(accounts, n, |stats| stats.total_sent)
+}
+
+/// The `n` accounts that received the most money, biggest first
+pub fn top_n_receivers(
+    accounts: &HashMap<String, AccountStats>,
+    n: usize,
+) -> Vec<(&str, &AccountStats)> {
+    top_n_by(accounts, n, |stats| stats.total_received)
+}
+
+/// The `n` accounts in the most transactions either way, the exact version of
+/// [`super::top_k_accounts`]
+pub fn top_n_active(
+    accounts: &HashMap<String, AccountStats>,
+    n: usize,
+) -> Vec<(&str, &AccountStats)> {
+    top_n_by(accounts, n, |stats| stats.transaction_count() as f64)
+}
+
+fn top_n_by(
+    accounts: &HashMap<String, AccountStats>,
+    n: usize,
+    key: impl Fn(&AccountStats) -> f64,
+) -> Vec<(&str, &AccountStats)> {
+    let mut ranked: Vec<_> = accounts
+        .iter()
+        .map(|(id, stats)| (id.as_str(), stats))
+        .collect();
+
+    // partial select before sorting, no need to order millions of accounts for a top 10
+    let by_key_desc = |a: &(&str, &AccountStats), b: &(&str, &AccountStats)| {
+        key(b.1).total_cmp(&key(a.1)).then_with(|| a.0.cmp(b.0))
+    };
+    if n < ranked.len() {
+        ranked.select_nth_unstable_by(n, by_key_desc);
+        ranked.truncate(n);
+    }
+    ranked.sort_by(by_key_desc);
+
+    ranked
+}
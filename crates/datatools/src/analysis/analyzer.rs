@@ -0,0 +1,138 @@
+use super::AnalysisResult;
+use crate::data_ingestion::ClientData;
+
+/// One way of aggregating the records, so benches and tools can treat them all the same
+pub trait Analyzer: Send + Sync {
+    /// shows up in bench output and results files, keep it stable across runs
+    fn name(&self) -> &str;
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult;
+
+    /// setup costs (conversions, query planning) that dwarf the sum itself, benches take fewer samples
+    fn expensive(&self) -> bool {
+        false
+    }
+}
+
+pub struct Greedy;
+
+impl Analyzer for Greedy {
+    fn name(&self) -> &str {
+        "Greedy Approach (for loop with mut)"
+    }
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult {
+        super::analyze_greedy(records)
+    }
+}
+
+pub struct Functional;
+
+impl Analyzer for Functional {
+    fn name(&self) -> &str {
+        "Functional Approach (map + sum)"
+    }
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult {
+        super::analyze_functional(records)
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub struct Rayon;
+
+#[cfg(feature = "rayon")]
+impl Analyzer for Rayon {
+    fn name(&self) -> &str {
+        "Rayon Parallel Approach"
+    }
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult {
+        super::analyze_rayon(records)
+    }
+}
+
+/// [`super::analyze_threads`] with a thread per core
+pub struct Threads;
+
+impl Analyzer for Threads {
+    fn name(&self) -> &str {
+        "Scoped Threads Approach (chunk per core)"
+    }
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult {
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+        super::analyze_threads(records, threads)
+    }
+}
+
+#[cfg(feature = "polars")]
+pub struct Polars;
+
+#[cfg(feature = "polars")]
+impl Analyzer for Polars {
+    fn name(&self) -> &str {
+        "Polars DataFrame Approach"
+    }
+
+    fn analyze(&self, records: &[ClientData]) -> AnalysisResult {
+        // building a frame from valid records only fails on a polars bug
+        super::analyze_polars(records).expect("Polars analysis failed")
+    }
+
+    fn expensive(&self) -> bool {
+        true
+    }
+}
+
+/// The analyzers a bench (or anything else) runs, in registration order
+pub struct Registry {
+    analyzers: Vec<Box<dyn Analyzer>>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl Registry {
+    /// Nothing registered yet
+    pub fn empty() -> Self {
+        Self {
+            analyzers: Vec::new(),
+        }
+    }
+
+    /// Every approach in this crate that's compiled in
+    pub fn builtin() -> Self {
+        let registry = Self::empty().register(Greedy).register(Functional);
+        #[cfg(feature = "rayon")]
+        let registry = registry.register(Rayon);
+        let registry = registry.register(Threads);
+        #[cfg(feature = "polars")]
+        let registry = registry.register(Polars);
+        registry
+    }
+
+    pub fn register(mut self, analyzer: impl Analyzer + 'static) -> Self {
+        self.analyzers.push(Box::new(analyzer));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Analyzer> {
+        self.iter().find(|analyzer| analyzer.name() == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Analyzer> {
+        self.analyzers.iter().map(|analyzer| analyzer.as_ref())
+    }
+
+    pub fn len(&self) -> usize {
+        self.analyzers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.analyzers.is_empty()
+    }
+}
@@ -0,0 +1,289 @@
+//! Flags records that look suspicious.
+//!
+//! The checks are cheap heuristics, not fraud detection: amounts far from the
+//! mean, accounts suddenly sending a lot more than they used to, money moving
+//! from an account to itself, and suspiciously round amounts.
+//!
+//! [`AnomalyDetector`] takes one record at a time, so it works straight off a
+//! [`crate::data_ingestion::RecordIter`]. [`detect_anomalies`] is the in-memory
+//! version, it knows the real mean up front instead of learning it as it goes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use super::Window;
+use crate::data_ingestion::ClientData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnomalyConfig {
+    /// amounts more than this many standard deviations from the mean are outliers
+    pub stddev_threshold: f64,
+    /// streaming mode only flags outliers after this many records, earlier stats are noise
+    pub warmup: usize,
+    /// window the per-account volume is counted in, needs timestamps
+    pub spike_window: Window,
+    /// a window with more than `spike_factor` times the account's average count is a spike
+    pub spike_factor: f64,
+    /// windows of history an account needs before it can spike
+    pub min_history_windows: usize,
+    /// amounts that are a multiple of this are round...
+    pub round_unit: f64,
+    /// ...as long as they're at least this big, 50.00 is just a normal payment
+    pub round_min: f64,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            stddev_threshold: 3.0,
+            warmup: 1000,
+            spike_window: Window::Hourly,
+            spike_factor: 5.0,
+            min_history_windows: 3,
+            round_unit: 1000.0,
+            round_min: 10_000.0,
+        }
+    }
+}
+
+/// Why a record got flagged
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reason {
+    Outlier {
+        zscore: f64,
+    },
+    /// `count` transactions sent in the window starting at `window_start`, against a usual `baseline`
+    VolumeSpike {
+        account: String,
+        window_start: i64,
+        count: usize,
+        baseline: f64,
+    },
+    SelfTransfer,
+    RoundAmount,
+}
+
+impl Reason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Reason::Outlier { .. } => "outlier",
+            Reason::VolumeSpike { .. } => "volume_spike",
+            Reason::SelfTransfer => "self_transfer",
+            Reason::RoundAmount => "round_amount",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub id: String,
+    pub reason: Reason,
+}
+
+/// Flagged records, a record can show up once per reason
+#[derive(Debug, Clone, Default)]
+pub struct AnomalyReport {
+    /// records looked at
+    pub records: usize,
+    pub flagged: Vec<Anomaly>,
+}
+
+impl AnomalyReport {
+    pub fn is_empty(&self) -> bool {
+        self.flagged.is_empty()
+    }
+
+    /// Ids of the flagged records, in the order they were seen, without repeats
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.flagged.iter().map(|a| a.id.as_str()).collect();
+        ids.dedup();
+        ids
+    }
+
+    pub fn count_by_reason(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts = BTreeMap::new();
+        for anomaly in &self.flagged {
+            *counts.entry(anomaly.reason.name()).or_default() += 1;
+        }
+        counts
+    }
+}
+
+/// Send activity of one account: the window it's currently in and what came before
+#[derive(Debug, Clone, Copy)]
+struct Activity {
+    window_start: i64,
+    current: usize,
+    past_windows: usize,
+    past_count: usize,
+    flagged: bool,
+}
+
+/// Streaming detector, feed it with [`AnomalyDetector::push`]
+#[derive(Debug, Clone)]
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    /// known mean/stddev, otherwise they are learned on the fly (Welford)
+    baseline: Option<(f64, f64)>,
+    count: usize,
+    mean: f64,
+    m2: f64,
+    accounts: HashMap<String, Activity>,
+    report: AnomalyReport,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        Self {
+            config,
+            baseline: None,
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            accounts: HashMap::new(),
+            report: AnomalyReport::default(),
+        }
+    }
+
+    /// Judges outliers against a mean/stddev computed beforehand, warmup is skipped
+    pub fn with_baseline(mut self, mean: f64, stddev: f64) -> Self {
+        self.baseline = Some((mean, stddev));
+        self
+    }
+
+    pub fn push(&mut self, record: &ClientData) {
+        self.report.records += 1;
+
+        if let Some(zscore) = self.zscore(record.amount) {
+            self.flag(record, Reason::Outlier { zscore });
+        }
+        if let Some(reason) = self.spike(record) {
+            self.flag(record, reason);
+        }
+        if record.from_id == record.to_id {
+            self.flag(record, Reason::SelfTransfer);
+        }
+        if record.amount >= self.config.round_min && record.amount % self.config.round_unit == 0.0 {
+            self.flag(record, Reason::RoundAmount);
+        }
+
+        // the record is judged against the stats before it, then it becomes part of them
+        self.count += 1;
+        let delta = record.amount - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (record.amount - self.mean);
+    }
+
+    pub fn report(&self) -> &AnomalyReport {
+        &self.report
+    }
+
+    pub fn finish(self) -> AnomalyReport {
+        self.report
+    }
+
+    fn flag(&mut self, record: &ClientData, reason: Reason) {
+        self.report.flagged.push(Anomaly {
+            id: record.id.clone(),
+            reason,
+        });
+    }
+
+    /// z-score of an outlier, `None` for normal amounts
+    fn zscore(&self, amount: f64) -> Option<f64> {
+        let (mean, stddev) = match self.baseline {
+            Some(baseline) => baseline,
+            None if self.count >= self.config.warmup.max(2) => {
+                (self.mean, (self.m2 / self.count as f64).sqrt())
+            }
+            None => return None,
+        };
+        if stddev == 0.0 {
+            return None;
+        }
+
+        let zscore = (amount - mean) / stddev;
+        (zscore.abs() > self.config.stddev_threshold).then_some(zscore)
+    }
+
+    fn spike(&mut self, record: &ClientData) -> Option<Reason> {
+        let ts = record.timestamp?;
+        let window = self.config.spike_window;
+        let start = window.start_of(ts);
+
+        let activity = match self.accounts.get_mut(&record.from_id) {
+            Some(activity) => activity,
+            None => self
+                .accounts
+                .entry(record.from_id.clone())
+                .or_insert(Activity {
+                    window_start: start,
+                    current: 0,
+                    past_windows: 0,
+                    past_count: 0,
+                    flagged: false,
+                }),
+        };
+
+        // out of order records just count towards the current window
+        if start > activity.window_start {
+            // windows without any activity count as quiet ones
+            activity.past_windows += ((start - activity.window_start) / window.seconds()) as usize;
+            activity.past_count += activity.current;
+            activity.window_start = start;
+            activity.current = 0;
+            activity.flagged = false;
+        }
+        activity.current += 1;
+
+        if activity.flagged || activity.past_windows < self.config.min_history_windows {
+            return None;
+        }
+        // an account going from nothing to a single transaction isn't a spike
+        let baseline = (activity.past_count as f64 / activity.past_windows as f64).max(1.0);
+        if activity.current as f64 <= self.config.spike_factor * baseline {
+            return None;
+        }
+
+        // one flag per window, the record that tipped it over
+        activity.flagged = true;
+        Some(Reason::VolumeSpike {
+            account: record.from_id.clone(),
+            window_start: start,
+            count: activity.current,
+            baseline,
+        })
+    }
+}
+
+/// Streams records through a fresh detector
+pub fn detect_anomalies_iter(
+    records: impl IntoIterator<Item = ClientData>,
+    config: AnomalyConfig,
+) -> AnomalyReport {
+    let mut detector = AnomalyDetector::new(config);
+    for record in records {
+        detector.push(&record);
+    }
+    detector.finish()
+}
+
+/// In-memory version, outliers are judged against the mean of the whole dataset
+pub fn detect_anomalies(records: &[ClientData], config: AnomalyConfig) -> AnomalyReport {
+    let mut detector = AnomalyDetector::new(config);
+
+    if !records.is_empty() {
+        let count = records.len() as f64;
+        let mean = records.iter().map(|r| r.amount).sum::<f64>() / count;
+        let variance = records
+            .iter()
+            .map(|r| (r.amount - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        detector = detector.with_baseline(mean, variance.sqrt());
+    }
+
+    for record in records {
+        detector.push(record);
+    }
+    detector.finish()
+}
@@ -0,0 +1,30 @@
+use arrow_arith::aggregate::{max, min, sum};
+use arrow_array::{Array, Float64Array, RecordBatch};
+use arrow_schema::ArrowError;
+
+use super::{AnalysisResult, Totals};
+
+/// Sum/count/min/max over the `amount` column of every batch, with arrow's own (vectorized) kernel
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(batches = batches.len()))
+)]
+pub fn analyze_arrow(batches: &[RecordBatch]) -> Result<AnalysisResult, ArrowError> {
+    let mut totals = Totals::default();
+
+    for batch in batches {
+        let amounts = batch
+            .column_by_name("amount")
+            .and_then(|c| c.as_any().downcast_ref::<Float64Array>())
+            .ok_or_else(|| ArrowError::SchemaError("expected Float64 column `amount`".into()))?;
+
+        totals = totals.merge(Totals {
+            sum: sum(amounts).unwrap_or(0.0),
+            count: amounts.len() - amounts.null_count(),
+            min: min(amounts).unwrap_or(f64::INFINITY),
+            max: max(amounts).unwrap_or(f64::NEG_INFINITY),
+        });
+    }
+
+    Ok(totals.finish())
+}
@@ -0,0 +1,67 @@
+//! The parallel analyzers with a [`CancellationToken`]: each thread goes
+//! through its share a piece at a time and stops at the next piece once the
+//! token is cancelled. What they return covers the pieces done by then, so
+//! the result's `count` short of the records means it was cut short
+use super::{AnalysisResult, Totals};
+use crate::cancel::CancellationToken;
+use crate::data_ingestion::ClientData;
+
+/// Records summed between two looks at the token
+const PIECE_LEN: usize = 64 * 1024;
+
+fn sum_pieces(records: &[ClientData], token: &CancellationToken) -> Totals {
+    let mut totals = Totals::default();
+    for piece in records.chunks(PIECE_LEN) {
+        if token.is_cancelled() {
+            break;
+        }
+        totals = piece.iter().map(|r| r.amount).fold(totals, Totals::push);
+    }
+    totals
+}
+
+/// [`super::analyze_threads`] that stops once `token` is cancelled
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_threads_cancellable(
+    records: &[ClientData],
+    n_threads: usize,
+    token: &CancellationToken,
+) -> AnalysisResult {
+    let chunk_len = records.len().div_ceil(n_threads.max(1)).max(1);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = records
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(|| sum_pieces(chunk, token)))
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("analysis thread panicked"))
+            .fold(Totals::default(), Totals::merge)
+    })
+    .finish()
+}
+
+/// [`super::analyze_rayon`] that stops once `token` is cancelled, the pieces
+/// not started by then are skipped
+#[cfg(feature = "rayon")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_rayon_cancellable(
+    records: &[ClientData],
+    token: &CancellationToken,
+) -> AnalysisResult {
+    use rayon::prelude::*;
+
+    records
+        .par_chunks(PIECE_LEN)
+        .map(|piece| sum_pieces(piece, token))
+        .reduce(Totals::default, Totals::merge)
+        .finish()
+}
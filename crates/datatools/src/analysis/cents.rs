@@ -0,0 +1,120 @@
+//! Totals in integer cents: the amounts are read with
+//! [`crate::data_ingestion::parse_cents`] and added up with checked
+//! arithmetic, so the sum is exact and a sum too large for `i64` is an error
+//! instead of wrapping around into a plausible looking number
+use std::fmt;
+
+use super::AnalysisResult;
+use crate::data_ingestion::{DirtyDataReport, IngestError, RecordIter, parse_line_cents};
+use crate::format::Format;
+
+/// The sum left `i64` cents (about ±92 quadrillion dollars)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("the sum of the amounts overflowed i64 cents at record {records}")]
+pub struct CentsOverflow {
+    /// records that went in fine, the one that overflowed is the next
+    pub records: usize,
+}
+
+/// What [`analyze_file_cents`] can fail with
+#[derive(Debug, thiserror::Error)]
+pub enum CentsError {
+    #[error(transparent)]
+    Ingest(#[from] IngestError),
+    #[error(transparent)]
+    Overflow(#[from] CentsOverflow),
+}
+
+/// Sum, count, min and max in cents
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CentsTotals {
+    pub sum: i64,
+    pub count: usize,
+    /// `None` until the first amount
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    /// lines that didn't parse, skipped
+    pub dirty: DirtyDataReport,
+}
+
+impl CentsTotals {
+    pub fn push(&mut self, cents: i64) -> Result<(), CentsOverflow> {
+        self.sum = self.sum.checked_add(cents).ok_or(CentsOverflow {
+            records: self.count,
+        })?;
+        self.count += 1;
+        self.min = Some(self.min.map_or(cents, |min| min.min(cents)));
+        self.max = Some(self.max.map_or(cents, |max| max.max(cents)));
+        Ok(())
+    }
+
+    /// Folds in the totals of another part of the same input
+    pub fn merge(&mut self, other: CentsTotals) -> Result<(), CentsOverflow> {
+        self.sum = self.sum.checked_add(other.sum).ok_or(CentsOverflow {
+            records: self.count,
+        })?;
+        self.count += other.count;
+        self.min = self.min.into_iter().chain(other.min).min();
+        self.max = self.max.into_iter().chain(other.max).max();
+        self.dirty.merge(other.dirty);
+        Ok(())
+    }
+
+    /// The totals in the shape the float approaches return, exact as long as
+    /// the sum is under 2^53 cents
+    pub fn result(&self) -> AnalysisResult {
+        let dollars = |cents: i64| cents as f64 / 100.0;
+        AnalysisResult {
+            sum: dollars(self.sum),
+            count: self.count,
+            min: self.min.map(dollars),
+            max: self.max.map(dollars),
+            mean: (self.count > 0).then(|| dollars(self.sum) / self.count as f64),
+            distinct_accounts: None,
+        }
+    }
+}
+
+/// `1234.50`, `-0.05`, exact where `{:.2}` of the float could round
+struct Cents(i64);
+
+impl fmt::Display for Cents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{sign}{}.{:02}", cents / 100, cents % 100)
+    }
+}
+
+impl fmt::Display for CentsTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sum={}, count={}", Cents(self.sum), self.count)?;
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            write!(f, ", min={}, max={}", Cents(min), Cents(max))?;
+        }
+        Ok(())
+    }
+}
+
+/// One pass over a delimited file (compressed or not) that only parses the
+/// amounts, straight into cents. Broken lines are skipped into
+/// [`CentsTotals::dirty`], a sum past `i64` stops the pass with [`CentsError::Overflow`]
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn analyze_file_cents(path: &str) -> Result<CentsTotals, CentsError> {
+    if Format::from_path(path) != Format::Delimited {
+        return Err(IngestError::Schema("integer cents read delimited files only".into()).into());
+    }
+
+    let start = crate::Instant::now();
+    let reader = crate::compression::open_reader(path).map_err(IngestError::from)?;
+    let mut totals = CentsTotals::default();
+    for amount in RecordIter::with_parser(reader, parse_line_cents) {
+        match amount {
+            Ok(cents) => totals.push(cents)?,
+            Err(e) => totals.dirty.absorb(e)?,
+        }
+    }
+
+    crate::metrics::record_batch("cents", totals.count, &totals.dirty, start.elapsed());
+    Ok(totals)
+}
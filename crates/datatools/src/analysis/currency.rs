@@ -0,0 +1,74 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::data_ingestion::{ClientData, Currency};
+
+/// Sum and count per currency like [`super::totals_by_kind`], records without
+/// one are under `None`
+pub fn totals_by_currency(records: &[ClientData]) -> BTreeMap<Option<Currency>, (f64, usize)> {
+    let mut totals: BTreeMap<Option<Currency>, (f64, usize)> = BTreeMap::new();
+
+    for record in records {
+        let (sum, count) = totals.entry(record.currency).or_default();
+        *sum += record.amount;
+        *count += 1;
+    }
+
+    totals
+}
+
+/// A currency [`RateTable::convert`] has no rate for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("no exchange rate for {0}")]
+pub struct MissingRate(pub Currency);
+
+/// What one unit of each currency is worth in the base currency, e.g. with a
+/// USD base `EUR -> 1.08`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateTable {
+    pub base: Currency,
+    pub rates: HashMap<Currency, f64>,
+}
+
+impl RateTable {
+    pub fn new(base: Currency) -> Self {
+        Self {
+            base,
+            rates: HashMap::new(),
+        }
+    }
+
+    pub fn rate(mut self, currency: Currency, rate: f64) -> Self {
+        self.rates.insert(currency, rate);
+        self
+    }
+
+    /// `amount` in the base currency, a record without a currency already is
+    pub fn convert(&self, amount: f64, currency: Option<Currency>) -> Result<f64, MissingRate> {
+        match currency {
+            None => Ok(amount),
+            Some(currency) if currency == self.base => Ok(amount),
+            Some(currency) => self
+                .rates
+                .get(&currency)
+                .map(|rate| amount * rate)
+                .ok_or(MissingRate(currency)),
+        }
+    }
+
+    /// Rewrites every record into the base currency, so the single-currency
+    /// analyses add up. Nothing is touched when a rate is missing
+    pub fn convert_all(&self, records: &mut [ClientData]) -> Result<(), MissingRate> {
+        if let Some(missing) = records
+            .iter()
+            .find_map(|record| self.convert(0.0, record.currency).err())
+        {
+            return Err(missing);
+        }
+
+        for record in records {
+            record.amount = self.convert(record.amount, record.currency)?;
+            record.currency = Some(self.base);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+//! A parallel sum that comes out the same to the last bit on every run and
+//! machine. Rayon's splits follow the work stealing, so [`super::analyze_rayon`]
+//! adds the chunks up in a different order from run to run and float addition
+//! isn't associative. Here the chunk boundaries only depend on the number of
+//! records and the partial sums are added up along a fixed binary tree,
+//! whichever thread happens to run which half
+use super::{AnalysisResult, Totals};
+use crate::data_ingestion::ClientData;
+
+/// Records summed left to right before the tree takes over, sized so a chunk
+/// is worth sending to another thread
+pub const DETERMINISTIC_CHUNK: usize = 16 * 1024;
+
+/// [`super::analyze_rayon`] that's reproducible bit for bit, see the module
+/// docs. The sum isn't any closer to the exact one, only always the same
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_rayon_deterministic(records: &[ClientData]) -> AnalysisResult {
+    tree(records).finish()
+}
+
+/// The halves split on a chunk boundary, so the leaves are the same
+/// [`DETERMINISTIC_CHUNK`]s whatever got split where
+fn tree(records: &[ClientData]) -> Totals {
+    let chunks = records.len().div_ceil(DETERMINISTIC_CHUNK);
+    if chunks <= 1 {
+        return records
+            .iter()
+            .map(|r| r.amount)
+            .fold(Totals::default(), Totals::push);
+    }
+    let (left, right) = records.split_at(chunks / 2 * DETERMINISTIC_CHUNK);
+    let (left, right) = rayon::join(|| tree(left), || tree(right));
+    left.merge(right)
+}
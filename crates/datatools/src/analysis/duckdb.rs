@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use ::duckdb::{Connection, Result};
+
+use super::AnalysisResult;
+
+/// `read_csv` over the file, broken lines (wrong field count) are skipped like in the loaders
+fn scan(path: &str) -> String {
+    // table functions don't take bind parameters, so the path gets quoted by hand
+    let path = path.replace('\'', "''");
+    format!("read_csv('{path}', delim = '|', header = true, ignore_errors = true)")
+}
+
+/// Sum, count, min and max, with DuckDB reading the file itself
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn analyze_duckdb(path: &str) -> Result<AnalysisResult> {
+    let conn = Connection::open_in_memory()?;
+    let sql = format!(
+        "SELECT SUM(amount), COUNT(amount), MIN(amount), MAX(amount) FROM {}",
+        scan(path)
+    );
+
+    conn.query_row(&sql, [], |row| {
+        let sum: Option<f64> = row.get(0)?;
+        let count: i64 = row.get(1)?;
+        let min: Option<f64> = row.get(2)?;
+        let max: Option<f64> = row.get(3)?;
+        Ok(AnalysisResult::new(
+            sum.unwrap_or(0.0),
+            count as usize,
+            min.unwrap_or(f64::INFINITY),
+            max.unwrap_or(f64::NEG_INFINITY),
+        ))
+    })
+}
+
+/// Total and count of what every account sent, the group-by counterpart of
+/// [`super::aggregate_by_account`]
+pub fn totals_by_sender_duckdb(path: &str) -> Result<HashMap<String, (f64, usize)>> {
+    let conn = Connection::open_in_memory()?;
+    let sql = format!(
+        "SELECT from_id, SUM(amount), COUNT(*) FROM {} GROUP BY from_id",
+        scan(path)
+    );
+
+    let mut statement = conn.prepare(&sql)?;
+    let rows = statement.query_map([], |row| {
+        let count: i64 = row.get(2)?;
+        Ok((row.get(0)?, (row.get(1)?, count as usize)))
+    })?;
+
+    rows.collect()
+}
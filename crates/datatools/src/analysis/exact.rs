@@ -0,0 +1,56 @@
+use rust_decimal::Decimal;
+
+use crate::data_ingestion::{ClientDataCents, ClientDataDecimal};
+
+/// Integer cents: exact, and integer adds are as cheap as float ones
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_cents(records: &[ClientDataCents]) -> (i64, usize) {
+    let total_cents: i64 = records.iter().map(|r| r.amount).sum();
+
+    (total_cents, records.len())
+}
+
+/// Decimal: exact, but every add goes through 96-bit software arithmetic
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_decimal(records: &[ClientDataDecimal]) -> (Decimal, usize) {
+    let total_amount: Decimal = records.iter().map(|r| r.amount).sum();
+
+    (total_amount, records.len())
+}
+
+/// Integer cents + rayon, integer addition is associative so the result is deterministic
+#[cfg(feature = "rayon")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_cents_rayon(records: &[ClientDataCents]) -> (i64, usize) {
+    use rayon::prelude::*;
+
+    let total_cents: i64 = records.par_iter().map(|r| r.amount).sum();
+
+    (total_cents, records.len())
+}
+
+/// Decimal + rayon
+#[cfg(feature = "rayon")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_decimal_rayon(records: &[ClientDataDecimal]) -> (Decimal, usize) {
+    use rayon::prelude::*;
+
+    let total_amount = records
+        .par_iter()
+        .map(|r| r.amount)
+        .reduce(|| Decimal::ZERO, |a, b| a + b);
+
+    (total_amount, records.len())
+}
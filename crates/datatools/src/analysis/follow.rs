@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use super::StreamStats;
+use crate::data_ingestion::{Follower, IngestError};
+
+/// How often [`LiveStats::watch`] looks at the file without the `notify`
+/// feature, and at the latest with it: events get coalesced, or never come
+/// on network filesystems
+pub const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+/// [`StreamStats`] kept current over a file that is still being written
+#[derive(Debug, Clone)]
+pub struct LiveStats {
+    follower: Follower,
+    totals: StreamStats,
+}
+
+impl LiveStats {
+    pub fn new(follower: Follower) -> Self {
+        Self {
+            follower,
+            totals: StreamStats::default(),
+        }
+    }
+
+    /// Everything read so far
+    pub fn totals(&self) -> &StreamStats {
+        &self.totals
+    }
+
+    /// Folds in whatever was appended since the last call and returns the
+    /// stats of just those records, `count` is 0 when nothing was. Broken lines
+    /// only show up in the totals
+    pub fn update(&mut self) -> Result<StreamStats, IngestError> {
+        let mut update = StreamStats::default();
+        for record in self.follower.poll()? {
+            update.add(&record);
+        }
+
+        self.totals.merge(update.clone());
+        self.totals.dirty = self.follower.dirty().clone();
+        Ok(update)
+    }
+
+    /// Calls `on_update` with each update and the totals after it until the
+    /// file can't be read anymore, the first call has whatever was already there
+    pub fn watch(
+        &mut self,
+        mut on_update: impl FnMut(&StreamStats, &StreamStats),
+    ) -> Result<(), IngestError> {
+        let mut wait = Waiter::new(&self.follower)?;
+        loop {
+            let update = self.update()?;
+            if update.count > 0 {
+                on_update(&update, &self.totals);
+            }
+            wait.next()?;
+        }
+    }
+}
+
+/// inotify & co. telling us the file changed
+#[cfg(feature = "notify")]
+struct Waiter {
+    events: std::sync::mpsc::Receiver<notify::Result<notify::Event>>,
+    // dropping it stops the events
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "notify")]
+impl Waiter {
+    fn new(follower: &Follower) -> std::io::Result<Self> {
+        use notify::Watcher;
+
+        let (sender, events) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(std::io::Error::other)?;
+        watcher
+            .watch(
+                std::path::Path::new(follower.path()),
+                notify::RecursiveMode::NonRecursive,
+            )
+            .map_err(std::io::Error::other)?;
+        Ok(Self {
+            events,
+            _watcher: watcher,
+        })
+    }
+
+    fn next(&mut self) -> std::io::Result<()> {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        match self.events.recv_timeout(FOLLOW_INTERVAL) {
+            Ok(Err(e)) => return Err(std::io::Error::other(e)),
+            Ok(Ok(_)) | Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(std::io::Error::other("file watcher stopped"));
+            }
+        }
+        // a burst of writes is one update, not one per event
+        while self.events.try_recv().is_ok() {}
+        Ok(())
+    }
+}
+
+/// Plain polling
+#[cfg(not(feature = "notify"))]
+struct Waiter;
+
+#[cfg(not(feature = "notify"))]
+impl Waiter {
+    fn new(_follower: &Follower) -> std::io::Result<Self> {
+        Ok(Self)
+    }
+
+    fn next(&mut self) -> std::io::Result<()> {
+        std::thread::sleep(FOLLOW_INTERVAL);
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! Money flow between accounts as a directed graph.
+//!
+//! Nodes are accounts, an edge `A -> B` holds everything `A` sent to `B`.
+//! Accounts are interned to indices once, all the traversals work on those.
+
+use std::collections::HashMap;
+
+use crate::data_ingestion::ClientData;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Edge {
+    to: usize,
+    total: f64,
+    count: usize,
+}
+
+/// One edge of the graph, with the account names resolved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flow<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub total: f64,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AccountGraph {
+    accounts: Vec<String>,
+    index: HashMap<String, usize>,
+    edges: Vec<Vec<Edge>>,
+}
+
+impl AccountGraph {
+    pub fn from_records(records: &[ClientData]) -> Self {
+        let mut graph = Self::default();
+        // position of every edge in its adjacency list, only needed while building
+        let mut positions: HashMap<(usize, usize), usize> = HashMap::new();
+
+        for record in records {
+            let from = graph.intern(&record.from_id);
+            let to = graph.intern(&record.to_id);
+            let adjacent = &mut graph.edges[from];
+            let position = *positions.entry((from, to)).or_insert_with(|| {
+                adjacent.push(Edge {
+                    to,
+                    total: 0.0,
+                    count: 0,
+                });
+                adjacent.len() - 1
+            });
+            adjacent[position].total += record.amount;
+            adjacent[position].count += 1;
+        }
+
+        graph
+    }
+
+    fn intern(&mut self, account: &str) -> usize {
+        if let Some(&index) = self.index.get(account) {
+            return index;
+        }
+        let index = self.accounts.len();
+        self.accounts.push(account.to_string());
+        self.index.insert(account.to_string(), index);
+        self.edges.push(Vec::new());
+        index
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.iter().map(Vec::len).sum()
+    }
+
+    pub fn accounts(&self) -> &[String] {
+        &self.accounts
+    }
+
+    fn flow(&self, from: usize, edge: &Edge) -> Flow<'_> {
+        Flow {
+            from: &self.accounts[from],
+            to: &self.accounts[edge.to],
+            total: edge.total,
+            count: edge.count,
+        }
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = Flow<'_>> {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, edges)| edges.iter().map(move |edge| self.flow(from, edge)))
+    }
+
+    /// Everything `account` sent, grouped by receiver
+    pub fn outgoing(&self, account: &str) -> impl Iterator<Item = Flow<'_>> {
+        let from = self.index.get(account).copied();
+        from.into_iter().flat_map(move |from| {
+            self.edges[from]
+                .iter()
+                .map(move |edge| self.flow(from, edge))
+        })
+    }
+
+    /// Received minus sent for every account
+    pub fn net_balances(&self) -> HashMap<&str, f64> {
+        let mut net = vec![0.0; self.accounts.len()];
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                net[from] -= edge.total;
+                net[edge.to] += edge.total;
+            }
+        }
+
+        self.accounts.iter().map(String::as_str).zip(net).collect()
+    }
+
+    /// Component index of every node (Tarjan).
+    ///
+    /// Iterative, with millions of accounts a recursive version blows the stack.
+    fn component_ids(&self) -> Vec<usize> {
+        const UNVISITED: usize = usize::MAX;
+
+        let n = self.accounts.len();
+        let mut order = vec![UNVISITED; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut component = vec![UNVISITED; n];
+        let mut stack = Vec::new();
+        let mut next_order = 0;
+        let mut next_component = 0;
+
+        for root in 0..n {
+            if order[root] != UNVISITED {
+                continue;
+            }
+
+            // (node, next edge to look at) - the call stack of the recursive version
+            let mut calls = vec![(root, 0)];
+            order[root] = next_order;
+            low[root] = next_order;
+            next_order += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(frame) = calls.last_mut() {
+                let node = frame.0;
+                if let Some(edge) = self.edges[node].get(frame.1) {
+                    frame.1 += 1;
+                    let next = edge.to;
+                    if order[next] == UNVISITED {
+                        order[next] = next_order;
+                        low[next] = next_order;
+                        next_order += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        calls.push((next, 0));
+                    } else if on_stack[next] {
+                        low[node] = low[node].min(order[next]);
+                    }
+                    continue;
+                }
+
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[node]);
+                }
+                if low[node] == order[node] {
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component[member] = next_component;
+                        if member == node {
+                            break;
+                        }
+                    }
+                    next_component += 1;
+                }
+            }
+        }
+
+        component
+    }
+
+    /// Groups of accounts that can all reach each other, with at least two accounts in them
+    pub fn strongly_connected_components(&self) -> Vec<Vec<&str>> {
+        let mut groups: HashMap<usize, Vec<&str>> = HashMap::new();
+        for (node, component) in self.component_ids().into_iter().enumerate() {
+            groups
+                .entry(component)
+                .or_default()
+                .push(&self.accounts[node]);
+        }
+
+        let mut components: Vec<_> = groups.into_values().filter(|g| g.len() > 1).collect();
+        components.sort_by_key(|g| std::cmp::Reverse(g.len()));
+        components
+    }
+
+    /// Money loops like `A -> B -> C -> A`, with at most `max_len` accounts, stops after `limit`.
+    ///
+    /// Every cycle is reported once, starting at the account that was seen first.
+    /// Self-transfers aren't loops here, see [`super::anomaly`] for those.
+    pub fn cycles(&self, max_len: usize, limit: usize) -> Vec<Vec<&str>> {
+        let mut search = CycleSearch {
+            graph: self,
+            component: self.component_ids(),
+            on_path: vec![false; self.accounts.len()],
+            path: Vec::new(),
+            max_len,
+            limit,
+            found: Vec::new(),
+        };
+
+        for start in 0..self.accounts.len() {
+            if search.found.len() >= limit {
+                break;
+            }
+            search.walk(start, start);
+        }
+
+        search
+            .found
+            .into_iter()
+            .map(|cycle| {
+                cycle
+                    .into_iter()
+                    .map(|n| self.accounts[n].as_str())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Depth-limited DFS state for [`AccountGraph::cycles`]
+struct CycleSearch<'a> {
+    graph: &'a AccountGraph,
+    component: Vec<usize>,
+    on_path: Vec<bool>,
+    path: Vec<usize>,
+    max_len: usize,
+    limit: usize,
+    found: Vec<Vec<usize>>,
+}
+
+impl CycleSearch<'_> {
+    fn walk(&mut self, start: usize, node: usize) {
+        self.path.push(node);
+        self.on_path[node] = true;
+
+        for edge in &self.graph.edges[node] {
+            if self.found.len() >= self.limit {
+                break;
+            }
+            let next = edge.to;
+            if next == start && self.path.len() > 1 {
+                self.found.push(self.path.clone());
+            } else if next > start
+                && !self.on_path[next]
+                // a loop never leaves its component, no point looking there
+                && self.component[next] == self.component[start]
+                && self.path.len() < self.max_len
+            {
+                self.walk(start, next);
+            }
+        }
+
+        self.on_path[node] = false;
+        self.path.pop();
+    }
+}
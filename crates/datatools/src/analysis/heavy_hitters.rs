@@ -0,0 +1,244 @@
+//! The most active accounts without a counter per account: Space-Saving
+//! (Metwally et al. 2005) keeps a fixed number of counters and hands the
+//! smallest one to every account it hasn't got a counter for yet.
+//!
+//! An account that makes up more than `1 / capacity` of the total always ends
+//! up in the sketch, and every estimate is off by at most its `error`, in the
+//! overcounting direction. On skewed data the heavy hitters are found long
+//! before the counters run out, on uniform data nothing stands out to find
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, RefReader};
+
+/// Counters per wanted account in [`top_k_accounts`], the more the closer the ranking
+pub const COUNTERS_PER_K: usize = 10;
+
+/// Fewest counters [`top_k_accounts`] keeps, a long tail eats up small sketches
+pub const MIN_COUNTERS: usize = 1024;
+
+/// One account of a [`SpaceSaving`] result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeavyHitter {
+    pub account: String,
+    /// at least the real count or volume, at most `error` more than it
+    pub estimate: f64,
+    pub error: f64,
+}
+
+impl HeavyHitter {
+    /// What the account has for sure
+    pub fn guaranteed(&self) -> f64 {
+        self.estimate - self.error
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Counter {
+    account: String,
+    value: f64,
+    error: f64,
+    /// where in the heap it is
+    slot: usize,
+}
+
+/// Space-Saving sketch over weighted items, `capacity` counters at most.
+///
+/// `heap` is a min-heap of counter numbers so the one to give away is always
+/// on top, and sifting it never has to hash an account. `index` finds an
+/// account's counter. Weights have to be positive
+#[derive(Debug, Clone)]
+pub struct SpaceSaving {
+    capacity: usize,
+    counters: Vec<Counter>,
+    heap: Vec<usize>,
+    index: HashMap<String, usize>,
+}
+
+impl SpaceSaving {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            counters: Vec::with_capacity(capacity),
+            heap: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn insert(&mut self, account: &str, weight: f64) {
+        // looked up by &str, only accounts that get a counter allocate
+        if let Some(&counter) = self.index.get(account) {
+            self.counters[counter].value += weight;
+            self.sift_down(self.counters[counter].slot);
+            return;
+        }
+
+        if self.counters.len() < self.capacity {
+            let counter = self.counters.len();
+            self.counters.push(Counter {
+                account: account.to_string(),
+                value: weight,
+                error: 0.0,
+                slot: counter,
+            });
+            self.heap.push(counter);
+            self.index.insert(account.to_string(), counter);
+            self.sift_up(counter);
+            return;
+        }
+
+        // the smallest counter changes hands, what it had counts as the new
+        // account's error. The old key's buffer is reused for the new one
+        let counter = self.heap[0];
+        let smallest = &mut self.counters[counter];
+        let (mut key, _) = self
+            .index
+            .remove_entry(&smallest.account)
+            .expect("every counter is indexed");
+        key.clear();
+        key.push_str(account);
+        smallest.account.clone_from(&key);
+        smallest.error = smallest.value;
+        smallest.value += weight;
+        self.index.insert(key, counter);
+        self.sift_down(0);
+    }
+
+    /// The `k` biggest counters, biggest first
+    pub fn top(&self, k: usize) -> Vec<HeavyHitter> {
+        let mut counters: Vec<&Counter> = self.counters.iter().collect();
+        counters.sort_unstable_by(|a, b| {
+            b.value
+                .total_cmp(&a.value)
+                .then_with(|| a.account.cmp(&b.account))
+        });
+        counters
+            .into_iter()
+            .take(k)
+            .map(|c| HeavyHitter {
+                account: c.account.clone(),
+                estimate: c.value,
+                error: c.error,
+            })
+            .collect()
+    }
+
+    fn value(&self, slot: usize) -> f64 {
+        self.counters[self.heap[slot]].value
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.counters[self.heap[a]].slot = a;
+        self.counters[self.heap[b]].slot = b;
+    }
+
+    fn sift_up(&mut self, mut slot: usize) {
+        while slot > 0 {
+            let parent = (slot - 1) / 2;
+            if self.value(parent) <= self.value(slot) {
+                break;
+            }
+            self.swap(parent, slot);
+            slot = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut slot: usize) {
+        loop {
+            let (left, right) = (2 * slot + 1, 2 * slot + 2);
+            let mut smallest = slot;
+            if left < self.heap.len() && self.value(left) < self.value(smallest) {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.value(right) < self.value(smallest) {
+                smallest = right;
+            }
+            if smallest == slot {
+                return;
+            }
+            self.swap(smallest, slot);
+            slot = smallest;
+        }
+    }
+}
+
+/// Most active accounts two ways, senders and receivers together
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopK {
+    /// by transactions
+    pub by_count: Vec<HeavyHitter>,
+    /// by money moved, negative amounts count with their size
+    pub by_amount: Vec<HeavyHitter>,
+}
+
+/// Both sketches of [`top_k_accounts`], for records that don't come from a reader
+#[derive(Debug, Clone)]
+pub struct TopAccounts {
+    k: usize,
+    by_count: SpaceSaving,
+    by_amount: SpaceSaving,
+}
+
+impl TopAccounts {
+    /// [`COUNTERS_PER_K`] times `k` counters per sketch, [`MIN_COUNTERS`] at least
+    pub fn new(k: usize) -> Self {
+        Self::with_capacity(k, (k * COUNTERS_PER_K).max(MIN_COUNTERS))
+    }
+
+    pub fn with_capacity(k: usize, capacity: usize) -> Self {
+        Self {
+            k,
+            by_count: SpaceSaving::new(capacity),
+            by_amount: SpaceSaving::new(capacity),
+        }
+    }
+
+    pub fn add(&mut self, record: &ClientData) {
+        self.push(&record.from_id, &record.to_id, record.amount);
+    }
+
+    fn push(&mut self, from_id: &str, to_id: &str, amount: f64) {
+        for account in [from_id, to_id] {
+            self.by_count.insert(account, 1.0);
+            self.by_amount.insert(account, amount.abs());
+        }
+    }
+
+    pub fn result(&self) -> TopK {
+        TopK {
+            by_count: self.by_count.top(self.k),
+            by_amount: self.by_amount.top(self.k),
+        }
+    }
+}
+
+/// The `k` most active accounts of a delimited stream (header first) in one
+/// pass, by count and by amount. Memory is [`COUNTERS_PER_K`] times `k`
+/// counters ([`MIN_COUNTERS`] at least) whatever the number of accounts. Broken lines are skipped
+/// and come back counted next to the result, any other error ends the pass
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn top_k_accounts(
+    reader: impl BufRead,
+    k: usize,
+) -> Result<(TopK, DirtyDataReport), IngestError> {
+    let mut reader = RefReader::new(reader);
+    let mut top = TopAccounts::new(k);
+    let mut dirty = DirtyDataReport::default();
+
+    while let Some(record) = reader.next_record() {
+        match record {
+            Ok(record) => top.push(record.from_id, record.to_id, record.amount),
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+
+    Ok((top.result(), dirty))
+}
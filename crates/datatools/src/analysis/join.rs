@@ -0,0 +1,435 @@
+//! Transactions joined with the accounts dimension table, summed per country.
+//!
+//! The accounts are the build side: hashed by id once, then every transaction
+//! probes with its sender's (or receiver's) id. [`totals_by_country`] does that
+//! with both tables in memory. [`totals_by_country_files`] streams the files,
+//! and when the accounts' hash table wouldn't fit in the memory budget it does
+//! a grace hash join instead: both files are split by the hash of the key into
+//! partitions on disk, and the partitions are joined one pair at a time
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::data_ingestion::{
+    Account, AccountIter, ClientData, DirtyDataReport, IngestError, RecordIter,
+};
+
+/// Roughly what the hash table takes up per byte of accounts file
+const TABLE_OVERHEAD: u64 = 3;
+
+/// Most partitions a grace join splits into, each one is an open file per side
+pub const MAX_PARTITIONS: usize = 256;
+
+/// Tells the temp dirs of joins running at the same time apart
+static JOINS: AtomicUsize = AtomicUsize::new(0);
+
+/// Whose account a transaction is joined with
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinSide {
+    #[default]
+    Sender,
+    Receiver,
+}
+
+impl JoinSide {
+    fn key<'a>(self, from_id: &'a str, to_id: &'a str) -> &'a str {
+        match self {
+            JoinSide::Sender => from_id,
+            JoinSide::Receiver => to_id,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CountryStats {
+    pub transactions: usize,
+    pub amount: f64,
+}
+
+impl CountryStats {
+    pub fn mean(&self) -> f64 {
+        if self.transactions == 0 {
+            0.0
+        } else {
+            self.amount / self.transactions as f64
+        }
+    }
+}
+
+/// What a join per country came to
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CountryTotals {
+    pub countries: BTreeMap<String, CountryStats>,
+    /// transactions whose account isn't in the accounts table
+    pub unmatched: usize,
+    /// 1 when the hash table fit in memory and nothing touched the disk
+    pub partitions: usize,
+    /// lines of the accounts file [`totals_by_country_files`] left out, they didn't parse
+    #[serde(skip)]
+    pub dirty_accounts: DirtyDataReport,
+    /// same for the transactions file
+    #[serde(skip)]
+    pub dirty_transactions: DirtyDataReport,
+}
+
+impl CountryTotals {
+    fn add(&mut self, country: Option<&str>, amount: f64) {
+        let Some(country) = country else {
+            self.unmatched += 1;
+            return;
+        };
+        // countries are few, allocating the key once per country is enough
+        if let Some(stats) = self.countries.get_mut(country) {
+            stats.transactions += 1;
+            stats.amount += amount;
+        } else {
+            self.countries.insert(
+                country.to_string(),
+                CountryStats {
+                    transactions: 1,
+                    amount,
+                },
+            );
+        }
+    }
+
+    fn merge(&mut self, other: CountryTotals) {
+        for (country, stats) in other.countries {
+            let total = self.countries.entry(country).or_default();
+            total.transactions += stats.transactions;
+            total.amount += stats.amount;
+        }
+        self.unmatched += other.unmatched;
+    }
+
+    /// Biggest amount first
+    pub fn by_amount(&self) -> Vec<(&str, &CountryStats)> {
+        let mut countries: Vec<_> = self
+            .countries
+            .iter()
+            .map(|(country, stats)| (country.as_str(), stats))
+            .collect();
+        countries.sort_by(|a, b| b.1.amount.total_cmp(&a.1.amount).then(a.0.cmp(b.0)));
+        countries
+    }
+}
+
+/// Inner join: every record whose `side` account is in `accounts`, with that account
+pub fn hash_join<'a>(
+    records: &'a [ClientData],
+    accounts: &'a [Account],
+    side: JoinSide,
+) -> impl Iterator<Item = (&'a ClientData, &'a Account)> + 'a {
+    let table: HashMap<&str, &Account> = accounts.iter().map(|a| (a.id.as_str(), a)).collect();
+    records.iter().filter_map(move |record| {
+        let key = side.key(&record.from_id, &record.to_id);
+        table.get(key).map(|account| (record, *account))
+    })
+}
+
+/// Per-country totals with both tables in memory
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len(), accounts = accounts.len()))
+)]
+pub fn totals_by_country(
+    records: &[ClientData],
+    accounts: &[Account],
+    side: JoinSide,
+) -> CountryTotals {
+    let table: HashMap<&str, &str> = accounts
+        .iter()
+        .map(|a| (a.id.as_str(), a.country.as_str()))
+        .collect();
+
+    let mut totals = CountryTotals {
+        partitions: 1,
+        ..CountryTotals::default()
+    };
+    for record in records {
+        let key = side.key(&record.from_id, &record.to_id);
+        totals.add(table.get(key).copied(), record.amount);
+    }
+    totals
+}
+
+/// Knobs for [`totals_by_country_files`]
+#[derive(Debug, Clone)]
+pub struct JoinOptions {
+    pub side: JoinSide,
+    /// roughly how many bytes the accounts' hash table can take up, past
+    /// that the join is partitioned
+    pub memory_budget: usize,
+    /// `None` works it out from the accounts file and the budget
+    pub partitions: Option<usize>,
+    /// where the partitions go, the system temp dir if unset
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for JoinOptions {
+    fn default() -> Self {
+        Self {
+            side: JoinSide::default(),
+            memory_budget: 256 * 1024 * 1024,
+            partitions: None,
+            temp_dir: None,
+        }
+    }
+}
+
+impl JoinOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn side(mut self, side: JoinSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Always this many partitions (at most [`MAX_PARTITIONS`]), 1 never spills
+    pub fn partitions(mut self, partitions: usize) -> Self {
+        self.partitions = Some(partitions);
+        self
+    }
+
+    /// Somewhere with room for the key and amount of every transaction
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    /// Compressed accounts files count by their compressed size, so they get
+    /// fewer partitions than they need
+    fn partitions_for(&self, accounts: &str) -> std::io::Result<usize> {
+        let partitions = match self.partitions {
+            Some(partitions) => partitions,
+            None => {
+                let table = std::fs::metadata(accounts)?.len() * TABLE_OVERHEAD;
+                table.div_ceil(self.memory_budget.max(1) as u64) as usize
+            }
+        };
+        Ok(partitions.clamp(1, MAX_PARTITIONS))
+    }
+}
+
+/// Per-country totals of a transactions file (delimited or JSON Lines, compressed
+/// or not) joined with an accounts table, within `options.memory_budget`.
+/// Broken lines in either are skipped and counted, each file in its own report
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(transactions = %transactions, accounts = %accounts))
+)]
+pub fn totals_by_country_files(
+    transactions: &str,
+    accounts: &str,
+    options: &JoinOptions,
+) -> Result<CountryTotals, IngestError> {
+    let partitions = options.partitions_for(accounts)?;
+    let mut dirty_accounts = DirtyDataReport::default();
+    let mut dirty_transactions = DirtyDataReport::default();
+    if partitions == 1 {
+        let table = BuildTable::from_accounts(accounts, &mut dirty_accounts)?;
+        let mut totals = CountryTotals {
+            partitions,
+            ..CountryTotals::default()
+        };
+        for_each_record(transactions, &mut dirty_transactions, |record| {
+            let key = options.side.key(&record.from_id, &record.to_id);
+            totals.add(table.country(key), record.amount);
+            Ok(())
+        })?;
+        return Ok(CountryTotals {
+            dirty_accounts,
+            dirty_transactions,
+            ..totals
+        });
+    }
+
+    let base = options.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+    let dir = TempDir::create(base.join(format!(
+        "p01-join-{}-{}",
+        std::process::id(),
+        JOINS.fetch_add(1, Ordering::Relaxed)
+    )))?;
+
+    // only what the join needs goes to disk: id and country, key and amount
+    let mut account_parts = Partitions::create(&dir.0, "accounts", partitions)?;
+    for account in AccountIter::open(accounts)? {
+        match account {
+            Ok(account) => writeln!(
+                account_parts.of(&account.id),
+                "{}|{}",
+                account.id,
+                account.country
+            )?,
+            Err(e) => dirty_accounts.absorb(e)?,
+        }
+    }
+    let account_parts = account_parts.finish()?;
+
+    let mut transaction_parts = Partitions::create(&dir.0, "transactions", partitions)?;
+    for_each_record(transactions, &mut dirty_transactions, |record| {
+        let key = options.side.key(&record.from_id, &record.to_id);
+        // `{}` writes the shortest form that parses back to the same f64
+        writeln!(transaction_parts.of(key), "{key}|{}", record.amount)?;
+        Ok(())
+    })?;
+    let transaction_parts = transaction_parts.finish()?;
+
+    let mut totals = CountryTotals {
+        partitions,
+        dirty_accounts,
+        dirty_transactions,
+        ..CountryTotals::default()
+    };
+    for (accounts, transactions) in account_parts.iter().zip(&transaction_parts) {
+        totals.merge(join_partition(accounts, transactions)?);
+    }
+    Ok(totals)
+}
+
+/// Every transaction of a file, broken lines are left out into `dirty`
+fn for_each_record(
+    path: &str,
+    dirty: &mut DirtyDataReport,
+    mut f: impl FnMut(ClientData) -> Result<(), IngestError>,
+) -> Result<(), IngestError> {
+    for record in RecordIter::open(path)? {
+        match record {
+            Ok(record) => f(record)?,
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    Ok(())
+}
+
+/// One partition pair: the accounts half into a hash table, the transactions
+/// half streamed past it
+fn join_partition(accounts: &Path, transactions: &Path) -> Result<CountryTotals, IngestError> {
+    let mut table = BuildTable::default();
+    for line in BufReader::new(File::open(accounts)?).lines() {
+        let line = line?;
+        if let Some((id, country)) = line.split_once('|') {
+            table.insert(id, country);
+        }
+    }
+
+    let mut totals = CountryTotals::default();
+    for line in BufReader::new(File::open(transactions)?).lines() {
+        let line = line?;
+        let Some((key, amount)) = line.split_once('|') else {
+            continue;
+        };
+        let amount = amount
+            .parse()
+            .map_err(|source| IngestError::InvalidAmount {
+                value: amount.to_string(),
+                source,
+            })?;
+        totals.add(table.country(key), amount);
+    }
+    Ok(totals)
+}
+
+/// Account id to country, the few distinct countries are stored once
+#[derive(Default)]
+struct BuildTable {
+    countries: Vec<String>,
+    index: HashMap<String, usize>,
+}
+
+impl BuildTable {
+    fn from_accounts(path: &str, dirty: &mut DirtyDataReport) -> Result<Self, IngestError> {
+        let mut table = Self::default();
+        for account in AccountIter::open(path)? {
+            match account {
+                Ok(account) => table.insert(&account.id, &account.country),
+                Err(e) => dirty.absorb(e)?,
+            }
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, id: &str, country: &str) {
+        let country = match self.countries.iter().position(|c| c == country) {
+            Some(country) => country,
+            None => {
+                self.countries.push(country.to_string());
+                self.countries.len() - 1
+            }
+        };
+        self.index.insert(id.to_string(), country);
+    }
+
+    fn country(&self, id: &str) -> Option<&str> {
+        self.index.get(id).map(|&c| self.countries[c].as_str())
+    }
+}
+
+/// One writer per partition, a key always lands in the same one on both sides
+struct Partitions {
+    paths: Vec<PathBuf>,
+    writers: Vec<BufWriter<File>>,
+}
+
+impl Partitions {
+    fn create(dir: &Path, name: &str, partitions: usize) -> std::io::Result<Self> {
+        let paths: Vec<PathBuf> = (0..partitions)
+            .map(|i| dir.join(format!("{name}-{i:03}")))
+            .collect();
+        let writers = paths
+            .iter()
+            .map(|path| File::create(path).map(BufWriter::new))
+            .collect::<std::io::Result<_>>()?;
+        Ok(Self { paths, writers })
+    }
+
+    /// `DefaultHasher` with its fixed keys, both sides have to agree
+    fn of(&mut self, key: &str) -> &mut BufWriter<File> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let partition = hasher.finish() % self.writers.len() as u64;
+        &mut self.writers[partition as usize]
+    }
+
+    fn finish(self) -> std::io::Result<Vec<PathBuf>> {
+        for mut writer in self.writers {
+            writer.flush()?;
+        }
+        Ok(self.paths)
+    }
+}
+
+/// The partitions only mean something to the join that wrote them
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn create(path: PathBuf) -> std::io::Result<Self> {
+        std::fs::create_dir_all(&path)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(dir = %self.0.display(), %e, "failed to remove the partitions");
+            #[cfg(not(feature = "tracing"))]
+            let _ = e;
+        }
+    }
+}
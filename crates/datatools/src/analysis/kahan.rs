@@ -0,0 +1,100 @@
+use crate::data_ingestion::ClientData;
+
+/// Running compensated sum (Neumaier's variant of Kahan, also safe when a term outgrows the sum)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KahanSum {
+    sum: f64,
+    compensation: f64,
+    abs_sum: f64,
+    count: usize,
+}
+
+impl KahanSum {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, value: f64) {
+        let t = self.sum + value;
+        // whichever operand is smaller lost its low-order bits, keep them
+        if self.sum.abs() >= value.abs() {
+            self.compensation += (self.sum - t) + value;
+        } else {
+            self.compensation += (value - t) + self.sum;
+        }
+        self.sum = t;
+        self.abs_sum += value.abs();
+        self.count += 1;
+    }
+
+    /// Combine two partial sums, used as the rayon reduce step
+    pub fn merge(mut self, other: KahanSum) -> KahanSum {
+        let count = self.count + other.count;
+        let abs_sum = self.abs_sum + other.abs_sum;
+        self.add(other.sum);
+        self.add(other.compensation);
+
+        KahanSum {
+            count,
+            abs_sum,
+            ..self
+        }
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum + self.compensation
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Worst-case error of the compensated sum: `2ε·Σ|x|` (plus a negligible `nε²` term)
+    pub fn error_bound(&self) -> f64 {
+        let eps = f64::EPSILON / 2.0;
+        (2.0 * eps + self.count as f64 * eps * eps) * self.abs_sum
+    }
+
+    /// Worst-case error of plain left-to-right summation of the same values: `(n-1)ε·Σ|x|`
+    pub fn naive_error_bound(&self) -> f64 {
+        let eps = f64::EPSILON / 2.0;
+        self.count.saturating_sub(1) as f64 * eps * self.abs_sum
+    }
+}
+
+impl Extend<f64> for KahanSum {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+/// Kahan approach: compensated summation, slower but with a tight error bound
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_kahan(records: &[ClientData]) -> KahanSum {
+    let mut sum = KahanSum::new();
+    sum.extend(records.iter().map(|r| r.amount));
+    sum
+}
+
+/// Kahan + rayon: compensated partial sums per split, merged with compensation too
+#[cfg(feature = "rayon")]
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_kahan_rayon(records: &[ClientData]) -> KahanSum {
+    use rayon::prelude::*;
+
+    records
+        .par_iter()
+        .fold(KahanSum::new, |mut sum, r| {
+            sum.add(r.amount);
+            sum
+        })
+        .reduce(KahanSum::new, KahanSum::merge)
+}
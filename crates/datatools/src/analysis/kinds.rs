@@ -0,0 +1,16 @@
+use std::collections::BTreeMap;
+
+use crate::data_ingestion::{ClientData, TransactionKind};
+
+/// Sum and count per transaction kind, kinds that never show up are left out
+pub fn totals_by_kind(records: &[ClientData]) -> BTreeMap<TransactionKind, (f64, usize)> {
+    let mut totals: BTreeMap<TransactionKind, (f64, usize)> = BTreeMap::new();
+
+    for record in records {
+        let (sum, count) = totals.entry(record.kind).or_default();
+        *sum += record.amount;
+        *count += 1;
+    }
+
+    totals
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use crate::data_ingestion::{ClientData, TransactionKind};
+
+/// Outcome of [`verify_conservation`], all amounts in cents so nothing is lost to rounding
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConservationReport {
+    /// accounts that show up in the data, each opened with the same balance
+    pub accounts: usize,
+    pub opening_cents: i64,
+    pub deposit_cents: i64,
+    /// withdrawals and fees, money that left the system
+    pub outflow_cents: i64,
+    pub closing_cents: i64,
+    /// ids of the records that took their sender below zero, in file order
+    pub overdrafts: Vec<String>,
+    /// no generated ledger moves a negative amount
+    pub negative_amounts: usize,
+}
+
+impl ConservationReport {
+    /// closing - (opening + deposits - outflows), zero for a consistent ledger
+    pub fn discrepancy_cents(&self) -> i64 {
+        self.closing_cents - (self.opening_cents + self.deposit_cents - self.outflow_cents)
+    }
+
+    pub fn is_conserved(&self) -> bool {
+        self.overdrafts.is_empty() && self.negative_amounts == 0 && self.discrepancy_cents() == 0
+    }
+}
+
+/// Replays the records in order against per-account balances.
+///
+/// Meant for files generated with a [`crate::generator::Ledger`] of the same
+/// `opening_balance`: transfers move money between accounts, deposits bring it
+/// in, withdrawals and fees take it out, and no account ever goes negative.
+/// A dropped line (or a corrupted amount) usually shows up as an overdraft further on
+pub fn verify_conservation(records: &[ClientData], opening_balance: f64) -> ConservationReport {
+    let opening = to_cents(opening_balance);
+    let mut balances: HashMap<&str, i64> = HashMap::new();
+    let mut report = ConservationReport::default();
+
+    for record in records {
+        let amount = to_cents(record.amount);
+        if amount < 0 {
+            report.negative_amounts += 1;
+        }
+
+        let sender = match record.kind {
+            TransactionKind::Deposit => {
+                report.deposit_cents += amount;
+                None
+            }
+            TransactionKind::Transfer => Some(&record.from_id),
+            TransactionKind::Withdrawal | TransactionKind::Fee => {
+                report.outflow_cents += amount;
+                Some(&record.from_id)
+            }
+        };
+
+        if let Some(sender) = sender {
+            let balance = balances.entry(sender).or_insert(opening);
+            *balance -= amount;
+            if *balance < 0 {
+                report.overdrafts.push(record.id.clone());
+            }
+        }
+        // withdrawals and fees leave the system, they don't land anywhere
+        let received = match record.kind {
+            TransactionKind::Transfer | TransactionKind::Deposit => amount,
+            TransactionKind::Withdrawal | TransactionKind::Fee => 0,
+        };
+        *balances.entry(&record.to_id).or_insert(opening) += received;
+    }
+
+    report.accounts = balances.len();
+    report.opening_cents = opening * balances.len() as i64;
+    report.closing_cents = balances.values().sum();
+    report
+}
+
+fn to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
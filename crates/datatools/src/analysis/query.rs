@@ -0,0 +1,603 @@
+//! A tiny query engine over [`ClientData`]: filter, group by, aggregate.
+//!
+//! Queries are built, not parsed, e.g.
+//! `Query::new().filter(col("amount").gt(5000)).group_by("from_id").agg(sum("amount"))`.
+//! Column names and types are only checked when the query runs, so a typo is an
+//! [`IngestError::Schema`] there and not a panic while building it. The same query
+//! runs over records in memory ([`Query::run`]) or straight off a file in one pass
+//! ([`Query::run_file`]).
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use super::Totals;
+use crate::data_ingestion::{ClientData, Column, DirtyDataReport, IngestError, RecordIter};
+
+/// A literal in a query, or a cell of its result
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    /// a missing timestamp, or the min of a group without any
+    Null,
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Number(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Number(value as f64)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Number(value.into())
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{n}"),
+            Value::Text(s) => write!(f, "{s}"),
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl Value {
+    /// Numbers first, then text, nulls last, for ordering result rows
+    fn total_cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a.total_cmp(b),
+            (Value::Text(a), Value::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Value::Number(_) => 0,
+            Value::Text(_) => 1,
+            Value::Null => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering.is_eq(),
+            CompareOp::Ne => ordering.is_ne(),
+            CompareOp::Lt => ordering.is_lt(),
+            CompareOp::Le => ordering.is_le(),
+            CompareOp::Gt => ordering.is_gt(),
+            CompareOp::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// A column, a literal or a condition on them. Plain numbers and strings
+/// turn into literals wherever an `impl Into<Expr>` is taken
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Column(String),
+    Literal(Value),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+}
+
+/// The column called `name`, one of the [`Column`] names
+pub fn col(name: &str) -> Expr {
+    Expr::Column(name.to_string())
+}
+
+pub fn lit(value: impl Into<Value>) -> Expr {
+    Expr::Literal(value.into())
+}
+
+impl<T: Into<Value>> From<T> for Expr {
+    fn from(value: T) -> Self {
+        Expr::Literal(value.into())
+    }
+}
+
+impl Expr {
+    fn compare(self, op: CompareOp, other: impl Into<Expr>) -> Self {
+        Expr::Compare(Box::new(self), op, Box::new(other.into()))
+    }
+
+    pub fn eq(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Eq, other)
+    }
+
+    pub fn ne(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Ne, other)
+    }
+
+    pub fn lt(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Lt, other)
+    }
+
+    pub fn le(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Le, other)
+    }
+
+    pub fn gt(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Gt, other)
+    }
+
+    pub fn ge(self, other: impl Into<Expr>) -> Self {
+        self.compare(CompareOp::Ge, other)
+    }
+
+    pub fn and(self, other: Expr) -> Self {
+        Expr::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Expr) -> Self {
+        Expr::Or(Box::new(self), Box::new(other))
+    }
+}
+
+impl std::ops::Not for Expr {
+    type Output = Expr;
+
+    fn not(self) -> Expr {
+        Expr::Not(Box::new(self))
+    }
+}
+
+/// One output column per aggregate, computed per group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Agg {
+    /// rows in the group
+    Count,
+    Sum(String),
+    Min(String),
+    Max(String),
+    Mean(String),
+}
+
+pub fn count() -> Agg {
+    Agg::Count
+}
+
+pub fn sum(column: &str) -> Agg {
+    Agg::Sum(column.to_string())
+}
+
+pub fn min(column: &str) -> Agg {
+    Agg::Min(column.to_string())
+}
+
+pub fn max(column: &str) -> Agg {
+    Agg::Max(column.to_string())
+}
+
+pub fn mean(column: &str) -> Agg {
+    Agg::Mean(column.to_string())
+}
+
+impl Agg {
+    /// Header of its result column, e.g. `sum(amount)`
+    pub fn name(&self) -> String {
+        match self {
+            Agg::Count => "count".to_string(),
+            Agg::Sum(column) => format!("sum({column})"),
+            Agg::Min(column) => format!("min({column})"),
+            Agg::Max(column) => format!("max({column})"),
+            Agg::Mean(column) => format!("mean({column})"),
+        }
+    }
+}
+
+/// Filter, then group, then aggregate. Without a `group_by` everything is one
+/// group, without an `agg` the result is just the distinct groups
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    pub filter: Option<Expr>,
+    pub group_by: Vec<String>,
+    pub aggs: Vec<Agg>,
+}
+
+/// Rows ordered by their group values
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    /// the group columns, then one per aggregate
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+    /// lines [`Query::run_iter`] skipped because they didn't parse
+    pub dirty: DirtyDataReport,
+}
+
+impl fmt::Display for QueryResult {
+    /// Pipe-delimited with a header, like the data files
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.columns.join("|"))?;
+        for row in &self.rows {
+            let cells: Vec<_> = row.iter().map(Value::to_string).collect();
+            writeln!(f, "{}", cells.join("|"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only records `predicate` holds for, several filters all have to hold
+    pub fn filter(mut self, predicate: Expr) -> Self {
+        self.filter = Some(match self.filter {
+            Some(filter) => filter.and(predicate),
+            None => predicate,
+        });
+        self
+    }
+
+    /// Adds a group column, groups are the distinct combinations of all of them
+    pub fn group_by(mut self, column: &str) -> Self {
+        self.group_by.push(column.to_string());
+        self
+    }
+
+    pub fn agg(mut self, agg: Agg) -> Self {
+        self.aggs.push(agg);
+        self
+    }
+
+    pub fn run(&self, records: &[ClientData]) -> Result<QueryResult, IngestError> {
+        let mut plan = Plan::compile(self)?;
+        for record in records {
+            plan.push(record);
+        }
+        Ok(plan.finish())
+    }
+
+    /// Runs over records as they're read, broken lines are skipped into [`QueryResult::dirty`]
+    pub fn run_iter(
+        &self,
+        records: impl IntoIterator<Item = Result<ClientData, IngestError>>,
+    ) -> Result<QueryResult, IngestError> {
+        let mut plan = Plan::compile(self)?;
+        let mut dirty = DirtyDataReport::default();
+        for record in records {
+            match record {
+                Ok(record) => plan.push(&record),
+                Err(e) => dirty.absorb(e)?,
+            }
+        }
+        Ok(QueryResult {
+            dirty,
+            ..plan.finish()
+        })
+    }
+
+    /// [`Query::run_iter`] over a file, only the groups are held in memory
+    pub fn run_file(&self, path: &str) -> Result<QueryResult, IngestError> {
+        self.run_iter(RecordIter::open(path)?)
+    }
+}
+
+/// A column's value in one record, borrowed so comparing ids doesn't allocate
+#[derive(Clone, Copy)]
+enum Cell<'r> {
+    Number(f64),
+    Text(&'r str),
+    Null,
+}
+
+impl Cell<'_> {
+    fn of(record: &ClientData, column: Column) -> Cell<'_> {
+        match column {
+            Column::Id => Cell::Text(&record.id),
+            Column::FromId => Cell::Text(&record.from_id),
+            Column::ToId => Cell::Text(&record.to_id),
+            Column::Amount => Cell::Number(record.amount),
+            Column::Timestamp => record
+                .timestamp
+                .map_or(Cell::Null, |t| Cell::Number(t as f64)),
+            Column::Kind => Cell::Text(record.kind.as_str()),
+            Column::Currency => record
+                .currency
+                .as_ref()
+                .map_or(Cell::Null, |currency| Cell::Text(currency.as_str())),
+        }
+    }
+}
+
+fn numeric(column: Column) -> bool {
+    matches!(column, Column::Amount | Column::Timestamp)
+}
+
+fn resolve(name: &str) -> Result<Column, IngestError> {
+    Column::from_name(name).ok_or_else(|| IngestError::Schema(format!("unknown column `{name}`")))
+}
+
+/// Either side of a comparison
+enum Operand {
+    Column(Column),
+    Literal(Value),
+}
+
+impl Operand {
+    fn compile(expr: &Expr) -> Result<Self, IngestError> {
+        match expr {
+            Expr::Column(name) => resolve(name).map(Operand::Column),
+            Expr::Literal(value) => Ok(Operand::Literal(value.clone())),
+            other => Err(IngestError::Schema(format!(
+                "can only compare columns and literals, not {other:?}"
+            ))),
+        }
+    }
+
+    /// `None` for a null literal, which compares with nothing
+    fn is_number(&self) -> Option<bool> {
+        match self {
+            Operand::Column(column) => Some(numeric(*column)),
+            Operand::Literal(Value::Number(_)) => Some(true),
+            Operand::Literal(Value::Text(_)) => Some(false),
+            Operand::Literal(Value::Null) => None,
+        }
+    }
+
+    fn cell<'r>(&'r self, record: &'r ClientData) -> Cell<'r> {
+        match self {
+            Operand::Column(column) => Cell::of(record, *column),
+            Operand::Literal(Value::Number(n)) => Cell::Number(*n),
+            Operand::Literal(Value::Text(s)) => Cell::Text(s),
+            Operand::Literal(Value::Null) => Cell::Null,
+        }
+    }
+}
+
+enum Predicate {
+    Compare(Operand, CompareOp, Operand),
+    And(Box<Predicate>, Box<Predicate>),
+    Or(Box<Predicate>, Box<Predicate>),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn compile(expr: &Expr) -> Result<Self, IngestError> {
+        Ok(match expr {
+            Expr::Compare(left, op, right) => {
+                let (left, right) = (Operand::compile(left)?, Operand::compile(right)?);
+                // ids against numbers would just be false for every record
+                if let (Some(a), Some(b)) = (left.is_number(), right.is_number())
+                    && a != b
+                {
+                    return Err(IngestError::Schema(format!(
+                        "can't compare a number with text in {expr:?}"
+                    )));
+                }
+                Predicate::Compare(left, *op, right)
+            }
+            Expr::And(a, b) => {
+                Predicate::And(Box::new(Self::compile(a)?), Box::new(Self::compile(b)?))
+            }
+            Expr::Or(a, b) => {
+                Predicate::Or(Box::new(Self::compile(a)?), Box::new(Self::compile(b)?))
+            }
+            Expr::Not(a) => Predicate::Not(Box::new(Self::compile(a)?)),
+            other => {
+                return Err(IngestError::Schema(format!(
+                    "a filter needs a condition, not {other:?}"
+                )));
+            }
+        })
+    }
+
+    /// Comparisons with a null are false, whatever the operator
+    fn holds(&self, record: &ClientData) -> bool {
+        match self {
+            Predicate::Compare(left, op, right) => {
+                let ordering = match (left.cell(record), right.cell(record)) {
+                    (Cell::Number(a), Cell::Number(b)) => a.partial_cmp(&b),
+                    (Cell::Text(a), Cell::Text(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                ordering.is_some_and(|ordering| op.holds(ordering))
+            }
+            Predicate::And(a, b) => a.holds(record) && b.holds(record),
+            Predicate::Or(a, b) => a.holds(record) || b.holds(record),
+            Predicate::Not(a) => !a.holds(record),
+        }
+    }
+}
+
+/// Hashable group value, numbers by their bits
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum KeyPart {
+    Number(u64),
+    Text(String),
+    Null,
+}
+
+impl From<Cell<'_>> for KeyPart {
+    fn from(cell: Cell<'_>) -> Self {
+        match cell {
+            Cell::Number(n) => KeyPart::Number(n.to_bits()),
+            Cell::Text(s) => KeyPart::Text(s.to_string()),
+            Cell::Null => KeyPart::Null,
+        }
+    }
+}
+
+impl From<KeyPart> for Value {
+    fn from(part: KeyPart) -> Self {
+        match part {
+            KeyPart::Number(bits) => Value::Number(f64::from_bits(bits)),
+            KeyPart::Text(s) => Value::Text(s),
+            KeyPart::Null => Value::Null,
+        }
+    }
+}
+
+/// Rows seen and the totals of every aggregated column
+struct Group {
+    rows: usize,
+    totals: Vec<Totals>,
+}
+
+/// A [`Query`] with its names resolved and types checked, plus the groups so far
+struct Plan {
+    filter: Option<Predicate>,
+    group_by: Vec<Column>,
+    aggs: Vec<(Agg, Option<Column>)>,
+    columns: Vec<String>,
+    groups: HashMap<Vec<KeyPart>, Group>,
+}
+
+impl Plan {
+    fn compile(query: &Query) -> Result<Self, IngestError> {
+        let filter = query.filter.as_ref().map(Predicate::compile).transpose()?;
+        let group_by = query
+            .group_by
+            .iter()
+            .map(|name| resolve(name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut aggs = Vec::with_capacity(query.aggs.len());
+        for agg in &query.aggs {
+            let column = match agg {
+                Agg::Count => None,
+                Agg::Sum(name) | Agg::Min(name) | Agg::Max(name) | Agg::Mean(name) => {
+                    let column = resolve(name)?;
+                    if !numeric(column) {
+                        return Err(IngestError::Schema(format!(
+                            "can't aggregate `{name}`, it isn't a number"
+                        )));
+                    }
+                    Some(column)
+                }
+            };
+            aggs.push((agg.clone(), column));
+        }
+
+        let columns = query
+            .group_by
+            .iter()
+            .cloned()
+            .chain(query.aggs.iter().map(Agg::name))
+            .collect();
+
+        let mut groups = HashMap::new();
+        // no groups asked for, one row even when nothing matches
+        if group_by.is_empty() {
+            groups.insert(Vec::new(), Group::new(aggs.len()));
+        }
+
+        Ok(Self {
+            filter,
+            group_by,
+            aggs,
+            columns,
+            groups,
+        })
+    }
+
+    fn push(&mut self, record: &ClientData) {
+        if self
+            .filter
+            .as_ref()
+            .is_some_and(|filter| !filter.holds(record))
+        {
+            return;
+        }
+
+        let key: Vec<KeyPart> = self
+            .group_by
+            .iter()
+            .map(|&column| Cell::of(record, column).into())
+            .collect();
+        let aggs = self.aggs.len();
+        let group = self.groups.entry(key).or_insert_with(|| Group::new(aggs));
+
+        group.rows += 1;
+        for ((_, column), totals) in self.aggs.iter().zip(&mut group.totals) {
+            // count has no column, null timestamps don't count towards the rest
+            if let Some(Cell::Number(n)) = column.map(|column| Cell::of(record, column)) {
+                *totals = totals.push(n);
+            }
+        }
+    }
+
+    fn finish(self) -> QueryResult {
+        let aggs = self.aggs;
+        let mut rows: Vec<Vec<Value>> = self
+            .groups
+            .into_iter()
+            .map(|(key, group)| {
+                let values = aggs.iter().zip(group.totals).map(|((agg, _), totals)| {
+                    let result = totals.finish();
+                    match agg {
+                        Agg::Count => Value::Number(group.rows as f64),
+                        Agg::Sum(_) => Value::Number(result.sum),
+                        Agg::Min(_) => result.min.map_or(Value::Null, Value::Number),
+                        Agg::Max(_) => result.max.map_or(Value::Null, Value::Number),
+                        Agg::Mean(_) => result.mean.map_or(Value::Null, Value::Number),
+                    }
+                });
+                key.into_iter().map(Value::from).chain(values).collect()
+            })
+            .collect();
+
+        let keys = self.group_by.len();
+        rows.sort_by(|a, b| {
+            a[..keys]
+                .iter()
+                .zip(&b[..keys])
+                .map(|(a, b)| a.total_cmp(b))
+                .find(|ordering| ordering.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+
+        QueryResult {
+            columns: self.columns,
+            rows,
+            dirty: DirtyDataReport::default(),
+        }
+    }
+}
+
+impl Group {
+    fn new(aggs: usize) -> Self {
+        Self {
+            rows: 0,
+            totals: vec![Totals::default(); aggs],
+        }
+    }
+}
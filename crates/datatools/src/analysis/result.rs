@@ -0,0 +1,91 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::data_ingestion::ClientData;
+
+/// What an analysis approach found. `sum` (and with it `mean`) can differ in the
+/// last digits between approaches, they add in different orders
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct AnalysisResult {
+    pub sum: f64,
+    pub count: usize,
+    /// `None` without any records, same for `max` and `mean`
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    /// senders and receivers together. Only set by [`AnalysisResult::with_distinct_accounts`],
+    /// a hash set per call would drown out the sums the approaches are compared on
+    #[serde(default)]
+    pub distinct_accounts: Option<usize>,
+}
+
+impl AnalysisResult {
+    /// `min` / `max` as folded from infinity and minus infinity, dropped when `count` is 0
+    pub fn new(sum: f64, count: usize, min: f64, max: f64) -> Self {
+        let any = count > 0;
+        Self {
+            sum,
+            count,
+            min: any.then_some(min),
+            max: any.then_some(max),
+            mean: any.then(|| sum / count as f64),
+            distinct_accounts: None,
+        }
+    }
+
+    pub fn with_distinct_accounts(mut self, records: &[ClientData]) -> Self {
+        let accounts: HashSet<&str> = records
+            .iter()
+            .flat_map(|r| [r.from_id.as_str(), r.to_id.as_str()])
+            .collect();
+        self.distinct_accounts = Some(accounts.len());
+        self
+    }
+}
+
+/// Running sum, count, min and max - the fold state of the iterator and parallel approaches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Totals {
+    pub sum: f64,
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for Totals {
+    fn default() -> Self {
+        Self {
+            sum: 0.0,
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl Totals {
+    /// By value, so it slots into `fold` directly
+    pub fn push(self, amount: f64) -> Self {
+        Self {
+            sum: self.sum + amount,
+            count: self.count + 1,
+            min: self.min.min(amount),
+            max: self.max.max(amount),
+        }
+    }
+
+    /// `self` covers the records before `other`'s
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            sum: self.sum + other.sum,
+            count: self.count + other.count,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    pub fn finish(self) -> AnalysisResult {
+        AnalysisResult::new(self.sum, self.count, self.min, self.max)
+    }
+}
@@ -0,0 +1,408 @@
+//! Descriptive statistics over the `amount` field.
+//!
+//! [`amount_stats`] is exact but needs every amount in memory (it sorts them),
+//! [`StreamingStats`] takes one value at a time and approximates the percentiles
+//! with a [`TDigest`], so it works on datasets of any size.
+
+use serde::{Deserialize, Serialize};
+
+use crate::data_ingestion::ClientData;
+
+/// The usual p50 / p95 / p99
+pub const DEFAULT_PERCENTILES: [f64; 3] = [0.50, 0.95, 0.99];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmountStats {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub median: f64,
+    /// population standard deviation
+    pub stddev: f64,
+    /// `(percentile, value)` pairs, percentiles in `0.0..=1.0`
+    pub percentiles: Vec<(f64, f64)>,
+}
+
+impl AmountStats {
+    /// Value for a percentile that was requested when computing the stats
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        self.percentiles
+            .iter()
+            .find(|(q, _)| (q - p).abs() < f64::EPSILON)
+            .map(|&(_, value)| value)
+    }
+}
+
+/// Exact stats, `None` for an empty slice
+pub fn amount_stats(records: &[ClientData], percentiles: &[f64]) -> Option<AmountStats> {
+    let mut amounts: Vec<f64> = records.iter().map(|r| r.amount).collect();
+    exact_stats(&mut amounts, percentiles)
+}
+
+/// Exact stats over raw amounts, sorts the slice in place
+pub fn exact_stats(amounts: &mut [f64], percentiles: &[f64]) -> Option<AmountStats> {
+    if amounts.is_empty() {
+        return None;
+    }
+    amounts.sort_unstable_by(f64::total_cmp);
+
+    let count = amounts.len();
+    let mean = amounts.iter().sum::<f64>() / count as f64;
+    let variance = amounts.iter().map(|a| (a - mean).powi(2)).sum::<f64>() / count as f64;
+
+    Some(AmountStats {
+        count,
+        min: amounts[0],
+        max: amounts[count - 1],
+        mean,
+        median: sorted_percentile(amounts, 0.5),
+        stddev: variance.sqrt(),
+        percentiles: percentiles
+            .iter()
+            .map(|&p| (p, sorted_percentile(amounts, p)))
+            .collect(),
+    })
+}
+
+/// Linear interpolation between the closest ranks
+fn sorted_percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let (low, high) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[low] + (sorted[high] - sorted[low]) * (rank - low as f64)
+}
+
+/// One-pass stats: Welford for mean/variance, t-digest for the percentiles
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamingStats {
+    count: usize,
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    digest: TDigest,
+}
+
+impl Default for StreamingStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamingStats {
+    pub fn new() -> Self {
+        Self::with_digest(TDigest::new(100.0))
+    }
+
+    pub fn with_digest(digest: TDigest) -> Self {
+        Self {
+            count: 0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            digest,
+        }
+    }
+
+    pub fn push(&mut self, amount: f64) {
+        self.count += 1;
+        self.min = self.min.min(amount);
+        self.max = self.max.max(amount);
+
+        let delta = amount - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (amount - self.mean);
+
+        self.digest.push(amount);
+    }
+
+    /// Combine with stats collected elsewhere, e.g. on another thread
+    pub fn merge(&mut self, other: &StreamingStats) {
+        if other.count == 0 {
+            return;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * (self.count * other.count) as f64 / count as f64;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.digest.merge(&other.digest);
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn digest(&mut self) -> &mut TDigest {
+        &mut self.digest
+    }
+
+    /// Snapshot of the current state, percentiles are t-digest estimates
+    pub fn finish(&mut self, percentiles: &[f64]) -> Option<AmountStats> {
+        if self.count == 0 {
+            return None;
+        }
+
+        Some(AmountStats {
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: self.mean,
+            median: self.digest.quantile(0.5)?,
+            stddev: (self.m2 / self.count as f64).sqrt(),
+            percentiles: percentiles
+                .iter()
+                .map(|&p| self.digest.quantile(p).map(|value| (p, value)))
+                .collect::<Option<_>>()?,
+        })
+    }
+
+    /// See [`TDigest::histogram`]
+    pub fn histogram(&mut self, buckets: usize) -> Vec<Bucket> {
+        self.digest.histogram(buckets)
+    }
+}
+
+impl Extend<f64> for StreamingStats {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for amount in iter {
+            self.push(amount);
+        }
+    }
+}
+
+/// One bar of a [`TDigest::histogram`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bucket {
+    pub low: f64,
+    pub high: f64,
+    /// estimated values in `low..high`, the last bucket has `high` too
+    pub count: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Centroid {
+    pub mean: f64,
+    pub weight: f64,
+}
+
+/// Merging t-digest (Dunning & Ertl) with the arcsine scale function.
+///
+/// Memory is bounded by the compression factor (~`compression` centroids),
+/// accuracy is best towards the tails which is where p95/p99 live.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TDigest {
+    compression: f64,
+    centroids: Vec<Centroid>,
+    buffer: Vec<f64>,
+    total_weight: f64,
+    min: f64,
+    max: f64,
+}
+
+impl TDigest {
+    pub fn new(compression: f64) -> Self {
+        Self {
+            compression,
+            centroids: Vec::new(),
+            buffer: Vec::with_capacity(Self::buffer_capacity(compression)),
+            total_weight: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    fn buffer_capacity(compression: f64) -> usize {
+        (compression as usize * 5).max(32)
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.buffer.push(value);
+        if self.buffer.len() >= Self::buffer_capacity(self.compression) {
+            self.compress();
+        }
+    }
+
+    pub fn merge(&mut self, other: &TDigest) {
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.buffer.extend_from_slice(&other.buffer);
+        self.centroids.extend_from_slice(&other.centroids);
+        self.compress();
+    }
+
+    pub fn count(&self) -> f64 {
+        self.total_weight + self.buffer.len() as f64
+    }
+
+    /// Compressed centroids, sorted by mean
+    pub fn centroids(&mut self) -> &[Centroid] {
+        self.compress();
+        &self.centroids
+    }
+
+    fn scale(&self, q: f64) -> f64 {
+        self.compression / (2.0 * std::f64::consts::PI) * (2.0 * q - 1.0).asin()
+    }
+
+    /// Folds the buffer into the centroids
+    fn compress(&mut self) {
+        if self.buffer.is_empty() && self.centroids.len() <= 1 {
+            return;
+        }
+
+        let mut items = std::mem::take(&mut self.centroids);
+        items.extend(
+            self.buffer
+                .drain(..)
+                .map(|mean| Centroid { mean, weight: 1.0 }),
+        );
+        items.sort_unstable_by(|a, b| a.mean.total_cmp(&b.mean));
+
+        let total: f64 = items.iter().map(|c| c.weight).sum();
+        let mut merged = Vec::with_capacity(self.compression as usize * 2);
+        let mut current = items[0];
+        let mut weight_before = 0.0;
+
+        for item in &items[1..] {
+            let q_left = weight_before / total;
+            let q_right = (weight_before + current.weight + item.weight) / total;
+
+            // centroid can keep growing while it spans at most one unit of the scale function
+            if self.scale(q_right) - self.scale(q_left) <= 1.0 {
+                let weight = current.weight + item.weight;
+                current.mean += (item.mean - current.mean) * item.weight / weight;
+                current.weight = weight;
+            } else {
+                weight_before += current.weight;
+                merged.push(current);
+                current = *item;
+            }
+        }
+        merged.push(current);
+
+        self.centroids = merged;
+        self.total_weight = total;
+    }
+
+    /// Estimated value at quantile `q` (`0.0..=1.0`), `None` when empty
+    pub fn quantile(&mut self, q: f64) -> Option<f64> {
+        self.compress();
+        let centroids = &self.centroids;
+        let (first, last) = (centroids.first()?, centroids.last()?);
+        if centroids.len() == 1 {
+            return Some(first.mean);
+        }
+
+        let target = q.clamp(0.0, 1.0) * self.total_weight;
+
+        // left tail: between the observed min and the first centroid's center
+        if target < first.weight / 2.0 {
+            let t = target / (first.weight / 2.0);
+            return Some(self.min + (first.mean - self.min) * t);
+        }
+
+        let mut cumulative = 0.0;
+        for pair in centroids.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let left = cumulative + a.weight / 2.0;
+            let right = cumulative + a.weight + b.weight / 2.0;
+            if target <= right {
+                let t = (target - left) / (right - left);
+                return Some(a.mean + (b.mean - a.mean) * t);
+            }
+            cumulative += a.weight;
+        }
+
+        // right tail: between the last centroid's center and the observed max
+        let left = self.total_weight - last.weight / 2.0;
+        let t = ((target - left) / (last.weight / 2.0)).min(1.0);
+        Some(last.mean + (self.max - last.mean) * t)
+    }
+
+    /// Estimated fraction of the values at or below `x`, the inverse of
+    /// [`TDigest::quantile`]. `None` when empty
+    pub fn cdf(&mut self, x: f64) -> Option<f64> {
+        self.compress();
+        let centroids = &self.centroids;
+        let (first, last) = (centroids.first()?, centroids.last()?);
+        if x < self.min {
+            return Some(0.0);
+        }
+        if x >= self.max {
+            return Some(1.0);
+        }
+        if centroids.len() == 1 {
+            return Some((x - self.min) / (self.max - self.min));
+        }
+
+        // left tail, same interpolation as in quantile
+        if x < first.mean {
+            let t = (x - self.min) / (first.mean - self.min);
+            return Some(t * first.weight / 2.0 / self.total_weight);
+        }
+
+        let mut cumulative = 0.0;
+        for pair in centroids.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if x < b.mean {
+                let left = cumulative + a.weight / 2.0;
+                let right = cumulative + a.weight + b.weight / 2.0;
+                let t = (x - a.mean) / (b.mean - a.mean);
+                return Some((left + (right - left) * t) / self.total_weight);
+            }
+            cumulative += a.weight;
+        }
+
+        let left = self.total_weight - last.weight / 2.0;
+        let t = (x - last.mean) / (self.max - last.mean);
+        Some((left + last.weight / 2.0 * t) / self.total_weight)
+    }
+
+    /// `buckets` equal-width buckets from the smallest value seen to the
+    /// largest, counts estimated from the centroids. Empty when the digest is
+    pub fn histogram(&mut self, buckets: usize) -> Vec<Bucket> {
+        let total = self.count();
+        if total == 0.0 || buckets == 0 {
+            return Vec::new();
+        }
+        let (min, max) = (self.min, self.max);
+        if min == max {
+            return vec![Bucket {
+                low: min,
+                high: max,
+                count: total,
+            }];
+        }
+
+        let width = (max - min) / buckets as f64;
+        let mut below = 0.0;
+        (1..=buckets)
+            .map(|i| {
+                let high = if i == buckets {
+                    max
+                } else {
+                    min + width * i as f64
+                };
+                let upto = if i == buckets {
+                    1.0
+                } else {
+                    self.cdf(high).unwrap_or(1.0)
+                };
+                let bucket = Bucket {
+                    low: min + width * (i - 1) as f64,
+                    high,
+                    count: (upto - below) * total,
+                };
+                below = upto;
+                bucket
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::HashMap;
+use std::io::BufRead;
+
+use super::stats::StreamingStats;
+use super::{AccountStats, AnalysisResult};
+use crate::cancel::CancellationToken;
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, RefReader};
+
+/// Totals from [`analyze_streaming`], memory grows with the number of accounts, not records
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamStats {
+    pub sum: f64,
+    pub count: usize,
+    /// `None` until the first record
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub accounts: HashMap<String, AccountStats>,
+    /// lines that didn't parse, skipped like [`crate::data_ingestion::open_file`] does
+    pub dirty: DirtyDataReport,
+    /// the amounts' percentiles and histogram, only kept after
+    /// [`StreamStats::with_quantiles`] since the t-digest costs a bit per record
+    pub amounts: Option<StreamingStats>,
+    /// the pass was cancelled, the totals are of the records before that
+    pub cancelled: bool,
+}
+
+impl StreamStats {
+    /// Totals that keep a t-digest of the amounts too, memory stays bounded
+    pub fn with_quantiles() -> Self {
+        Self {
+            amounts: Some(StreamingStats::new()),
+            ..Self::default()
+        }
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+
+    /// The totals in the shape the in-memory approaches return, with the
+    /// distinct accounts (already known here, no extra pass)
+    pub fn result(&self) -> AnalysisResult {
+        AnalysisResult {
+            sum: self.sum,
+            count: self.count,
+            min: self.min,
+            max: self.max,
+            mean: self.mean(),
+            distinct_accounts: Some(self.accounts.len()),
+        }
+    }
+
+    /// Folds in the totals of records that came after these, e.g. a
+    /// [`super::LiveStats::update`]
+    pub fn merge(&mut self, other: StreamStats) {
+        self.sum += other.sum;
+        self.count += other.count;
+        self.min = match (self.min, other.min) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        self.max = match (self.max, other.max) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        for (id, other) in other.accounts {
+            let account = self.accounts.entry(id).or_default();
+            account.total_sent += other.total_sent;
+            account.sent_count += other.sent_count;
+            account.total_received += other.total_received;
+            account.received_count += other.received_count;
+        }
+        self.dirty.merge(other.dirty);
+        self.cancelled |= other.cancelled;
+        match (&mut self.amounts, other.amounts) {
+            (Some(amounts), Some(other)) => amounts.merge(&other),
+            (amounts @ None, other) => *amounts = other,
+            (Some(_), None) => {}
+        }
+    }
+
+    /// Counts one more record, for records that don't come from a reader
+    pub fn add(&mut self, record: &ClientData) {
+        self.push(&record.from_id, &record.to_id, record.amount);
+    }
+
+    /// [`add`](Self::add) from borrowed fields, e.g. a
+    /// [`crate::data_ingestion::RefReader`]'s records, without a `ClientData` in between
+    pub fn push(&mut self, from_id: &str, to_id: &str, amount: f64) {
+        self.sum += amount;
+        self.count += 1;
+        self.min = Some(self.min.map_or(amount, |min| min.min(amount)));
+        self.max = Some(self.max.map_or(amount, |max| max.max(amount)));
+        if let Some(amounts) = &mut self.amounts {
+            amounts.push(amount);
+        }
+
+        let sender = account(&mut self.accounts, from_id);
+        sender.total_sent += amount;
+        sender.sent_count += 1;
+        let receiver = account(&mut self.accounts, to_id);
+        receiver.total_received += amount;
+        receiver.received_count += 1;
+    }
+}
+
+// looked up by &str, only accounts we haven't seen yet allocate a key
+fn account<'a>(accounts: &'a mut HashMap<String, AccountStats>, id: &str) -> &'a mut AccountStats {
+    if !accounts.contains_key(id) {
+        accounts.insert(id.to_string(), AccountStats::default());
+    }
+    accounts.get_mut(id).expect("just inserted")
+}
+
+/// Sum, count, min, max and per-account totals in one pass over a delimited
+/// stream (header first), e.g. stdin. Records are borrowed from the line
+/// buffer, nothing is kept once it's counted. Broken lines are skipped, a
+/// failing reader ends the pass with its error
+pub fn analyze_streaming(reader: impl BufRead) -> Result<StreamStats, IngestError> {
+    analyze_streaming_into(reader, StreamStats::default())
+}
+
+/// [`analyze_streaming`] on top of `stats`, e.g. [`StreamStats::with_quantiles`]
+/// for p50/p95/p99 of the amounts in the same pass
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn analyze_streaming_into(
+    reader: impl BufRead,
+    stats: StreamStats,
+) -> Result<StreamStats, IngestError> {
+    stream(reader, stats, None)
+}
+
+/// [`analyze_streaming_into`] that stops before the next record once `token`
+/// is cancelled, with [`StreamStats::cancelled`] set on the totals so far
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn analyze_streaming_cancellable(
+    reader: impl BufRead,
+    stats: StreamStats,
+    token: &CancellationToken,
+) -> Result<StreamStats, IngestError> {
+    stream(reader, stats, Some(token))
+}
+
+fn stream(
+    reader: impl BufRead,
+    mut stats: StreamStats,
+    token: Option<&CancellationToken>,
+) -> Result<StreamStats, IngestError> {
+    let start = crate::Instant::now();
+    let mut reader = RefReader::new(reader);
+
+    while let Some(record) = reader.next_record() {
+        if token.is_some_and(CancellationToken::is_cancelled) {
+            stats.cancelled = true;
+            break;
+        }
+        match record {
+            Ok(record) => stats.push(record.from_id, record.to_id, record.amount),
+            Err(e) => stats.dirty.absorb(e)?,
+        }
+    }
+
+    crate::metrics::record_batch("streaming", stats.count, &stats.dirty, start.elapsed());
+    Ok(stats)
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use rayon::prelude::*;
+
+use super::{AnalysisResult, Totals};
+use crate::Instant;
+use crate::data_ingestion::{ClientColumns, ClientData};
+
+/// Knobs for the rayon paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisOptions {
+    /// smallest piece rayon splits the input into (`with_min_len`), 1 lets it split all the way down
+    pub min_len: usize,
+}
+
+impl Default for AnalysisOptions {
+    fn default() -> Self {
+        Self { min_len: 1 }
+    }
+}
+
+impl AnalysisOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+}
+
+/// [`super::analyze_rayon`] with explicit chunking
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = records.len()))
+)]
+pub fn analyze_rayon_with(records: &[ClientData], options: &AnalysisOptions) -> AnalysisResult {
+    records
+        .par_iter()
+        .with_min_len(options.min_len.max(1))
+        .map(|r| r.amount)
+        .fold(Totals::default, Totals::push)
+        .reduce(Totals::default, Totals::merge)
+        .finish()
+}
+
+/// [`super::analyze_columnar_rayon`] with explicit chunking
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(records = columns.len()))
+)]
+pub fn analyze_columnar_rayon_with(
+    columns: &ClientColumns,
+    options: &AnalysisOptions,
+) -> AnalysisResult {
+    columns
+        .amounts
+        .par_iter()
+        .with_min_len(options.min_len.max(1))
+        .copied()
+        .fold(Totals::default, Totals::push)
+        .reduce(Totals::default, Totals::merge)
+        .finish()
+}
+
+/// What [`autotune`] measured, fastest run per candidate
+#[derive(Debug, Clone, PartialEq)]
+pub struct Autotune {
+    pub options: AnalysisOptions,
+    pub timings: Vec<(usize, Duration)>,
+}
+
+/// Chunk sizes tried by [`autotune`]: 1 (rayon splits as it likes), then the
+/// powers of 4 from 64 to 1M, smaller pieces than 64 records only add overhead
+pub const CANDIDATE_MIN_LENS: [usize; 9] = [1, 64, 256, 1024, 4096, 16384, 65536, 262144, 1048576];
+
+/// Times [`analyze_rayon_with`] on the first `sample` records for every
+/// candidate that still leaves each thread a piece, best of 5, and picks the fastest.
+/// Worth redoing per machine, the best size moves with core count and NUMA layout
+pub fn autotune(records: &[ClientData], sample: usize) -> Autotune {
+    let sample = &records[..sample.min(records.len())];
+    let threads = rayon::current_num_threads();
+
+    let timings: Vec<(usize, Duration)> = CANDIDATE_MIN_LENS
+        .iter()
+        .filter(|&&min_len| min_len == 1 || min_len * threads <= sample.len())
+        .map(|&min_len| {
+            let options = AnalysisOptions { min_len };
+            let best = (0..5)
+                .map(|_| {
+                    let start = Instant::now();
+                    std::hint::black_box(analyze_rayon_with(sample, &options));
+                    start.elapsed()
+                })
+                .min()
+                .unwrap_or_default();
+            (min_len, best)
+        })
+        .collect();
+
+    let min_len = timings
+        .iter()
+        .min_by_key(|(_, elapsed)| *elapsed)
+        .map_or(1, |&(min_len, _)| min_len);
+
+    Autotune {
+        options: AnalysisOptions { min_len },
+        timings,
+    }
+}
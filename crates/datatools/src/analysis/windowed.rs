@@ -0,0 +1,61 @@
+use std::collections::BTreeMap;
+
+use crate::data_ingestion::ClientData;
+
+/// Fixed-size time buckets, aligned to the unix epoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Hourly,
+    Daily,
+    Seconds(u32),
+}
+
+impl Window {
+    pub fn seconds(self) -> i64 {
+        match self {
+            Window::Hourly => 3600,
+            Window::Daily => 86_400,
+            Window::Seconds(secs) => secs.max(1) as i64,
+        }
+    }
+
+    /// Start of the window `ts` falls into
+    pub fn start_of(self, ts: i64) -> i64 {
+        // div_euclid so timestamps before 1970 still round down
+        ts.div_euclid(self.seconds()) * self.seconds()
+    }
+}
+
+/// Totals of one window, `end` is exclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WindowStats {
+    pub start: i64,
+    pub end: i64,
+    pub total: f64,
+    pub count: usize,
+}
+
+/// Buckets records into windows, sorted by start. Empty windows are left out,
+/// and so are records without a timestamp
+pub fn aggregate_windows<'a>(
+    records: impl IntoIterator<Item = &'a ClientData>,
+    window: Window,
+) -> Vec<WindowStats> {
+    let mut windows: BTreeMap<i64, WindowStats> = BTreeMap::new();
+
+    for record in records {
+        let Some(ts) = record.timestamp else {
+            continue;
+        };
+        let start = window.start_of(ts);
+        let stats = windows.entry(start).or_insert(WindowStats {
+            start,
+            end: start + window.seconds(),
+            ..Default::default()
+        });
+        stats.total += record.amount;
+        stats.count += 1;
+    }
+
+    windows.into_values().collect()
+}
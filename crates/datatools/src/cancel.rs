@@ -0,0 +1,54 @@
+//! Stopping long runs halfway without killing the process: generation,
+//! ingestion and the analyzers that take a [`CancellationToken`] look at it
+//! between records (or chunks of them), stop, leave their output complete up to there and hand
+//! back what they have, marked as cancelled.
+//!
+//! [`ctrl_c`] (with the `signal` feature) trips a token on Ctrl-C, a second
+//! one kills the process like it always did
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag shared by whoever asks for the stop and whatever is running, clones
+/// share it
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token over a flag that already exists, e.g. one a signal handler sets
+    pub fn from_flag(flag: Arc<AtomicBool>) -> Self {
+        Self(flag)
+    }
+
+    /// The flag underneath, to hand to something that sets an `AtomicBool`
+    pub fn flag(&self) -> &Arc<AtomicBool> {
+        &self.0
+    }
+
+    /// Asks everything holding a clone to stop, there's no taking it back
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A token that Ctrl-C (SIGINT) and SIGTERM cancel. The second signal exits
+/// the process right away, for whatever doesn't look at the token
+#[cfg(feature = "signal")]
+pub fn ctrl_c() -> std::io::Result<CancellationToken> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+
+    let token = CancellationToken::new();
+    for signal in [SIGINT, SIGTERM] {
+        // registered first, so it sees the flag before this signal sets it
+        signal_hook::flag::register_conditional_shutdown(signal, 130, token.flag().clone())?;
+        signal_hook::flag::register(signal, token.flag().clone())?;
+    }
+    Ok(token)
+}
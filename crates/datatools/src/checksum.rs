@@ -0,0 +1,50 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use sha2::{Digest, Sha256};
+
+/// `<path>.sha256`, in the format `sha256sum -c` understands
+pub fn sidecar_path(path: &str) -> String {
+    format!("{path}.sha256")
+}
+
+/// Hex SHA-256 of the file as it is on disk (compressed bytes for `.gz` / `.zst`)
+pub fn sha256_file(path: &str) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+/// Hashes `path` and writes its sidecar, returns the hash
+pub fn write_sidecar(path: &str) -> std::io::Result<String> {
+    let hash = sha256_file(path)?;
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or(path);
+    std::fs::write(sidecar_path(path), format!("{hash}  {name}\n"))?;
+    Ok(hash)
+}
+
+/// The hash stored in the sidecar, `None` if there is no sidecar
+pub fn read_sidecar(path: &str) -> std::io::Result<Option<String>> {
+    match std::fs::read_to_string(sidecar_path(path)) {
+        Ok(contents) => Ok(contents.split_whitespace().next().map(str::to_string)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
@@ -0,0 +1,223 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression codec of a data file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Guess from the extension: `.gz` / `.zst`, anything else is plain
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Sniff the magic bytes, falling back to the extension for files too short to tell
+    pub fn detect(path: &str) -> std::io::Result<Self> {
+        let mut magic = [0u8; 4];
+        let mut file = File::open(path)?;
+        let mut read = 0;
+        while read < magic.len() {
+            match file.read(&mut magic[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+
+        Ok(match Self::from_magic(&magic[..read]) {
+            Some(compression) => compression,
+            None if read < magic.len() => Self::from_path(path),
+            None => Compression::None,
+        })
+    }
+
+    /// What the first bytes of a file say, `None` when they aren't a known magic
+    pub fn from_magic(head: &[u8]) -> Option<Self> {
+        if head.starts_with(&GZIP_MAGIC) {
+            Some(Compression::Gzip)
+        } else if head.starts_with(&ZSTD_MAGIC) {
+            Some(Compression::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn not_compiled_in(feature: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{feature} support is not compiled in, enable the `{feature}` feature"),
+    )
+}
+
+/// Seeking/mapping ingestion paths can't look inside compressed files
+pub fn require_plain(path: &str) -> std::io::Result<()> {
+    if crate::remote::is_object_uri(path) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{path} is in an object store, this reader needs a local file"),
+        ));
+    }
+    match Compression::detect(path)? {
+        Compression::None => Ok(()),
+        compression => Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{path} is {compression:?} compressed, this reader needs a plain file"),
+        )),
+    }
+}
+
+/// Buffered reader over the file, decompressing on the fly when needed.
+/// `s3://` / `gs://` URIs are streamed from the bucket, see [`crate::remote`]
+pub fn open_reader(path: &str) -> std::io::Result<Box<dyn BufRead + Send>> {
+    if crate::remote::is_object_uri(path) {
+        return decompress(crate::remote::open_reader(path)?);
+    }
+    // everything that comes through here reads front to back
+    let file = crate::metrics::Metered(crate::page_cache::open_sequential(path)?);
+
+    Ok(match Compression::detect(path)? {
+        Compression::None => Box::new(BufReader::new(file)),
+
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(
+            BufReader::new(file),
+        ))),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip => return Err(not_compiled_in("gzip")),
+
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::new(file)?)),
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => return Err(not_compiled_in("zstd")),
+    })
+}
+
+/// [`open_reader`] over a stream that is already open, e.g. stdin, the codec
+/// comes from its first bytes
+pub fn decompress(mut reader: Box<dyn BufRead + Send>) -> std::io::Result<Box<dyn BufRead + Send>> {
+    Ok(
+        match Compression::from_magic(reader.fill_buf()?).unwrap_or_default() {
+            Compression::None => reader,
+
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => {
+                Box::new(BufReader::new(flate2::read::MultiGzDecoder::new(reader)))
+            }
+            #[cfg(not(feature = "gzip"))]
+            Compression::Gzip => return Err(not_compiled_in("gzip")),
+
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?)),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => return Err(not_compiled_in("zstd")),
+        },
+    )
+}
+
+/// Writer side of [`Compression`], has to be [`finish`](Self::finish)ed to get a valid file
+pub enum CompressedWriter<W: Write = BufWriter<File>> {
+    Plain(W),
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<W>),
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl CompressedWriter {
+    pub fn create(path: &str, compression: Compression) -> std::io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), compression)
+    }
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Compresses into any writer, e.g. stdout, it should be buffered already
+    pub fn new(writer: W, compression: Compression) -> std::io::Result<Self> {
+        Ok(match compression {
+            Compression::None => CompressedWriter::Plain(writer),
+
+            #[cfg(feature = "gzip")]
+            Compression::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            #[cfg(not(feature = "gzip"))]
+            Compression::Gzip => return Err(not_compiled_in("gzip")),
+
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(writer, 3)?),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => return Err(not_compiled_in("zstd")),
+        })
+    }
+
+    /// Writes the trailer (if any) and flushes everything to disk
+    // only the plain variant is left with compression features off
+    #[allow(clippy::infallible_destructuring_match)]
+    pub fn finish(self) -> std::io::Result<()> {
+        let mut file = match self {
+            CompressedWriter::Plain(file) => file,
+            #[cfg(feature = "gzip")]
+            CompressedWriter::Gzip(encoder) => encoder.finish()?,
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(encoder) => encoder.finish()?,
+        };
+        file.flush()
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write(buf),
+            #[cfg(feature = "gzip")]
+            CompressedWriter::Gzip(w) => w.write(buf),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.write(buf),
+        }
+    }
+
+    // a `BufWriter` takes a batch of lines in one go, the default would take the first only
+    fn write_vectored(&mut self, bufs: &[std::io::IoSlice<'_>]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write_vectored(bufs),
+            #[cfg(feature = "gzip")]
+            CompressedWriter::Gzip(w) => w.write_vectored(bufs),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.write_vectored(bufs),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.write_all(buf),
+            #[cfg(feature = "gzip")]
+            CompressedWriter::Gzip(w) => w.write_all(buf),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.flush(),
+            #[cfg(feature = "gzip")]
+            CompressedWriter::Gzip(w) => w.flush(),
+            #[cfg(feature = "zstd")]
+            CompressedWriter::Zstd(w) => w.flush(),
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{DirtyDataReport, IngestError, decode_line, line_too_long, read_line};
+
+/// Header of an accounts table, what [`crate::generator::generate_accounts`] writes
+pub const ACCOUNTS_HEADER: &str = "account_id|country|opened|risk_tier";
+
+/// How much the bank trusts an account
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RiskTier {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskTier {
+    pub const ALL: [RiskTier; 3] = [RiskTier::Low, RiskTier::Medium, RiskTier::High];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RiskTier::Low => "low",
+            RiskTier::Medium => "medium",
+            RiskTier::High => "high",
+        }
+    }
+}
+
+impl FromStr for RiskTier {
+    type Err = IngestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RiskTier::ALL
+            .into_iter()
+            .find(|tier| tier.as_str() == s)
+            .ok_or_else(|| IngestError::InvalidAccount {
+                column: "risk_tier",
+                value: s.to_string(),
+            })
+    }
+}
+
+impl fmt::Display for RiskTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One row of the accounts dimension table, the ids are the ones in the
+/// transactions' `from_id` and `to_id`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub id: String,
+    /// ISO 3166 alpha-2 code, e.g. `DE`
+    pub country: String,
+    /// `YYYY-MM-DD`, so comparing the strings compares the dates
+    pub opened: String,
+    pub risk_tier: RiskTier,
+}
+
+impl Account {
+    /// One `account_id|country|opened|risk_tier` line
+    pub fn parse(line: &str) -> Result<Self, IngestError> {
+        let fields: Vec<&str> = line.split('|').map(str::trim).collect();
+        let [id, country, opened, risk_tier] = fields[..] else {
+            return Err(IngestError::FieldCount {
+                expected: 4,
+                got: fields.len(),
+            });
+        };
+
+        if id.is_empty() {
+            return Err(IngestError::InvalidId {
+                value: id.to_string(),
+            });
+        }
+        if country.len() != 2 || !country.bytes().all(|b| b.is_ascii_uppercase()) {
+            return Err(IngestError::InvalidAccount {
+                column: "country",
+                value: country.to_string(),
+            });
+        }
+        if !is_date(opened) {
+            return Err(IngestError::InvalidAccount {
+                column: "opened",
+                value: opened.to_string(),
+            });
+        }
+
+        Ok(Self {
+            id: id.to_string(),
+            country: country.to_string(),
+            opened: opened.to_string(),
+            risk_tier: risk_tier.parse()?,
+        })
+    }
+
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.id, self.country, self.opened, self.risk_tier
+        )
+    }
+}
+
+/// `YYYY-MM-DD` by shape, the calendar isn't checked
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// Lazy reader over an accounts table, the first line is the header and
+/// blank lines are skipped
+pub struct AccountIter<R = Box<dyn BufRead + Send>> {
+    reader: R,
+    buf: Vec<u8>,
+    line_num: usize,
+}
+
+impl AccountIter {
+    /// A plain, `.gz` or `.zst` accounts table
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        Ok(Self::new(crate::compression::open_reader(path)?))
+    }
+}
+
+impl<R: BufRead> AccountIter<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_num: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for AccountIter<R> {
+    type Item = Result<Account, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            let n = match read_line(&mut self.reader, &mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => n,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line_num += 1;
+            if n > self.buf.len() {
+                return Some(Err(line_too_long(&self.buf, n, self.line_num)));
+            }
+
+            let line = match decode_line(&self.buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            if self.line_num == 1 {
+                if line != ACCOUNTS_HEADER {
+                    return Some(Err(IngestError::Schema(format!(
+                        "expected an accounts table starting with {ACCOUNTS_HEADER:?}, got {line:?}"
+                    ))));
+                }
+                continue;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(Account::parse(line).map_err(|e| IngestError::Line {
+                line_num: self.line_num,
+                line: line.to_string(),
+                source: Box::new(e),
+            }));
+        }
+    }
+}
+
+/// The whole accounts table, broken lines are skipped like in [`super::open_file`]
+/// and come back counted
+pub fn load_accounts(path: &str) -> Result<(Vec<Account>, DirtyDataReport), IngestError> {
+    let mut accounts = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+    for account in AccountIter::open(path)? {
+        match account {
+            Ok(account) => accounts.push(account),
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    Ok((accounts, dirty))
+}
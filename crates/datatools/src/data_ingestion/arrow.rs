@@ -0,0 +1,278 @@
+use std::io::BufRead;
+use std::sync::Arc;
+
+use arrow_array::builder::{Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{ArrowError, DataType, Field, Schema, SchemaRef};
+
+use super::{
+    ClientData, Column, Currency, DirtyDataReport, IngestError, Layout, TransactionKind,
+    decode_line, parse_amount,
+};
+use crate::format::Format;
+
+/// Rows per batch when the caller doesn't care
+pub const DEFAULT_BATCH_ROWS: usize = 64 * 1024;
+
+/// Arrow schema for a layout, the same one the parquet files use
+pub fn arrow_schema(layout: &Layout) -> Schema {
+    let fields: Vec<_> = layout
+        .columns()
+        .iter()
+        .map(|&column| match column {
+            Column::Id | Column::FromId | Column::ToId => {
+                Field::new(column.name(), DataType::Utf8, false)
+            }
+            Column::Amount => Field::new(column.name(), DataType::Float64, false),
+            Column::Timestamp => Field::new(column.name(), DataType::Int64, true),
+            Column::Kind => Field::new(column.name(), DataType::Utf8, false),
+            Column::Currency => Field::new(column.name(), DataType::Utf8, true),
+        })
+        .collect();
+
+    Schema::new(fields)
+}
+
+/// Column builders for one batch, filled from records or straight from lines
+pub struct BatchBuilder {
+    layout: Layout,
+    schema: SchemaRef,
+    ids: StringBuilder,
+    from_ids: StringBuilder,
+    to_ids: StringBuilder,
+    amounts: Float64Builder,
+    timestamps: Int64Builder,
+    kinds: StringBuilder,
+    currencies: StringBuilder,
+    rows: usize,
+}
+
+impl BatchBuilder {
+    pub fn new(layout: &Layout) -> Self {
+        Self {
+            layout: layout.clone(),
+            schema: Arc::new(arrow_schema(layout)),
+            ids: StringBuilder::new(),
+            from_ids: StringBuilder::new(),
+            to_ids: StringBuilder::new(),
+            amounts: Float64Builder::new(),
+            timestamps: Int64Builder::new(),
+            kinds: StringBuilder::new(),
+            currencies: StringBuilder::new(),
+            rows: 0,
+        }
+    }
+
+    pub fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    pub fn push(&mut self, record: &ClientData) {
+        self.ids.append_value(&record.id);
+        self.from_ids.append_value(&record.from_id);
+        self.to_ids.append_value(&record.to_id);
+        self.amounts.append_value(record.amount);
+        self.timestamps.append_option(record.timestamp);
+        self.kinds.append_value(record.kind.as_str());
+        self.currencies
+            .append_option(record.currency.as_ref().map(Currency::as_str));
+        self.rows += 1;
+    }
+
+    /// Appends a delimited line without building a `ClientData` first, the
+    /// strings go from the line buffer right into the column buffers.
+    ///
+    /// Everything gets validated before anything is appended, a broken line
+    /// leaves the builders untouched
+    pub fn push_line(&mut self, line: &str) -> Result<(), IngestError> {
+        let columns = self.layout.columns();
+        let got = line.split('|').count();
+        if got != columns.len() {
+            return Err(IngestError::FieldCount {
+                expected: columns.len(),
+                got,
+            });
+        }
+
+        let mut amount = 0.0;
+        let mut timestamp = None;
+        let mut kind = TransactionKind::default();
+        let mut currency = None;
+        for (column, value) in columns.iter().zip(line.split('|')) {
+            let value = value.trim();
+            match column {
+                Column::Amount => amount = parse_amount(value)?,
+                Column::Timestamp if !value.is_empty() => {
+                    timestamp =
+                        Some(
+                            value
+                                .parse()
+                                .map_err(|source| IngestError::InvalidTimestamp {
+                                    value: value.to_string(),
+                                    source,
+                                })?,
+                        )
+                }
+                Column::Kind => kind = value.parse()?,
+                Column::Currency if !value.is_empty() => {
+                    currency = Some(value.parse::<Currency>()?)
+                }
+                _ => {}
+            }
+        }
+
+        for (column, value) in columns.iter().zip(line.split('|')) {
+            let value = value.trim();
+            match column {
+                Column::Id => self.ids.append_value(value),
+                Column::FromId => self.from_ids.append_value(value),
+                Column::ToId => self.to_ids.append_value(value),
+                Column::Amount => self.amounts.append_value(amount),
+                Column::Timestamp => self.timestamps.append_option(timestamp),
+                Column::Kind => self.kinds.append_value(kind.as_str()),
+                Column::Currency => self
+                    .currencies
+                    .append_option(currency.as_ref().map(Currency::as_str)),
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Turns the rows so far into a batch and starts over
+    pub fn finish(&mut self) -> Result<RecordBatch, ArrowError> {
+        let columns: Vec<ArrayRef> = self
+            .layout
+            .columns()
+            .iter()
+            .map(|column| -> ArrayRef {
+                match column {
+                    Column::Id => Arc::new(self.ids.finish()),
+                    Column::FromId => Arc::new(self.from_ids.finish()),
+                    Column::ToId => Arc::new(self.to_ids.finish()),
+                    Column::Amount => Arc::new(self.amounts.finish()),
+                    Column::Timestamp => Arc::new(self.timestamps.finish()),
+                    Column::Kind => Arc::new(self.kinds.finish()),
+                    Column::Currency => Arc::new(self.currencies.finish()),
+                }
+            })
+            .collect();
+        // push() fills every builder, the ones outside the layout still hold rows
+        self.timestamps = Int64Builder::new();
+        self.kinds = StringBuilder::new();
+        self.currencies = StringBuilder::new();
+        self.rows = 0;
+
+        RecordBatch::try_new(self.schema.clone(), columns)
+    }
+}
+
+/// Reads a delimited file as arrow batches of up to `batch_size` rows.
+///
+/// Broken lines get skipped, same as [`super::open_file`], and counted in
+/// [`BatchReader::dirty`]
+pub struct BatchReader<R = Box<dyn BufRead + Send>> {
+    reader: R,
+    buf: Vec<u8>,
+    line_num: usize,
+    batch_size: usize,
+    builder: Option<BatchBuilder>,
+    dirty: DirtyDataReport,
+}
+
+impl<R: BufRead> BatchReader<R> {
+    pub fn new(reader: R, batch_size: usize) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_num: 0,
+            batch_size: batch_size.max(1),
+            builder: None,
+            dirty: DirtyDataReport::default(),
+        }
+    }
+
+    /// Broken lines of the batches read so far
+    pub fn dirty(&self) -> &DirtyDataReport {
+        &self.dirty
+    }
+
+    /// Schema of the batches, known once the header has been read
+    pub fn schema(&self) -> Option<SchemaRef> {
+        self.builder.as_ref().map(BatchBuilder::schema)
+    }
+}
+
+impl<R: BufRead> Iterator for BatchReader<R> {
+    type Item = Result<RecordBatch, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            let read = match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(read) => read,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if read == 0 {
+                // whatever is left makes the last, shorter batch
+                let builder = self.builder.as_mut().filter(|b| !b.is_empty())?;
+                return Some(builder.finish().map_err(IngestError::from));
+            }
+            self.line_num += 1;
+
+            let line = match decode_line(&self.buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => match self.dirty.absorb(e) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                },
+            };
+            let Some(builder) = self.builder.as_mut() else {
+                match Layout::from_header(line) {
+                    Ok(layout) => self.builder = Some(BatchBuilder::new(&layout)),
+                    Err(e) => return Some(Err(e)),
+                }
+                continue;
+            };
+
+            if let Err(e) = builder.push_line(line) {
+                let e = IngestError::Line {
+                    line_num: self.line_num,
+                    line: line.to_string(),
+                    source: Box::new(e),
+                };
+                match self.dirty.absorb(e) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            if builder.len() == self.batch_size {
+                return Some(builder.finish().map_err(IngestError::from));
+            }
+        }
+    }
+}
+
+/// Streams a (possibly compressed) delimited file as arrow batches
+pub fn open_file_arrow(path: &str, batch_size: usize) -> Result<BatchReader, IngestError> {
+    if Format::from_path(path) != Format::Delimited {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only delimited files can be read as batches",
+        )
+        .into());
+    }
+
+    Ok(BatchReader::new(
+        crate::compression::open_reader(path)?,
+        batch_size,
+    ))
+}
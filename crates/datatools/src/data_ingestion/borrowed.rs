@@ -0,0 +1,160 @@
+use std::io::BufRead;
+
+use super::{
+    ClientData, Column, IngestError, Layout, decode_line, line_too_long, parse_amount, read_line,
+};
+
+/// A record borrowing its strings from the line it was parsed from.
+///
+/// Nothing gets allocated, which is most of what ingestion costs. Only has the
+/// core columns, `to_owned` fills the optional ones with their defaults
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClientDataRef<'a> {
+    pub id: &'a str,
+    pub from_id: &'a str,
+    pub to_id: &'a str,
+    pub amount: f64,
+}
+
+impl ClientDataRef<'_> {
+    pub fn to_owned(&self) -> ClientData {
+        ClientData {
+            id: self.id.to_string(),
+            from_id: self.from_id.to_string(),
+            to_id: self.to_id.to_string(),
+            amount: self.amount,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<ClientDataRef<'_>> for ClientData {
+    fn from(record: ClientDataRef<'_>) -> Self {
+        record.to_owned()
+    }
+}
+
+/// [`super::parse_line_fast`], but borrowing
+pub fn parse_line_ref(line: &str) -> Result<ClientDataRef<'_>, IngestError> {
+    let mut delimiters = memchr::memchr_iter(b'|', line.as_bytes());
+
+    let (Some(first), Some(second), Some(third), None) = (
+        delimiters.next(),
+        delimiters.next(),
+        delimiters.next(),
+        delimiters.next(),
+    ) else {
+        return Err(IngestError::FieldCount {
+            expected: 4,
+            got: memchr::memchr_iter(b'|', line.as_bytes()).count() + 1,
+        });
+    };
+
+    Ok(ClientDataRef {
+        id: line[..first].trim(),
+        from_id: line[first + 1..second].trim(),
+        to_id: line[second + 1..third].trim(),
+        amount: parse_amount(line[third + 1..].trim())?,
+    })
+}
+
+impl Layout {
+    /// Borrowing parse, the optional columns are checked for presence but not parsed
+    pub fn parse_ref<'a>(&self, line: &'a str) -> Result<ClientDataRef<'a>, IngestError> {
+        if *self == Layout::default() {
+            return parse_line_ref(line);
+        }
+
+        let got = line.split(self.delimiter()).count();
+        if got != self.columns().len() {
+            return Err(IngestError::FieldCount {
+                expected: self.columns().len(),
+                got,
+            });
+        }
+
+        let mut record = ClientDataRef {
+            id: "",
+            from_id: "",
+            to_id: "",
+            amount: 0.0,
+        };
+        for (column, value) in self.columns().iter().zip(line.split(self.delimiter())) {
+            let value = value.trim();
+            match column {
+                Column::Id => record.id = value,
+                Column::FromId => record.from_id = value,
+                Column::ToId => record.to_id = value,
+                Column::Amount => record.amount = self.number_format().parse(value)?,
+                Column::Timestamp | Column::Kind | Column::Currency => {}
+            }
+        }
+
+        Ok(record)
+    }
+}
+
+/// Reads records that borrow from one reused line buffer.
+///
+/// Can't be an `Iterator` - every record points into the buffer the next call
+/// overwrites - so it's a `while let Some(record) = reader.next_record()` loop
+pub struct RefReader<R = Box<dyn BufRead + Send>> {
+    reader: R,
+    buf: Vec<u8>,
+    line_num: usize,
+    layout: Layout,
+}
+
+impl<R: BufRead> RefReader<R> {
+    /// The first line is the header, same as [`super::RecordIter::new`]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_num: 0,
+            layout: Layout::default(),
+        }
+    }
+
+    pub fn line_num(&self) -> usize {
+        self.line_num
+    }
+
+    pub fn next_record(&mut self) -> Option<Result<ClientDataRef<'_>, IngestError>> {
+        loop {
+            self.buf.clear();
+            let n = match read_line(&mut self.reader, &mut self.buf) {
+                Ok(0) => return None,
+                Ok(n) => n,
+                Err(e) => return Some(Err(e.into())),
+            };
+            self.line_num += 1;
+            if n > self.buf.len() {
+                return Some(Err(line_too_long(&self.buf, n, self.line_num)));
+            }
+
+            if self.line_num == 1 {
+                match decode_line(&self.buf, 1).and_then(Layout::from_header) {
+                    Ok(layout) => self.layout = layout,
+                    Err(e) => return Some(Err(e)),
+                }
+                continue;
+            }
+
+            let line = match decode_line(&self.buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            return Some(self.layout.parse_ref(line).map_err(|e| IngestError::Line {
+                line_num: self.line_num,
+                line: line.to_string(),
+                source: Box::new(e),
+            }));
+        }
+    }
+}
+
+/// Opens a (possibly compressed) delimited file for borrowed reading
+pub fn open_file_ref(path: &str) -> std::io::Result<RefReader> {
+    Ok(RefReader::new(crate::compression::open_reader(path)?))
+}
@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{BufWriter, Read, Write};
+
+use super::{ClientData, IngestError, open_file};
+
+const MAGIC: &[u8; 8] = b"P01CACHE";
+/// bump whenever `ClientData` or the parsing rules change, old caches are then just misses
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 4 + 8;
+
+/// Where [`open_file_cached`] keeps the parsed records of `path`
+pub fn cache_path(path: &str) -> String {
+    format!("{path}.cache")
+}
+
+/// [`open_file`] with a binary (postcard) copy of the records next to the file.
+///
+/// The cache remembers a hash of the source bytes, a regenerated or edited file
+/// is parsed again and the cache rewritten. Hashing still reads the whole file,
+/// but that's a lot cheaper than parsing it
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_cached(path: &str) -> Result<Vec<ClientData>, IngestError> {
+    let hash = content_hash(path)?;
+    let cache = cache_path(path);
+
+    if let Some(records) = read_cache(&cache, hash) {
+        return Ok(records);
+    }
+
+    let records = open_file(path)?;
+    // a cache we can't write is a slow next run, not an error
+    if let Err(e) = write_cache(&cache, hash, &records) {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(%cache, %e, "failed to write the cache");
+        #[cfg(not(feature = "tracing"))]
+        let _ = e;
+    }
+    Ok(records)
+}
+
+fn content_hash(path: &str) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// `None` for anything that isn't a matching cache: missing, stale, truncated, older version
+fn read_cache(cache: &str, hash: u64) -> Option<Vec<ClientData>> {
+    let bytes = std::fs::read(cache).ok()?;
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return None;
+    }
+
+    let (version, stored) = bytes[MAGIC.len()..HEADER_LEN].split_at(4);
+    if u32::from_le_bytes(version.try_into().ok()?) != VERSION
+        || u64::from_le_bytes(stored.try_into().ok()?) != hash
+    {
+        return None;
+    }
+
+    postcard::from_bytes(&bytes[HEADER_LEN..]).ok()
+}
+
+fn write_cache(cache: &str, hash: u64, records: &[ClientData]) -> std::io::Result<()> {
+    let body = postcard::to_stdvec(records).map_err(std::io::Error::other)?;
+
+    // written aside and renamed, so an interrupted run never leaves half a cache behind
+    let tmp = format!("{cache}.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&hash.to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())?
+        .sync_all()?;
+    std::fs::rename(&tmp, cache)
+}
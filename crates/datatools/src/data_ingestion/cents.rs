@@ -0,0 +1,104 @@
+//! Amounts read straight into integer cents, digit by digit, without a float
+//! in between: exact, and cheaper than `f64::from_str`, which has to get the
+//! nearest float right for any number of digits.
+//!
+//! Only plain decimals like `-1234.56` read, no exponents, `inf` or `NaN`
+//! (money doesn't come like that, a file that does is broken)
+use super::{Column, IngestError, Layout};
+
+/// `value` in cents. Sub-cent digits are rounded half away from zero, an
+/// amount past what `i64` cents hold is an error rather than wrapped
+pub fn parse_cents(value: &str) -> Result<i64, IngestError> {
+    let invalid = |reason| IngestError::InvalidCents {
+        value: value.to_string(),
+        reason,
+    };
+    let bytes = value.as_bytes();
+    let (negative, digits) = match bytes.first() {
+        Some(b'-') => (true, &bytes[1..]),
+        Some(b'+') => (false, &bytes[1..]),
+        _ => (false, bytes),
+    };
+    let (integer, fraction) = match memchr::memchr(b'.', digits) {
+        Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+        None => (digits, &[][..]),
+    };
+    if integer.is_empty() && fraction.is_empty() {
+        return Err(invalid("not a decimal number"));
+    }
+
+    let digit = |b: u8| match b {
+        b'0'..=b'9' => Ok(i64::from(b - b'0')),
+        _ => Err(invalid("not a decimal number")),
+    };
+    let shift = |cents: i64, digit: i64| {
+        cents
+            .checked_mul(10)
+            .and_then(|cents| cents.checked_add(digit))
+            .ok_or_else(|| invalid("too large for i64 cents"))
+    };
+
+    let mut cents = 0;
+    for &b in integer {
+        cents = shift(cents, digit(b)?)?;
+    }
+    // missing cents are zeros, `12.5` is 1250
+    for i in 0..2 {
+        let d = match fraction.get(i) {
+            Some(&b) => digit(b)?,
+            None => 0,
+        };
+        cents = shift(cents, d)?;
+    }
+    // past the cents only the first digit decides, the rest has to be digits
+    if let Some(rest) = fraction.get(2..) {
+        for &b in rest {
+            digit(b)?;
+        }
+        if rest.first().is_some_and(|&b| b >= b'5') {
+            cents = cents
+                .checked_add(1)
+                .ok_or_else(|| invalid("too large for i64 cents"))?;
+        }
+    }
+    Ok(if negative { -cents } else { cents })
+}
+
+/// The amount of one delimited line in cents, the other fields are only
+/// counted, not parsed. A [`super::RecordIter::with_parser`] parser, the
+/// layout's number format doesn't apply
+pub fn parse_line_cents(layout: &Layout, line: &str) -> Result<i64, IngestError> {
+    // the original layout, the amount is the last field. Compared by hand,
+    // a `Layout::default()` per line would allocate
+    let original = [Column::Id, Column::FromId, Column::ToId, Column::Amount];
+    if layout.columns() == original && layout.delimiter() == '|' {
+        let mut delimiters = memchr::memchr_iter(b'|', line.as_bytes());
+        let (Some(_), Some(_), Some(third), None) = (
+            delimiters.next(),
+            delimiters.next(),
+            delimiters.next(),
+            delimiters.next(),
+        ) else {
+            return Err(IngestError::FieldCount {
+                expected: 4,
+                got: memchr::memchr_iter(b'|', line.as_bytes()).count() + 1,
+            });
+        };
+        return parse_cents(line[third + 1..].trim());
+    }
+
+    let columns = layout.columns();
+    let got = line.split(layout.delimiter()).count();
+    if got != columns.len() {
+        return Err(IngestError::FieldCount {
+            expected: columns.len(),
+            got,
+        });
+    }
+    let amount = columns
+        .iter()
+        .position(|&c| c == Column::Amount)
+        .and_then(|i| line.split(layout.delimiter()).nth(i))
+        .expect("every layout has an amount and the field count matched");
+    parse_cents(amount.trim())
+}
@@ -0,0 +1,61 @@
+use super::{ClientData, DirtyDataReport, IngestError, RecordIter};
+
+/// Struct-of-arrays layout, every field lives in its own contiguous `Vec`
+#[derive(Debug, Clone, Default)]
+pub struct ClientColumns {
+    pub ids: Vec<String>,
+    pub from_ids: Vec<String>,
+    pub to_ids: Vec<String>,
+    pub amounts: Vec<f64>,
+}
+
+impl ClientColumns {
+    pub fn len(&self) -> usize {
+        self.amounts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amounts.is_empty()
+    }
+
+    pub fn push(&mut self, record: ClientData) {
+        self.ids.push(record.id);
+        self.from_ids.push(record.from_id);
+        self.to_ids.push(record.to_id);
+        self.amounts.push(record.amount);
+    }
+}
+
+impl FromIterator<ClientData> for ClientColumns {
+    fn from_iter<I: IntoIterator<Item = ClientData>>(iter: I) -> Self {
+        let mut columns = ClientColumns::default();
+        for record in iter {
+            columns.push(record);
+        }
+        columns
+    }
+}
+
+impl From<&[ClientData]> for ClientColumns {
+    fn from(records: &[ClientData]) -> Self {
+        records.iter().cloned().collect()
+    }
+}
+
+/// [`super::open_file`], but straight into columns, the broken lines come back counted
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_columnar(path: &str) -> Result<(ClientColumns, DirtyDataReport), IngestError> {
+    let mut columns = ClientColumns::default();
+    let mut dirty = DirtyDataReport::default();
+
+    for record in RecordIter::open(path)? {
+        match record {
+            Ok(record) => columns.push(record),
+
+            // we decided to skip broken records
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+
+    Ok((columns, dirty))
+}
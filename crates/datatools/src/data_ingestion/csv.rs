@@ -0,0 +1,116 @@
+use std::io::Read;
+
+use csv::{ErrorKind, ReaderBuilder, StringRecord};
+
+use super::{ClientData, Column, IngestError, IngestOptions, Layout, NumberFormat, open_file_with};
+
+/// Record reader backed by the `csv` crate, so quoted fields and escaped
+/// delimiters (`"a|b"`, `"say ""hi"""`) come through intact.
+///
+/// Slower than [`super::RecordIter`], only worth it once the schema grows free-text fields
+pub struct CsvRecordIter<R> {
+    reader: csv::Reader<R>,
+    record: StringRecord,
+    header: bool,
+    layout: Layout,
+}
+
+impl CsvRecordIter<Box<dyn std::io::BufRead + Send>> {
+    /// Opens a plain, `.gz` or `.zst` file, the first line is the header
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        Ok(Self::new(crate::compression::open_reader(path)?))
+    }
+}
+
+impl<R: Read> CsvRecordIter<R> {
+    pub fn new(reader: R) -> Self {
+        let reader = ReaderBuilder::new()
+            .delimiter(b'|')
+            // the header goes through Layout::from_header like everywhere else
+            .has_headers(false)
+            // field count mismatches are ours to report, not a hard csv error
+            .flexible(true)
+            .from_reader(reader);
+
+        Self {
+            reader,
+            record: StringRecord::new(),
+            header: true,
+            layout: Layout::default(),
+        }
+    }
+
+    pub fn has_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// See [`Layout::project`]
+    pub fn project(mut self, columns: &[Column]) -> Self {
+        self.layout = self.layout.project(columns);
+        self
+    }
+
+    /// See [`Layout::with_number_format`]
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.layout = self.layout.with_number_format(number_format);
+        self
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    fn line_error(&self, source: IngestError) -> IngestError {
+        IngestError::Line {
+            line_num: self.record.position().map_or(0, |p| p.line() as usize),
+            line: self.record.iter().collect::<Vec<_>>().join("|"),
+            source: Box::new(source),
+        }
+    }
+}
+
+impl<R: Read> Iterator for CsvRecordIter<R> {
+    type Item = Result<ClientData, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.reader.read_record(&mut self.record) {
+                Ok(false) => return None,
+                Ok(true) => {}
+                // a bad line, not a bad file
+                Err(e) if matches!(e.kind(), ErrorKind::Utf8 { .. }) => {
+                    let line_num = e.position().map_or(0, |p| p.line() as usize);
+                    return Some(Err(IngestError::Line {
+                        line_num,
+                        line: String::new(),
+                        source: Box::new(e.into()),
+                    }));
+                }
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let fields: Vec<&str> = self.record.iter().collect();
+            // by record index, with `\r\n` endings the first data record reports line 1 as well
+            if self.header && self.record.position().is_some_and(|p| p.record() == 0) {
+                match Layout::from_header(&fields.join("|")) {
+                    Ok(layout) => {
+                        self.layout = self.layout.with_header(layout);
+                        continue;
+                    }
+                    Err(e) => return Some(Err(self.line_error(e))),
+                }
+            }
+
+            let record = self.layout.parse_fields(&fields);
+            return Some(record.map_err(|e| self.line_error(e)));
+        }
+    }
+}
+
+/// [`super::open_file`] through the `csv` crate, broken lines are skipped. For
+/// the counts, call [`open_file_with`] with `quoting(true)` directly
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_csv(path: &str) -> Result<Vec<ClientData>, IngestError> {
+    open_file_with(path, IngestOptions::new().quoting(true)).map(|(records, _)| records)
+}
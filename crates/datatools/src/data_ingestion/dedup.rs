@@ -0,0 +1,51 @@
+use std::collections::HashSet;
+
+use super::{ClientData, IngestError};
+
+/// What [`dedup`] does with records whose id was seen before
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DedupStrategy {
+    /// the first one wins, later ones are dropped (a retried upload)
+    #[default]
+    KeepFirst,
+    /// the last one wins, like a correction replacing the original, and sits where it was
+    KeepLast,
+    /// a repeated id is a bug upstream, stop at the first one
+    Error,
+}
+
+/// Drops records with a repeated `id`, everything else keeps its relative order
+pub fn dedup(
+    records: Vec<ClientData>,
+    strategy: DedupStrategy,
+) -> Result<Vec<ClientData>, IngestError> {
+    // decide first, so the ids can be borrowed instead of cloned into the set
+    let keep = {
+        let mut seen = HashSet::with_capacity(records.len());
+        let mut keep = vec![false; records.len()];
+        match strategy {
+            DedupStrategy::KeepFirst | DedupStrategy::Error => {
+                for (i, record) in records.iter().enumerate() {
+                    keep[i] = seen.insert(record.id.as_str());
+                    if !keep[i] && strategy == DedupStrategy::Error {
+                        return Err(IngestError::DuplicateId {
+                            id: record.id.clone(),
+                        });
+                    }
+                }
+            }
+            DedupStrategy::KeepLast => {
+                for (i, record) in records.iter().enumerate().rev() {
+                    keep[i] = seen.insert(record.id.as_str());
+                }
+            }
+        }
+        keep
+    };
+
+    Ok(records
+        .into_iter()
+        .zip(keep)
+        .filter_map(|(record, keep)| keep.then_some(record))
+        .collect())
+}
@@ -0,0 +1,27 @@
+use super::{ClientData, DirtyDataReport, IngestError, RecordIter};
+use crate::page_cache::DirectReader;
+
+/// Same lines as [`super::open_file`], read with `O_DIRECT` so none of it comes
+/// from (or stays in) the page cache, see [`DirectReader`]. The broken ones come
+/// back counted next to the records
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_direct(path: &str) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    crate::compression::require_plain(path)?;
+    let start = crate::Instant::now();
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+
+    for record in RecordIter::new(DirectReader::open(path)?) {
+        match record {
+            Ok(record) => records.push(record),
+
+            // we decided to skip broken records
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!(records = records.len(), "ingested");
+    crate::metrics::record_batch("direct", records.len(), &dirty, start.elapsed());
+
+    Ok((records, dirty))
+}
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{FailureKind, IngestError};
+
+/// Raw lines kept per category by default
+pub const DEFAULT_DIRTY_SAMPLES: usize = 10;
+
+/// Every broken line of one [`FailureKind`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirtyCategory {
+    pub count: usize,
+    /// `None` when none of the failures came with a line
+    pub first_line: Option<usize>,
+    pub last_line: Option<usize>,
+    /// `(line number, raw line)` of the first few
+    pub samples: Vec<(usize, String)>,
+}
+
+/// The lines that didn't parse, by why they didn't, so a dirty file can be
+/// looked into instead of scrolled past on stderr
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirtyDataReport {
+    pub categories: BTreeMap<FailureKind, DirtyCategory>,
+    /// samples kept per category
+    pub max_samples: usize,
+}
+
+impl Default for DirtyDataReport {
+    fn default() -> Self {
+        Self {
+            categories: BTreeMap::new(),
+            max_samples: DEFAULT_DIRTY_SAMPLES,
+        }
+    }
+}
+
+impl DirtyDataReport {
+    pub fn total(&self) -> usize {
+        self.categories
+            .values()
+            .map(|category| category.count)
+            .sum()
+    }
+
+    pub fn count(&self, kind: FailureKind) -> usize {
+        self.categories
+            .get(&kind)
+            .map_or(0, |category| category.count)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.categories.is_empty()
+    }
+
+    pub fn record(&mut self, error: &IngestError) {
+        let category = self.categories.entry(error.kind()).or_default();
+        category.count += 1;
+
+        if let IngestError::Line { line_num, line, .. } = error {
+            category.first_line = Some(category.first_line.map_or(*line_num, |n| n.min(*line_num)));
+            category.last_line = Some(category.last_line.map_or(*line_num, |n| n.max(*line_num)));
+            if category.samples.len() < self.max_samples {
+                category.samples.push((*line_num, line.clone()));
+            }
+        }
+    }
+
+    /// Where the loaders hand their errors: a broken line is counted (and logged
+    /// with the `tracing` feature) and reading goes on, anything else comes back
+    /// to end the read. Nothing in here writes to stderr, the caller decides
+    /// what to tell about the report
+    pub fn absorb(&mut self, error: IngestError) -> Result<(), IngestError> {
+        if !matches!(error, IngestError::Line { .. }) {
+            return Err(error);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::warn!(kind = ?error.kind(), %error, "failed to parse");
+        self.record(&error);
+        Ok(())
+    }
+
+    /// Folds in a report over another part of the same input, e.g. from another thread
+    pub fn merge(&mut self, other: DirtyDataReport) {
+        for (kind, theirs) in other.categories {
+            let ours = self.categories.entry(kind).or_default();
+            ours.count += theirs.count;
+            // None sorts first, so min has to skip it by hand
+            ours.first_line = match (ours.first_line, theirs.first_line) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            ours.last_line = ours.last_line.max(theirs.last_line);
+            ours.samples.extend(theirs.samples);
+            // parts can arrive in any order, keep the earliest lines
+            ours.samples.sort_unstable_by_key(|(line_num, _)| *line_num);
+            ours.samples.truncate(self.max_samples);
+        }
+    }
+}
+
+impl fmt::Display for DirtyDataReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, category) in &self.categories {
+            write!(f, "malformed ({kind:?}): {}", category.count)?;
+            if let (Some(first), Some(last)) = (category.first_line, category.last_line) {
+                write!(f, ", lines {first}..={last}")?;
+            }
+            writeln!(f)?;
+            for (line_num, line) in &category.samples {
+                writeln!(f, "  line {line_num}: {line:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use rust_decimal::Decimal;
+
+use super::{DirtyDataReport, IngestError, RecordIter};
+
+/// Amount type of the exact schema, parsed straight from the text without going through `f64`
+pub trait ExactAmount: Sized + Copy {
+    fn parse_amount(value: &str) -> Result<Self, IngestError>;
+}
+
+impl ExactAmount for Decimal {
+    fn parse_amount(value: &str) -> Result<Self, IngestError> {
+        value.parse().map_err(|source| IngestError::InvalidDecimal {
+            value: value.to_string(),
+            source,
+        })
+    }
+}
+
+/// Integer cents, see [`super::parse_cents`]
+impl ExactAmount for i64 {
+    fn parse_amount(value: &str) -> Result<Self, IngestError> {
+        super::parse_cents(value)
+    }
+}
+
+/// [`super::ClientData`] with a lossless amount
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientDataExact<A> {
+    pub id: String,
+    pub from_id: String,
+    pub to_id: String,
+    pub amount: A,
+}
+
+/// Amount in integer cents
+pub type ClientDataCents = ClientDataExact<i64>;
+
+/// Amount as a 96-bit decimal
+pub type ClientDataDecimal = ClientDataExact<Decimal>;
+
+pub fn parse_line_exact<A: ExactAmount>(line: &str) -> Result<ClientDataExact<A>, IngestError> {
+    let parts: Vec<&str> = line.split('|').collect();
+
+    let [id, from_id, to_id, amount] = parts[..] else {
+        return Err(IngestError::FieldCount {
+            expected: 4,
+            got: parts.len(),
+        });
+    };
+
+    Ok(ClientDataExact {
+        id: id.trim().to_string(),
+        from_id: from_id.trim().to_string(),
+        to_id: to_id.trim().to_string(),
+        amount: A::parse_amount(amount.trim())?,
+    })
+}
+
+/// [`super::open_file`] for the exact schema, broken records are skipped and counted
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_exact<A: ExactAmount>(
+    path: &str,
+) -> Result<(Vec<ClientDataExact<A>>, DirtyDataReport), IngestError> {
+    let reader = crate::compression::open_reader(path)?;
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+
+    for record in RecordIter::with_parser(reader, |_, line| parse_line_exact::<A>(line)) {
+        match record {
+            Ok(record) => records.push(record),
+
+            // we decided to skip broken records
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+
+    Ok((records, dirty))
+}
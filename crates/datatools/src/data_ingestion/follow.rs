@@ -0,0 +1,131 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+
+use super::{ClientData, DirtyDataReport, IngestError, Layout, decode_line};
+use crate::metrics::Metered;
+
+/// Reads a file another process keeps appending to. Every [`Follower::poll`]
+/// parses the complete lines added since the last one, a line that is still
+/// being written is left for the next poll
+#[derive(Debug, Clone)]
+pub struct Follower {
+    path: String,
+    /// end of the last complete line parsed
+    offset: u64,
+    line_num: usize,
+    header: bool,
+    layout: Layout,
+    dirty: DirtyDataReport,
+}
+
+impl Follower {
+    /// Delimited text with a header, like [`super::open_file`]
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            offset: 0,
+            line_num: 0,
+            header: true,
+            layout: Layout::default(),
+            dirty: DirtyDataReport::default(),
+        }
+    }
+
+    /// Headerless input with a known layout
+    pub fn with_layout(path: &str, layout: Layout) -> Self {
+        Self {
+            header: false,
+            layout,
+            ..Self::new(path)
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Where the next poll starts reading
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// 1-based number of the last complete line read (header included)
+    pub fn line_num(&self) -> usize {
+        self.line_num
+    }
+
+    /// Broken lines of every poll so far, they are skipped and counted here
+    pub fn dirty(&self) -> &DirtyDataReport {
+        &self.dirty
+    }
+
+    /// The records appended since the last poll, empty when nothing was. A file
+    /// that got shorter was truncated or replaced and is read again from the start
+    pub fn poll(&mut self) -> Result<Vec<ClientData>, IngestError> {
+        if self.line_num == 0 {
+            crate::compression::require_plain(&self.path)?;
+        }
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(path = %self.path, len, "shrank, reading it again from the start");
+            self.offset = 0;
+            self.line_num = 0;
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let start = crate::Instant::now();
+        let mut reader = BufReader::new(Metered(file.take(len - self.offset)));
+        let mut buf = Vec::new();
+        let mut records = Vec::new();
+        let mut dirty = DirtyDataReport::default();
+        loop {
+            buf.clear();
+            let n = reader.read_until(b'\n', &mut buf)?;
+            // nothing new, or the writer is halfway through a line
+            if n == 0 || !buf.ends_with(b"\n") {
+                break;
+            }
+            self.offset += n as u64;
+            self.line_num += 1;
+
+            let line = match decode_line(&buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => {
+                    dirty.absorb(e)?;
+                    continue;
+                }
+            };
+
+            // header only tells us the column order
+            if self.header && self.line_num == 1 {
+                let header = Layout::from_names(line.split(self.layout.delimiter()));
+                match header {
+                    Ok(header) => self.layout = self.layout.with_header(header),
+                    Err(e) => {
+                        return Err(IngestError::Line {
+                            line_num: self.line_num,
+                            line: line.to_string(),
+                            source: Box::new(e),
+                        });
+                    }
+                }
+                continue;
+            }
+
+            match self.layout.parse(line) {
+                Ok(record) => records.push(record),
+                Err(e) => dirty.absorb(IngestError::Line {
+                    line_num: self.line_num,
+                    line: line.to_string(),
+                    source: Box::new(e),
+                })?,
+            }
+        }
+
+        crate::metrics::record_batch("follow", records.len(), &dirty, start.elapsed());
+        self.dirty.merge(dirty);
+        Ok(records)
+    }
+}
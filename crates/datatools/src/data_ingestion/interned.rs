@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::{ClientData, Currency, DirtyDataReport, IngestError, RecordIter, TransactionKind};
+
+/// Hands out one shared `Arc<str>` per distinct string
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(s) {
+            return existing.clone();
+        }
+        let interned: Arc<str> = Arc::from(s);
+        self.strings.insert(interned.clone());
+        interned
+    }
+
+    /// Distinct strings seen so far
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+/// [`ClientData`] with the account ids shared through an [`Interner`].
+///
+/// Transaction ids are unique anyway, interning those would only add a lookup
+#[derive(Debug, Clone)]
+pub struct ClientDataInterned {
+    pub id: String,
+    pub from_id: Arc<str>,
+    pub to_id: Arc<str>,
+    pub amount: f64,
+    pub timestamp: Option<i64>,
+    pub kind: TransactionKind,
+    pub currency: Option<Currency>,
+}
+
+impl ClientDataInterned {
+    pub fn new(record: ClientData, accounts: &mut Interner) -> Self {
+        Self {
+            from_id: accounts.intern(&record.from_id),
+            to_id: accounts.intern(&record.to_id),
+            id: record.id,
+            amount: record.amount,
+            timestamp: record.timestamp,
+            kind: record.kind,
+            currency: record.currency,
+        }
+    }
+}
+
+/// Records plus the interner their account ids came from
+#[derive(Debug, Clone, Default)]
+pub struct InternedRecords {
+    pub records: Vec<ClientDataInterned>,
+    pub accounts: Interner,
+    /// lines that didn't parse, left out
+    pub dirty: DirtyDataReport,
+}
+
+impl InternedRecords {
+    /// Free, the interner already knows
+    pub fn distinct_accounts(&self) -> usize {
+        self.accounts.len()
+    }
+}
+
+/// [`super::open_file`] with interned account ids
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_interned(path: &str) -> Result<InternedRecords, IngestError> {
+    let mut interned = InternedRecords::default();
+
+    for record in RecordIter::open(path)? {
+        match record {
+            Ok(record) => {
+                let record = ClientDataInterned::new(record, &mut interned.accounts);
+                interned.records.push(record);
+            }
+
+            // we decided to skip broken records
+            Err(e) => interned.dirty.absorb(e)?,
+        }
+    }
+
+    Ok(interned)
+}
@@ -0,0 +1,56 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::IngestError;
+
+/// What a transaction does with the money, files without a `kind` column are all transfers
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TransactionKind {
+    #[default]
+    Transfer,
+    Deposit,
+    Withdrawal,
+    Fee,
+}
+
+impl TransactionKind {
+    pub const ALL: [TransactionKind; 4] = [
+        TransactionKind::Transfer,
+        TransactionKind::Deposit,
+        TransactionKind::Withdrawal,
+        TransactionKind::Fee,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TransactionKind::Transfer => "transfer",
+            TransactionKind::Deposit => "deposit",
+            TransactionKind::Withdrawal => "withdrawal",
+            TransactionKind::Fee => "fee",
+        }
+    }
+}
+
+impl FromStr for TransactionKind {
+    type Err = IngestError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TransactionKind::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == s)
+            .ok_or_else(|| IngestError::InvalidKind {
+                value: s.to_string(),
+            })
+    }
+}
+
+impl fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::Write as _;
+
+use super::{ClientData, IngestError, NumberFormat, parse_line_fast};
+
+/// A column the [`ClientData`] schema knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    Id,
+    FromId,
+    ToId,
+    Amount,
+    Timestamp,
+    Kind,
+    Currency,
+}
+
+impl Column {
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::FromId => "from_id",
+            Column::ToId => "to_id",
+            Column::Amount => "amount",
+            Column::Timestamp => "timestamp",
+            Column::Kind => "kind",
+            Column::Currency => "currency",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "id" => Column::Id,
+            "from_id" => Column::FromId,
+            "to_id" => Column::ToId,
+            "amount" => Column::Amount,
+            "timestamp" => Column::Timestamp,
+            "kind" => Column::Kind,
+            "currency" => Column::Currency,
+            _ => return None,
+        })
+    }
+}
+
+/// Column order of a delimited file, normally read from its header.
+///
+/// Optional columns can't be guessed from the field count alone - a corrupted
+/// delimiter in a 5 column file leaves 4 fields, which would look like a valid
+/// record with everything shifted by one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    columns: Vec<Column>,
+    /// columns that get parsed, `None` for all of them
+    projection: Option<Vec<Column>>,
+    delimiter: char,
+    number_format: NumberFormat,
+}
+
+impl Default for Layout {
+    /// The original `id|from_id|to_id|amount`
+    fn default() -> Self {
+        Self {
+            columns: vec![Column::Id, Column::FromId, Column::ToId, Column::Amount],
+            projection: None,
+            delimiter: '|',
+            number_format: NumberFormat::plain(),
+        }
+    }
+}
+
+impl Layout {
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn contains(&self, column: Column) -> bool {
+        self.columns.contains(&column)
+    }
+
+    /// Appends an optional column, adding one that is already there is a no-op
+    pub fn with(mut self, column: Column) -> Self {
+        if !self.contains(column) {
+            self.columns.push(column);
+        }
+        self
+    }
+
+    /// Only parse these columns, the others stay at their default (empty ids
+    /// allocate nothing) and aren't checked either. The field count still is
+    pub fn project(mut self, columns: &[Column]) -> Self {
+        self.projection = Some(columns.to_vec());
+        self
+    }
+
+    pub fn projection(&self) -> Option<&[Column]> {
+        self.projection.as_deref()
+    }
+
+    /// Field separator of the lines, `|` unless a [`super::sniff`] found another
+    pub fn delimiter(&self) -> char {
+        self.delimiter
+    }
+
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn number_format(&self) -> NumberFormat {
+        self.number_format
+    }
+
+    /// How the amounts are written, anything but the plain format costs the fast path
+    pub fn with_number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// `header`'s columns with the delimiter, number format and
+    /// projection asked for up front, before the header was read
+    pub(crate) fn with_header(&self, header: Layout) -> Self {
+        let layout = Self {
+            delimiter: self.delimiter,
+            number_format: self.number_format,
+            ..header
+        };
+        match self.projection() {
+            Some(columns) => layout.project(columns),
+            None => layout,
+        }
+    }
+
+    /// Reads a pipe-delimited header, the four core columns have to be there
+    pub fn from_header(header: &str) -> Result<Self, IngestError> {
+        Self::from_names(header.split('|'))
+    }
+
+    /// Column names in file order, same checks as [`Layout::from_header`]
+    pub fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, IngestError> {
+        let mut columns = Vec::new();
+        for name in names.into_iter().map(str::trim) {
+            let column = Column::from_name(name)
+                .ok_or_else(|| IngestError::Schema(format!("unknown column `{name}`")))?;
+            if columns.contains(&column) {
+                return Err(IngestError::Schema(format!("duplicate column `{name}`")));
+            }
+            columns.push(column);
+        }
+
+        let layout = Self {
+            columns,
+            ..Self::default()
+        };
+        for required in Layout::default().columns {
+            if !layout.contains(required) {
+                return Err(IngestError::Schema(format!(
+                    "missing column `{}`",
+                    required.name()
+                )));
+            }
+        }
+
+        Ok(layout)
+    }
+
+    pub fn header(&self, delimiter: char) -> String {
+        let names: Vec<_> = self.columns.iter().map(|c| c.name()).collect();
+        names.join(&delimiter.to_string())
+    }
+
+    /// The record as a line [`Layout::parse`] reads back, without the line
+    /// ending. Amounts are written plain and in full whatever the number
+    /// format, text goes in as is: a field holding the delimiter, a line
+    /// break or surrounding whitespace won't come back the same
+    pub fn to_line(&self, record: &ClientData) -> String {
+        let mut line = String::new();
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                line.push(self.delimiter);
+            }
+            match column {
+                Column::Id => line.push_str(&record.id),
+                Column::FromId => line.push_str(&record.from_id),
+                Column::ToId => line.push_str(&record.to_id),
+                // Display is the shortest string that parses back to the same f64
+                Column::Amount => write!(line, "{}", record.amount).unwrap(),
+                Column::Timestamp => {
+                    if let Some(ts) = record.timestamp {
+                        write!(line, "{ts}").unwrap();
+                    }
+                }
+                Column::Kind => line.push_str(record.kind.as_str()),
+                Column::Currency => {
+                    if let Some(currency) = record.currency {
+                        line.push_str(currency.as_str());
+                    }
+                }
+            }
+        }
+        line
+    }
+
+    pub fn parse(&self, line: &str) -> Result<ClientData, IngestError> {
+        // the original layout keeps its dedicated fast path
+        if self.columns.len() == 4 && *self == Layout::default() {
+            return parse_line_fast(line);
+        }
+
+        let parts: Vec<&str> = line.split(self.delimiter).collect();
+        self.parse_fields(&parts)
+    }
+
+    /// Same as [`Layout::parse`] for a line that's already split, e.g. by the `csv` crate
+    pub fn parse_fields(&self, parts: &[&str]) -> Result<ClientData, IngestError> {
+        if parts.len() != self.columns.len() {
+            return Err(IngestError::FieldCount {
+                expected: self.columns.len(),
+                got: parts.len(),
+            });
+        }
+
+        let mut record = ClientData::default();
+        for (column, value) in self.columns.iter().zip(parts.iter().copied()) {
+            if self
+                .projection
+                .as_ref()
+                .is_some_and(|keep| !keep.contains(column))
+            {
+                continue;
+            }
+            let value = value.trim();
+            match column {
+                Column::Id => record.id = value.to_string(),
+                Column::FromId => record.from_id = value.to_string(),
+                Column::ToId => record.to_id = value.to_string(),
+                Column::Amount => record.amount = self.number_format.parse(value)?,
+                Column::Timestamp if value.is_empty() => record.timestamp = None,
+                Column::Timestamp => {
+                    record.timestamp =
+                        Some(
+                            value
+                                .parse()
+                                .map_err(|source| IngestError::InvalidTimestamp {
+                                    value: value.to_string(),
+                                    source,
+                                })?,
+                        )
+                }
+                Column::Kind => record.kind = value.parse()?,
+                Column::Currency if value.is_empty() => record.currency = None,
+                Column::Currency => record.currency = Some(value.parse()?),
+            }
+        }
+
+        Ok(record)
+    }
+}
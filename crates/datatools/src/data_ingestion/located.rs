@@ -0,0 +1,53 @@
+use std::io::BufRead;
+
+use super::{ClientData, DirtyDataReport, IngestError, RecordIter};
+
+/// A record and where it is in the file it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Located<T = ClientData> {
+    /// 1-based, the header is line 1
+    pub line: usize,
+    /// start of the line in the decompressed input. For a plain file that's a
+    /// position to seek to and read the line again
+    pub byte_offset: u64,
+    pub record: T,
+}
+
+/// [`RecordIter::located`]
+pub struct LocatedIter<R, T> {
+    records: RecordIter<R, T>,
+}
+
+impl<R: BufRead, T> LocatedIter<R, T> {
+    pub(crate) fn new(records: RecordIter<R, T>) -> Self {
+        Self { records }
+    }
+}
+
+impl<R: BufRead, T> Iterator for LocatedIter<R, T> {
+    type Item = Result<Located<T>, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let record = self.records.next()?;
+        Some(record.map(|record| Located {
+            line: self.records.line_num(),
+            byte_offset: self.records.byte_offset(),
+            record,
+        }))
+    }
+}
+
+/// [`super::open_file`] with every record's line and byte offset, broken
+/// lines skipped and counted
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_located(path: &str) -> Result<(Vec<Located>, DirtyDataReport), IngestError> {
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+    for record in RecordIter::open(path)?.located() {
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    Ok((records, dirty))
+}
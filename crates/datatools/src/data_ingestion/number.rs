@@ -0,0 +1,152 @@
+use std::fmt;
+
+use super::{IngestError, parse_amount};
+
+/// Symbols [`NumberFormat::currency`] strips off an amount, ISO codes like
+/// `EUR` are recognised by being uppercase letters
+const CURRENCY_SYMBOLS: &str = "$€£¥₹₩₽₺₪₫฿¢";
+
+/// How amounts are written in a file, e.g. `1,234.56` or `1.234,56 €`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberFormat {
+    pub decimal_separator: char,
+    /// between groups of three digits, `None` for no grouping at all
+    pub thousands_separator: Option<char>,
+    /// a currency symbol or ISO code may come before or after the number
+    pub currency: bool,
+}
+
+impl Default for NumberFormat {
+    /// What the generator writes and `f64::from_str` reads, `1234.56`
+    fn default() -> Self {
+        Self::plain()
+    }
+}
+
+impl NumberFormat {
+    pub const fn plain() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: None,
+            currency: false,
+        }
+    }
+
+    /// `1,234.56`
+    pub const fn us() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: Some(','),
+            currency: false,
+        }
+    }
+
+    /// `1.234,56`, what most continental exports look like
+    pub const fn european() -> Self {
+        Self {
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            currency: false,
+        }
+    }
+
+    pub fn currency(mut self, currency: bool) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    pub fn parse(&self, value: &str) -> Result<f64, IngestError> {
+        if *self == Self::plain() {
+            return parse_amount(value);
+        }
+
+        let invalid = || IngestError::InvalidNumber {
+            value: value.to_string(),
+            format: *self,
+        };
+        let number = match self.currency {
+            true => strip_currency(value),
+            false => value.to_string(),
+        };
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, number.strip_prefix('+').unwrap_or(&number)),
+        };
+
+        let (integer, fraction) = match digits.split_once(self.decimal_separator) {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+        let integer = match self.thousands_separator {
+            Some(separator) => ungroup(integer, separator).ok_or_else(invalid)?,
+            None => integer.to_string(),
+        };
+
+        // back to the plain format, whatever is left over fails the float parse
+        let mut plain = String::with_capacity(digits.len() + 1);
+        if negative {
+            plain.push('-');
+        }
+        plain.push_str(&integer);
+        if let Some(fraction) = fraction {
+            plain.push('.');
+            plain.push_str(fraction);
+        }
+        if !plain
+            .bytes()
+            .all(|b| b.is_ascii_digit() || b == b'.' || b == b'-')
+        {
+            return Err(invalid());
+        }
+        plain.parse().map_err(|source| IngestError::InvalidAmount {
+            value: value.to_string(),
+            source,
+        })
+    }
+}
+
+impl fmt::Display for NumberFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let thousands = self.thousands_separator.map_or(String::new(), String::from);
+        write!(f, "1{thousands}234{}56", self.decimal_separator)?;
+        if self.currency {
+            f.write_str(" (with currency)")?;
+        }
+        Ok(())
+    }
+}
+
+/// `-$1,234.56` -> `-1,234.56`, `1.234,56 EUR` -> `1.234,56`
+fn strip_currency(value: &str) -> String {
+    let is_currency =
+        |c: char| c.is_whitespace() || c.is_ascii_uppercase() || CURRENCY_SYMBOLS.contains(c);
+    // the sign can sit on either side of the symbol
+    let (negative, rest) = match value.trim().strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.trim()),
+    };
+    let number = rest.trim_matches(is_currency);
+    match negative {
+        true => format!("-{number}"),
+        false => number.to_string(),
+    }
+}
+
+/// `1,234,567` -> `1234567`, `None` when the groups aren't three digits each.
+/// Ungrouped integers are fine too, plenty of exports only group some columns
+fn ungroup(integer: &str, separator: char) -> Option<String> {
+    let mut groups = integer.split(separator);
+    let first = groups.next()?;
+    let mut ungrouped = first.to_string();
+    let grouped = integer.contains(separator);
+    if grouped && !(1..=3).contains(&first.len()) {
+        return None;
+    }
+    for group in groups {
+        if group.len() != 3 {
+            return None;
+        }
+        ungrouped.push_str(group);
+    }
+    Some(ungrouped)
+}
@@ -0,0 +1,368 @@
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+
+use super::{
+    ClientData, Column, IngestError, IngestReport, NumberFormat, RecordIter, RecoveringReader,
+    SkippedRanges,
+};
+use crate::cancel::CancellationToken;
+use crate::encoding::Encoding;
+use crate::format::Format;
+
+/// What to do with a line that doesn't parse
+pub enum ErrorPolicy<'a> {
+    /// stop at the first broken line and return its error
+    FailFast,
+    /// drop the line without a word (it's still counted in the report)
+    Skip,
+    /// drop the line and log a warning through `tracing` (with the feature on).
+    /// It used to go to stderr, a library has no business writing there
+    Warn,
+    /// drop the line and hand the error to the caller
+    Callback(Box<dyn FnMut(&IngestError) + 'a>),
+}
+
+type Filter<'a> = Box<dyn Fn(&ClientData) -> bool + 'a>;
+
+/// Controls how [`open_file_with`] treats malformed lines
+pub struct IngestOptions<'a> {
+    pub on_error: ErrorPolicy<'a>,
+    /// abort once this fraction of data lines failed, e.g. `0.001` for 0.1%
+    pub max_error_rate: Option<f64>,
+    /// lines to read before the error rate is checked, so one early bad line doesn't abort
+    pub min_lines_for_rate: usize,
+    /// write rejected lines to `<path>.rejects`
+    pub write_rejects: bool,
+    /// read through the `csv` crate, so quoted fields and escaped delimiters work (delimited files only)
+    #[cfg(feature = "csv")]
+    pub quoting: bool,
+    /// records it returns false for are dropped right after parsing
+    pub filter: Option<Filter<'a>>,
+    /// columns to parse, see [`super::Layout::project`]. `None` parses everything
+    pub columns: Option<Vec<Column>>,
+    pub encoding: Encoding,
+    /// how the amounts are written, delimited files only (JSON has its own numbers)
+    pub number_format: NumberFormat,
+    /// read on past a failed read, see [`IngestOptions::recover`]
+    pub recover: bool,
+    /// stops reading early when cancelled, see [`IngestReport::cancelled`]
+    pub cancel: Option<CancellationToken>,
+}
+
+impl Default for IngestOptions<'_> {
+    fn default() -> Self {
+        Self {
+            on_error: ErrorPolicy::Warn,
+            max_error_rate: None,
+            min_lines_for_rate: 1000,
+            write_rejects: false,
+            #[cfg(feature = "csv")]
+            quoting: false,
+            filter: None,
+            columns: None,
+            encoding: Encoding::Auto,
+            number_format: NumberFormat::plain(),
+            recover: false,
+            cancel: None,
+        }
+    }
+}
+
+impl<'a> IngestOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn fail_fast(mut self) -> Self {
+        self.on_error = ErrorPolicy::FailFast;
+        self
+    }
+
+    pub fn skip(mut self) -> Self {
+        self.on_error = ErrorPolicy::Skip;
+        self
+    }
+
+    pub fn warn(mut self) -> Self {
+        self.on_error = ErrorPolicy::Warn;
+        self
+    }
+
+    pub fn on_error(mut self, callback: impl FnMut(&IngestError) + 'a) -> Self {
+        self.on_error = ErrorPolicy::Callback(Box::new(callback));
+        self
+    }
+
+    pub fn max_error_rate(mut self, rate: f64) -> Self {
+        self.max_error_rate = Some(rate);
+        self
+    }
+
+    pub fn min_lines_for_rate(mut self, lines: usize) -> Self {
+        self.min_lines_for_rate = lines;
+        self
+    }
+
+    pub fn write_rejects(mut self, write_rejects: bool) -> Self {
+        self.write_rejects = write_rejects;
+        self
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn quoting(mut self, quoting: bool) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Keep only the records `predicate` accepts, e.g. `|r| r.amount > 10_000.0`.
+    /// They're dropped before the `Vec` ever holds them
+    pub fn filter(mut self, predicate: impl Fn(&ClientData) -> bool + 'a) -> Self {
+        self.filter = Some(Box::new(predicate));
+        self
+    }
+
+    /// Only parse `columns` of a delimited file, the rest of each record is left
+    /// empty. The filter sees the projected record, so it needs its columns in here too
+    pub fn project(mut self, columns: &[Column]) -> Self {
+        self.columns = Some(columns.to_vec());
+        self
+    }
+
+    /// Transcode from `encoding` instead of detecting it, needed for Latin-1
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// e.g. `NumberFormat::european().currency(true)` for `1.234,56 €`
+    pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+        self.number_format = number_format;
+        self
+    }
+
+    /// Get past reads that fail instead of returning their error: the broken
+    /// line and the bytes up to the next newline a block further on are
+    /// skipped and land in [`IngestReport::skipped_ranges`]. Line numbers
+    /// after a skip count on from the last line before it
+    pub fn recover(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Stop before the next record once `token` is cancelled, with the
+    /// records read so far
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    fn rate_exceeded(&self, report: &IngestReport) -> Option<f64> {
+        let threshold = self.max_error_rate?;
+        if report.lines < self.min_lines_for_rate {
+            return None;
+        }
+
+        let rate = report.malformed_total() as f64 / report.lines as f64;
+        (rate > threshold).then_some(rate)
+    }
+}
+
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options)))]
+pub fn open_file_with(
+    path: &str,
+    options: IngestOptions,
+) -> Result<(Vec<ClientData>, IngestReport), IngestError> {
+    let mut records = Vec::new();
+    let report = open_into(path, options, |record| records.push(record))?;
+    Ok((records, report))
+}
+
+/// [`open_file_with`] without the `Vec`, every record that makes it through
+/// goes to `accept`. The rejects, the recovery and the policy are the same
+pub(super) fn open_into(
+    path: &str,
+    options: IngestOptions,
+    accept: impl FnMut(ClientData),
+) -> Result<IngestReport, IngestError> {
+    let mut report = IngestReport::default();
+    let mut rejects = if options.write_rejects {
+        let rejects_path = format!("{path}.rejects");
+        let writer = BufWriter::new(File::create(&rejects_path)?);
+        report.rejects_path = Some(rejects_path);
+        Some(writer)
+    } else {
+        None
+    };
+
+    let (reader, skipped) = match options.recover {
+        true => {
+            let (reader, skipped) = super::open_recovering(path)?;
+            (reader, Some(skipped))
+        }
+        false => (crate::compression::open_reader(path)?, None),
+    };
+    let source = records(reader, Format::from_path(path), &options)?;
+    let report = ingest(source, options, report, skipped, rejects.as_mut(), accept)?;
+    if let Some(mut writer) = rejects {
+        writer.flush()?;
+    }
+    Ok(report)
+}
+
+/// [`open_file_with`] for input that isn't a file, e.g. an upload held in
+/// memory. Compression is detected from the magic bytes like for files,
+/// `write_rejects` is ignored since there's no path to write them next to
+pub fn read_with(
+    reader: Box<dyn BufRead + Send>,
+    format: Format,
+    options: IngestOptions,
+) -> Result<(Vec<ClientData>, IngestReport), IngestError> {
+    let reader = crate::compression::decompress(reader)?;
+    // no seeking in a stream, a failed read ends it
+    let (reader, skipped): (Box<dyn BufRead + Send>, _) = match options.recover {
+        true => {
+            let reader = RecoveringReader::new(reader);
+            let skipped = reader.skipped();
+            (Box::new(reader), Some(skipped))
+        }
+        false => (reader, None),
+    };
+    let source = records(reader, format, &options)?;
+    let mut records = Vec::new();
+    let report = ingest(
+        source,
+        options,
+        IngestReport::default(),
+        skipped,
+        None::<&mut BufWriter<File>>,
+        |record| records.push(record),
+    )?;
+    Ok((records, report))
+}
+
+type Records = Box<dyn Iterator<Item = Result<ClientData, IngestError>>>;
+
+/// The records of a decompressed stream, parsed the way `options` asks for
+fn records(
+    reader: Box<dyn BufRead + Send>,
+    format: Format,
+    options: &IngestOptions,
+) -> std::io::Result<Records> {
+    // no projection at all, even one of every column would cost the default layout its fast path
+    let columns = options.columns.as_deref();
+    #[cfg(feature = "csv")]
+    if options.quoting && format == Format::Delimited {
+        // transcoded like the line-based path, the csv crate only reads UTF-8
+        let reader = crate::encoding::decode(reader, options.encoding)?;
+        let iter = super::CsvRecordIter::new(reader).number_format(options.number_format);
+        return Ok(Box::new(match columns {
+            Some(columns) => iter.project(columns),
+            None => iter,
+        }));
+    }
+
+    let iter = RecordIter::from_reader(reader, format, options.encoding)?
+        .number_format(options.number_format);
+    Ok(Box::new(match columns {
+        Some(columns) => iter.project(columns),
+        None => iter,
+    }))
+}
+
+/// Everything after opening: the error policy, the rate limit, the filter
+/// and the report, rejected lines go to `rejects` and what a
+/// [`RecoveringReader`] read past comes out of `skipped`. The records that
+/// get through go to `accept`
+fn ingest(
+    source: Records,
+    mut options: IngestOptions,
+    mut report: IngestReport,
+    skipped: Option<SkippedRanges>,
+    mut rejects: Option<&mut impl Write>,
+    mut accept: impl FnMut(ClientData),
+) -> Result<IngestReport, IngestError> {
+    let start = crate::Instant::now();
+    for record in source {
+        if let Some(token) = &options.cancel
+            && token.is_cancelled()
+        {
+            report.cancelled = true;
+            break;
+        }
+        let error = match record {
+            Ok(record) => {
+                report.lines += 1;
+                match &options.filter {
+                    Some(keep) if !keep(&record) => report.filtered += 1,
+                    _ => {
+                        report.records += 1;
+                        accept(record);
+                    }
+                }
+                continue;
+            }
+            Err(e @ IngestError::Line { .. }) => e,
+            Err(e) => return Err(e),
+        };
+
+        report.lines += 1;
+        report.record_failure(&error);
+        #[cfg(feature = "tracing")]
+        tracing::debug!(kind = ?error.kind(), %error, "broken line");
+        if let (
+            Some(writer),
+            IngestError::Line {
+                line_num,
+                line,
+                source,
+            },
+        ) = (rejects.as_mut(), &error)
+        {
+            writeln!(writer, "{line_num}\t{source}\t{line}")?;
+        }
+
+        match &mut options.on_error {
+            ErrorPolicy::FailFast => return Err(error),
+            ErrorPolicy::Skip => {}
+            #[cfg(feature = "tracing")]
+            ErrorPolicy::Warn => tracing::warn!(%error, "failed to parse"),
+            #[cfg(not(feature = "tracing"))]
+            ErrorPolicy::Warn => {}
+            ErrorPolicy::Callback(callback) => callback(&error),
+        }
+
+        if let Some(rate) = options.rate_exceeded(&report) {
+            return Err(IngestError::ErrorRateExceeded {
+                rate,
+                threshold: options.max_error_rate.unwrap_or_default(),
+                lines: report.lines,
+            });
+        }
+    }
+
+    if let Some(skipped) = skipped {
+        report.skipped_ranges = skipped.get();
+        #[cfg(feature = "tracing")]
+        if matches!(options.on_error, ErrorPolicy::Warn) {
+            for range in &report.skipped_ranges {
+                tracing::warn!(%range, "skipped");
+            }
+        }
+    }
+    crate::metrics::record_batch(
+        "open_file",
+        report.lines - report.malformed_total(),
+        &report.dirty,
+        start.elapsed(),
+    );
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        lines = report.lines,
+        records = report.records,
+        malformed = report.malformed_total(),
+        filtered = report.filtered,
+        cancelled = report.cancelled,
+        "ingested"
+    );
+    Ok(report)
+}
@@ -0,0 +1,255 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+
+use super::{ClientData, DirtyDataReport, IngestError, Layout, RecordIter, decode_line};
+use crate::metrics::Metered;
+
+/// Parses the file in newline-aligned chunks across the rayon pool, records keep file order.
+/// The broken lines come back counted, their line numbers count from the start of their chunk
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_parallel(path: &str) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    #[cfg(feature = "object_store")]
+    if crate::remote::is_object_uri(path) {
+        return open_remote_parallel(path);
+    }
+
+    let chunks = split_chunks(path)?;
+    let layout = read_layout(path)?;
+
+    #[cfg(feature = "tracing")]
+    let parent = tracing::Span::current();
+    let parsed = chunks
+        .par_iter()
+        .map(|&(start, end)| {
+            // rayon's threads don't know the span we're in
+            #[cfg(feature = "tracing")]
+            let _parent = parent.enter();
+            parse_chunk(path, start, end, &layout)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(concat(parsed))
+}
+
+/// Same as [`open_file_parallel`], but chunks are appended as soon as they finish
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_parallel_unordered(
+    path: &str,
+) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    // ranged GETs are all over the network anyway, the ordered version costs nothing more
+    #[cfg(feature = "object_store")]
+    if crate::remote::is_object_uri(path) {
+        return open_remote_parallel(path);
+    }
+
+    let chunks = split_chunks(path)?;
+    let layout = read_layout(path)?;
+    let parsed = Mutex::new((Vec::new(), DirtyDataReport::default()));
+
+    #[cfg(feature = "tracing")]
+    let parent = tracing::Span::current();
+    chunks.par_iter().try_for_each(|&(start, end)| {
+        #[cfg(feature = "tracing")]
+        let _parent = parent.enter();
+        let (chunk, dirty) = parse_chunk(path, start, end, &layout)?;
+        let (records, chunks_dirty) = &mut *parsed.lock().unwrap();
+        records.extend(chunk);
+        chunks_dirty.merge(dirty);
+        Ok::<_, IngestError>(())
+    })?;
+
+    Ok(parsed.into_inner().unwrap())
+}
+
+/// The chunks' records end to end, and their broken lines in one report
+fn concat(parsed: Vec<(Vec<ClientData>, DirtyDataReport)>) -> (Vec<ClientData>, DirtyDataReport) {
+    let mut records = Vec::with_capacity(parsed.iter().map(|(chunk, _)| chunk.len()).sum());
+    let mut dirty = DirtyDataReport::default();
+    for (chunk, chunk_dirty) in parsed {
+        records.extend(chunk);
+        dirty.merge(chunk_dirty);
+    }
+    (records, dirty)
+}
+
+/// Byte ranges covering the file, every range starts right after a newline
+fn split_chunks(path: &str) -> std::io::Result<Vec<(u64, u64)>> {
+    crate::compression::require_plain(path)?;
+    let file_len = File::open(path)?.metadata()?.len();
+    // a few chunks per thread so a slow one doesn't stall the rest
+    let chunk_count = (rayon::current_num_threads() * 4) as u64;
+    let chunk_size = (file_len / chunk_count).max(1);
+
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut boundaries = vec![0];
+    let mut skipped = Vec::new();
+
+    for i in 1..chunk_count {
+        let guess = i * chunk_size;
+        if guess <= *boundaries.last().unwrap() {
+            continue;
+        }
+
+        // move the boundary forward to the start of the next line
+        reader.seek(SeekFrom::Start(guess))?;
+        skipped.clear();
+        let boundary = guess + reader.read_until(b'\n', &mut skipped)? as u64;
+        if boundary >= file_len {
+            break;
+        }
+        boundaries.push(boundary);
+    }
+    boundaries.push(file_len);
+    #[cfg(feature = "tracing")]
+    tracing::debug!(bytes = file_len, chunks = boundaries.len() - 1, "split");
+
+    Ok(boundaries.windows(2).map(|w| (w[0], w[1])).collect())
+}
+
+/// Chunks past the first one never see the header, so it's read upfront. A header
+/// that isn't one is an error, same as in `sort_into`
+fn read_layout(path: &str) -> Result<Layout, IngestError> {
+    let mut header = Vec::new();
+    if BufReader::new(File::open(path)?).read_until(b'\n', &mut header)? == 0 {
+        return Ok(Layout::default());
+    }
+
+    decode_line(&header, 1).and_then(Layout::from_header)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", name = "chunk", skip(path, layout))
+)]
+fn parse_chunk(
+    path: &str,
+    start: u64,
+    end: u64,
+    layout: &Layout,
+) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(start))?;
+    parse_lines(
+        BufReader::new(Metered(file.take(end - start))),
+        start,
+        layout,
+    )
+}
+
+fn parse_lines<R: BufRead>(
+    reader: R,
+    start: u64,
+    layout: &Layout,
+) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    // only the first chunk carries the header
+    let lines = if start == 0 {
+        RecordIter::new(reader)
+    } else {
+        RecordIter::with_layout(reader, layout.clone())
+    };
+
+    let started = crate::Instant::now();
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+    for record in lines {
+        match record {
+            Ok(record) => records.push(record),
+
+            // we decided to skip broken records, line numbers are relative to the chunk
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    crate::metrics::record_batch("parallel", records.len(), &dirty, started.elapsed());
+    #[cfg(feature = "tracing")]
+    tracing::debug!(records = records.len(), "parsed");
+
+    Ok((records, dirty))
+}
+
+/// How far a chunk reads past its end at a time looking for the end of its last line
+#[cfg(feature = "object_store")]
+const OVERFETCH: u64 = 64 * 1024;
+
+/// [`open_file_parallel`] for an object in a bucket, every chunk is its own
+/// ranged GET so nothing is downloaded twice or kept beyond its chunk
+#[cfg(feature = "object_store")]
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+fn open_remote_parallel(uri: &str) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    let file = crate::remote::RemoteFile::open(uri)?;
+    let chunk_count = (rayon::current_num_threads() * 4) as u64;
+    let chunk_size = (file.len() / chunk_count).max(1);
+    let chunks: Vec<(u64, u64)> = (0..file.len())
+        .step_by(chunk_size as usize)
+        .map(|start| (start, (start + chunk_size).min(file.len())))
+        .collect();
+
+    let head = file.read_range(0..OVERFETCH)?;
+    if let Some(compression) = crate::compression::Compression::from_magic(&head) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{uri} is {compression:?} compressed, ranged reads need a plain object"),
+        )
+        .into());
+    }
+    let layout = match head.split(|&b| b == b'\n').next() {
+        Some(header) if !header.is_empty() => {
+            decode_line(header, 1).and_then(Layout::from_header)?
+        }
+        _ => Layout::default(),
+    };
+
+    #[cfg(feature = "tracing")]
+    let parent = tracing::Span::current();
+    let parsed = chunks
+        .par_iter()
+        .map(|&(start, end)| {
+            #[cfg(feature = "tracing")]
+            let _chunk = tracing::debug_span!(parent: &parent, "chunk", start, end).entered();
+            let lines = read_remote_chunk(&file, start, end)?;
+            parse_lines(lines.as_slice(), start, &layout)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(concat(parsed))
+}
+
+/// The lines starting in `start..end`, the chunks split the object at arbitrary
+/// bytes. The byte before `start` tells whether a line starts right at it,
+/// the last line is finished with further reads past `end`
+#[cfg(feature = "object_store")]
+fn read_remote_chunk(
+    file: &crate::remote::RemoteFile,
+    start: u64,
+    end: u64,
+) -> std::io::Result<Vec<u8>> {
+    let bytes = file.read_range(start.saturating_sub(1)..end)?;
+    let mut lines = match start {
+        0 => bytes.to_vec(),
+        // the previous chunk owns whatever line is running into this one
+        _ => match memchr::memchr(b'\n', &bytes) {
+            Some(newline) => bytes[newline + 1..].to_vec(),
+            None => return Ok(Vec::new()),
+        },
+    };
+
+    // the line starting in this chunk but not ending in it, if any
+    if lines.is_empty() || lines.ends_with(b"\n") {
+        return Ok(lines);
+    }
+    let mut at = end;
+    while at < file.len() {
+        let more = file.read_range(at..at + OVERFETCH)?;
+        match memchr::memchr(b'\n', &more) {
+            Some(newline) => {
+                lines.extend_from_slice(&more[..=newline]);
+                break;
+            }
+            None => lines.extend_from_slice(&more),
+        }
+        at += OVERFETCH;
+    }
+    Ok(lines)
+}
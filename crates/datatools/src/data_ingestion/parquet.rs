@@ -0,0 +1,81 @@
+use std::fs::File;
+
+use arrow_array::{Array, Float64Array, Int64Array, RecordBatch, StringArray};
+use parquet::arrow::arrow_reader::{ParquetRecordBatchReader, ParquetRecordBatchReaderBuilder};
+
+use super::{ClientData, IngestError, TransactionKind};
+
+/// Reads a parquet file back into records, batch by batch
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_parquet(path: &str) -> Result<Vec<ClientData>, IngestError> {
+    // the footer is at the end and the row groups all over, simplest to fetch it whole
+    #[cfg(feature = "object_store")]
+    if crate::remote::is_object_uri(path) {
+        let file = crate::remote::RemoteFile::open(path)?;
+        let bytes = file.read_range(0..file.len())?;
+        return read_batches(ParquetRecordBatchReaderBuilder::try_new(bytes)?.build()?);
+    }
+
+    let file = File::open(path)?;
+    crate::metrics::record_bytes(file.metadata()?.len());
+    read_batches(ParquetRecordBatchReaderBuilder::try_new(file)?.build()?)
+}
+
+fn read_batches(reader: ParquetRecordBatchReader) -> Result<Vec<ClientData>, IngestError> {
+    let mut records = Vec::new();
+
+    for batch in reader {
+        let batch = batch?;
+        let ids = string_column(&batch, "id")?;
+        let from_ids = string_column(&batch, "from_id")?;
+        let to_ids = string_column(&batch, "to_id")?;
+        let amounts = batch
+            .column_by_name("amount")
+            .and_then(|c| c.as_any().downcast_ref::<Float64Array>())
+            .ok_or_else(|| IngestError::Schema("expected Float64 column `amount`".into()))?;
+        // optional, older files don't have it
+        let timestamps = batch
+            .column_by_name("timestamp")
+            .and_then(|c| c.as_any().downcast_ref::<Int64Array>());
+        let kinds = match batch.column_by_name("kind") {
+            Some(_) => Some(string_column(&batch, "kind")?),
+            None => None,
+        };
+        let currencies = match batch.column_by_name("currency") {
+            Some(_) => Some(string_column(&batch, "currency")?),
+            None => None,
+        };
+
+        records.reserve(batch.num_rows());
+        for row in 0..batch.num_rows() {
+            records.push(ClientData {
+                id: ids.value(row).to_string(),
+                from_id: from_ids.value(row).to_string(),
+                to_id: to_ids.value(row).to_string(),
+                amount: amounts.value(row),
+                timestamp: timestamps
+                    .filter(|ts| ts.is_valid(row))
+                    .map(|ts| ts.value(row)),
+                kind: match kinds {
+                    Some(kinds) => kinds.value(row).parse()?,
+                    None => TransactionKind::default(),
+                },
+                currency: match currencies {
+                    Some(currencies) if currencies.is_valid(row) => {
+                        Some(currencies.value(row).parse()?)
+                    }
+                    _ => None,
+                },
+            });
+        }
+    }
+
+    Ok(records)
+}
+
+fn string_column<'a>(batch: &'a RecordBatch, name: &str) -> Result<&'a StringArray, IngestError> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<StringArray>())
+        .ok_or_else(|| IngestError::Schema(format!("expected Utf8 column `{name}`")))
+}
@@ -0,0 +1,202 @@
+use std::io::BufRead;
+
+use crossbeam_channel::{Receiver, Sender, bounded};
+
+use super::{
+    ClientData, DirtyDataReport, IngestError, IngestReport, Layout, decode_line, parse_json_line,
+};
+use crate::compression::open_reader;
+use crate::format::Format;
+
+/// Knobs for [`pipeline`]
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    /// parser threads, the reader and the consumer get one each on top
+    pub parsers: usize,
+    /// lines per message, one allocation per batch instead of one per line
+    pub batch_lines: usize,
+    /// batches in flight per channel before the sender blocks
+    pub capacity: usize,
+}
+
+impl Default for PipelineOptions {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+        Self {
+            parsers: threads.saturating_sub(2).max(1),
+            batch_lines: 8192,
+            capacity: 16,
+        }
+    }
+}
+
+impl PipelineOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parsers(mut self, parsers: usize) -> Self {
+        self.parsers = parsers;
+        self
+    }
+
+    pub fn batch_lines(mut self, batch_lines: usize) -> Self {
+        self.batch_lines = batch_lines;
+        self
+    }
+
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+}
+
+/// Raw lines on their way to a parser, with the number of the first one
+struct Batch {
+    first_line: usize,
+    bytes: Vec<u8>,
+}
+
+type Parse = fn(&Layout, &str) -> Result<ClientData, IngestError>;
+
+/// Reader thread -> parser threads -> `consume` on the calling thread, over
+/// bounded channels so a slow stage holds the others back instead of piling
+/// up batches. Batches arrive in whatever order the parsers finish them.
+///
+/// Plain or compressed, delimited or JSON Lines. Broken lines are counted in
+/// the report, not printed
+pub fn pipeline(
+    path: &str,
+    options: &PipelineOptions,
+    mut consume: impl FnMut(Vec<ClientData>),
+) -> Result<IngestReport, IngestError> {
+    let format = Format::from_path(path);
+    let parse: Parse = match format {
+        Format::Delimited => Layout::parse,
+        Format::JsonLines => |_, line| parse_json_line(line),
+        Format::Parquet => {
+            return Err(IngestError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "parquet is not line-based, use open_file_parquet",
+            )));
+        }
+    };
+
+    let mut reader = open_reader(path)?;
+    let mut layout = Layout::default();
+    let mut first_line = 1;
+    if format == Format::Delimited {
+        let mut header = Vec::new();
+        if reader.read_until(b'\n', &mut header)? > 0 {
+            layout = decode_line(&header, 1).and_then(Layout::from_header)?;
+            first_line = 2;
+        }
+    }
+
+    let (batch_tx, batch_rx) = bounded(options.capacity);
+    let (records_tx, records_rx) = bounded(options.capacity);
+
+    std::thread::scope(|scope| {
+        let batch_lines = options.batch_lines.max(1);
+        let reader = scope.spawn(move || read_batches(reader, first_line, batch_lines, batch_tx));
+        let parsers: Vec<_> = (0..options.parsers.max(1))
+            .map(|_| {
+                let (batch_rx, records_tx) = (batch_rx.clone(), records_tx.clone());
+                let layout = &layout;
+                scope.spawn(move || parse_batches(batch_rx, records_tx, layout, parse))
+            })
+            .collect();
+        // the parsers hold the only ends left, the loop ends when they're done
+        drop((batch_rx, records_tx));
+
+        for records in records_rx {
+            consume(records);
+        }
+
+        let mut report = IngestReport {
+            lines: reader.join().expect("pipeline reader panicked")?,
+            ..IngestReport::default()
+        };
+        for parser in parsers {
+            let parsed = parser.join().expect("pipeline parser panicked");
+            report.records += parsed.records;
+            report.dirty.merge(parsed.dirty);
+        }
+        Ok(report)
+    })
+}
+
+/// [`pipeline`] collecting everything, records are not in file order
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options)))]
+pub fn open_file_pipelined(
+    path: &str,
+    options: &PipelineOptions,
+) -> Result<Vec<ClientData>, IngestError> {
+    let mut records = Vec::new();
+    pipeline(path, options, |batch| records.extend(batch))?;
+    Ok(records)
+}
+
+/// Returns the number of lines read
+fn read_batches(
+    mut reader: impl BufRead,
+    mut first_line: usize,
+    batch_lines: usize,
+    batches: Sender<Batch>,
+) -> std::io::Result<usize> {
+    let mut lines = 0;
+    loop {
+        // whole lines appended into one buffer, the parser splits them again
+        let mut bytes = Vec::with_capacity(batch_lines * 64);
+        let mut count = 0;
+        while count < batch_lines && reader.read_until(b'\n', &mut bytes)? > 0 {
+            count += 1;
+        }
+        if count == 0 {
+            return Ok(lines);
+        }
+        lines += count;
+
+        // every parser gone means every receiver is, nobody left to read for
+        if batches.send(Batch { first_line, bytes }).is_err() {
+            return Ok(lines);
+        }
+        first_line += count;
+    }
+}
+
+fn parse_batches(
+    batches: Receiver<Batch>,
+    records: Sender<Vec<ClientData>>,
+    layout: &Layout,
+    parse: Parse,
+) -> IngestReport {
+    let mut report = IngestReport::default();
+    for batch in batches {
+        let start = crate::Instant::now();
+        let mut dirty = DirtyDataReport::default();
+        let mut parsed = Vec::with_capacity(batch.bytes.len() / 64);
+        for (i, line) in batch.bytes.split_inclusive(|&b| b == b'\n').enumerate() {
+            let line_num = batch.first_line + i;
+            let record = decode_line(line, line_num).and_then(|line| {
+                parse(layout, line).map_err(|e| IngestError::Line {
+                    line_num,
+                    line: line.to_string(),
+                    source: Box::new(e),
+                })
+            });
+            match record {
+                Ok(record) => parsed.push(record),
+                Err(e) => dirty.record(&e),
+            }
+        }
+
+        crate::metrics::record_batch("pipeline", parsed.len(), &dirty, start.elapsed());
+        report.records += parsed.len();
+        report.dirty.merge(dirty);
+        if records.send(parsed).is_err() {
+            break;
+        }
+    }
+    report
+}
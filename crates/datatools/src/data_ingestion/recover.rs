@@ -0,0 +1,285 @@
+//! Getting past a bad block instead of giving up on the file: a read that
+//! fails is retried, then reading picks up again a block further on, at the
+//! first newline from there. The line the failure cut into and everything up
+//! to that newline are dropped whole, so no record is ever glued together
+//! from both sides of the hole, and the byte range shows up in the report.
+//!
+//! Broken lines were already skipped one at a time, what this adds is the
+//! reads that fail (a bad sector, a truncated network mount). A compressed
+//! stream can't be picked up in the middle, there the records before the
+//! failure are kept and the rest is given up on.
+use std::fmt;
+use std::io::{BufRead, Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+
+use super::MAX_LINE_BYTES;
+use crate::compression::Compression;
+
+/// Reading picks up again on the next multiple of this past a failure, a
+/// page, so a bad sector costs about what it holds
+pub const SKIP_STEP: u64 = 4096;
+
+/// Tries of a read before its bytes count as lost
+const READ_ATTEMPTS: usize = 3;
+
+const READ_CHUNK: usize = 64 * 1024;
+
+/// Input that [`RecoveringReader`] read past, in bytes from the start of the
+/// (decompressed) input, like [`super::RecordIter::byte_offset`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRange {
+    /// where the line the failure cut into starts
+    pub start: u64,
+    /// just past the newline reading picked up at, `None` when the rest of
+    /// the input was given up on
+    pub end: Option<u64>,
+    /// the read error that started it
+    pub error: String,
+}
+
+impl SkippedRange {
+    /// `None` for a range that goes to the end of the input
+    pub fn len(&self) -> Option<u64> {
+        self.end.map(|end| end - self.start)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+}
+
+impl fmt::Display for SkippedRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "bytes {}..{end}", self.start)?,
+            None => write!(f, "bytes {}.. to the end", self.start)?,
+        }
+        write!(f, " after a read error: {}", self.error)
+    }
+}
+
+/// The ranges a [`RecoveringReader`] skipped, still filled in while the
+/// reader is in use somewhere else
+#[derive(Debug, Clone, Default)]
+pub struct SkippedRanges(Arc<Mutex<Vec<SkippedRange>>>);
+
+impl SkippedRanges {
+    pub fn get(&self) -> Vec<SkippedRange> {
+        self.0.lock().expect("skipped ranges poisoned").clone()
+    }
+
+    fn push(&self, range: SkippedRange) {
+        self.0.lock().expect("skipped ranges poisoned").push(range);
+    }
+}
+
+type SeekFn<R> = fn(&mut R, SeekFrom) -> std::io::Result<u64>;
+
+/// A [`BufRead`] that only hands out whole lines (a last one without a
+/// newline at the end of the input too), holding the rest back until its
+/// newline is read. That's what lets a failed read drop the line it cut into.
+///
+/// Over a seekable reader, see [`RecoveringReader::seekable`], reading goes on
+/// past the failure, otherwise the input ends there
+pub struct RecoveringReader<R> {
+    inner: R,
+    seek: Option<SeekFn<R>>,
+    buf: Vec<u8>,
+    /// handed out up to here
+    pos: usize,
+    /// can be handed out up to here, just past the last newline in `buf`
+    released: usize,
+    /// where `buf` starts in the input
+    offset: u64,
+    eof: bool,
+    skipped: SkippedRanges,
+}
+
+impl<R: Read> RecoveringReader<R> {
+    /// For a stream that can't jump ahead, e.g. a decompressor: a failed
+    /// read ends the input, the whole lines before it are kept
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            seek: None,
+            buf: Vec::new(),
+            pos: 0,
+            released: 0,
+            offset: 0,
+            eof: false,
+            skipped: SkippedRanges::default(),
+        }
+    }
+
+    /// The ranges skipped so far, cloned before the reader goes off into a
+    /// [`super::RecordIter`]
+    pub fn skipped(&self) -> SkippedRanges {
+        self.skipped.clone()
+    }
+
+    /// Releases the bytes of `buf[from..]` up to the last newline in there, or
+    /// all of them once the line is past what a record can be
+    fn release(&mut self, from: usize) {
+        match memchr::memrchr(b'\n', &self.buf[from..]) {
+            Some(end) => self.released = from + end + 1,
+            None if self.buf.len() - self.released > MAX_LINE_BYTES => {
+                self.released = self.buf.len();
+            }
+            None => {}
+        }
+    }
+
+    /// Drops the line a failed read cut into and finds the next one
+    fn resync(&mut self, error: std::io::Error) -> std::io::Result<()> {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(offset = self.offset, %error, "read failed, skipping ahead");
+        let start = self.offset;
+        let mut at = self.offset + self.buf.len() as u64;
+        self.buf.clear();
+        let error = error.to_string();
+
+        let Some(seek) = self.seek else {
+            self.skipped.push(SkippedRange {
+                start,
+                end: None,
+                error,
+            });
+            self.eof = true;
+            return Ok(());
+        };
+
+        let len = seek(&mut self.inner, SeekFrom::End(0))?;
+        let mut chunk = vec![0; READ_CHUNK];
+        let mut failed = true;
+        loop {
+            if failed {
+                at = ((at / SKIP_STEP + 1) * SKIP_STEP).min(len);
+                seek(&mut self.inner, SeekFrom::Start(at))?;
+            }
+            // past the end there's nothing to fail anymore, whatever the
+            // reader says
+            let read = match at < len {
+                true => read_retrying(&mut self.inner, &mut chunk),
+                false => Ok(0),
+            };
+            match read {
+                Ok(0) => {
+                    self.offset = at;
+                    self.eof = true;
+                    self.skipped.push(SkippedRange {
+                        start,
+                        end: Some(at),
+                        error,
+                    });
+                    return Ok(());
+                }
+                Ok(n) => match memchr::memchr(b'\n', &chunk[..n]) {
+                    Some(newline) => {
+                        let end = at + newline as u64 + 1;
+                        self.buf.extend_from_slice(&chunk[newline + 1..n]);
+                        self.offset = end;
+                        self.release(0);
+                        self.skipped.push(SkippedRange {
+                            start,
+                            end: Some(end),
+                            error,
+                        });
+                        return Ok(());
+                    }
+                    // still the broken line, read on from here
+                    None => {
+                        at += n as u64;
+                        failed = false;
+                    }
+                },
+                Err(_) => failed = true,
+            }
+        }
+    }
+}
+
+impl<R: Read + Seek> RecoveringReader<R> {
+    /// For a file, reading goes on a [`SKIP_STEP`] past a failure, again and
+    /// again until a read works
+    pub fn seekable(inner: R) -> Self {
+        Self {
+            seek: Some(R::seek),
+            ..Self::new(inner)
+        }
+    }
+}
+
+impl<R: Read> Read for RecoveringReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for RecoveringReader<R> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        while self.pos == self.released {
+            if self.eof {
+                // the last line, newline or not
+                self.released = self.buf.len();
+                break;
+            }
+
+            self.buf.drain(..self.pos);
+            self.offset += self.pos as u64;
+            self.released -= self.pos;
+            self.pos = 0;
+
+            let filled = self.buf.len();
+            self.buf.resize(filled + READ_CHUNK, 0);
+            match read_retrying(&mut self.inner, &mut self.buf[filled..]) {
+                Ok(0) => {
+                    self.buf.truncate(filled);
+                    self.eof = true;
+                }
+                Ok(n) => {
+                    self.buf.truncate(filled + n);
+                    self.release(filled);
+                }
+                Err(e) => {
+                    self.buf.truncate(filled);
+                    self.resync(e)?;
+                }
+            }
+        }
+        Ok(&self.buf[self.pos..self.released])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.released);
+    }
+}
+
+/// A read tried [`READ_ATTEMPTS`] times before its error counts
+fn read_retrying(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut attempts = 1;
+    loop {
+        match reader.read(buf) {
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(_) if attempts < READ_ATTEMPTS => attempts += 1,
+            result => return result,
+        }
+    }
+}
+
+/// [`crate::compression::open_reader`] through a [`RecoveringReader`]:
+/// seekable over a plain local file, over the decompressed stream otherwise
+pub fn open_recovering(path: &str) -> std::io::Result<(Box<dyn BufRead + Send>, SkippedRanges)> {
+    if !crate::remote::is_object_uri(path) && Compression::detect(path)? == Compression::None {
+        let file = crate::metrics::Metered(crate::page_cache::open_sequential(path)?);
+        let reader = RecoveringReader::seekable(file);
+        let skipped = reader.skipped();
+        return Ok((Box::new(reader), skipped));
+    }
+    let reader = RecoveringReader::new(crate::compression::open_reader(path)?);
+    let skipped = reader.skipped();
+    Ok((Box::new(reader), skipped))
+}
@@ -0,0 +1,116 @@
+use super::{
+    ClientData, DirtyDataReport, IngestError, IngestOptions, SkippedRange, open_file_with,
+};
+
+/// Why a line got rejected, [`IngestError::Line`] is looked through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FailureKind {
+    /// nothing but whitespace, caught before the field count
+    EmptyLine,
+    FieldCount,
+    InvalidAmount,
+    InvalidTimestamp,
+    InvalidKind,
+    InvalidCurrency,
+    InvalidId,
+    InvalidValue,
+    Utf8,
+    /// over [`super::MAX_LINE_BYTES`]
+    LineTooLong,
+    Json,
+    Io,
+    Schema,
+    Checksum,
+    DuplicateId,
+    ErrorRate,
+}
+
+impl IngestError {
+    pub fn kind(&self) -> FailureKind {
+        match self {
+            IngestError::FieldCount { .. } => FailureKind::FieldCount,
+            IngestError::InvalidAmount { .. } => FailureKind::InvalidAmount,
+            IngestError::InvalidTimestamp { .. } => FailureKind::InvalidTimestamp,
+            IngestError::InvalidKind { .. } => FailureKind::InvalidKind,
+            IngestError::InvalidCurrency { .. } => FailureKind::InvalidCurrency,
+            IngestError::InvalidId { .. } => FailureKind::InvalidId,
+            IngestError::InvalidValue { .. } | IngestError::InvalidAccount { .. } => {
+                FailureKind::InvalidValue
+            }
+            IngestError::InvalidNumber { .. } | IngestError::InvalidCents { .. } => {
+                FailureKind::InvalidAmount
+            }
+            #[cfg(feature = "decimal")]
+            IngestError::InvalidDecimal { .. } => FailureKind::InvalidAmount,
+            IngestError::Utf8(_) => FailureKind::Utf8,
+            IngestError::Json(_) => FailureKind::Json,
+            IngestError::Io(_) => FailureKind::Io,
+            IngestError::Schema(_) => FailureKind::Schema,
+            IngestError::MissingChecksum(_) | IngestError::ChecksumMismatch { .. } => {
+                FailureKind::Checksum
+            }
+            IngestError::DuplicateId { .. } => FailureKind::DuplicateId,
+            #[cfg(feature = "csv")]
+            IngestError::Csv(e) => match e.kind() {
+                ::csv::ErrorKind::Utf8 { .. } => FailureKind::Utf8,
+                ::csv::ErrorKind::Io(_) => FailureKind::Io,
+                _ => FailureKind::Schema,
+            },
+            #[cfg(feature = "parquet")]
+            IngestError::Parquet(_) => FailureKind::Schema,
+            #[cfg(feature = "arrow")]
+            IngestError::Arrow(_) => FailureKind::Schema,
+            #[cfg(feature = "kafka")]
+            IngestError::Kafka(_) => FailureKind::Io,
+            #[cfg(feature = "sqlite")]
+            IngestError::Sqlite(_) => FailureKind::Io,
+            IngestError::Line { line, .. } if line.trim().is_empty() => FailureKind::EmptyLine,
+            IngestError::Line { source, .. } => source.kind(),
+            IngestError::LineTooLong { .. } => FailureKind::LineTooLong,
+            IngestError::ErrorRateExceeded { .. } => FailureKind::ErrorRate,
+        }
+    }
+}
+
+/// What happened during ingestion, returned next to the records
+#[derive(Debug, Clone, Default)]
+pub struct IngestReport {
+    /// data lines seen, header excluded
+    pub lines: usize,
+    pub records: usize,
+    /// records that parsed but were dropped by [`super::IngestOptions::filter`]
+    pub filtered: usize,
+    /// the broken lines by [`FailureKind`], with where they were and a few samples
+    pub dirty: DirtyDataReport,
+    /// sidecar file with the rejected lines, if it was requested
+    pub rejects_path: Option<String>,
+    /// input read past after a failed read, see [`super::IngestOptions::recover`]
+    pub skipped_ranges: Vec<SkippedRange>,
+    /// [`super::IngestOptions::cancellation`] stopped the read, the records are
+    /// the ones before that
+    pub cancelled: bool,
+}
+
+impl IngestReport {
+    pub fn malformed_total(&self) -> usize {
+        self.dirty.total()
+    }
+
+    pub fn record_failure(&mut self, error: &IngestError) {
+        self.dirty.record(error);
+    }
+}
+
+/// Like [`super::open_file`], but counts the broken lines instead of printing them.
+///
+/// With `write_rejects` every rejected line lands in `<path>.rejects` as
+/// `line_num<TAB>reason<TAB>raw line`, so it can be inspected or fed back later.
+pub fn open_file_with_report(
+    path: &str,
+    write_rejects: bool,
+) -> Result<(Vec<ClientData>, IngestReport), IngestError> {
+    open_file_with(
+        path,
+        IngestOptions::new().skip().write_rejects(write_rejects),
+    )
+}
@@ -0,0 +1,236 @@
+use std::io::BufRead;
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+
+use super::{DirtyDataReport, Field, FieldType, IngestError, Schema, decode_line};
+
+/// One field of a [`Row`], typed per its [`Field`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    Text(String),
+    /// an empty field in a nullable column
+    Null,
+}
+
+impl Value {
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(n) => Some(*n as f64),
+            Value::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    fn parse(field: &Field, value: &str) -> Result<Self, IngestError> {
+        if value.is_empty() && field.nullable {
+            return Ok(Value::Null);
+        }
+        let invalid = || IngestError::InvalidValue {
+            column: field.name.clone(),
+            value: value.to_string(),
+            expected: field.ty,
+        };
+        Ok(match field.ty {
+            FieldType::Integer => Value::Integer(value.parse().map_err(|_| invalid())?),
+            FieldType::Float => Value::Float(value.parse().map_err(|_| invalid())?),
+            FieldType::Text => Value::Text(value.to_string()),
+        })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Value::Integer(n) => (*n).into(),
+            // NaN and infinities have no JSON number, they come through as null
+            Value::Float(n) => {
+                serde_json::Number::from_f64(*n).map_or(serde_json::Value::Null, Into::into)
+            }
+            Value::Text(s) => s.as_str().into(),
+            Value::Null => serde_json::Value::Null,
+        }
+    }
+}
+
+/// A line's values in [`Schema::fields`] order
+pub type Row = Vec<Value>;
+
+impl Schema {
+    /// Position of the field called `name`, to pick it out of a [`Row`]
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.name == name)
+    }
+
+    pub fn parse_row(&self, line: &str) -> Result<Row, IngestError> {
+        let parts: Vec<&str> = line.split(self.delimiter).collect();
+        if parts.len() != self.fields.len() {
+            return Err(IngestError::FieldCount {
+                expected: self.fields.len(),
+                got: parts.len(),
+            });
+        }
+
+        self.fields
+            .iter()
+            .zip(parts)
+            .map(|(field, value)| Value::parse(field, value.trim()))
+            .collect()
+    }
+
+    /// Hands the row to serde by field name, so any `#[derive(Deserialize)]`
+    /// struct with matching field names (or `#[serde(rename)]`s) works
+    pub fn deserialize_row<T: DeserializeOwned>(&self, row: &[Value]) -> Result<T, IngestError> {
+        let object: serde_json::Map<String, serde_json::Value> = self
+            .fields
+            .iter()
+            .zip(row)
+            .map(|(field, value)| (field.name.clone(), value.to_json()))
+            .collect();
+        Ok(serde_json::from_value(object.into())?)
+    }
+
+    /// A header that doesn't name the fields in order means the schema is for another file
+    fn check_header(&self, header: &str) -> Result<(), IngestError> {
+        let names: Vec<&str> = header.split(self.delimiter).map(str::trim).collect();
+        let expected: Vec<&str> = self
+            .fields
+            .iter()
+            .map(|field| field.name.as_str())
+            .collect();
+        if names != expected {
+            return Err(IngestError::Schema(format!(
+                "header `{header}` doesn't match the schema's `{}`",
+                expected.join(&self.delimiter.to_string())
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Lazy [`Row`] reader for any delimited file, the schema-driven [`super::RecordIter`]
+pub struct RowIter<R = Box<dyn BufRead + Send>> {
+    reader: R,
+    buf: Vec<u8>,
+    line_num: usize,
+    schema: Schema,
+}
+
+impl<R: BufRead> RowIter<R> {
+    pub fn new(reader: R, schema: Schema) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_num: 0,
+            schema,
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Each row deserialized into a `T`, see [`Schema::deserialize_row`]
+    pub fn deserialize<T: DeserializeOwned>(self) -> Deserialized<R, T> {
+        Deserialized {
+            rows: self,
+            _record: PhantomData,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RowIter<R> {
+    type Item = Result<Row, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line_num += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let line = match decode_line(&self.buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            if self.schema.has_header && self.line_num == 1 {
+                match self.schema.check_header(line) {
+                    Ok(()) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+
+            return Some(self.schema.parse_row(line).map_err(|e| IngestError::Line {
+                line_num: self.line_num,
+                line: line.to_string(),
+                source: Box::new(e),
+            }));
+        }
+    }
+}
+
+/// [`RowIter::deserialize`]
+pub struct Deserialized<R, T> {
+    rows: RowIter<R>,
+    _record: PhantomData<T>,
+}
+
+impl<R: BufRead, T: DeserializeOwned> Iterator for Deserialized<R, T> {
+    type Item = Result<T, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let row = self.rows.next()?;
+        Some(row.and_then(|row| {
+            self.rows
+                .schema
+                .deserialize_row(&row)
+                .map_err(|e| IngestError::Line {
+                    line_num: self.rows.line_num,
+                    // the line is still in the buffer, and it parsed so it's valid UTF-8
+                    line: String::from_utf8_lossy(&self.rows.buf)
+                        .trim_end_matches(['\n', '\r'])
+                        .to_string(),
+                    source: Box::new(e),
+                })
+        }))
+    }
+}
+
+/// Rows of a plain, `.gz` or `.zst` delimited file. The schema can come from
+/// [`super::sniff`] or be written out for a file that's known
+pub fn open_rows(path: &str, schema: Schema) -> Result<RowIter, IngestError> {
+    Ok(RowIter::new(crate::compression::open_reader(path)?, schema))
+}
+
+/// Every row of the file as a `T`, broken lines skipped and counted
+pub fn open_file_as<T: DeserializeOwned>(
+    path: &str,
+    schema: Schema,
+) -> Result<(Vec<T>, DirtyDataReport), IngestError> {
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+    for record in open_rows(path, schema)?.deserialize() {
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    Ok((records, dirty))
+}
@@ -0,0 +1,60 @@
+use rand::rngs::ChaCha8Rng;
+use rand::{RngExt, SeedableRng};
+
+use super::options::open_into;
+use super::{ClientData, IngestError, IngestOptions, IngestReport, RecordIter};
+
+/// 1000 clean records with every optional column, small enough to ship in the
+/// binary. `p01 generate --sample-dataset --out - > crates/datatools/data/sample.csv`
+/// makes it again, [`crate::generator::verify_sample_dataset`] checks it still would
+pub const SAMPLE_DATASET: &str = include_str!("../../data/sample.csv");
+
+/// `n` records picked uniformly from the whole file in one pass (reservoir
+/// sampling), only the sample is ever held in memory.
+///
+/// The same seed on the same file picks the same records. They come back in
+/// file order, a file with fewer than `n` valid records comes back whole.
+/// Broken lines can't be picked, [`sample_with`] says how many there were
+pub fn sample(path: &str, n: usize, seed: u64) -> Result<Vec<ClientData>, IngestError> {
+    sample_with(path, n, seed, IngestOptions::new()).map(|(sample, _)| sample)
+}
+
+/// [`sample`] through [`IngestOptions`]: the error policy, the filter and the
+/// `.rejects` file work like in [`super::open_file_with`], the report counts
+/// every line read. The filter runs first, the sample is drawn from what it keeps
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(options)))]
+pub fn sample_with(
+    path: &str,
+    n: usize,
+    seed: u64,
+    options: IngestOptions,
+) -> Result<(Vec<ClientData>, IngestReport), IngestError> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    // (position among the valid records, record), to put them back in order at the end
+    let mut reservoir: Vec<(usize, ClientData)> = Vec::with_capacity(n);
+    let mut seen = 0;
+
+    let report = open_into(path, options, |record| {
+        // algorithm R: record number `seen` replaces a random slot with chance n / (seen + 1)
+        if reservoir.len() < n {
+            reservoir.push((seen, record));
+        } else {
+            let slot = rng.random_range(0..=seen);
+            if slot < n {
+                reservoir[slot] = (seen, record);
+            }
+        }
+        seen += 1;
+    })?;
+
+    reservoir.sort_unstable_by_key(|(position, _)| *position);
+    let sample = reservoir.into_iter().map(|(_, record)| record).collect();
+    Ok((sample, report))
+}
+
+/// [`SAMPLE_DATASET`] parsed, for trying the API without generating a file first
+pub fn sample_dataset() -> Vec<ClientData> {
+    RecordIter::new(SAMPLE_DATASET.as_bytes())
+        .collect::<Result<_, _>>()
+        .expect("the sample dataset is clean")
+}
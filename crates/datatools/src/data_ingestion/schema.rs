@@ -0,0 +1,328 @@
+use std::fmt;
+
+use super::{
+    ClientData, Column, DirtyDataReport, IngestError, Layout, RecordIter, decode_line, read_line,
+};
+use crate::format::Format;
+
+/// Lines [`sniff`] looks at, header included
+pub const SNIFF_LINES: usize = 1000;
+
+/// Tried in this order, the first one wins a tie
+const DELIMITERS: [char; 4] = ['|', ',', '\t', ';'];
+
+/// The narrowest type every non-empty value of a column parses as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    Integer,
+    Float,
+    Text,
+}
+
+impl FieldType {
+    fn of(value: &str) -> Self {
+        if value.parse::<i64>().is_ok() {
+            FieldType::Integer
+        } else if value.parse::<f64>().is_ok() {
+            FieldType::Float
+        } else {
+            FieldType::Text
+        }
+    }
+
+    /// The type both values fit in, integers widen to floats and anything to text
+    fn widen(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (FieldType::Text, _) | (_, FieldType::Text) => FieldType::Text,
+            _ => FieldType::Float,
+        }
+    }
+
+    pub fn is_numeric(self) -> bool {
+        self != FieldType::Text
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FieldType::Integer => "integer",
+            FieldType::Float => "float",
+            FieldType::Text => "text",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// from the header, `column_1`, `column_2`... without one
+    pub name: String,
+    pub ty: FieldType,
+    /// some lines leave it empty
+    pub nullable: bool,
+}
+
+/// What a delimited file looks like, as far as its first lines tell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub delimiter: char,
+    pub has_header: bool,
+    pub fields: Vec<Field>,
+}
+
+impl Default for Schema {
+    /// Nothing known yet: `|`-delimited with a header, fields added with [`Schema::field`]
+    fn default() -> Self {
+        Self {
+            delimiter: '|',
+            has_header: true,
+            fields: Vec::new(),
+        }
+    }
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// A column that has to be there on every line
+    pub fn field(mut self, name: &str, ty: FieldType) -> Self {
+        self.fields.push(Field {
+            name: name.to_string(),
+            ty,
+            nullable: false,
+        });
+        self
+    }
+
+    /// A column that can be left empty, which reads as [`super::Value::Null`]
+    pub fn optional_field(mut self, name: &str, ty: FieldType) -> Self {
+        self.fields.push(Field {
+            name: name.to_string(),
+            ty,
+            nullable: true,
+        });
+        self
+    }
+
+    /// How to read these lines into [`ClientData`]. Headers need the
+    /// [`Column`] names, headerless files the column order the generator
+    /// writes: `id`, `from_id`, `to_id`, `amount`, then a `timestamp` (integers)
+    /// and/or a `kind` (text)
+    pub fn layout(&self) -> Result<Layout, IngestError> {
+        if self.has_header {
+            let layout = Layout::from_names(self.fields.iter().map(|field| field.name.as_str()))?;
+            return Ok(layout.with_delimiter(self.delimiter));
+        }
+
+        let mismatch = || {
+            IngestError::Schema(format!(
+                "no header, and {} columns that don't look like id{d}from_id{d}to_id{d}amount",
+                self.fields.len(),
+                d = self.delimiter
+            ))
+        };
+        let types: Vec<_> = self.fields.iter().map(|field| field.ty).collect();
+        let [
+            FieldType::Text,
+            FieldType::Text,
+            FieldType::Text,
+            amount,
+            optional @ ..,
+        ] = types.as_slice()
+        else {
+            return Err(mismatch());
+        };
+        if !amount.is_numeric() {
+            return Err(mismatch());
+        }
+
+        let mut layout = Layout::default();
+        for ty in optional {
+            let column = match ty {
+                FieldType::Integer => Column::Timestamp,
+                FieldType::Text => Column::Kind,
+                FieldType::Float => return Err(mismatch()),
+            };
+            if layout.contains(column) {
+                return Err(mismatch());
+            }
+            layout = layout.with(column);
+        }
+        Ok(layout.with_delimiter(self.delimiter))
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let header = if self.has_header {
+            "header"
+        } else {
+            "no header"
+        };
+        writeln!(f, "delimiter {:?}, {header}", self.delimiter)?;
+        for field in &self.fields {
+            let nullable = if field.nullable { ", nullable" } else { "" };
+            writeln!(f, "  {}: {}{nullable}", field.name, field.ty)?;
+        }
+        Ok(())
+    }
+}
+
+/// Guesses the delimiter, header and column types of a delimited file (plain,
+/// `.gz` or `.zst`) from its first [`SNIFF_LINES`] lines.
+///
+/// Lines that don't split like most of the others are left out of the type
+/// guess, like the corrupted ones a generated file has
+pub fn sniff(path: &str) -> Result<Schema, IngestError> {
+    if Format::from_path(path) != Format::Delimited {
+        return Err(IngestError::Schema(format!(
+            "{path} isn't delimited text, its fields are named and typed already"
+        )));
+    }
+
+    let mut reader = crate::compression::open_reader(path)?;
+    let mut lines = Vec::new();
+    let mut buf = Vec::new();
+    while lines.len() < SNIFF_LINES {
+        buf.clear();
+        let n = read_line(&mut reader, &mut buf)?;
+        if n == 0 {
+            break;
+        }
+        // a broken line says nothing about the rest, neither does one cut short
+        if n == buf.len()
+            && let Ok(line) = decode_line(&buf, lines.len() + 1)
+            && !line.trim().is_empty()
+        {
+            lines.push(line.to_string());
+        }
+    }
+
+    sniff_lines(&lines)
+}
+
+/// [`sniff`] over lines already in memory
+pub fn sniff_lines(lines: &[impl AsRef<str>]) -> Result<Schema, IngestError> {
+    let lines: Vec<&str> = lines.iter().map(AsRef::as_ref).collect();
+    let (delimiter, width) = detect_delimiter(&lines)
+        .ok_or_else(|| IngestError::Schema("no delimiter splits the lines evenly".to_string()))?;
+
+    let rows: Vec<Vec<&str>> = lines
+        .iter()
+        .map(|line| line.split(delimiter).map(str::trim).collect::<Vec<_>>())
+        .filter(|fields| fields.len() == width)
+        .collect();
+    let first_is_header = rows
+        .first()
+        .is_some_and(|first| looks_like_header(first, &rows[1..]));
+
+    let (names, data): (Vec<String>, &[Vec<&str>]) = match rows.split_first() {
+        Some((first, rest)) if first_is_header => {
+            (first.iter().map(|s| s.to_string()).collect(), rest)
+        }
+        _ => (
+            (1..=width).map(|i| format!("column_{i}")).collect(),
+            &rows[..],
+        ),
+    };
+
+    let fields = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values = data.iter().map(|row| row[i]);
+            let ty = values
+                .clone()
+                .filter(|value| !value.is_empty())
+                .map(FieldType::of)
+                .reduce(FieldType::widen)
+                .unwrap_or(FieldType::Text);
+            Field {
+                name,
+                ty,
+                nullable: values.clone().any(str::is_empty),
+            }
+        })
+        .collect();
+
+    Ok(Schema {
+        delimiter,
+        has_header: first_is_header,
+        fields,
+    })
+}
+
+/// The delimiter most lines agree on a field count for, and that count
+fn detect_delimiter(lines: &[&str]) -> Option<(char, usize)> {
+    let mut best: Option<(char, usize, usize)> = None;
+    for delimiter in DELIMITERS {
+        let mut counts = std::collections::HashMap::new();
+        for line in lines {
+            *counts
+                .entry(line.split(delimiter).count())
+                .or_insert(0usize) += 1;
+        }
+        // the most common field count, one field means the delimiter isn't there at all
+        let Some((width, agreeing)) = counts
+            .into_iter()
+            .filter(|&(width, _)| width > 1)
+            .max_by_key(|&(width, agreeing)| (agreeing, width))
+        else {
+            continue;
+        };
+        if best.is_none_or(|(_, _, most)| agreeing > most) {
+            best = Some((delimiter, width, agreeing));
+        }
+    }
+    best.map(|(delimiter, width, _)| (delimiter, width))
+}
+
+/// Known column names, or text on top of a column that's numbers below
+fn looks_like_header(first: &[&str], rest: &[Vec<&str>]) -> bool {
+    if first.iter().all(|name| Column::from_name(name).is_some()) {
+        return true;
+    }
+    (0..first.len()).any(|i| {
+        FieldType::of(first[i]) == FieldType::Text
+            && !rest.is_empty()
+            && rest
+                .iter()
+                .all(|row| row[i].is_empty() || FieldType::of(row[i]).is_numeric())
+    })
+}
+
+/// [`super::open_file`] for a file in any layout [`sniff`] can make out, broken
+/// lines are skipped and counted
+pub fn open_file_sniffed(path: &str) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    let schema = sniff(path)?;
+    let layout = schema.layout()?;
+    let reader = crate::compression::open_reader(path)?;
+    let records = match schema.has_header {
+        // the header goes through the iterator, which checks it again
+        true => RecordIter::with_layout(reader, layout).has_header(true),
+        false => RecordIter::with_layout(reader, layout),
+    };
+
+    let mut kept = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+    for record in records {
+        match record {
+            Ok(record) => kept.push(record),
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    Ok((kept, dirty))
+}
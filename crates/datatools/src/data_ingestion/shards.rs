@@ -0,0 +1,82 @@
+use super::{ClientData, IngestError, open_file};
+use crate::format::Format;
+
+/// The `shard-*` data files in `dir`, in shard order. Sidecars (`.sha256`,
+/// `.cache`, `.rejects`) and anything else that isn't a dataset are left out
+pub fn shard_files(dir: &str) -> std::io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+
+        let data = name.trim_end_matches(".gz").trim_end_matches(".zst");
+        if name.starts_with("shard-")
+            && [".csv", ".jsonl", ".parquet"]
+                .iter()
+                .any(|ext| data.ends_with(ext))
+        {
+            files.push(path.to_string_lossy().into_owned());
+        }
+    }
+
+    // zero padded names, so this is shard order
+    files.sort();
+    Ok(files)
+}
+
+/// Every shard in `dir` as one dataset, records come back in shard order
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_dir(dir: &str) -> Result<Vec<ClientData>, IngestError> {
+    let files = non_empty_shard_files(dir)?;
+    let mut records = Vec::new();
+    for file in &files {
+        records.append(&mut open_shard(file)?);
+    }
+    Ok(records)
+}
+
+/// [`open_dir`] with the shards parsed in parallel, still concatenated in shard order
+#[cfg(feature = "rayon")]
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_dir_parallel(dir: &str) -> Result<Vec<ClientData>, IngestError> {
+    use rayon::prelude::*;
+
+    let files = non_empty_shard_files(dir)?;
+    #[cfg(feature = "tracing")]
+    let parent = tracing::Span::current();
+    let shards = files
+        .par_iter()
+        .map(|file| {
+            #[cfg(feature = "tracing")]
+            let _parent = parent.enter();
+            open_shard(file)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(shards.concat())
+}
+
+/// an empty directory is almost certainly the wrong path, not an empty dataset
+fn non_empty_shard_files(dir: &str) -> std::io::Result<Vec<String>> {
+    let files = shard_files(dir)?;
+    if files.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no shard-* files in {dir}"),
+        ));
+    }
+    Ok(files)
+}
+
+pub(crate) fn open_shard(path: &str) -> Result<Vec<ClientData>, IngestError> {
+    match Format::from_path(path) {
+        #[cfg(feature = "parquet")]
+        Format::Parquet => super::open_file_parquet(path),
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => Err(IngestError::Schema(format!(
+            "{path} needs the `parquet` feature"
+        ))),
+        Format::Delimited | Format::JsonLines => open_file(path),
+    }
+}
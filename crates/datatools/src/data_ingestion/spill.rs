@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::{ClientData, Column, IngestError, RecordIter};
+use crate::compression::{CompressedWriter, Compression};
+use crate::sort::{Key, SortOptions, SortReport};
+
+/// The runs are written once and read back once per pass, zstd is the cheapest
+/// to do that with when it's compiled in
+const RUN_COMPRESSION: Compression = if cfg!(feature = "zstd") {
+    Compression::Zstd
+} else if cfg!(feature = "gzip") {
+    Compression::Gzip
+} else {
+    Compression::None
+};
+
+/// Tells the temp dirs of spills running at the same time apart
+static SPILLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Roughly what a record takes up in memory, its strings included
+fn footprint(record: &ClientData) -> usize {
+    std::mem::size_of::<ClientData>()
+        + record.id.capacity()
+        + record.from_id.capacity()
+        + record.to_id.capacity()
+}
+
+/// The records of a file in [`SortOptions::key`] order, with at most
+/// [`SortOptions::memory_budget`] of them in memory at any time. What didn't
+/// fit sits in sorted runs on disk (see [`SortOptions::temp_dir`]) until this
+/// is dropped
+pub struct Spilled {
+    /// all of them, sorted, when they fit in the budget
+    records: Vec<ClientData>,
+    runs: Vec<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    key: Column,
+    pub report: SortReport,
+}
+
+/// Reads `path` (delimited or JSON Lines, compressed or not) within
+/// `options.memory_budget`. Whenever the records read so far reach it they're
+/// sorted and spilled to a compressed run, [`Spilled::iter`] merges the runs
+/// back. Sorting is stable like [`crate::sort::sort_file`], broken lines are
+/// skipped and counted in [`SortReport::dirty`]
+pub fn open_file_spilled(path: &str, options: &SortOptions) -> Result<Spilled, IngestError> {
+    let start = crate::Instant::now();
+    let mut spilled = Spilled {
+        records: Vec::new(),
+        runs: Vec::new(),
+        temp_dir: None,
+        key: options.key,
+        report: SortReport::default(),
+    };
+    let mut bytes = 0;
+
+    for record in RecordIter::open(path)? {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                spilled.report.dirty.absorb(e)?;
+                continue;
+            }
+        };
+        bytes += footprint(&record);
+        spilled.records.push(record);
+        spilled.report.records += 1;
+
+        if bytes >= options.memory_budget {
+            spilled.spill(options)?;
+            bytes = 0;
+        }
+    }
+
+    if spilled.runs.is_empty() {
+        let key = spilled.key;
+        spilled.records.sort_by_cached_key(|r| Key::of(r, key));
+        spilled.report.runs = 1;
+    } else {
+        if !spilled.records.is_empty() {
+            spilled.spill(options)?;
+        }
+        spilled.report.runs = spilled.runs.len();
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        records = spilled.report.records,
+        runs = spilled.report.runs,
+        "ingested"
+    );
+    crate::metrics::record_batch(
+        "spilled",
+        spilled.report.records,
+        &spilled.report.dirty,
+        start.elapsed(),
+    );
+    Ok(spilled)
+}
+
+impl Spilled {
+    /// Sorts what's in memory and writes it out as the next run, leaving it empty
+    fn spill(&mut self, options: &SortOptions) -> Result<(), IngestError> {
+        if self.temp_dir.is_none() {
+            let base = options.temp_dir.clone().unwrap_or_else(std::env::temp_dir);
+            let dir = base.join(format!(
+                "p01-spill-{}-{}",
+                std::process::id(),
+                SPILLS.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::create_dir_all(&dir)?;
+            self.temp_dir = Some(dir);
+        }
+        let temp_dir = self.temp_dir.as_ref().expect("created above");
+        let extension = match RUN_COMPRESSION {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        };
+        let path = temp_dir.join(format!("run-{:04}.jsonl{extension}", self.runs.len()));
+
+        // sort_by_cached_key is stable, and each key is worked out only once
+        let key = self.key;
+        self.records.sort_by_cached_key(|r| Key::of(r, key));
+        let file = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        let mut writer = CompressedWriter::new(file, RUN_COMPRESSION)?;
+        for record in self.records.drain(..) {
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.finish()?;
+        self.runs.push(path);
+        Ok(())
+    }
+
+    /// Every record in key order, merged from the runs. Can be called again
+    /// for another pass
+    pub fn iter(&self) -> Result<SpilledIter<'_>, IngestError> {
+        if self.runs.is_empty() {
+            return Ok(SpilledIter(Source::Memory(self.records.iter())));
+        }
+        let mut merge = Merge {
+            key: self.key,
+            runs: self
+                .runs
+                .iter()
+                .map(|path| run_reader(path))
+                .collect::<Result<_, _>>()?,
+            heads: Vec::with_capacity(self.runs.len()),
+            heap: BinaryHeap::with_capacity(self.runs.len()),
+            error: None,
+        };
+        for run in 0..merge.runs.len() {
+            merge.heads.push(None);
+            merge.advance(run)?;
+        }
+        Ok(SpilledIter(Source::Merge(merge)))
+    }
+
+    /// Consecutive records with the same key, e.g. every transaction of one
+    /// sender when the key is [`Column::FromId`]. Only one group is in memory
+    /// at a time
+    pub fn groups(&self) -> Result<Groups<'_>, IngestError> {
+        Ok(Groups {
+            records: self.iter()?,
+            key: self.key,
+            next: None,
+        })
+    }
+
+    /// Directory the runs are in, `None` when everything fit in memory
+    pub fn temp_dir(&self) -> Option<&Path> {
+        self.temp_dir.as_deref()
+    }
+}
+
+impl Drop for Spilled {
+    // runs only mean something to the Spilled that wrote them
+    fn drop(&mut self) {
+        if let Some(dir) = &self.temp_dir
+            && let Err(e) = std::fs::remove_dir_all(dir)
+        {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(dir = %dir.display(), %e, "failed to remove the runs");
+            #[cfg(not(feature = "tracing"))]
+            let _ = e;
+        }
+    }
+}
+
+fn run_reader(path: &Path) -> Result<RecordIter, IngestError> {
+    let reader = crate::compression::open_reader(&path.to_string_lossy())?;
+    Ok(RecordIter::json_lines(reader))
+}
+
+/// [`Spilled::iter`]
+pub struct SpilledIter<'a>(Source<'a>);
+
+enum Source<'a> {
+    Memory(std::slice::Iter<'a, ClientData>),
+    Merge(Merge),
+}
+
+/// k-way merge of the runs. Ties go to the lower run, which holds the earlier
+/// records of the file, so the order stays stable
+struct Merge {
+    key: Column,
+    runs: Vec<RecordIter>,
+    /// the next record of every run, its key is in `heap`
+    heads: Vec<Option<ClientData>>,
+    heap: BinaryHeap<Reverse<(Key, usize)>>,
+    /// a run that failed while the record before it was handed out, reported next
+    error: Option<IngestError>,
+}
+
+impl Merge {
+    fn advance(&mut self, run: usize) -> Result<(), IngestError> {
+        if let Some(record) = self.runs[run].next().transpose()? {
+            self.heap.push(Reverse((Key::of(&record, self.key), run)));
+            self.heads[run] = Some(record);
+        }
+        Ok(())
+    }
+}
+
+impl Iterator for SpilledIter<'_> {
+    type Item = Result<ClientData, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            Source::Memory(records) => records.next().cloned().map(Ok),
+            Source::Merge(merge) => {
+                if let Some(e) = merge.error.take() {
+                    return Some(Err(e));
+                }
+                let Reverse((_, run)) = merge.heap.pop()?;
+                let record = merge.heads[run]
+                    .take()
+                    .expect("every key in the heap has a head");
+                if let Err(e) = merge.advance(run) {
+                    merge.error = Some(e);
+                }
+                Some(Ok(record))
+            }
+        }
+    }
+}
+
+/// [`Spilled::groups`]
+pub struct Groups<'a> {
+    records: SpilledIter<'a>,
+    key: Column,
+    /// first record of the next group, read while looking for the end of this one
+    next: Option<ClientData>,
+}
+
+impl Iterator for Groups<'_> {
+    type Item = Result<Vec<ClientData>, IngestError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = match self.next.take() {
+            Some(record) => record,
+            None => match self.records.next()? {
+                Ok(record) => record,
+                Err(e) => return Some(Err(e)),
+            },
+        };
+        let key = Key::of(&first, self.key);
+        let mut group = vec![first];
+        for record in self.records.by_ref() {
+            match record {
+                Ok(record) if Key::of(&record, self.key) == key => group.push(record),
+                Ok(record) => {
+                    self.next = Some(record);
+                    break;
+                }
+                Err(e) => return Some(Err(e)),
+            }
+        }
+        Some(Ok(group))
+    }
+}
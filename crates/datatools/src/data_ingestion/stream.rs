@@ -0,0 +1,135 @@
+use futures_util::stream::{self, Stream};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use super::{ClientData, IngestError, Layout, decode_line, parse_json_line};
+use crate::compression::Compression;
+use crate::encoding::{Encoding, UTF8_BOM};
+use crate::format::Format;
+use crate::remote::is_object_uri;
+
+/// What [`super::RecordIter`] keeps between lines, owned by the stream between polls
+struct AsyncLines<R> {
+    reader: R,
+    buf: Vec<u8>,
+    line_num: usize,
+    header: bool,
+    layout: Layout,
+    parse: fn(&Layout, &str) -> Result<ClientData, IngestError>,
+}
+
+impl<R: AsyncBufRead + Unpin> AsyncLines<R> {
+    fn new(reader: R, parse: fn(&Layout, &str) -> Result<ClientData, IngestError>) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            line_num: 0,
+            header: true,
+            layout: Layout::default(),
+            parse,
+        }
+    }
+
+    async fn next_record(&mut self) -> Option<Result<ClientData, IngestError>> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_until(b'\n', &mut self.buf).await {
+                Ok(0) => return None,
+                Ok(_) => self.line_num += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            let line = match decode_line(&self.buf, self.line_num) {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            // same header handling as the blocking reader
+            if self.header && self.line_num == 1 {
+                match Layout::from_names(line.split(self.layout.delimiter())) {
+                    Ok(layout) => {
+                        self.layout = self.layout.with_header(layout);
+                        continue;
+                    }
+                    Err(e) => {
+                        return Some(Err(IngestError::Line {
+                            line_num: self.line_num,
+                            line: line.to_string(),
+                            source: Box::new(e),
+                        }));
+                    }
+                }
+            }
+
+            return Some(
+                (self.parse)(&self.layout, line).map_err(|e| IngestError::Line {
+                    line_num: self.line_num,
+                    line: line.to_string(),
+                    source: Box::new(e),
+                }),
+            );
+        }
+    }
+
+    fn into_stream(self) -> impl Stream<Item = Result<ClientData, IngestError>> {
+        stream::unfold(self, |mut lines| async move {
+            let record = lines.next_record().await?;
+            Some((record, lines))
+        })
+    }
+}
+
+/// Async [`super::RecordIter::new`]: delimited text with a header from any
+/// async reader, e.g. a socket or a request body. Broken lines come out as
+/// [`IngestError::Line`] and the stream carries on after them.
+/// The stream isn't `Unpin`, pin it (`std::pin::pin!`) before calling `next`
+pub fn stream_records<R: AsyncBufRead + Unpin>(
+    reader: R,
+) -> impl Stream<Item = Result<ClientData, IngestError>> {
+    AsyncLines::new(reader, Layout::parse).into_stream()
+}
+
+/// Opens a delimited or JSON Lines file with [`tokio::fs`] (or an object store
+/// URI, see [`crate::remote`]), nothing blocks a runtime thread. Only plain
+/// UTF-8 files, decompression and transcoding are blocking readers, run
+/// [`super::open_file`] in `spawn_blocking` for those
+pub async fn open_file_async(
+    path: &str,
+) -> Result<impl Stream<Item = Result<ClientData, IngestError>>, IngestError> {
+    let format = Format::from_path(path);
+    if format == Format::Parquet {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "parquet is not line-based, use open_file_parquet",
+        )
+        .into());
+    }
+
+    let mut reader: Box<dyn AsyncBufRead + Send + Unpin> = match is_object_uri(path) {
+        true => crate::remote::open_async_reader(path).await?,
+        false => Box::new(BufReader::new(tokio::fs::File::open(path).await?)),
+    };
+    let head = reader.fill_buf().await?;
+    let unsupported = |what: String| {
+        std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("{path} is {what}, the async reader needs a plain UTF-8 file"),
+        )
+    };
+    if let Some(compression) = Compression::from_magic(head) {
+        return Err(unsupported(format!("{compression:?} compressed")).into());
+    }
+    match Encoding::detect(head) {
+        Encoding::Utf8 if head.starts_with(&UTF8_BOM) => reader.consume(UTF8_BOM.len()),
+        Encoding::Utf8 => {}
+        encoding => return Err(unsupported(format!("{encoding:?}")).into()),
+    }
+
+    let lines = match format {
+        Format::JsonLines => AsyncLines {
+            header: false,
+            ..AsyncLines::new(reader, |_, line| parse_json_line(line))
+        },
+        _ => AsyncLines::new(reader, Layout::parse),
+    };
+    Ok(lines.into_stream())
+}
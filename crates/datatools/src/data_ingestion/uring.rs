@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, Read};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::FileExt;
+
+use io_uring::{IoUring, opcode, types};
+
+use super::{ClientData, DirtyDataReport, IngestError, RecordIter};
+
+/// Bytes per read, a few big sequential ones is what the ring is good at
+pub const URING_READ_SIZE: usize = 1024 * 1024;
+
+/// Reads in flight, the parser works through one buffer while the kernel fills the rest
+pub const URING_QUEUE_DEPTH: usize = 4;
+
+/// A whole file read front to back through io_uring, as a [`BufRead`]. `fill_buf`
+/// hands out the next completed buffer, and once it's consumed it goes straight
+/// back to the kernel for the next read ahead
+pub struct UringReader {
+    ring: IoUring,
+    file: File,
+    len: u64,
+    read_size: usize,
+    /// where the next read gets submitted
+    next_offset: u64,
+    buffers: Vec<Vec<u8>>,
+    /// buffers in flight, in file order, with the offset each was submitted at
+    queue: VecDeque<(usize, u64)>,
+    /// per buffer, the result of its read once it completed
+    completed: Vec<Option<i32>>,
+    /// the buffer being handed out, its length and how much of it is consumed
+    current: Option<(usize, usize, usize)>,
+}
+
+impl UringReader {
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        Self::with_depth(path, URING_QUEUE_DEPTH, URING_READ_SIZE)
+    }
+
+    /// `depth` reads of `read_size` bytes in flight at once
+    pub fn with_depth(path: &str, depth: usize, read_size: usize) -> std::io::Result<Self> {
+        let depth = depth.max(1);
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut reader = Self {
+            ring: IoUring::new(depth.next_power_of_two() as u32)?,
+            file,
+            len,
+            read_size: read_size.max(1),
+            next_offset: 0,
+            buffers: (0..depth).map(|_| vec![0; read_size.max(1)]).collect(),
+            queue: VecDeque::with_capacity(depth),
+            completed: vec![None; depth],
+            current: None,
+        };
+        for buffer in 0..depth {
+            reader.submit(buffer)?;
+        }
+        Ok(reader)
+    }
+
+    /// Queues a read into `buffer` at the next offset, nothing once the whole file is asked for
+    fn submit(&mut self, buffer: usize) -> std::io::Result<()> {
+        if self.next_offset >= self.len {
+            return Ok(());
+        }
+        let want = (self.len - self.next_offset).min(self.read_size as u64) as u32;
+        let read = opcode::Read::new(
+            types::Fd(self.file.as_raw_fd()),
+            self.buffers[buffer].as_mut_ptr(),
+            want,
+        )
+        .offset(self.next_offset)
+        .build()
+        .user_data(buffer as u64);
+        // safety: the buffer is neither moved nor touched until its completion is
+        // reaped, and Drop waits for whatever is still in flight
+        unsafe {
+            self.ring
+                .submission()
+                .push(&read)
+                .map_err(|_| std::io::Error::other("io_uring submission queue is full"))?;
+        }
+        self.completed[buffer] = None;
+        self.queue.push_back((buffer, self.next_offset));
+        self.next_offset += u64::from(want);
+        self.ring.submit()?;
+        Ok(())
+    }
+
+    /// Waits until `buffer`'s read is done, reaping whatever else completed meanwhile
+    fn wait_for(&mut self, buffer: usize) -> std::io::Result<i32> {
+        loop {
+            for cqe in self.ring.completion() {
+                self.completed[cqe.user_data() as usize] = Some(cqe.result());
+            }
+            if let Some(result) = self.completed[buffer] {
+                return Ok(result);
+            }
+            match self.ring.submit_and_wait(1) {
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                result => {
+                    result?;
+                }
+            }
+        }
+    }
+
+    /// The next buffer in file order, `None` at the end of the file
+    fn next_buffer(&mut self) -> std::io::Result<Option<(usize, usize)>> {
+        let Some(&(buffer, offset)) = self.queue.front() else {
+            return Ok(None);
+        };
+        let result = self.wait_for(buffer)?;
+        self.queue.pop_front();
+        if result < 0 {
+            return Err(std::io::Error::from_raw_os_error(-result));
+        }
+
+        // a short read is rare on a regular file, the rest is read the plain way
+        // so the reads queued behind this one still line up
+        let want = (self.len - offset).min(self.read_size as u64) as usize;
+        let got = result as usize;
+        if got < want {
+            self.file
+                .read_exact_at(&mut self.buffers[buffer][got..want], offset + got as u64)?;
+        }
+        crate::metrics::record_bytes(want as u64);
+        Ok(Some((buffer, want)))
+    }
+}
+
+impl BufRead for UringReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if let Some((buffer, len, pos)) = self.current
+            && pos >= len
+        {
+            // all handed out, back to the kernel for the next stretch of the file
+            self.current = None;
+            self.submit(buffer)?;
+        }
+        if self.current.is_none() {
+            match self.next_buffer()? {
+                Some((buffer, len)) => self.current = Some((buffer, len, 0)),
+                None => return Ok(&[]),
+            }
+        }
+        let (buffer, len, pos) = self.current.expect("set above");
+        Ok(&self.buffers[buffer][pos..len])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Some((_, len, pos)) = &mut self.current {
+            *pos = (*pos + amount).min(*len);
+        }
+    }
+}
+
+impl Read for UringReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl Drop for UringReader {
+    // the kernel still writes into the buffers of reads in flight, they can't
+    // be freed before those are done
+    fn drop(&mut self) {
+        while let Some((buffer, _)) = self.queue.pop_front() {
+            if self.wait_for(buffer).is_err() {
+                // the ring itself broke, leaking is all that's left to do safely
+                std::mem::forget(std::mem::take(&mut self.buffers));
+                return;
+            }
+        }
+    }
+}
+
+/// Same lines as [`super::open_file`], read through [`UringReader`] instead of a `BufReader`.
+/// The broken ones come back counted next to the records
+#[cfg_attr(feature = "tracing", tracing::instrument)]
+pub fn open_file_uring(path: &str) -> Result<(Vec<ClientData>, DirtyDataReport), IngestError> {
+    crate::compression::require_plain(path)?;
+    let start = crate::Instant::now();
+    let mut records = Vec::new();
+    let mut dirty = DirtyDataReport::default();
+
+    for record in RecordIter::new(UringReader::open(path)?) {
+        match record {
+            Ok(record) => records.push(record),
+
+            // we decided to skip broken records
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!(records = records.len(), "ingested");
+    crate::metrics::record_batch("uring", records.len(), &dirty, start.elapsed());
+
+    Ok((records, dirty))
+}
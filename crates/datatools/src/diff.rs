@@ -0,0 +1,340 @@
+//! What changed between two versions of a dataset: both are read sorted by
+//! transaction id (spilled to disk past the memory budget, like
+//! [`crate::data_ingestion::open_file_spilled`]) and walked side by side, so
+//! records match up however the files are ordered.
+//!
+//! Ids compare the way [`crate::sort::sort_file`] orders them, `TXN01` and
+//! `TXN1` are the same transaction with a changed id. An id that's in a file
+//! more than once is matched up in file order
+use std::cmp::Ordering;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::data_ingestion::{ClientData, Column, IngestError, Layout, open_file_spilled};
+use crate::sort::{Key, SortOptions};
+
+/// Changes kept as examples by default
+pub const DEFAULT_DIFF_SAMPLES: usize = 10;
+
+/// Knobs for [`compare_with`]
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    /// shared by the two files, each gets half
+    pub memory_budget: usize,
+    pub temp_dir: Option<PathBuf>,
+    /// how many differences [`DiffReport::samples`] keeps
+    pub samples: usize,
+    /// amounts closer than this are the same, 0 means exactly equal
+    pub tolerance: f64,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            memory_budget: 256 * 1024 * 1024,
+            temp_dir: None,
+            samples: DEFAULT_DIFF_SAMPLES,
+            tolerance: 0.0,
+        }
+    }
+}
+
+impl DiffOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// e.g. `0.005` to ignore what rounding to cents does
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance.abs();
+        self
+    }
+}
+
+/// One record that isn't the same in both files
+#[derive(Debug, Clone, PartialEq)]
+pub enum Difference {
+    /// only in the second file
+    Added(ClientData),
+    /// only in the first file
+    Removed(ClientData),
+    Changed {
+        before: ClientData,
+        after: ClientData,
+        columns: Vec<Column>,
+    },
+}
+
+/// How many changed records had a different value in each column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ColumnChanges {
+    pub id: usize,
+    pub from_id: usize,
+    pub to_id: usize,
+    pub amount: usize,
+    pub timestamp: usize,
+    pub kind: usize,
+    pub currency: usize,
+}
+
+impl ColumnChanges {
+    fn count(&mut self, column: Column) {
+        match column {
+            Column::Id => self.id += 1,
+            Column::FromId => self.from_id += 1,
+            Column::ToId => self.to_id += 1,
+            Column::Amount => self.amount += 1,
+            Column::Timestamp => self.timestamp += 1,
+            Column::Kind => self.kind += 1,
+            Column::Currency => self.currency += 1,
+        }
+    }
+
+    /// (column, records) of the columns that changed anywhere
+    pub fn iter(&self) -> impl Iterator<Item = (Column, usize)> {
+        [
+            (Column::Id, self.id),
+            (Column::FromId, self.from_id),
+            (Column::ToId, self.to_id),
+            (Column::Amount, self.amount),
+            (Column::Timestamp, self.timestamp),
+            (Column::Kind, self.kind),
+            (Column::Currency, self.currency),
+        ]
+        .into_iter()
+        .filter(|&(_, n)| n > 0)
+    }
+}
+
+/// Where the total amount moved between the two files
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AmountDelta {
+    /// sum of the added records' amounts
+    pub added: f64,
+    /// sum of the removed records' amounts
+    pub removed: f64,
+    /// sum of after - before over the changed records
+    pub changed: f64,
+    /// the largest change of a single record, by size
+    pub largest: f64,
+}
+
+impl AmountDelta {
+    /// How much the second file's total differs from the first's
+    pub fn net(&self) -> f64 {
+        self.added - self.removed + self.changed
+    }
+}
+
+/// What [`compare`] found
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffReport {
+    /// records of the first file
+    pub left: usize,
+    /// records of the second file
+    pub right: usize,
+    pub unchanged: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub columns: ColumnChanges,
+    pub amount: AmountDelta,
+    /// the first [`DiffOptions::samples`] differences in id order
+    pub samples: Vec<Difference>,
+    /// lines that didn't parse in either file, left out of the comparison
+    pub skipped: usize,
+}
+
+impl DiffReport {
+    /// Whether both files have the same records, whatever their order
+    pub fn is_identical(&self) -> bool {
+        self.added == 0 && self.removed == 0 && self.changed == 0
+    }
+
+    fn sample(&mut self, options: &DiffOptions, difference: impl FnOnce() -> Difference) {
+        if self.samples.len() < options.samples {
+            self.samples.push(difference());
+        }
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "records: {} -> {}, unchanged: {}, added: {}, removed: {}, changed: {}",
+            self.left, self.right, self.unchanged, self.added, self.removed, self.changed
+        )?;
+        if self.skipped > 0 {
+            writeln!(f, "  {} lines skipped, they didn't parse", self.skipped)?;
+        }
+        for (column, records) in self.columns.iter() {
+            writeln!(f, "  {}: {records} changed", column.name())?;
+        }
+        writeln!(
+            f,
+            "amount: net={:+.2} (added={:.2}, removed={:.2}, changed={:+.2}, largest={:.2})",
+            self.amount.net(),
+            self.amount.added,
+            self.amount.removed,
+            self.amount.changed,
+            self.amount.largest
+        )?;
+        // every column, so a changed kind or currency shows up
+        let layout = Layout::default()
+            .with(Column::Timestamp)
+            .with(Column::Kind)
+            .with(Column::Currency);
+        for difference in &self.samples {
+            match difference {
+                Difference::Added(record) => writeln!(f, "+ {}", layout.to_line(record))?,
+                Difference::Removed(record) => writeln!(f, "- {}", layout.to_line(record))?,
+                Difference::Changed {
+                    before,
+                    after,
+                    columns,
+                } => {
+                    let names: Vec<&str> = columns.iter().map(|c| c.name()).collect();
+                    writeln!(f, "~ {} ({})", before.id, names.join(", "))?;
+                    writeln!(f, "  - {}", layout.to_line(before))?;
+                    writeln!(f, "  + {}", layout.to_line(after))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`compare_with`] with the default options
+pub fn compare(path_a: &str, path_b: &str) -> Result<DiffReport, IngestError> {
+    compare_with(path_a, path_b, &DiffOptions::default())
+}
+
+/// Records added, removed and changed from `path_a` to `path_b` (delimited or
+/// JSON Lines, compressed or not, the two don't have to match)
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(a = %path_a, b = %path_b))
+)]
+pub fn compare_with(
+    path_a: &str,
+    path_b: &str,
+    options: &DiffOptions,
+) -> Result<DiffReport, IngestError> {
+    let mut sort = SortOptions::new()
+        .key(Column::Id)
+        .memory_budget(options.memory_budget / 2);
+    if let Some(dir) = &options.temp_dir {
+        sort = sort.temp_dir(dir);
+    }
+    let left = open_file_spilled(path_a, &sort)?;
+    let right = open_file_spilled(path_b, &sort)?;
+
+    let mut report = DiffReport {
+        left: left.report.records,
+        right: right.report.records,
+        skipped: left.report.dirty.total() + right.report.dirty.total(),
+        ..DiffReport::default()
+    };
+    let mut a = left.iter()?;
+    let mut b = right.iter()?;
+    let mut next_a = a.next().transpose()?;
+    let mut next_b = b.next().transpose()?;
+
+    loop {
+        let order = match (&next_a, &next_b) {
+            (Some(ra), Some(rb)) => Key::of(ra, Column::Id).cmp(&Key::of(rb, Column::Id)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match order {
+            Ordering::Less => {
+                let record = next_a.take().expect("matched above");
+                report.removed += 1;
+                report.amount.removed += record.amount;
+                report.sample(options, || Difference::Removed(record));
+                next_a = a.next().transpose()?;
+            }
+            Ordering::Greater => {
+                let record = next_b.take().expect("matched above");
+                report.added += 1;
+                report.amount.added += record.amount;
+                report.sample(options, || Difference::Added(record));
+                next_b = b.next().transpose()?;
+            }
+            Ordering::Equal => {
+                let before = next_a.take().expect("matched above");
+                let after = next_b.take().expect("matched above");
+                let columns = changed_columns(&before, &after, options.tolerance);
+                if columns.is_empty() {
+                    report.unchanged += 1;
+                } else {
+                    report.changed += 1;
+                    for &column in &columns {
+                        report.columns.count(column);
+                    }
+                    let delta = after.amount - before.amount;
+                    report.amount.changed += delta;
+                    if delta.abs() > report.amount.largest.abs() {
+                        report.amount.largest = delta;
+                    }
+                    report.sample(options, || Difference::Changed {
+                        before,
+                        after,
+                        columns,
+                    });
+                }
+                next_a = a.next().transpose()?;
+                next_b = b.next().transpose()?;
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// The columns `before` and `after` differ in
+fn changed_columns(before: &ClientData, after: &ClientData, tolerance: f64) -> Vec<Column> {
+    let mut columns = Vec::new();
+    if before.id != after.id {
+        columns.push(Column::Id);
+    }
+    if before.from_id != after.from_id {
+        columns.push(Column::FromId);
+    }
+    if before.to_id != after.to_id {
+        columns.push(Column::ToId);
+    }
+    // NaN == NaN for this, it's the same value in both files
+    let same_amount = before.amount == after.amount
+        || (before.amount - after.amount).abs() <= tolerance
+        || (before.amount.is_nan() && after.amount.is_nan());
+    if !same_amount {
+        columns.push(Column::Amount);
+    }
+    if before.timestamp != after.timestamp {
+        columns.push(Column::Timestamp);
+    }
+    if before.kind != after.kind {
+        columns.push(Column::Kind);
+    }
+    if before.currency != after.currency {
+        columns.push(Column::Currency);
+    }
+    columns
+}
@@ -0,0 +1,134 @@
+use std::io::BufRead;
+
+pub(crate) const UTF8_BOM: [u8; 3] = [0xef, 0xbb, 0xbf];
+const UTF16LE_BOM: [u8; 2] = [0xff, 0xfe];
+const UTF16BE_BOM: [u8; 2] = [0xfe, 0xff];
+
+/// Text encoding of a data file, everything is transcoded to UTF-8 before parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// the BOM if there is one, UTF-16 if every other byte is a NUL, UTF-8 otherwise.
+    /// Latin-1 can't be told apart from a UTF-8 file with broken lines, it has to be asked for
+    #[default]
+    Auto,
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    /// read as windows-1252 like browsers do, the same as ISO-8859-1 except for 0x80..0xA0
+    Latin1,
+}
+
+impl Encoding {
+    /// `utf-8`, `utf-16le`, `latin-1`... case and dashes don't matter
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        Some(match name.as_str() {
+            "auto" => Encoding::Auto,
+            "utf8" => Encoding::Utf8,
+            "utf16" | "utf16le" => Encoding::Utf16Le,
+            "utf16be" => Encoding::Utf16Be,
+            "latin1" | "iso88591" | "windows1252" | "cp1252" => Encoding::Latin1,
+            _ => return None,
+        })
+    }
+
+    /// What [`Encoding::Auto`] makes of the first bytes of the input
+    pub fn detect(head: &[u8]) -> Self {
+        if head.starts_with(&UTF8_BOM) {
+            return Encoding::Utf8;
+        }
+        if head.starts_with(&UTF16LE_BOM) {
+            return Encoding::Utf16Le;
+        }
+        if head.starts_with(&UTF16BE_BOM) {
+            return Encoding::Utf16Be;
+        }
+
+        // ASCII text as UTF-16 without a BOM, the NULs are the high bytes
+        let pairs = head.chunks_exact(2);
+        if pairs.len() >= 2 {
+            let (even, odd) = pairs.fold((0, 0), |(even, odd), pair| {
+                (
+                    even + (pair[0] == 0) as usize,
+                    odd + (pair[1] == 0) as usize,
+                )
+            });
+            let half = head.len() / 4;
+            if odd > half && even == 0 {
+                return Encoding::Utf16Le;
+            }
+            if even > half && odd == 0 {
+                return Encoding::Utf16Be;
+            }
+        }
+        Encoding::Utf8
+    }
+}
+
+#[cfg(not(feature = "encoding"))]
+fn not_compiled_in(encoding: Encoding) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{encoding:?} input needs the `encoding` feature to be transcoded"),
+    )
+}
+
+/// [`crate::compression::open_reader`] transcoding to UTF-8 on the fly. UTF-8
+/// input goes through as is minus its BOM, so only other encodings pay for it
+pub fn open_reader(path: &str, encoding: Encoding) -> std::io::Result<Box<dyn BufRead + Send>> {
+    decode(crate::compression::open_reader(path)?, encoding)
+}
+
+/// [`open_reader`] for a stream that is already open and decompressed, e.g.
+/// an upload held in memory
+pub fn decode(
+    mut reader: Box<dyn BufRead + Send>,
+    encoding: Encoding,
+) -> std::io::Result<Box<dyn BufRead + Send>> {
+    // the first block of the decompressed stream, nothing is consumed yet
+    let head = reader.fill_buf()?;
+    let bom = head.starts_with(&UTF8_BOM);
+    let encoding = match encoding {
+        Encoding::Auto => Encoding::detect(head),
+        encoding => encoding,
+    };
+
+    if encoding == Encoding::Utf8 {
+        if bom {
+            reader.consume(UTF8_BOM.len());
+        }
+        return Ok(reader);
+    }
+    transcode(reader, encoding)
+}
+
+#[cfg(feature = "encoding")]
+fn transcode(
+    reader: Box<dyn BufRead + Send>,
+    encoding: Encoding,
+) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let encoding = match encoding {
+        Encoding::Utf16Le => encoding_rs::UTF_16LE,
+        Encoding::Utf16Be => encoding_rs::UTF_16BE,
+        Encoding::Latin1 => encoding_rs::WINDOWS_1252,
+        Encoding::Auto | Encoding::Utf8 => unreachable!("utf-8 is never transcoded"),
+    };
+    // malformed sequences come out as U+FFFD instead of failing the whole line
+    let decoder = encoding_rs_io::DecodeReaderBytesBuilder::new()
+        .encoding(Some(encoding))
+        .strip_bom(true)
+        .build(reader);
+    Ok(Box::new(std::io::BufReader::new(decoder)))
+}
+
+#[cfg(not(feature = "encoding"))]
+fn transcode(
+    _reader: Box<dyn BufRead + Send>,
+    encoding: Encoding,
+) -> std::io::Result<Box<dyn BufRead + Send>> {
+    Err(not_compiled_in(encoding))
+}
@@ -0,0 +1,311 @@
+//! Records out to other tools' formats, and back. For now that's SQLite: a
+//! `transactions` table anything that speaks SQL can open
+
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError};
+
+/// Rows per transaction, committing once per row is what makes naive inserts slow
+pub const DEFAULT_BATCH_ROWS: usize = 100_000;
+
+/// SQLite caps the parameters of one statement at 32766, 7 of them per row
+const MAX_ROWS_PER_INSERT: usize = 32766 / 7;
+
+/// What gets exported: records already loaded, or a file to stream them from
+#[derive(Debug, Clone, Copy)]
+pub enum Records<'a> {
+    Loaded(&'a [ClientData]),
+    /// delimited or JSON Lines, compressed or not, read a line at a time
+    File(&'a str),
+}
+
+impl<'a> From<&'a [ClientData]> for Records<'a> {
+    fn from(records: &'a [ClientData]) -> Self {
+        Records::Loaded(records)
+    }
+}
+
+impl<'a> From<&'a Vec<ClientData>> for Records<'a> {
+    fn from(records: &'a Vec<ClientData>) -> Self {
+        Records::Loaded(records)
+    }
+}
+
+impl<'a> From<&'a str> for Records<'a> {
+    fn from(path: &'a str) -> Self {
+        Records::File(path)
+    }
+}
+
+/// How [`to_sqlite_with`] inserts
+#[derive(Debug, Clone)]
+pub struct SqliteOptions {
+    /// rows per transaction, 1 commits every row on its own
+    pub batch_rows: usize,
+    /// rows per `INSERT`, more than 1 binds several `VALUES` tuples at once.
+    /// Capped at what fits in SQLite's parameter limit
+    pub rows_per_insert: usize,
+    /// index `id`, `from_id`, `to_id` and `timestamp` once everything is in
+    pub indexes: bool,
+}
+
+impl Default for SqliteOptions {
+    fn default() -> Self {
+        Self {
+            batch_rows: DEFAULT_BATCH_ROWS,
+            rows_per_insert: 1,
+            indexes: true,
+        }
+    }
+}
+
+impl SqliteOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn batch_rows(mut self, rows: usize) -> Self {
+        self.batch_rows = rows.max(1);
+        self
+    }
+
+    pub fn rows_per_insert(mut self, rows: usize) -> Self {
+        self.rows_per_insert = rows.clamp(1, MAX_ROWS_PER_INSERT);
+        self
+    }
+
+    pub fn indexes(mut self, indexes: bool) -> Self {
+        self.indexes = indexes;
+        self
+    }
+}
+
+/// What a finished export did
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExportReport {
+    pub records: usize,
+    /// lines of a [`Records::File`] that didn't parse, left out
+    pub dirty: DirtyDataReport,
+    /// transactions committed
+    pub batches: usize,
+}
+
+/// [`to_sqlite_with`] with the default options: batches of
+/// [`DEFAULT_BATCH_ROWS`], one row per prepared `INSERT`, indexed
+pub fn to_sqlite<'a>(
+    records: impl Into<Records<'a>>,
+    db_path: &str,
+) -> Result<ExportReport, IngestError> {
+    to_sqlite_with(records, db_path, &SqliteOptions::new())
+}
+
+#[cfg(feature = "sqlite")]
+pub use sqlite::{from_sqlite, to_sqlite_with};
+
+#[cfg(not(feature = "sqlite"))]
+fn not_compiled_in() -> IngestError {
+    IngestError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "SQLite export needs the `sqlite` feature",
+    ))
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn to_sqlite_with<'a>(
+    _records: impl Into<Records<'a>>,
+    _db_path: &str,
+    _options: &SqliteOptions,
+) -> Result<ExportReport, IngestError> {
+    Err(not_compiled_in())
+}
+
+#[cfg(not(feature = "sqlite"))]
+pub fn from_sqlite(_db_path: &str) -> Result<Vec<ClientData>, IngestError> {
+    Err(not_compiled_in())
+}
+
+#[cfg(feature = "sqlite")]
+mod sqlite {
+    use rusqlite::Connection;
+
+    use super::{ExportReport, MAX_ROWS_PER_INSERT, Records, SqliteOptions};
+    use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, RecordIter};
+
+    const COLUMNS: &str = "id, from_id, to_id, amount, timestamp, kind, currency";
+
+    // no primary key, generated data can have duplicate ids on purpose
+    const SCHEMA: &str = "
+        DROP TABLE IF EXISTS transactions;
+        CREATE TABLE transactions (
+            id TEXT NOT NULL,
+            from_id TEXT NOT NULL,
+            to_id TEXT NOT NULL,
+            amount REAL NOT NULL,
+            timestamp INTEGER,
+            kind TEXT NOT NULL,
+            currency TEXT
+        );";
+
+    // built once the rows are in, keeping them up to date row by row is slower
+    const INDEXES: &str = "
+        CREATE INDEX transactions_id ON transactions (id);
+        CREATE INDEX transactions_from_id ON transactions (from_id);
+        CREATE INDEX transactions_to_id ON transactions (to_id);
+        CREATE INDEX transactions_timestamp ON transactions (timestamp);";
+
+    /// `INSERT` of `rows` rows, one `VALUES` tuple each
+    fn insert_sql(rows: usize) -> String {
+        let tuple = "(?, ?, ?, ?, ?, ?, ?)";
+        let values = vec![tuple; rows].join(", ");
+        format!("INSERT INTO transactions ({COLUMNS}) VALUES {values}")
+    }
+
+    /// Inserts into an open transaction, and commits every `batch_rows`
+    struct Loader<'c> {
+        conn: &'c Connection,
+        batch_rows: usize,
+        /// rows since the last commit, 0 when no transaction is open
+        pending: usize,
+        batches: usize,
+    }
+
+    impl Loader<'_> {
+        fn insert(&mut self, rows: &[ClientData]) -> rusqlite::Result<()> {
+            if self.pending == 0 {
+                self.conn.execute_batch("BEGIN")?;
+            }
+            // cached, so it's only prepared once per chunk size
+            let mut stmt = self.conn.prepare_cached(&insert_sql(rows.len()))?;
+            for (i, record) in rows.iter().enumerate() {
+                let at = i * 7;
+                stmt.raw_bind_parameter(at + 1, &record.id)?;
+                stmt.raw_bind_parameter(at + 2, &record.from_id)?;
+                stmt.raw_bind_parameter(at + 3, &record.to_id)?;
+                stmt.raw_bind_parameter(at + 4, record.amount)?;
+                stmt.raw_bind_parameter(at + 5, record.timestamp)?;
+                stmt.raw_bind_parameter(at + 6, record.kind.as_str())?;
+                stmt.raw_bind_parameter(at + 7, record.currency.as_ref().map(|c| c.as_str()))?;
+            }
+            stmt.raw_execute()?;
+
+            self.pending += rows.len();
+            if self.pending >= self.batch_rows {
+                self.commit()?;
+            }
+            Ok(())
+        }
+
+        fn commit(&mut self) -> rusqlite::Result<()> {
+            if self.pending > 0 {
+                self.conn.execute_batch("COMMIT")?;
+                self.pending = 0;
+                self.batches += 1;
+            }
+            Ok(())
+        }
+    }
+
+    /// Writes the records into a `transactions` table of the database at
+    /// `db_path`, created if needed. A table of that name that's already there
+    /// is replaced. Rows go in through prepared statements, `options.batch_rows`
+    /// to a transaction
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(records)))]
+    pub fn to_sqlite_with<'a>(
+        records: impl Into<Records<'a>>,
+        db_path: &str,
+        options: &SqliteOptions,
+    ) -> Result<ExportReport, IngestError> {
+        let conn = Connection::open(db_path)?;
+        // a load that dies halfway leaves a table to redo anyway, so nothing
+        // waits on fsync. The pragmas only last as long as this connection
+        conn.execute_batch("PRAGMA synchronous = OFF; PRAGMA journal_mode = MEMORY;")?;
+        conn.execute_batch(SCHEMA)?;
+
+        let chunk = options.rows_per_insert.clamp(1, MAX_ROWS_PER_INSERT);
+        let mut loader = Loader {
+            conn: &conn,
+            batch_rows: options.batch_rows.max(1),
+            pending: 0,
+            batches: 0,
+        };
+        let mut report = ExportReport::default();
+
+        match records.into() {
+            Records::Loaded(records) => {
+                for rows in records.chunks(chunk) {
+                    loader.insert(rows)?;
+                }
+                report.records = records.len();
+            }
+            Records::File(path) => {
+                let mut rows = Vec::with_capacity(chunk);
+                for record in RecordIter::open(path)? {
+                    match record {
+                        Ok(record) => rows.push(record),
+                        Err(e) => {
+                            report.dirty.absorb(e)?;
+                            continue;
+                        }
+                    }
+                    if rows.len() == chunk {
+                        loader.insert(&rows)?;
+                        report.records += rows.len();
+                        rows.clear();
+                    }
+                }
+                if !rows.is_empty() {
+                    loader.insert(&rows)?;
+                    report.records += rows.len();
+                }
+            }
+        }
+        loader.commit()?;
+        report.batches = loader.batches;
+
+        if options.indexes {
+            conn.execute_batch(INDEXES)?;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            records = report.records,
+            batches = report.batches,
+            "exported"
+        );
+        Ok(report)
+    }
+
+    /// Every row of the `transactions` table [`to_sqlite_with`] wrote, in the
+    /// order they went in
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub fn from_sqlite(db_path: &str) -> Result<Vec<ClientData>, IngestError> {
+        let start = crate::Instant::now();
+        let conn =
+            Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM transactions ORDER BY rowid"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut records = Vec::new();
+
+        while let Some(row) = rows.next()? {
+            let kind: String = row.get(5)?;
+            let currency: Option<String> = row.get(6)?;
+            records.push(ClientData {
+                id: row.get(0)?,
+                from_id: row.get(1)?,
+                to_id: row.get(2)?,
+                amount: row.get(3)?,
+                timestamp: row.get(4)?,
+                kind: kind.parse()?,
+                currency: currency.map(|c| c.parse()).transpose()?,
+            });
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!(records = records.len(), "ingested");
+        crate::metrics::record_batch(
+            "sqlite",
+            records.len(),
+            &DirtyDataReport::default(),
+            start.elapsed(),
+        );
+        Ok(records)
+    }
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+/// On-disk layout of a dataset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// `id|from_id|to_id|amount` text, the original one
+    #[default]
+    Delimited,
+    /// columnar, needs the `parquet` feature
+    Parquet,
+    /// one JSON object per line, no header
+    JsonLines,
+}
+
+impl Format {
+    /// Guess from the extension, compression suffixes (`.gz`, `.zst`) are looked through
+    pub fn from_path(path: &str) -> Self {
+        let path = Path::new(path);
+        let path = match path.extension().and_then(|e| e.to_str()) {
+            Some("gz" | "zst") => Path::new(path.file_stem().unwrap_or_default()),
+            _ => path,
+        };
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("parquet") => Format::Parquet,
+            Some("jsonl" | "ndjson") => Format::JsonLines,
+            _ => Format::Delimited,
+        }
+    }
+}
@@ -0,0 +1,1408 @@
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::Duration;
+
+use rand::rngs::ChaCha8Rng;
+use rand::{RngExt, SeedableRng};
+
+use crate::Instant;
+use crate::cancel::CancellationToken;
+use crate::compression::Compression;
+use crate::data_ingestion::{ClientData, Column, Currency, Layout, TransactionKind};
+use crate::format::Format;
+
+mod accounts;
+mod corruption;
+mod disorder;
+mod ledger;
+#[cfg(feature = "parquet")]
+mod parquet;
+mod pipelined;
+#[cfg(feature = "indicatif")]
+mod progress_bar;
+mod resume;
+mod sampler;
+mod sharded;
+mod sink;
+mod throttle;
+
+pub use accounts::{AccountsConfig, generate_accounts, generate_accounts_to};
+use corruption::Corruption;
+pub use corruption::CorruptionProfile;
+pub use disorder::Disorder;
+use disorder::Reorder;
+use ledger::Balances;
+#[cfg(feature = "indicatif")]
+pub use progress_bar::IndicatifProgress;
+use sampler::Sampler;
+use sink::{Callback, FileSink};
+pub use sink::{GeneratedRecord, RecordSink, TextSink};
+use throttle::Throttle;
+
+#[cfg(feature = "parquet")]
+pub(crate) use parquet::ParquetSink;
+pub use pipelined::{
+    DEFAULT_BLOCK_BYTES, ParallelCompression, generate_mock_data_pipelined,
+    generate_mock_data_pipelined_with_progress,
+};
+
+pub use sharded::{generate_mock_data_sharded, generate_mock_data_sharded_with, shard_path};
+
+/// How much data to generate - roughly this many bytes, or exactly this many records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetSize {
+    Bytes(usize),
+    Records(usize),
+}
+
+/// `2gb`, `100MB`, `64k`... binary units, a bare number is bytes
+pub fn parse_size(size: &str) -> Result<usize, String> {
+    let size = size.trim().to_ascii_lowercase();
+    let digits = size.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let multiplier = match &size[digits.len()..] {
+        "" | "b" => 1,
+        "k" | "kb" => 1024,
+        "m" | "mb" => 1024 * 1024,
+        "g" | "gb" => 1024 * 1024 * 1024,
+        unit => return Err(format!("unknown size unit `{unit}`")),
+    };
+
+    let invalid = || format!("invalid size `{size}`");
+    let value: f64 = digits.parse().map_err(|_| invalid())?;
+    let bytes = value * multiplier as f64;
+    // `as usize` saturates, -3mb would come out as 0 and 1e30g as usize::MAX
+    if !bytes.is_finite() || bytes < 0.0 || bytes >= usize::MAX as f64 {
+        return Err(invalid());
+    }
+    Ok(bytes as usize)
+}
+
+/// How formatted lines get from the generator into the writer, `bench_generate` compares them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteStrategy {
+    /// a fresh buffer for every line, written as soon as it's formatted
+    #[default]
+    PerLine,
+    /// one line buffer, cleared and formatted into again after every write
+    ReusedBuffer,
+    /// this many lines held back and handed over in one `write_vectored`,
+    /// a single `writev` once they're more than the `BufWriter` holds
+    Vectored(usize),
+}
+
+/// What `BufWriter::new` gives you
+pub const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// Seed of [`GeneratorConfig::sample_dataset`]
+pub const SAMPLE_DATASET_SEED: u64 = 20_260_101;
+
+/// Shape of the generated `amount` column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AmountDistribution {
+    Uniform {
+        min: f64,
+        max: f64,
+    },
+    /// lots of small payments and a long tail of big ones, like real transfers.
+    /// `median` is in currency units, `sigma` the spread of `ln(amount)`
+    LogNormal {
+        median: f64,
+        sigma: f64,
+    },
+}
+
+/// How often each account shows up as `from_id` / `to_id`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum AccountDistribution {
+    /// every account equally likely, the original behavior
+    #[default]
+    Uniform,
+    /// account `k` (by id, the first one is the most popular) is picked with
+    /// probability proportional to `1 / k^exponent`, ~1.0 is the classic shape
+    Zipf { exponent: f64 },
+}
+
+/// A handful of accounts (exchanges, payroll) taking a fixed share of all traffic,
+/// on top of the [`AccountDistribution`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HotAccounts {
+    /// the hot ones are the first `count` account ids
+    pub count: u32,
+    /// 0.0..=1.0 of the picks that go to a hot account
+    pub share: f64,
+}
+
+impl Default for HotAccounts {
+    fn default() -> Self {
+        Self {
+            count: 100,
+            share: 0.2,
+        }
+    }
+}
+
+/// Optional `timestamp` column: unix seconds, increasing by a jittered step per record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timestamps {
+    pub start: i64,
+    /// steps are uniform in `0..=2 * mean_interval_secs`, so they average out to this
+    pub mean_interval_secs: u32,
+}
+
+impl Default for Timestamps {
+    fn default() -> Self {
+        Self {
+            // 2026-01-01T00:00:00Z
+            start: 1_767_225_600,
+            mean_interval_secs: 1,
+        }
+    }
+}
+
+/// Relative weights of the `kind` column, they don't have to add up to 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KindMix {
+    pub transfer: f64,
+    pub deposit: f64,
+    pub withdrawal: f64,
+    pub fee: f64,
+}
+
+impl Default for KindMix {
+    fn default() -> Self {
+        Self {
+            transfer: 0.7,
+            deposit: 0.12,
+            withdrawal: 0.12,
+            fee: 0.06,
+        }
+    }
+}
+
+impl KindMix {
+    fn weight(&self, kind: TransactionKind) -> f64 {
+        match kind {
+            TransactionKind::Transfer => self.transfer,
+            TransactionKind::Deposit => self.deposit,
+            TransactionKind::Withdrawal => self.withdrawal,
+            TransactionKind::Fee => self.fee,
+        }
+    }
+
+    fn pick(&self, rng: &mut impl RngExt) -> TransactionKind {
+        let total: f64 = TransactionKind::ALL.iter().map(|&k| self.weight(k)).sum();
+        let mut roll = rng.random::<f64>() * total;
+        for kind in TransactionKind::ALL {
+            roll -= self.weight(kind);
+            if roll < 0.0 {
+                return kind;
+            }
+        }
+        // only reachable through float rounding (or all weights zero)
+        TransactionKind::default()
+    }
+}
+
+/// Relative weights of the `currency` column's codes, like [`KindMix`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencyMix {
+    pub weights: Vec<(Currency, f64)>,
+}
+
+impl Default for CurrencyMix {
+    /// Mostly dollars and euros
+    fn default() -> Self {
+        Self::new(&[
+            (Currency::USD, 0.55),
+            (Currency::EUR, 0.25),
+            (Currency::GBP, 0.1),
+            (Currency::JPY, 0.05),
+            (Currency::CHF, 0.05),
+        ])
+    }
+}
+
+impl CurrencyMix {
+    pub fn new(weights: &[(Currency, f64)]) -> Self {
+        Self {
+            weights: weights.to_vec(),
+        }
+    }
+
+    fn pick(&self, rng: &mut impl RngExt) -> Option<Currency> {
+        let total: f64 = self.weights.iter().map(|&(_, weight)| weight).sum();
+        let mut roll = rng.random::<f64>() * total;
+        for &(currency, weight) in &self.weights {
+            roll -= weight;
+            if roll < 0.0 {
+                return Some(currency);
+            }
+        }
+        // float rounding again, or nothing to pick from
+        self.weights.last().map(|&(currency, _)| currency)
+    }
+}
+
+/// Internally consistent money: every account opens with the same balance, and
+/// transfers, withdrawals and fees are capped at what the sender has. Only
+/// deposits bring new money in, so the file can be checked with
+/// [`crate::analysis::verify_conservation`]. Each file (or shard) runs its own ledger
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ledger {
+    pub opening_balance: f64,
+}
+
+impl Default for Ledger {
+    fn default() -> Self {
+        Self {
+            opening_balance: 10_000.0,
+        }
+    }
+}
+
+/// Knobs for [`generate_mock_data_with`], defaults match [`generate_mock_data`]
+#[derive(Debug, Clone)]
+pub struct GeneratorConfig {
+    pub target: TargetSize,
+    pub corruption: CorruptionProfile,
+    pub amounts: AmountDistribution,
+    pub accounts: u32,
+    pub account_distribution: AccountDistribution,
+    pub hot_accounts: Option<HotAccounts>,
+    pub delimiter: char,
+    pub header: bool,
+    /// end every line with `\r\n`, like files exported on Windows
+    pub crlf: bool,
+    pub seed: Option<u64>,
+    /// `None` picks the codec from the output extension (`.gz`, `.zst`)
+    pub compression: Option<Compression>,
+    /// `None` picks the format from the output extension (`.parquet`)
+    pub format: Option<Format>,
+    pub timestamps: Option<Timestamps>,
+    pub kinds: Option<KindMix>,
+    pub currencies: Option<CurrencyMix>,
+    /// `TXN` number of the first record
+    pub first_id: usize,
+    /// carry on from what's already in the output file instead of overwriting it
+    pub append: bool,
+    pub ledger: Option<Ledger>,
+    pub disorder: Option<Disorder>,
+    /// emit records in real time at this rate instead of as fast as possible,
+    /// flushing as it goes so whoever reads the output sees them arrive
+    pub records_per_second: Option<f64>,
+    /// records between progress callbacks, [`PROGRESS_EVERY`] by default
+    pub progress_every: usize,
+    /// bytes the `BufWriter` in front of text output holds, [`DEFAULT_BUFFER_CAPACITY`] by default
+    pub buffer_capacity: usize,
+    pub write_strategy: WriteStrategy,
+    /// write delimited lines through the `csv` crate, quoting fields that need it
+    #[cfg(feature = "csv")]
+    pub quoting: bool,
+    /// stops the run early when cancelled, see [`GenerationReport::cancelled`]
+    pub cancel: Option<CancellationToken>,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            target: TargetSize::Bytes(1024 * 1024 * 1024),
+            corruption: CorruptionProfile::default(),
+            amounts: AmountDistribution::Uniform {
+                min: 1.0,
+                max: 100000.0,
+            },
+            accounts: 8_999_999,
+            account_distribution: AccountDistribution::Uniform,
+            hot_accounts: None,
+            delimiter: '|',
+            header: true,
+            crlf: false,
+            seed: None,
+            compression: None,
+            format: None,
+            timestamps: None,
+            kinds: None,
+            currencies: None,
+            first_id: 0,
+            append: false,
+            ledger: None,
+            disorder: None,
+            records_per_second: None,
+            progress_every: PROGRESS_EVERY,
+            buffer_capacity: DEFAULT_BUFFER_CAPACITY,
+            write_strategy: WriteStrategy::PerLine,
+            #[cfg(feature = "csv")]
+            quoting: false,
+            cancel: None,
+        }
+    }
+}
+
+impl GeneratorConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What [`crate::data_ingestion::SAMPLE_DATASET`] was generated with: 1000
+    /// clean records between 100 accounts, every optional column, a fixed seed
+    pub fn sample_dataset() -> Self {
+        Self::new()
+            .target_records(1000)
+            .accounts(100)
+            .timestamps(Timestamps::default())
+            .kinds(KindMix::default())
+            .currencies(CurrencyMix::default())
+            .corruption(CorruptionProfile::none())
+            .seed(SAMPLE_DATASET_SEED)
+    }
+
+    pub fn target_bytes(mut self, bytes: usize) -> Self {
+        self.target = TargetSize::Bytes(bytes);
+        self
+    }
+
+    pub fn target_records(mut self, records: usize) -> Self {
+        self.target = TargetSize::Records(records);
+        self
+    }
+
+    /// Chance (0.0..=1.0) of a single record getting a corrupted delimiter
+    pub fn corruption_probability(mut self, probability: f64) -> Self {
+        self.corruption.delimiter = probability;
+        self
+    }
+
+    /// All the other ways to break a record, see [`CorruptionProfile`]
+    pub fn corruption(mut self, corruption: CorruptionProfile) -> Self {
+        self.corruption = corruption;
+        self
+    }
+
+    pub fn amounts(mut self, amounts: AmountDistribution) -> Self {
+        self.amounts = amounts;
+        self
+    }
+
+    /// Number of distinct account IDs used for `from_id` / `to_id`
+    pub fn accounts(mut self, accounts: u32) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    pub fn account_distribution(mut self, distribution: AccountDistribution) -> Self {
+        self.account_distribution = distribution;
+        self
+    }
+
+    pub fn hot_accounts(mut self, hot_accounts: HotAccounts) -> Self {
+        self.hot_accounts = Some(hot_accounts);
+        self
+    }
+
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    pub fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// `\r\n` line endings throughout, header and blank lines included
+    pub fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// Same seed -> byte-identical output, corrupted records included
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Parquet output ignores the delimiter, header and corruption settings, JSON Lines the first two
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Adds the `timestamp` column
+    pub fn timestamps(mut self, timestamps: Timestamps) -> Self {
+        self.timestamps = Some(timestamps);
+        self
+    }
+
+    /// Adds the `kind` column
+    pub fn kinds(mut self, kinds: KindMix) -> Self {
+        self.kinds = Some(kinds);
+        self
+    }
+
+    /// Adds the `currency` column
+    pub fn currencies(mut self, currencies: CurrencyMix) -> Self {
+        self.currencies = Some(currencies);
+        self
+    }
+
+    #[cfg(feature = "csv")]
+    pub fn quoting(mut self, quoting: bool) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Id of the first record, so shards of one dataset don't reuse ids
+    pub fn first_id(mut self, first_id: usize) -> Self {
+        self.first_id = first_id;
+        self
+    }
+
+    /// Resume an interrupted run: the existing file is kept, a truncated last line
+    /// dropped and generation continues until the target is reached. Plain text only,
+    /// compressed and parquet output can't be appended to. With the seed of the
+    /// interrupted run the result is byte-identical to an uninterrupted one
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Keep a balance per account and never let one go below zero, see [`Ledger`]
+    pub fn ledger(mut self, ledger: Ledger) -> Self {
+        self.ledger = Some(ledger);
+        self
+    }
+
+    /// Repeat some records and write others late, see [`Disorder`]
+    pub fn disorder(mut self, disorder: Disorder) -> Self {
+        self.disorder = Some(disorder);
+        self
+    }
+
+    /// A live feed rather than a file that's complete right away, see
+    /// [`GeneratorConfig::records_per_second`]
+    pub fn records_per_second(mut self, records_per_second: f64) -> Self {
+        self.records_per_second = Some(records_per_second);
+        self
+    }
+
+    /// More often for something that redraws, e.g. a progress bar
+    pub fn progress_every(mut self, records: usize) -> Self {
+        self.progress_every = records.max(1);
+        self
+    }
+
+    /// 0 is unbuffered, every line its own write
+    pub fn buffer_capacity(mut self, bytes: usize) -> Self {
+        self.buffer_capacity = bytes;
+        self
+    }
+
+    /// Stop at the next record once `token` is cancelled
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn write_strategy(mut self, strategy: WriteStrategy) -> Self {
+        self.write_strategy = strategy;
+        self
+    }
+
+    /// Columns the generated files will have
+    pub fn layout(&self) -> Layout {
+        let mut layout = Layout::default();
+        if self.timestamps.is_some() {
+            layout = layout.with(Column::Timestamp);
+        }
+        if self.kinds.is_some() {
+            layout = layout.with(Column::Kind);
+        }
+        if self.currencies.is_some() {
+            layout = layout.with(Column::Currency);
+        }
+        layout
+    }
+
+    /// ChaCha instead of `StdRng` - its output is stable across platforms and releases
+    fn rng(&self) -> ChaCha8Rng {
+        match self.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => rand::make_rng(),
+        }
+    }
+
+    /// What the builder lets through but the generation loop would panic on,
+    /// checked before anything is created
+    fn check(&self) -> std::io::Result<()> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+        if self.accounts == 0 {
+            return Err(invalid("there has to be at least one account".into()));
+        }
+        // the csv writer takes a single byte
+        #[cfg(feature = "csv")]
+        if self.quoting && !self.delimiter.is_ascii() {
+            return Err(invalid(format!(
+                "quoting needs an ASCII delimiter, got `{}`",
+                self.delimiter
+            )));
+        }
+
+        let mut chances: Vec<(String, f64)> = self
+            .corruption
+            .rates()
+            .into_iter()
+            .map(|(name, rate)| (format!("corruption.{name}"), rate))
+            .collect();
+        if let Some(hot) = self.hot_accounts {
+            chances.push(("hot_accounts.share".into(), hot.share));
+        }
+        if let Some(disorder) = self.disorder {
+            chances.push(("disorder.duplicate".into(), disorder.duplicate));
+            chances.push(("disorder.shuffle".into(), disorder.shuffle));
+        }
+        match chances
+            .into_iter()
+            .find(|(_, chance)| !(0.0..=1.0).contains(chance))
+        {
+            Some((name, chance)) => Err(invalid(format!(
+                "{name} is a chance, it has to be within 0.0..=1.0, got {chance}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    fn is_done(&self, written_bytes: usize, record_count: usize) -> bool {
+        match self.target {
+            TargetSize::Bytes(bytes) => written_bytes >= bytes,
+            TargetSize::Records(records) => record_count >= records,
+        }
+    }
+}
+
+/// The random part of one record, drawn in the order the output depends on
+struct Draw {
+    from: u32,
+    to: u32,
+    amount: f64,
+    corruption: Corruption,
+    timestamp: Option<i64>,
+    kind: TransactionKind,
+    currency: Option<Currency>,
+}
+
+impl Draw {
+    fn next(
+        rng: &mut ChaCha8Rng,
+        sampler: &Sampler,
+        config: &GeneratorConfig,
+        clock: &mut Option<i64>,
+    ) -> Self {
+        let from = sampler.account(rng);
+        let to = sampler.account(rng);
+        let amount = sampler.amount(rng);
+
+        // Inject corruption (lowercase L instead of the delimiter)
+        let delimiter = rng.random_bool(config.corruption.delimiter);
+
+        let mut timestamp = None;
+        if let (Some(clock), Some(timestamps)) = (clock.as_mut(), config.timestamps) {
+            *clock += rng.random_range(0..=2 * timestamps.mean_interval_secs as i64);
+            timestamp = Some(*clock);
+        }
+        let kind = match &config.kinds {
+            Some(kinds) => kinds.pick(rng),
+            None => TransactionKind::default(),
+        };
+        let corruption = Corruption::draw(rng, &config.corruption, delimiter);
+        // after everything else, seeded output without currencies stays the same
+        let currency = config
+            .currencies
+            .as_ref()
+            .and_then(|currencies| currencies.pick(rng));
+
+        Self {
+            from,
+            to,
+            amount,
+            corruption,
+            timestamp,
+            kind,
+            currency,
+        }
+    }
+
+    fn into_record(self, id: usize) -> ClientData {
+        ClientData {
+            id: format!("TXN{id:010}"),
+            from_id: account_id(self.from),
+            to_id: account_id(self.to),
+            amount: self.amount,
+            timestamp: self.timestamp,
+            kind: self.kind,
+            currency: self.currency,
+        }
+    }
+}
+
+/// The id of the 0-based account `index`, in the records and the accounts table
+fn account_id(index: u32) -> String {
+    format!("ACC{:08}", 1000000 + index)
+}
+
+/// One record as it goes into the file
+#[derive(Clone)]
+struct Line {
+    /// what the line was formatted from, the line-level breakage only shows in `bytes`
+    record: ClientData,
+    bytes: Vec<u8>,
+    malformed: usize,
+}
+
+/// Everything that carries over from one record to the next
+struct Source<'a> {
+    config: &'a GeneratorConfig,
+    rng: ChaCha8Rng,
+    sampler: Sampler,
+    clock: Option<i64>,
+    balances: Option<Balances>,
+    /// the last written line's buffer, formatted into again unless it's [`WriteStrategy::PerLine`]
+    spare: Vec<u8>,
+}
+
+impl<'a> Source<'a> {
+    fn new(config: &'a GeneratorConfig) -> std::io::Result<Self> {
+        Ok(Self {
+            config,
+            // this is must be mutable because it changes the state every time it generates a new number!
+            rng: config.rng(),
+            sampler: Sampler::new(config)?,
+            clock: config.timestamps.map(|t| t.start),
+            balances: config
+                .ledger
+                .map(|ledger| Balances::new(&ledger, config.accounts)),
+            spare: Vec::new(),
+        })
+    }
+
+    /// Takes back the buffer of a line that's been written
+    fn recycle(&mut self, spent: Vec<u8>) {
+        if self.config.write_strategy != WriteStrategy::PerLine {
+            self.spare = spent;
+        }
+    }
+
+    fn draw(&mut self) -> Draw {
+        let mut draw = Draw::next(&mut self.rng, &self.sampler, self.config, &mut self.clock);
+        if let Some(balances) = &mut self.balances {
+            draw.amount = balances.settle(draw.kind, draw.from, draw.to, draw.amount);
+        }
+        draw
+    }
+
+    fn next_line(&mut self, record_count: usize, layout: &Layout, format: Format) -> Line {
+        let config = self.config;
+        let draw = self.draw();
+        let corruption = draw.corruption;
+        let id = config.first_id + record_count;
+        let mut record = draw.into_record(match corruption.duplicate_id {
+            true => id.saturating_sub(1),
+            false => id,
+        });
+        if corruption.negative_amount {
+            record.amount = -record.amount;
+        }
+
+        let corrupted = corruption.delimiter;
+        let mut buffer = std::mem::take(&mut self.spare);
+        buffer.clear();
+        buffer.reserve(96);
+        let line = String::from_utf8(buffer).expect("an empty buffer is valid UTF-8");
+        let line = match format {
+            Format::JsonLines => json_line(line, &record, layout, corrupted),
+            #[cfg(feature = "csv")]
+            _ if config.quoting => quoted_line(line, &record, layout, config.delimiter, corrupted),
+            _ => delimited_line(line, &record, layout, config.delimiter, corrupted),
+        };
+
+        let bytes = corruption.apply(line, layout, config.delimiter, format);
+        Line {
+            bytes: match config.crlf {
+                true => to_crlf(bytes),
+                false => bytes,
+            },
+            malformed: corruption.malformed_lines(format),
+            record,
+        }
+    }
+}
+
+/// Every `\n` of a formatted line as `\r\n`, the ones the crlf corruption got already stay
+fn to_crlf(bytes: Vec<u8>) -> Vec<u8> {
+    let mut crlf = Vec::with_capacity(bytes.len() + 2);
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'\n' && (i == 0 || bytes[i - 1] != b'\r') {
+            crlf.push(b'\r');
+        }
+        crlf.push(b);
+    }
+    crlf
+}
+
+/// What a finished generation run produced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationReport {
+    pub records: usize,
+    /// size of the text representation, see [`generate_mock_data_with`]
+    pub bytes: usize,
+    pub malformed_records: usize,
+    /// extra copies written by [`Disorder`], not part of `records`
+    pub duplicates: usize,
+    pub elapsed: Duration,
+    /// [`GeneratorConfig::cancel`] stopped the run before the target. The
+    /// output ends after a complete record but gets no checksum sidecar, so it
+    /// reads as an interrupted run (plain text can be continued with `append`)
+    pub cancelled: bool,
+}
+
+/// Handed to the progress callback every [`GeneratorConfig::progress_every`] records
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub records: usize,
+    pub bytes: usize,
+    pub malformed_records: usize,
+    pub elapsed: Duration,
+    /// what the run is heading for, to show a percentage
+    pub target: TargetSize,
+    /// since the last callback
+    pub records_per_second: f64,
+    pub bytes_per_second: f64,
+    /// since the start of the run, or where an appending run picked up
+    pub average_records_per_second: f64,
+    pub average_bytes_per_second: f64,
+}
+
+impl Progress {
+    /// 0.0..=1.0 of the target
+    pub fn fraction(&self) -> f64 {
+        let fraction = match self.target {
+            TargetSize::Bytes(bytes) => self.bytes as f64 / bytes as f64,
+            TargetSize::Records(records) => self.records as f64 / records as f64,
+        };
+        fraction.min(1.0)
+    }
+
+    /// Time left at the average throughput so far, `None` before there is one
+    pub fn eta(&self) -> Option<Duration> {
+        let (left, per_second) = match self.target {
+            TargetSize::Bytes(bytes) => (
+                bytes.saturating_sub(self.bytes),
+                self.average_bytes_per_second,
+            ),
+            TargetSize::Records(records) => (
+                records.saturating_sub(self.records),
+                self.average_records_per_second,
+            ),
+        };
+        (per_second > 0.0).then(|| Duration::from_secs_f64(left as f64 / per_second))
+    }
+}
+
+pub const PROGRESS_EVERY: usize = 1_000_000;
+
+pub fn generate_mock_data(path: &str, target_size_gb: usize) -> std::io::Result<GenerationReport> {
+    let config = GeneratorConfig::new().target_bytes(target_size_gb * 1024 * 1024 * 1024);
+    generate_mock_data_with(path, &config)
+}
+
+/// Compressed streams and parquet footers can't just be continued
+fn check_appendable(path: &str, format: Format, config: &GeneratorConfig) -> std::io::Result<()> {
+    let compression = config
+        .compression
+        .unwrap_or_else(|| Compression::from_path(path));
+    if format == Format::Parquet || compression != Compression::None {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "only plain text output can be appended to",
+        ));
+    }
+    // resuming counts lines to know how many records are in
+    if config.corruption.empty_line > 0.0 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "can't append with empty line corruption, the line count wouldn't add up",
+        ));
+    }
+    if config.disorder.is_some() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "can't append with disorder, the line count wouldn't add up",
+        ));
+    }
+    Ok(())
+}
+
+/// Hands one line to the sink, from the loop or from the disorder buffer flushed after it
+fn emit(
+    sink: &mut impl RecordSink,
+    source: &mut Source<'_>,
+    written_bytes: &mut usize,
+    malformed_records: &mut usize,
+    line: Line,
+) -> std::io::Result<()> {
+    *written_bytes += line.bytes.len();
+    // only text carries the breakage, everything else gets the record clean
+    if sink.is_text() {
+        *malformed_records += line.malformed;
+    }
+    let spent = sink.write(GeneratedRecord {
+        record: rounded(line.record),
+        line: line.bytes,
+    })?;
+    source.recycle(spent);
+    Ok(())
+}
+
+/// The amount as it would read back from a text file, two decimals
+fn rounded(mut record: ClientData) -> ClientData {
+    record.amount = (record.amount * 100.0).round() / 100.0;
+    record
+}
+
+/// One text line for the record, the corrupted delimiter (if any) is the one in front of `to_id`
+fn delimited_line(
+    mut line: String,
+    record: &ClientData,
+    layout: &Layout,
+    d: char,
+    corrupted: bool,
+) -> String {
+    for (i, column) in layout.columns().iter().enumerate() {
+        if i > 0 {
+            line.push(if corrupted && *column == Column::ToId {
+                'l'
+            } else {
+                d
+            });
+        }
+        match column {
+            Column::Id => line.push_str(&record.id),
+            Column::FromId => line.push_str(&record.from_id),
+            Column::ToId => line.push_str(&record.to_id),
+            Column::Amount => write!(line, "{:.2}", record.amount).unwrap(),
+            Column::Timestamp => {
+                if let Some(ts) = record.timestamp {
+                    write!(line, "{ts}").unwrap();
+                }
+            }
+            Column::Kind => line.push_str(record.kind.as_str()),
+            Column::Currency => {
+                if let Some(currency) = record.currency {
+                    line.push_str(currency.as_str());
+                }
+            }
+        }
+    }
+    line.push('\n');
+    line
+}
+
+/// [`delimited_line`] through the `csv` crate, the corrupted record gets `from_id` and `to_id`
+/// glued into one field so it's broken the same way
+#[cfg(feature = "csv")]
+fn quoted_line(
+    line: String,
+    record: &ClientData,
+    layout: &Layout,
+    d: char,
+    corrupted: bool,
+) -> String {
+    let mut fields: Vec<String> = Vec::with_capacity(layout.columns().len());
+    for column in layout.columns() {
+        let field = match column {
+            Column::Id => record.id.clone(),
+            Column::FromId => record.from_id.clone(),
+            Column::ToId if corrupted => {
+                let from_id = fields.pop().unwrap_or_default();
+                format!("{from_id}l{}", record.to_id)
+            }
+            Column::ToId => record.to_id.clone(),
+            Column::Amount => format!("{:.2}", record.amount),
+            Column::Timestamp => record
+                .timestamp
+                .map(|ts| ts.to_string())
+                .unwrap_or_default(),
+            Column::Kind => record.kind.as_str().to_string(),
+            Column::Currency => record.currency.map(String::from).unwrap_or_default(),
+        };
+        fields.push(field);
+    }
+
+    // its own buffer is one per line, the line's goes in and comes back out
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(u8::try_from(d).expect("GeneratorConfig::check lets only ASCII through"))
+        .buffer_capacity(256)
+        .from_writer(line.into_bytes());
+    writer
+        .write_record(&fields)
+        .expect("writing to a Vec can't fail");
+    let line = writer.into_inner().expect("writing to a Vec can't fail");
+    String::from_utf8(line).expect("fields are valid UTF-8")
+}
+
+/// Same trick for JSON, the comma in front of `to_id` goes missing
+fn json_line(mut line: String, record: &ClientData, layout: &Layout, corrupted: bool) -> String {
+    line.push('{');
+    for (i, column) in layout.columns().iter().enumerate() {
+        if i > 0 {
+            line.push(if corrupted && *column == Column::ToId {
+                'l'
+            } else {
+                ','
+            });
+        }
+        let name = column.name();
+        match column {
+            Column::Id => write!(line, r#""{name}":"{}""#, record.id),
+            Column::FromId => write!(line, r#""{name}":"{}""#, record.from_id),
+            Column::ToId => write!(line, r#""{name}":"{}""#, record.to_id),
+            Column::Amount => write!(line, r#""{name}":{:.2}"#, record.amount),
+            Column::Timestamp => match record.timestamp {
+                Some(ts) => write!(line, r#""{name}":{ts}"#),
+                None => write!(line, r#""{name}":null"#),
+            },
+            Column::Kind => write!(line, r#""{name}":"{}""#, record.kind),
+            Column::Currency => match record.currency {
+                Some(currency) => write!(line, r#""{name}":"{currency}""#),
+                None => write!(line, r#""{name}":null"#),
+            },
+        }
+        .unwrap();
+    }
+    line.push_str("}\n");
+    line
+}
+
+/// Generates [`GeneratorConfig::sample_dataset`] again and compares it with the
+/// embedded [`crate::data_ingestion::SAMPLE_DATASET`], they only differ once
+/// the generator's output for a seed changed
+pub fn verify_sample_dataset() -> std::io::Result<bool> {
+    let mut data = Vec::new();
+    generate_mock_data_to(&mut data, &GeneratorConfig::sample_dataset(), |_| {})?;
+    Ok(data == crate::data_ingestion::SAMPLE_DATASET.as_bytes())
+}
+
+/// Generates records per `config`.
+///
+/// Byte targets count the size of the text representation, so a parquet file
+/// generated with the same target holds the same records but is smaller on disk.
+pub fn generate_mock_data_with(
+    path: &str,
+    config: &GeneratorConfig,
+) -> std::io::Result<GenerationReport> {
+    generate_mock_data_with_progress(path, config, |_| {})
+}
+
+/// [`generate_mock_data_with`], calling `on_progress` every
+/// [`GeneratorConfig::progress_every`] records
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path, target = ?config.target))
+)]
+pub fn generate_mock_data_with_progress(
+    path: &str,
+    config: &GeneratorConfig,
+    on_progress: impl FnMut(Progress),
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+
+    // file for file op, writer for writer ops (compressing if asked to)
+    let format = config.format.unwrap_or_else(|| Format::from_path(path));
+    // a sidecar from an earlier run would vouch for a file we're about to replace
+    match std::fs::remove_file(crate::checksum::sidecar_path(path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let header = format == Format::Delimited && config.header;
+    let resume = if config.append {
+        check_appendable(path, format, config)?;
+        resume::recover(path, header)?
+    } else {
+        None
+    };
+    let report = match resume {
+        Some(_) => generate(
+            TextSink::append(path, config)?,
+            config,
+            format,
+            resume,
+            start,
+            on_progress,
+        )?,
+        None => generate(
+            FileSink::create(path, format, config)?,
+            config,
+            format,
+            None,
+            start,
+            on_progress,
+        )?,
+    };
+    // only written once the file is complete, so a missing sidecar means an interrupted run
+    if !report.cancelled {
+        crate::checksum::write_sidecar(path)?;
+    }
+    Ok(report)
+}
+
+/// [`generate_mock_data_with_progress`] into any writer instead of a file, e.g.
+/// stdout to pipe into another tool. Text formats only (delimited unless
+/// `config.format` says otherwise), compressed if `config.compression` asks for it.
+/// There's no file, so no checksum sidecar and no `append`
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(target = ?config.target))
+)]
+pub fn generate_mock_data_to(
+    writer: impl Write,
+    config: &GeneratorConfig,
+    on_progress: impl FnMut(Progress),
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+
+    let format = config.format.unwrap_or_default();
+    if format == Format::Parquet {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "parquet needs a file, its footer points back into it",
+        ));
+    }
+    if config.append {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "only a file can be appended to",
+        ));
+    }
+    let compression = config.compression.unwrap_or_default();
+    // one write per line otherwise, stdout would flush on every newline
+    let sink = TextSink::new(
+        std::io::BufWriter::with_capacity(config.buffer_capacity, writer),
+        compression,
+        config.write_strategy,
+    )?;
+
+    generate(sink, config, format, None, start, on_progress)
+}
+
+/// [`generate_mock_data_with_progress`] into any [`RecordSink`]: a
+/// [`TextSink`] writes the lines in `config.format` (delimited by default), any
+/// other sink gets the clean records. No file, so no checksum sidecar and no
+/// `append`
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(target = ?config.target))
+)]
+pub fn generate_mock_data_into(
+    sink: impl RecordSink,
+    config: &GeneratorConfig,
+    on_progress: impl FnMut(Progress),
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+
+    let format = config.format.unwrap_or_default();
+    if format == Format::Parquet && sink.is_text() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "parquet isn't text, generate it into a file",
+        ));
+    }
+    if config.append {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "only a file can be appended to",
+        ));
+    }
+
+    generate(sink, config, format, None, start, on_progress)
+}
+
+/// [`generate_mock_data_with`] into a callback instead of a file, e.g. to feed
+/// a consumer directly. Records come clean like in parquet output, corruption
+/// only exists in text. With [`GeneratorConfig::records_per_second`] they
+/// arrive in real time
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(target = ?config.target))
+)]
+pub fn generate_mock_records(
+    config: &GeneratorConfig,
+    on_record: impl FnMut(ClientData) -> std::io::Result<()>,
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+    generate(
+        Callback(on_record),
+        config,
+        Format::Delimited,
+        None,
+        start,
+        |_| {},
+    )
+}
+
+/// The generation loop, everything before it was about where `sink` goes
+fn generate(
+    mut sink: impl RecordSink,
+    config: &GeneratorConfig,
+    format: Format,
+    resume: Option<resume::Resume>,
+    start: Instant,
+    mut on_progress: impl FnMut(Progress),
+) -> std::io::Result<GenerationReport> {
+    let header = format == Format::Delimited && config.header;
+    let layout = config.layout();
+
+    let mut source = Source::new(config)?;
+    let mut written_bytes = 0;
+    let mut record_count = 0;
+    let mut malformed_records = 0;
+
+    match resume {
+        // replay the draws of the records already there, so the rng, the clock
+        // and the balances are where the interrupted run left them
+        Some(resume) => {
+            for _ in 0..resume.records {
+                let draw = source.draw();
+                malformed_records += draw.corruption.malformed_lines(format);
+            }
+            record_count = resume.records;
+            written_bytes = resume.bytes;
+            #[cfg(feature = "tracing")]
+            tracing::debug!(records = record_count, bytes = written_bytes, "appending");
+        }
+        // good old header for starters
+        None => {
+            if header && sink.is_text() {
+                let newline = if config.crlf { "\r\n" } else { "\n" };
+                let header = format!("{}{newline}", layout.header(config.delimiter));
+                sink.write_raw(header.as_bytes())?;
+            }
+        }
+    }
+
+    let mut reorder = config
+        .disorder
+        .map(|disorder| Reorder::new(disorder, config));
+    // a resumed run keeps the rate from where it picks up
+    let first_record = record_count;
+    let throttle = config.records_per_second.map(Throttle::new);
+    // (records, bytes, elapsed) the throughputs are measured from
+    let picked_up = (record_count, written_bytes, start.elapsed());
+    let mut last_progress = picked_up;
+
+    let mut cancelled = false;
+    while !config.is_done(written_bytes, record_count) {
+        if let Some(token) = &config.cancel
+            && token.is_cancelled()
+        {
+            cancelled = true;
+            break;
+        }
+        let line = source.next_line(record_count, &layout, format);
+
+        let mut emit = |line: Line| {
+            emit(
+                &mut sink,
+                &mut source,
+                &mut written_bytes,
+                &mut malformed_records,
+                line,
+            )
+        };
+        match &mut reorder {
+            Some(reorder) => reorder.place(record_count, line, emit)?,
+            None => emit(line)?,
+        }
+        record_count += 1;
+
+        if let Some(throttle) = &throttle
+            && throttle.is_due(record_count - first_record)
+        {
+            sink.flush()?;
+            throttle.wait(record_count - first_record);
+        }
+
+        if record_count.is_multiple_of(config.progress_every) {
+            let elapsed = start.elapsed();
+            let per_second = |(records, bytes, at): (usize, usize, Duration)| {
+                let secs = elapsed.saturating_sub(at).as_secs_f64().max(f64::EPSILON);
+                (
+                    (record_count - records) as f64 / secs,
+                    written_bytes.saturating_sub(bytes) as f64 / secs,
+                )
+            };
+            let (records_per_second, bytes_per_second) = per_second(last_progress);
+            let (average_records_per_second, average_bytes_per_second) = per_second(picked_up);
+            #[cfg(feature = "tracing")]
+            tracing::debug!(
+                records = record_count,
+                bytes = written_bytes,
+                malformed_records,
+                records_per_second,
+                bytes_per_second,
+                "progress"
+            );
+            on_progress(Progress {
+                records: record_count,
+                bytes: written_bytes,
+                malformed_records,
+                elapsed,
+                target: config.target,
+                records_per_second,
+                bytes_per_second,
+                average_records_per_second,
+                average_bytes_per_second,
+            });
+            last_progress = (record_count, written_bytes, elapsed);
+        }
+    }
+
+    let mut duplicates = 0;
+    if let Some(reorder) = &mut reorder {
+        reorder.flush(|line| {
+            emit(
+                &mut sink,
+                &mut source,
+                &mut written_bytes,
+                &mut malformed_records,
+                line,
+            )
+        })?;
+        duplicates = reorder.duplicates;
+    }
+
+    // half a record at the very end, no newline, as if the writer got killed
+    if config.corruption.truncated_last_line && !cancelled && sink.is_text() {
+        let line = source.next_line(record_count, &layout, format);
+        let cut = &line.bytes[..line.bytes.len() / 2];
+        sink.write_raw(cut)?;
+        written_bytes += cut.len();
+        malformed_records += 1;
+    }
+
+    sink.finish()?;
+
+    let report = GenerationReport {
+        records: record_count,
+        bytes: written_bytes,
+        malformed_records,
+        duplicates,
+        elapsed: start.elapsed(),
+        cancelled,
+    };
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        records = report.records,
+        bytes = report.bytes,
+        malformed_records,
+        duplicates,
+        elapsed_ms = report.elapsed.as_millis() as u64,
+        cancelled,
+        "generated"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn records(config: &GeneratorConfig) -> Vec<ClientData> {
+        let mut records = Vec::new();
+        generate_mock_data_into(&mut records, config, |_| {}).unwrap();
+        records
+    }
+
+    fn text(config: &GeneratorConfig) -> (Vec<u8>, GenerationReport) {
+        let mut bytes = Vec::new();
+        let sink = TextSink::new(&mut bytes, Compression::None, WriteStrategy::default()).unwrap();
+        let report = generate_mock_data_into(sink, config, |_| {}).unwrap();
+        (bytes, report)
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let config = GeneratorConfig::new()
+            .target_records(2_000)
+            .accounts(500)
+            .corruption(CorruptionProfile::all(0.01))
+            .seed(7);
+
+        assert_eq!(records(&config), records(&config));
+        assert_eq!(text(&config).0, text(&config).0);
+        // and the seed is what makes it the same
+        assert_ne!(text(&config).0, text(&config.clone().seed(8)).0);
+    }
+
+    #[test]
+    fn corruption_rate_near_the_chance() {
+        let chance = 0.05;
+        let config = GeneratorConfig::new()
+            .target_records(20_000)
+            .accounts(100)
+            .corruption(CorruptionProfile::none())
+            .corruption_probability(chance)
+            .seed(7);
+
+        let (bytes, report) = text(&config);
+        let broken = bytes
+            .split(|&b| b == b'\n')
+            .skip(1) // the header
+            .filter(|line| !line.is_empty())
+            .filter(|line| line.iter().filter(|&&b| b == b'|').count() != 3)
+            .count();
+        assert_eq!(broken, report.malformed_records);
+        let rate = broken as f64 / report.records as f64;
+        assert!((rate - chance).abs() < 0.01, "{rate} vs {chance}");
+
+        // the records themselves come out clean, only negative amounts get through
+        let config = config.corruption(CorruptionProfile {
+            negative_amount: chance,
+            ..CorruptionProfile::none()
+        });
+        let records = records(&config);
+        let negative = records.iter().filter(|r| r.amount < 0.0).count();
+        let rate = negative as f64 / records.len() as f64;
+        assert!((rate - chance).abs() < 0.01, "{rate} vs {chance}");
+    }
+
+    #[test]
+    fn account_pool_respected() {
+        let accounts = 50;
+        let config = GeneratorConfig::new()
+            .target_records(5_000)
+            .accounts(accounts)
+            .seed(7);
+
+        let records = records(&config);
+        assert_eq!(records.len(), 5_000);
+        let pool: HashSet<&str> = records
+            .iter()
+            .flat_map(|r| [r.from_id.as_str(), r.to_id.as_str()])
+            .collect();
+        // 10k draws from 50 accounts, every one of them shows up
+        assert_eq!(pool.len(), accounts as usize);
+        let ids: HashSet<String> = (0..accounts).map(account_id).collect();
+        assert!(pool.iter().all(|id| ids.contains(*id)));
+    }
+}
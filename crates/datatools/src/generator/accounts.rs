@@ -0,0 +1,127 @@
+use std::io::Write;
+
+use rand::rngs::ChaCha8Rng;
+use rand::{RngExt, SeedableRng};
+
+use super::{GeneratorConfig, account_id};
+use crate::compression::{CompressedWriter, Compression};
+use crate::data_ingestion::{ACCOUNTS_HEADER, RiskTier};
+use crate::partition::civil_from_days;
+
+/// Where accounts are opened, mostly the US and western Europe
+const COUNTRIES: [(&str, f64); 12] = [
+    ("US", 0.3),
+    ("DE", 0.1),
+    ("GB", 0.1),
+    ("FR", 0.08),
+    ("JP", 0.07),
+    ("CA", 0.06),
+    ("NL", 0.05),
+    ("CH", 0.05),
+    ("ES", 0.05),
+    ("IT", 0.05),
+    ("BR", 0.05),
+    ("IN", 0.04),
+];
+
+/// Most accounts are low risk, a few are watched closely
+const RISK_TIERS: [(RiskTier, f64); 3] = [
+    (RiskTier::Low, 0.8),
+    (RiskTier::Medium, 0.15),
+    (RiskTier::High, 0.05),
+];
+
+/// Days since 1970 of 2000-01-01 and 2025-12-31, accounts are opened in between
+const OPENED: std::ops::RangeInclusive<i64> = 10_957..=20_453;
+
+/// Knobs for [`generate_accounts`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccountsConfig {
+    pub accounts: u32,
+    pub seed: Option<u64>,
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self::for_transactions(&GeneratorConfig::default())
+    }
+}
+
+impl AccountsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A row for every account `config`'s records can be between
+    pub fn for_transactions(config: &GeneratorConfig) -> Self {
+        Self {
+            accounts: config.accounts,
+            seed: config.seed,
+        }
+    }
+
+    pub fn accounts(mut self, accounts: u32) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// Writes the accounts dimension table to `path`, compressed by its extension.
+/// Returns how many accounts it has
+pub fn generate_accounts(path: &str, config: &AccountsConfig) -> std::io::Result<usize> {
+    let mut writer = CompressedWriter::create(path, Compression::from_path(path))?;
+    let accounts = write_accounts(&mut writer, config)?;
+    writer.finish()?;
+    Ok(accounts)
+}
+
+/// [`generate_accounts`] into any writer, it should be buffered already
+pub fn generate_accounts_to(
+    mut writer: impl Write,
+    config: &AccountsConfig,
+) -> std::io::Result<usize> {
+    let accounts = write_accounts(&mut writer, config)?;
+    writer.flush()?;
+    Ok(accounts)
+}
+
+fn write_accounts(writer: &mut impl Write, config: &AccountsConfig) -> std::io::Result<usize> {
+    // the same seed as the transactions draws different numbers here, nothing
+    // in them depends on each other
+    let mut rng = match config.seed {
+        Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+        None => rand::make_rng(),
+    };
+
+    writeln!(writer, "{ACCOUNTS_HEADER}")?;
+    for index in 0..config.accounts {
+        let country = pick(&mut rng, &COUNTRIES);
+        let (year, month, day) = civil_from_days(rng.random_range(OPENED));
+        let risk_tier = pick(&mut rng, &RISK_TIERS);
+        writeln!(
+            writer,
+            "{}|{country}|{year:04}-{month:02}-{day:02}|{risk_tier}",
+            account_id(index)
+        )?;
+    }
+    Ok(config.accounts as usize)
+}
+
+/// Weighted pick like [`super::CurrencyMix`]'s
+fn pick<T: Copy>(rng: &mut impl RngExt, weights: &[(T, f64)]) -> T {
+    let total: f64 = weights.iter().map(|&(_, weight)| weight).sum();
+    let mut roll = rng.random::<f64>() * total;
+    for &(value, weight) in weights {
+        roll -= weight;
+        if roll < 0.0 {
+            return value;
+        }
+    }
+    // float rounding, the tables aren't empty
+    weights[weights.len() - 1].0
+}
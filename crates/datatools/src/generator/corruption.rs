@@ -0,0 +1,193 @@
+use rand::RngExt;
+
+use crate::data_ingestion::{Column, Layout};
+use crate::format::Format;
+
+/// Independent per-record chances (0.0..=1.0) for each way a line gets broken.
+///
+/// The field modes (missing, extra, non-numeric amount) only hit delimited
+/// output, parquet only gets the ones that survive typed columns (negative
+/// amounts, duplicated ids)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CorruptionProfile {
+    /// the original one, a lowercase L instead of the delimiter in front of `to_id`
+    pub delimiter: f64,
+    /// the last field is gone
+    pub missing_field: f64,
+    /// one field too many
+    pub extra_field: f64,
+    /// `N/A` where the amount should be
+    pub non_numeric_amount: f64,
+    /// parses fine, it's the analysis that should raise an eyebrow
+    pub negative_amount: f64,
+    /// a blank line in front of the record
+    pub empty_line: f64,
+    /// a `0xFF` byte in `from_id`
+    pub invalid_utf8: f64,
+    /// the id of the record before, parses fine
+    pub duplicate_id: f64,
+    /// `\r\n` instead of `\n`, parses fine
+    pub crlf: f64,
+    /// end the file with half a record, like an interrupted write
+    pub truncated_last_line: bool,
+}
+
+impl Default for CorruptionProfile {
+    fn default() -> Self {
+        Self {
+            // ~0.0001% of records get a lowercase L instead of a pipe
+            delimiter: 0.000_001,
+            ..Self::none()
+        }
+    }
+}
+
+impl CorruptionProfile {
+    /// Clean output
+    pub fn none() -> Self {
+        Self {
+            delimiter: 0.0,
+            missing_field: 0.0,
+            extra_field: 0.0,
+            non_numeric_amount: 0.0,
+            negative_amount: 0.0,
+            empty_line: 0.0,
+            invalid_utf8: 0.0,
+            duplicate_id: 0.0,
+            crlf: 0.0,
+            truncated_last_line: false,
+        }
+    }
+
+    /// Every mode at the same rate, and a truncated last line
+    pub fn all(rate: f64) -> Self {
+        Self {
+            delimiter: rate,
+            missing_field: rate,
+            extra_field: rate,
+            non_numeric_amount: rate,
+            negative_amount: rate,
+            empty_line: rate,
+            invalid_utf8: rate,
+            duplicate_id: rate,
+            crlf: rate,
+            truncated_last_line: true,
+        }
+    }
+}
+
+impl CorruptionProfile {
+    /// Every chance by its field name, for checking them
+    pub(super) fn rates(&self) -> [(&'static str, f64); 9] {
+        [
+            ("delimiter", self.delimiter),
+            ("missing_field", self.missing_field),
+            ("extra_field", self.extra_field),
+            ("non_numeric_amount", self.non_numeric_amount),
+            ("negative_amount", self.negative_amount),
+            ("empty_line", self.empty_line),
+            ("invalid_utf8", self.invalid_utf8),
+            ("duplicate_id", self.duplicate_id),
+            ("crlf", self.crlf),
+        ]
+    }
+}
+
+/// Which modes hit one record
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct Corruption {
+    pub delimiter: bool,
+    missing_field: bool,
+    extra_field: bool,
+    non_numeric_amount: bool,
+    pub negative_amount: bool,
+    empty_line: bool,
+    invalid_utf8: bool,
+    pub duplicate_id: bool,
+    crlf: bool,
+}
+
+impl Corruption {
+    /// `delimiter` is drawn by the caller, it's been in the draw order since before the others
+    pub fn draw(rng: &mut impl RngExt, profile: &CorruptionProfile, delimiter: bool) -> Self {
+        // modes at 0.0 don't draw, so seeded output without them stays what it was
+        let mut hit = |rate: f64| rate > 0.0 && rng.random_bool(rate);
+
+        Self {
+            delimiter,
+            missing_field: hit(profile.missing_field),
+            extra_field: hit(profile.extra_field),
+            non_numeric_amount: hit(profile.non_numeric_amount),
+            negative_amount: hit(profile.negative_amount),
+            empty_line: hit(profile.empty_line),
+            invalid_utf8: hit(profile.invalid_utf8),
+            duplicate_id: hit(profile.duplicate_id),
+            crlf: hit(profile.crlf),
+        }
+    }
+
+    /// Lines the parser will reject, the blank one counts on its own
+    pub fn malformed_lines(&self, format: Format) -> usize {
+        let fields = format == Format::Delimited
+            && (self.missing_field || self.extra_field || self.non_numeric_amount);
+        usize::from(self.delimiter || self.invalid_utf8 || fields) + usize::from(self.empty_line)
+    }
+
+    /// Breaks an already formatted line (delimiter corruption included) the rest of the way,
+    /// negative amounts and duplicate ids are already in the record it was formatted from
+    pub fn apply(&self, line: String, layout: &Layout, d: char, format: Format) -> Vec<u8> {
+        let untouched = !(self.missing_field
+            || self.extra_field
+            || self.non_numeric_amount
+            || self.empty_line
+            || self.invalid_utf8
+            || self.crlf);
+        if untouched {
+            return line.into_bytes();
+        }
+
+        let body = line.trim_end_matches('\n');
+        let body = match format {
+            Format::Delimited => self.apply_fields(body, layout, d),
+            _ => body.to_string(),
+        };
+
+        let mut bytes = Vec::with_capacity(body.len() + 3);
+        if self.empty_line {
+            bytes.push(b'\n');
+        }
+        bytes.extend_from_slice(body.as_bytes());
+        if self.invalid_utf8
+            && let Some(at) = body.find("ACC")
+        {
+            bytes[usize::from(self.empty_line) + at + 3] = 0xff;
+        }
+        bytes.extend_from_slice(if self.crlf { b"\r\n" } else { b"\n" });
+        bytes
+    }
+
+    fn apply_fields(&self, body: &str, layout: &Layout, d: char) -> String {
+        let mut fields: Vec<String> = body.split(d).map(str::to_string).collect();
+
+        // the broken delimiter glued two fields together, everything after moved one left
+        let amount_at = layout
+            .columns()
+            .iter()
+            .position(|c| *c == Column::Amount)
+            .map(|at| at - usize::from(self.delimiter));
+        if self.non_numeric_amount
+            && let Some(amount) = amount_at.and_then(|at| fields.get_mut(at))
+        {
+            *amount = "N/A".to_string();
+        }
+
+        if self.missing_field {
+            fields.pop();
+        }
+        // both would cancel out into a line that parses
+        if self.extra_field && !self.missing_field {
+            fields.push("EXTRA".to_string());
+        }
+        fields.join(&d.to_string())
+    }
+}
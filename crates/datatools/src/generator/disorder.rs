@@ -0,0 +1,97 @@
+use rand::rngs::ChaCha8Rng;
+use rand::{RngExt, SeedableRng};
+
+use super::{GeneratorConfig, Line};
+
+/// Records that parse fine one by one but are wrong as a set, for
+/// [`crate::data_ingestion::dedup`] and [`crate::sort`] to clean up.
+///
+/// These draw from their own rng, so the records themselves are the ones a
+/// clean run with the same seed writes: dedup and sort by id gets that file back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Disorder {
+    /// chance (0.0..=1.0) of a record being written a second time, a few records later
+    pub duplicate: f64,
+    /// chance of a record being held back and written a few records late
+    pub shuffle: f64,
+    /// how many records later a held back or repeated one can land
+    pub max_delay: usize,
+}
+
+impl Default for Disorder {
+    fn default() -> Self {
+        Self {
+            duplicate: 0.01,
+            shuffle: 0.01,
+            max_delay: 100,
+        }
+    }
+}
+
+/// Lines waiting for their turn, with the record count they're due at
+pub(super) struct Reorder {
+    disorder: Disorder,
+    rng: ChaCha8Rng,
+    pending: Vec<(usize, Line)>,
+    pub duplicates: usize,
+}
+
+impl Reorder {
+    pub fn new(disorder: Disorder, config: &GeneratorConfig) -> Self {
+        Self {
+            disorder,
+            // anything but the main seed, which would repeat the record draws
+            rng: match config.seed {
+                Some(seed) => ChaCha8Rng::seed_from_u64(seed ^ 0x6469_736f_7264_6572),
+                None => rand::make_rng(),
+            },
+            pending: Vec::new(),
+            duplicates: 0,
+        }
+    }
+
+    /// Takes the line of record `index` and hands whatever is due now to `emit`
+    pub fn place(
+        &mut self,
+        index: usize,
+        line: Line,
+        mut emit: impl FnMut(Line) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        let mut hit = |rate: f64| rate > 0.0 && self.rng.random_bool(rate);
+        let duplicate = hit(self.disorder.duplicate);
+        let shuffle = hit(self.disorder.shuffle);
+
+        if duplicate {
+            let due = index + self.delay();
+            self.pending.push((due, line.clone()));
+            self.duplicates += 1;
+        }
+        if shuffle {
+            let due = index + self.delay();
+            self.pending.push((due, line));
+        } else {
+            emit(line)?;
+        }
+
+        while let Some(at) = self.pending.iter().position(|(due, _)| *due <= index) {
+            emit(self.pending.remove(at).1)?;
+        }
+        Ok(())
+    }
+
+    /// Everything still held back, once the target is reached
+    pub fn flush(
+        &mut self,
+        mut emit: impl FnMut(Line) -> std::io::Result<()>,
+    ) -> std::io::Result<()> {
+        self.pending.sort_by_key(|(due, _)| *due);
+        for (_, line) in self.pending.drain(..) {
+            emit(line)?;
+        }
+        Ok(())
+    }
+
+    fn delay(&mut self) -> usize {
+        self.rng.random_range(1..=self.disorder.max_delay.max(1))
+    }
+}
@@ -0,0 +1,46 @@
+use super::Ledger;
+use crate::data_ingestion::TransactionKind;
+
+/// Every account's balance in cents, so nothing the generator writes overdraws
+pub(super) struct Balances {
+    cents: Vec<i64>,
+}
+
+impl Balances {
+    pub fn new(ledger: &Ledger, accounts: u32) -> Self {
+        Self {
+            cents: vec![to_cents(ledger.opening_balance); accounts as usize],
+        }
+    }
+
+    /// Books one drawn record and returns the amount it actually moves: whatever
+    /// leaves an account is capped at its balance, deposits come from outside
+    pub fn settle(&mut self, kind: TransactionKind, from: u32, to: u32, amount: f64) -> f64 {
+        let (from, to) = (from as usize, to as usize);
+        let wanted = to_cents(amount);
+
+        let moved = match kind {
+            TransactionKind::Deposit => {
+                self.cents[to] += wanted;
+                wanted
+            }
+            TransactionKind::Transfer => {
+                let moved = wanted.min(self.cents[from]);
+                self.cents[from] -= moved;
+                self.cents[to] += moved;
+                moved
+            }
+            TransactionKind::Withdrawal | TransactionKind::Fee => {
+                let moved = wanted.min(self.cents[from]);
+                self.cents[from] -= moved;
+                moved
+            }
+        };
+
+        moved as f64 / 100.0
+    }
+}
+
+fn to_cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
@@ -0,0 +1,50 @@
+use std::fs::File;
+
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+
+use crate::data_ingestion::{BatchBuilder, ClientData, DEFAULT_BATCH_ROWS, Layout};
+
+/// Buffers generated records into arrow batches and writes them as parquet row groups
+pub(crate) struct ParquetSink {
+    writer: ArrowWriter<File>,
+    batch: BatchBuilder,
+}
+
+impl ParquetSink {
+    pub fn create(path: &str, layout: &Layout) -> std::io::Result<Self> {
+        let batch = BatchBuilder::new(layout);
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, batch.schema(), Some(props))
+            .map_err(std::io::Error::other)?;
+
+        Ok(Self { writer, batch })
+    }
+
+    pub fn push(&mut self, record: &ClientData) -> std::io::Result<()> {
+        self.batch.push(record);
+
+        if self.batch.len() == DEFAULT_BATCH_ROWS {
+            self.flush_batch()?;
+        }
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> std::io::Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let batch = self.batch.finish().map_err(std::io::Error::other)?;
+        self.writer.write(&batch).map_err(std::io::Error::other)
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.flush_batch()?;
+        self.writer.close().map_err(std::io::Error::other)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,315 @@
+//! Compressed generation on every core: the generated text is cut into blocks,
+//! worker threads compress each one on its own (a zstd frame or a gzip member)
+//! and a writer thread puts them in the file in order. Decoders read the frames
+//! one after the other, so the file decompresses to what
+//! [`super::generate_mock_data_with`] writes for the same config.
+//!
+//! The records are still drawn and formatted on the calling thread, one rng,
+//! clock and set of balances has to go through them in order for a seed to
+//! mean the same output. Formatting keeps up with several compressors though,
+//! compression is what held a single writer back
+use std::io::Write;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::thread::JoinHandle;
+
+use super::{GenerationReport, GeneratorConfig, Progress, TextSink, generate};
+use crate::Instant;
+use crate::compression::Compression;
+use crate::format::Format;
+
+/// Bytes of text per compressed block, big enough that the frame headers and
+/// the window starting over cost next to nothing
+pub const DEFAULT_BLOCK_BYTES: usize = 4 * 1024 * 1024;
+
+/// Blocks queued per worker before the generator waits for it
+const BLOCKS_IN_FLIGHT: usize = 2;
+
+/// Knobs for [`generate_mock_data_pipelined`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParallelCompression {
+    /// compressing threads, the generating and the writing ones come on top
+    pub threads: usize,
+    pub block_bytes: usize,
+    /// zstd 1..=22 (3 by default) or gzip 0..=9 (6), what the single-threaded writer uses
+    pub level: Option<i32>,
+}
+
+impl Default for ParallelCompression {
+    fn default() -> Self {
+        let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+        Self {
+            threads: threads.saturating_sub(1).max(1),
+            block_bytes: DEFAULT_BLOCK_BYTES,
+            level: None,
+        }
+    }
+}
+
+impl ParallelCompression {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    pub fn block_bytes(mut self, bytes: usize) -> Self {
+        self.block_bytes = bytes.max(1);
+        self
+    }
+
+    pub fn level(mut self, level: i32) -> Self {
+        self.level = Some(level);
+        self
+    }
+}
+
+/// [`generate_mock_data_pipelined_with_progress`] without progress
+pub fn generate_mock_data_pipelined(
+    path: &str,
+    config: &GeneratorConfig,
+    options: &ParallelCompression,
+) -> std::io::Result<GenerationReport> {
+    generate_mock_data_pipelined_with_progress(path, config, options, |_| {})
+}
+
+/// [`super::generate_mock_data_with_progress`] compressing on `options.threads`
+/// threads. Gzip or zstd per `config.compression` or the extension, plain
+/// output has nothing to compress and is generated the usual way. Text formats
+/// only and no `append`, a compressed file can't be continued anyway
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(skip_all, fields(path = %path, threads = options.threads))
+)]
+pub fn generate_mock_data_pipelined_with_progress(
+    path: &str,
+    config: &GeneratorConfig,
+    options: &ParallelCompression,
+    on_progress: impl FnMut(Progress),
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+
+    let compression = config
+        .compression
+        .unwrap_or_else(|| Compression::from_path(path));
+    if compression == Compression::None {
+        return super::generate_mock_data_with_progress(path, config, on_progress);
+    }
+    let format = config.format.unwrap_or_else(|| Format::from_path(path));
+    if format == Format::Parquet {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "parquet compresses its own pages, generate it without the pipeline",
+        ));
+    }
+    if config.append {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "only plain text output can be appended to",
+        ));
+    }
+    match std::fs::remove_file(crate::checksum::sidecar_path(path)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+
+    let file =
+        std::io::BufWriter::with_capacity(config.buffer_capacity, std::fs::File::create(path)?);
+    let writer = BlockWriter::new(file, compression, options)?;
+    // the blocks are compressed already, the writer passes them through
+    let sink = TextSink::new(writer, Compression::None, config.write_strategy)?;
+
+    let report = generate(sink, config, format, None, start, on_progress)?;
+    if !report.cancelled {
+        crate::checksum::write_sidecar(path)?;
+    }
+    Ok(report)
+}
+
+enum Job {
+    Block(Vec<u8>),
+    /// everything before it has to be on disk, answered on `BlockWriter::flushed`
+    Flush,
+}
+
+/// Plain text in, compressed blocks out. Block `n` goes to worker
+/// `n % workers`, and the writer takes them back from the workers in the same
+/// turn, so they come out in order without anything to sort.
+///
+/// `flush` waits until everything handed over so far is written, that's where
+/// the errors of the other threads show up
+struct BlockWriter {
+    block: Vec<u8>,
+    block_bytes: usize,
+    jobs: Vec<SyncSender<Job>>,
+    /// worker the next job goes to
+    turn: usize,
+    flushed: Receiver<()>,
+    workers: Vec<JoinHandle<()>>,
+    writer: Option<JoinHandle<std::io::Result<()>>>,
+}
+
+impl BlockWriter {
+    fn new<W: Write + Send + 'static>(
+        mut file: W,
+        compression: Compression,
+        options: &ParallelCompression,
+    ) -> std::io::Result<Self> {
+        let level = options.level;
+        // an empty block tells right away whether the codec is compiled in
+        compress_block(&[], compression, level)?;
+
+        let mut jobs = Vec::with_capacity(options.threads);
+        let mut blocks = Vec::with_capacity(options.threads);
+        let mut workers = Vec::with_capacity(options.threads);
+        for _ in 0..options.threads.max(1) {
+            let (job_tx, job_rx) = sync_channel::<Job>(BLOCKS_IN_FLIGHT);
+            let (block_tx, block_rx) = sync_channel(BLOCKS_IN_FLIGHT);
+            workers.push(std::thread::spawn(move || {
+                for job in job_rx {
+                    let done = match job {
+                        Job::Block(block) => {
+                            compress_block(&block, compression, level).map(Job::Block)
+                        }
+                        Job::Flush => Ok(Job::Flush),
+                    };
+                    // the writer is gone, it has the error
+                    if block_tx.send(done).is_err() {
+                        return;
+                    }
+                }
+            }));
+            jobs.push(job_tx);
+            blocks.push(block_rx);
+        }
+
+        let (flushed_tx, flushed) = sync_channel(1);
+        let writer = std::thread::spawn(move || {
+            let mut turn = 0;
+            loop {
+                let Ok(done) = blocks[turn].recv() else {
+                    // the jobs went round robin, the first worker out of them
+                    // means all of them are
+                    return Ok(());
+                };
+                match done? {
+                    Job::Block(compressed) => file.write_all(&compressed)?,
+                    Job::Flush => {
+                        file.flush()?;
+                        let _ = flushed_tx.send(());
+                    }
+                }
+                turn = (turn + 1) % blocks.len();
+            }
+        });
+
+        Ok(Self {
+            block: Vec::with_capacity(options.block_bytes),
+            block_bytes: options.block_bytes,
+            jobs,
+            turn: 0,
+            flushed,
+            workers,
+            writer: Some(writer),
+        })
+    }
+
+    fn send(&mut self, job: Job) -> std::io::Result<()> {
+        let turn = self.turn;
+        self.turn = (turn + 1) % self.jobs.len();
+        if self.jobs[turn].send(job).is_err() {
+            return Err(self.failure());
+        }
+        Ok(())
+    }
+
+    fn ship(&mut self) -> std::io::Result<()> {
+        let block = std::mem::replace(&mut self.block, Vec::with_capacity(self.block_bytes));
+        self.send(Job::Block(block))
+    }
+
+    /// What stopped the writer thread, once a channel to it broke
+    fn failure(&mut self) -> std::io::Error {
+        self.jobs.clear();
+        let stopped = || std::io::Error::other("the compression pipeline stopped");
+        match self.writer.take().map(JoinHandle::join) {
+            Some(Ok(Err(e))) => e,
+            Some(Err(_)) => std::io::Error::other("the compression pipeline's writer panicked"),
+            _ => stopped(),
+        }
+    }
+}
+
+impl Write for BlockWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.block.extend_from_slice(buf);
+        if self.block.len() >= self.block_bytes {
+            self.ship()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.block.is_empty() {
+            self.ship()?;
+        }
+        self.send(Job::Flush)?;
+        match self.flushed.recv() {
+            Ok(()) => Ok(()),
+            Err(_) => Err(self.failure()),
+        }
+    }
+}
+
+impl Drop for BlockWriter {
+    // hanging up on the workers ends them, and then the writer
+    fn drop(&mut self) {
+        self.jobs.clear();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+/// One block into one self-contained frame
+#[cfg_attr(not(any(feature = "gzip", feature = "zstd")), allow(unused_variables))]
+fn compress_block(
+    block: &[u8],
+    compression: Compression,
+    level: Option<i32>,
+) -> std::io::Result<Vec<u8>> {
+    match compression {
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => zstd::bulk::compress(block, level.unwrap_or(3)),
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => {
+            let level = level.map_or_else(flate2::Compression::default, |level| {
+                flate2::Compression::new(level.clamp(0, 9) as u32)
+            });
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::with_capacity(block.len() / 4), level);
+            encoder.write_all(block)?;
+            encoder.finish()
+        }
+        #[cfg(not(feature = "zstd"))]
+        Compression::Zstd => Err(not_compiled_in("zstd")),
+        #[cfg(not(feature = "gzip"))]
+        Compression::Gzip => Err(not_compiled_in("gzip")),
+        // plain blocks one after the other are the plain text
+        Compression::None => Ok(block.to_vec()),
+    }
+}
+
+#[cfg(not(all(feature = "gzip", feature = "zstd")))]
+fn not_compiled_in(feature: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{feature} support is not compiled in, enable the `{feature}` feature"),
+    )
+}
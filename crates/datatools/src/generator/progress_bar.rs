@@ -0,0 +1,60 @@
+use indicatif::{HumanBytes, HumanCount, HumanDuration, ProgressBar, ProgressStyle};
+
+use super::{Progress, TargetSize};
+
+/// The progress callback drawn as an indicatif bar on stderr, e.g.
+/// `generate_mock_data_with_progress(path, &config, |p| bar.update(&p))`.
+/// Throughput and ETA are the generator's own, not indicatif's estimates
+pub struct IndicatifProgress {
+    bar: ProgressBar,
+}
+
+impl IndicatifProgress {
+    pub fn new(target: TargetSize) -> Self {
+        Self::with_bar(ProgressBar::new(0), target)
+    }
+
+    /// Someone else's bar, e.g. one of a `MultiProgress`
+    pub fn with_bar(bar: ProgressBar, target: TargetSize) -> Self {
+        let len = match target {
+            TargetSize::Bytes(bytes) => bytes,
+            TargetSize::Records(records) => records,
+        };
+        bar.set_length(len as u64);
+        bar.set_style(
+            ProgressStyle::with_template("{bar:40} {percent:>3}% {msg}")
+                .expect("valid template")
+                .progress_chars("=> "),
+        );
+        Self { bar }
+    }
+
+    pub fn bar(&self) -> &ProgressBar {
+        &self.bar
+    }
+
+    pub fn update(&self, progress: &Progress) {
+        let position = match progress.target {
+            TargetSize::Bytes(_) => progress.bytes,
+            TargetSize::Records(_) => progress.records,
+        };
+        let eta = match progress.eta() {
+            Some(eta) => HumanDuration(eta).to_string(),
+            None => "-".to_string(),
+        };
+        self.bar.set_position(position as u64);
+        self.bar.set_message(format!(
+            "{} records, {}, {}/s ({:.0} records/s), eta {eta}, {} malformed",
+            HumanCount(progress.records as u64),
+            HumanBytes(progress.bytes as u64),
+            HumanBytes(progress.bytes_per_second as u64),
+            progress.records_per_second,
+            HumanCount(progress.malformed_records as u64),
+        ));
+    }
+
+    /// Leaves the full bar on screen, the last message stays
+    pub fn finish(&self) {
+        self.bar.finish();
+    }
+}
@@ -0,0 +1,59 @@
+use std::fs::OpenOptions;
+use std::io::Read;
+
+/// How far an earlier, interrupted run got
+pub(super) struct Resume {
+    /// complete data lines, header excluded
+    pub records: usize,
+    /// bytes of those lines, counted like the generator counts them
+    pub bytes: usize,
+}
+
+/// Looks at what's already in `path`, cuts off a half-written last line and
+/// returns where generation should carry on. `None` means start from scratch:
+/// no file, an empty one, or not even a complete header
+pub(super) fn recover(path: &str, header: bool) -> std::io::Result<Option<Resume>> {
+    let mut file = match OpenOptions::new().read(true).write(true).open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut lines = 0;
+    let mut header_len = None;
+    // end of the last complete line
+    let mut complete = 0;
+    let mut offset = 0;
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for pos in memchr::memchr_iter(b'\n', &buf[..n]) {
+            lines += 1;
+            complete = offset + pos + 1;
+            header_len.get_or_insert(complete);
+        }
+        offset += n;
+    }
+
+    // whatever comes after the last newline was cut off mid-record
+    if complete < offset {
+        file.set_len(complete as u64)?;
+    }
+
+    let (lines, bytes) = match (header, header_len) {
+        (false, _) => (lines, complete),
+        (true, Some(header_len)) => (lines - 1, complete - header_len),
+        (true, None) => return Ok(None),
+    };
+    if lines == 0 && !header {
+        return Ok(None);
+    }
+
+    Ok(Some(Resume {
+        records: lines,
+        bytes,
+    }))
+}
@@ -0,0 +1,72 @@
+use rand::RngExt;
+use rand_distr::{Distribution, LogNormal, Zipf};
+
+use super::{AccountDistribution, AmountDistribution, GeneratorConfig, HotAccounts};
+
+/// The config's distributions, set up once per run instead of once per record
+pub(super) struct Sampler {
+    accounts: u32,
+    zipf: Option<Zipf<f64>>,
+    hot: Option<HotAccounts>,
+    amounts: Amounts,
+}
+
+enum Amounts {
+    Uniform { min: f64, max: f64 },
+    LogNormal(LogNormal<f64>),
+}
+
+impl Sampler {
+    pub fn new(config: &GeneratorConfig) -> std::io::Result<Self> {
+        let zipf = match config.account_distribution {
+            AccountDistribution::Uniform => None,
+            AccountDistribution::Zipf { exponent } => {
+                Some(Zipf::new(config.accounts as f64, exponent).map_err(invalid)?)
+            }
+        };
+        let amounts = match config.amounts {
+            AmountDistribution::Uniform { min, max } => Amounts::Uniform { min, max },
+            AmountDistribution::LogNormal { median, sigma } => {
+                Amounts::LogNormal(LogNormal::new(median.ln(), sigma).map_err(invalid)?)
+            }
+        };
+
+        Ok(Self {
+            accounts: config.accounts,
+            zipf,
+            hot: config.hot_accounts,
+            amounts,
+        })
+    }
+
+    /// 0-based account index, below `accounts`
+    pub fn account(&self, rng: &mut impl RngExt) -> u32 {
+        // no extra draw without hot accounts, seeded output stays what it was
+        if let Some(hot) = self.hot
+            && rng.random_bool(hot.share)
+        {
+            return rng.random_range(0..hot.count.clamp(1, self.accounts));
+        }
+
+        match &self.zipf {
+            // zipf ranks start at 1
+            Some(zipf) => zipf.sample(rng) as u32 - 1,
+            None => rng.random_range(0..self.accounts),
+        }
+    }
+
+    pub fn amount(&self, rng: &mut impl RngExt) -> f64 {
+        match &self.amounts {
+            Amounts::Uniform { min, max } => rng.random_range(*min..*max),
+            // no cap on the long tail, but nothing below a cent
+            Amounts::LogNormal(log_normal) => log_normal.sample(rng).max(0.01),
+        }
+    }
+}
+
+fn invalid(e: impl std::fmt::Display) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("invalid distribution: {e}"),
+    )
+}
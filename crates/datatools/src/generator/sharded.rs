@@ -0,0 +1,133 @@
+use std::path::Path;
+
+use rand::RngExt;
+
+use super::{GenerationReport, GeneratorConfig, TargetSize, generate_mock_data_with};
+use crate::Instant;
+use crate::compression::Compression;
+use crate::format::Format;
+
+/// No generated line is shorter than this (`TXN..|ACC..|ACC..|1.00\n` is 43), so a
+/// shard of `n` bytes never has more than `n / MIN_LINE_LEN + 1` records
+const MIN_LINE_LEN: usize = 40;
+
+/// `<dir>/shard-0003.csv`, `.jsonl` / `.parquet` and `.gz` / `.zst` per the config
+pub fn shard_path(dir: &str, index: usize, config: &GeneratorConfig) -> String {
+    let extension = match config.format.unwrap_or_default() {
+        Format::Delimited => "csv",
+        Format::JsonLines => "jsonl",
+        Format::Parquet => "parquet",
+    };
+    let compression = match config.compression.unwrap_or_default() {
+        Compression::None => "",
+        Compression::Gzip => ".gz",
+        Compression::Zstd => ".zst",
+    };
+
+    Path::new(dir)
+        .join(format!("shard-{index:04}.{extension}{compression}"))
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub fn generate_mock_data_sharded(
+    dir: &str,
+    total_size: usize,
+    shards: usize,
+) -> std::io::Result<GenerationReport> {
+    let config = GeneratorConfig::new().target_bytes(total_size);
+    generate_mock_data_sharded_with(dir, &config, shards)
+}
+
+/// Splits `config`'s target over `shards` files, generated on one thread each.
+///
+/// Every shard gets its own seed derived from the master one, so a seeded run
+/// is reproducible shard by shard. Ids stay unique across shards, with a byte
+/// target they leave gaps since the record count isn't known up front.
+/// Timestamps start over in every shard
+#[cfg_attr(feature = "tracing", tracing::instrument(skip(config)))]
+pub fn generate_mock_data_sharded_with(
+    dir: &str,
+    config: &GeneratorConfig,
+    shards: usize,
+) -> std::io::Result<GenerationReport> {
+    let start = Instant::now();
+    config.check()?;
+    let shards = shards.max(1);
+    std::fs::create_dir_all(dir)?;
+
+    let master_seed = config.seed.unwrap_or_else(|| rand::rng().random());
+    let configs: Vec<_> = (0..shards)
+        .map(|index| shard_config(config, index, shards, master_seed))
+        .collect();
+
+    // the shards' spans go under the caller's, not at the root of their threads
+    #[cfg(feature = "tracing")]
+    let parent = tracing::Span::current();
+    let reports = std::thread::scope(|scope| {
+        let handles: Vec<_> = configs
+            .iter()
+            .enumerate()
+            .map(|(index, shard)| {
+                let path = shard_path(dir, index, shard);
+                #[cfg(feature = "tracing")]
+                let parent = parent.clone();
+                scope.spawn(move || {
+                    #[cfg(feature = "tracing")]
+                    let _parent = parent.enter();
+                    generate_mock_data_with(&path, shard)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("shard generator panicked"))
+            .collect::<std::io::Result<Vec<_>>>()
+    })?;
+
+    Ok(GenerationReport {
+        records: reports.iter().map(|r| r.records).sum(),
+        bytes: reports.iter().map(|r| r.bytes).sum(),
+        malformed_records: reports.iter().map(|r| r.malformed_records).sum(),
+        duplicates: reports.iter().map(|r| r.duplicates).sum(),
+        elapsed: start.elapsed(),
+        cancelled: reports.iter().any(|r| r.cancelled),
+    })
+}
+
+fn shard_config(
+    config: &GeneratorConfig,
+    index: usize,
+    shards: usize,
+    master_seed: u64,
+) -> GeneratorConfig {
+    // the first `remainder` shards take one extra unit
+    let split = |total: usize| total / shards + usize::from(index < total % shards);
+    let before = |total: usize| index * (total / shards) + index.min(total % shards);
+
+    let (target, first_id) = match config.target {
+        TargetSize::Records(records) => (TargetSize::Records(split(records)), before(records)),
+        TargetSize::Bytes(bytes) => {
+            let stride = (bytes / shards + 1) / MIN_LINE_LEN + 1;
+            (TargetSize::Bytes(split(bytes)), index * stride)
+        }
+    };
+
+    GeneratorConfig {
+        target,
+        first_id: config.first_id + first_id,
+        // the shards run side by side, together they keep to the rate
+        records_per_second: config.records_per_second.map(|rate| rate / shards as f64),
+        ..config.clone()
+    }
+    .seed(shard_seed(master_seed, index))
+}
+
+/// splitmix64 of the master seed and the index, neighbouring shards get unrelated streams
+fn shard_seed(master_seed: u64, index: usize) -> u64 {
+    let mut z = master_seed.wrapping_add((index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
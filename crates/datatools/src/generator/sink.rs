@@ -0,0 +1,304 @@
+//! Where generated records go: the generation loop only knows a
+//! [`RecordSink`], the file, the compressed file, the channel and the Vec are
+//! all just sinks, so the distributions and the corruption can be looked at
+//! without a file in between
+use std::io::{IoSlice, Write};
+use std::sync::mpsc::{Sender, SyncSender};
+
+use super::{GeneratorConfig, WriteStrategy};
+use crate::compression::{CompressedWriter, Compression};
+use crate::data_ingestion::ClientData;
+use crate::format::Format;
+
+/// One record on its way out of the generator
+pub struct GeneratedRecord {
+    /// the amount rounded to the cent, the way it reads back from text. The
+    /// corruption that breaks a line only shows in `line`
+    pub record: ClientData,
+    /// the record in the run's text format, newline and corruption included
+    pub line: Vec<u8>,
+}
+
+/// Takes the generated records one at a time.
+///
+/// A text sink writes the lines as they are, the header and the corrupted
+/// ones included. Every other sink gets the clean records, the corruption
+/// only exists in text
+pub trait RecordSink {
+    /// Whether [`write`](Self::write) writes `line`, and
+    /// [`write_raw`](Self::write_raw) is worth calling
+    fn is_text(&self) -> bool {
+        false
+    }
+
+    /// Hands back a buffer for the next line to be formatted into, `line` if
+    /// it's done with it, an empty one if it kept it
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>>;
+
+    /// Text that isn't a record, the header and the half line of
+    /// [`super::CorruptionProfile::truncated_last_line`], after everything
+    /// written so far. Only called on a text sink
+    fn write_raw(&mut self, _bytes: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Pushes what's buffered out to the reader, a throttled run does it every step
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// After the last record, writes whatever trailer or footer the output needs
+    fn finish(self) -> std::io::Result<()>
+    where
+        Self: Sized,
+    {
+        Ok(())
+    }
+}
+
+/// Text lines into a writer, compressed if asked to
+pub struct TextSink<W: Write = std::io::BufWriter<std::fs::File>> {
+    writer: CompressedWriter<W>,
+    batch: Batch,
+}
+
+impl TextSink {
+    /// A new file at `path`, compressed per `config.compression` or the extension
+    pub fn create(path: &str, config: &GeneratorConfig) -> std::io::Result<Self> {
+        let compression = config
+            .compression
+            .unwrap_or_else(|| Compression::from_path(path));
+        let file =
+            std::io::BufWriter::with_capacity(config.buffer_capacity, std::fs::File::create(path)?);
+        TextSink::new(file, compression, config.write_strategy)
+    }
+
+    /// The end of the plain text file at `path`, see [`GeneratorConfig::append`]
+    pub(super) fn append(path: &str, config: &GeneratorConfig) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().append(true).open(path)?;
+        TextSink::new(
+            std::io::BufWriter::with_capacity(config.buffer_capacity, file),
+            Compression::None,
+            config.write_strategy,
+        )
+    }
+}
+
+impl<W: Write> TextSink<W> {
+    /// Into any writer, e.g. stdout, it should be buffered already
+    pub fn new(
+        writer: W,
+        compression: Compression,
+        strategy: WriteStrategy,
+    ) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: CompressedWriter::new(writer, compression)?,
+            batch: Batch::new(strategy),
+        })
+    }
+}
+
+impl<W: Write> RecordSink for TextSink<W> {
+    fn is_text(&self) -> bool {
+        true
+    }
+
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.batch.write(&mut self.writer, record.line)
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        // after the lines still queued, not in front of them
+        self.batch.write_out(&mut self.writer)?;
+        self.writer.write_all(bytes)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.batch.write_out(&mut self.writer)?;
+        self.writer.flush()
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.batch.write_out(&mut self.writer)?;
+        self.writer.finish()
+    }
+}
+
+/// Row groups are only readable once the footer is there, nothing to flush
+#[cfg(feature = "parquet")]
+impl RecordSink for super::ParquetSink {
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.push(&record.record)?;
+        Ok(record.line)
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        super::ParquetSink::finish(self)
+    }
+}
+
+/// The records collected in memory, e.g. to check what a config generates
+impl RecordSink for &mut Vec<ClientData> {
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.push(record.record);
+        Ok(record.line)
+    }
+}
+
+fn hung_up() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::BrokenPipe,
+        "the receiving end of the channel is gone",
+    )
+}
+
+/// The records to another thread, a gone receiver ends the run with
+/// [`std::io::ErrorKind::BrokenPipe`]
+impl RecordSink for Sender<ClientData> {
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.send(record.record).map_err(|_| hung_up())?;
+        Ok(record.line)
+    }
+}
+
+/// Like the [`Sender`], blocking while the channel is full, so a slow
+/// consumer slows the generator down instead of the records piling up
+impl RecordSink for SyncSender<ClientData> {
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.send(record.record).map_err(|_| hung_up())?;
+        Ok(record.line)
+    }
+}
+
+/// The records to a callback, [`super::generate_mock_records`]
+pub(super) struct Callback<F>(pub F);
+
+impl<F: FnMut(ClientData) -> std::io::Result<()>> RecordSink for Callback<F> {
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        (self.0)(record.record)?;
+        Ok(record.line)
+    }
+}
+
+/// A file in the format the path or the config asks for
+pub(super) enum FileSink {
+    Text(TextSink),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<super::ParquetSink>),
+}
+
+impl FileSink {
+    pub(super) fn create(
+        path: &str,
+        format: Format,
+        config: &GeneratorConfig,
+    ) -> std::io::Result<Self> {
+        match format {
+            Format::Delimited | Format::JsonLines => {
+                Ok(FileSink::Text(TextSink::create(path, config)?))
+            }
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Ok(FileSink::Parquet(Box::new(super::ParquetSink::create(
+                path,
+                &config.layout(),
+            )?))),
+            #[cfg(not(feature = "parquet"))]
+            Format::Parquet => Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "parquet support is not compiled in, enable the `parquet` feature",
+            )),
+        }
+    }
+}
+
+impl RecordSink for FileSink {
+    fn is_text(&self) -> bool {
+        matches!(self, FileSink::Text(_))
+    }
+
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        match self {
+            FileSink::Text(sink) => sink.write(record),
+            #[cfg(feature = "parquet")]
+            FileSink::Parquet(sink) => sink.write(record),
+        }
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        match self {
+            FileSink::Text(sink) => sink.write_raw(bytes),
+            #[cfg(feature = "parquet")]
+            FileSink::Parquet(sink) => sink.write_raw(bytes),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            FileSink::Text(sink) => sink.flush(),
+            #[cfg(feature = "parquet")]
+            FileSink::Parquet(sink) => sink.flush(),
+        }
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            FileSink::Text(sink) => sink.finish(),
+            #[cfg(feature = "parquet")]
+            FileSink::Parquet(sink) => sink.finish(),
+        }
+    }
+}
+
+/// Lines held back for one `write_vectored`, see [`WriteStrategy::Vectored`]
+#[derive(Default)]
+struct Batch {
+    /// 0 writes every line straight away
+    size: usize,
+    lines: Vec<Vec<u8>>,
+    /// buffers of lines already written, handed back out for the next ones
+    spent: Vec<Vec<u8>>,
+}
+
+impl Batch {
+    fn new(strategy: WriteStrategy) -> Self {
+        match strategy {
+            WriteStrategy::Vectored(lines) => Self {
+                size: lines.max(1),
+                lines: Vec::with_capacity(lines),
+                spent: Vec::with_capacity(lines),
+            },
+            WriteStrategy::PerLine | WriteStrategy::ReusedBuffer => Self::default(),
+        }
+    }
+
+    /// Writes `line` (or queues it), returns a buffer for the next one
+    fn write(&mut self, writer: &mut impl Write, line: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        if self.size == 0 {
+            writer.write_all(&line)?;
+            return Ok(line);
+        }
+        self.lines.push(line);
+        if self.lines.len() >= self.size {
+            self.write_out(writer)?;
+        }
+        Ok(self.spent.pop().unwrap_or_default())
+    }
+
+    /// Everything queued in as few `write_vectored` calls as the writer takes
+    fn write_out(&mut self, writer: &mut impl Write) -> std::io::Result<()> {
+        let mut slices: Vec<IoSlice<'_>> = self.lines.iter().map(|l| IoSlice::new(l)).collect();
+        let mut slices = slices.as_mut_slice();
+        // drops the empty ones up front, a writer returns 0 for those
+        IoSlice::advance_slices(&mut slices, 0);
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(n) => IoSlice::advance_slices(&mut slices, n),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        self.spent.append(&mut self.lines);
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use crate::Instant;
+
+/// Sleeps often enough to look live, ~100 times a second
+const STEPS_PER_SECOND: f64 = 100.0;
+
+/// Holds generation back to a number of records a second, checked every few
+/// records rather than after each so high rates don't drown in sleeps
+pub(super) struct Throttle {
+    records_per_second: f64,
+    every: usize,
+    start: Instant,
+}
+
+impl Throttle {
+    pub fn new(records_per_second: f64) -> Self {
+        Self {
+            records_per_second,
+            every: ((records_per_second / STEPS_PER_SECOND) as usize).max(1),
+            start: Instant::now(),
+        }
+    }
+
+    /// Whether it's time to hand `records` (counted since the start) out and wait
+    pub fn is_due(&self, records: usize) -> bool {
+        records.is_multiple_of(self.every)
+    }
+
+    /// Until `records` would have taken at the configured rate
+    pub fn wait(&self, records: usize) {
+        let due = Duration::from_secs_f64(records as f64 / self.records_per_second);
+        if let Some(ahead) = due.checked_sub(self.start.elapsed()) {
+            std::thread::sleep(ahead);
+        }
+    }
+}
@@ -0,0 +1,920 @@
+//! Generating, reading, analysing and reporting on transaction data, shared
+//! by the example posts. `p01` is a thin consumer that re-exports all of it,
+//! later posts depend on this crate instead of copying p01's modules over
+pub mod cancel;
+pub mod checksum;
+pub mod compression;
+pub mod diff;
+pub mod encoding;
+pub mod export;
+pub mod format;
+pub mod generator;
+pub mod metrics;
+pub mod page_cache;
+pub mod partition;
+pub mod remote;
+pub mod report;
+pub mod sort;
+#[cfg(feature = "kafka")]
+pub mod streaming;
+pub mod transform;
+pub mod validation;
+
+pub use generator::{
+    GenerationReport, GeneratorConfig, Progress, RecordSink, generate_mock_data,
+    generate_mock_data_into, generate_mock_data_to, generate_mock_data_with,
+    generate_mock_data_with_progress, generate_mock_records,
+};
+
+// std's Instant panics on wasm32-unknown-unknown, the browser's clock stands in there.
+// exported so p01 times its pipeline with the same one
+#[doc(hidden)]
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+pub use std::time::Instant;
+#[doc(hidden)]
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+pub use web_time::Instant;
+
+pub mod data_ingestion {
+    use serde::{Deserialize, Serialize};
+    use std::io::BufRead;
+
+    use crate::encoding::Encoding;
+    use crate::format::Format;
+
+    mod accounts;
+    mod borrowed;
+    mod cents;
+    mod columnar;
+    mod compact;
+    mod currency;
+    mod dedup;
+    mod dirty;
+    mod follow;
+    mod interned;
+    mod kind;
+    mod layout;
+    mod located;
+    mod number;
+    mod options;
+    mod recover;
+    mod report;
+    mod rows;
+    mod sample;
+    mod schema;
+    mod shards;
+    mod spill;
+
+    pub use accounts::{ACCOUNTS_HEADER, Account, AccountIter, RiskTier, load_accounts};
+    pub use borrowed::{ClientDataRef, RefReader, open_file_ref, parse_line_ref};
+    pub use cents::{parse_cents, parse_line_cents};
+    pub use columnar::{ClientColumns, open_file_columnar};
+    pub use compact::{AccountId, ClientDataCompact, TxnId, open_file_compact};
+    pub use currency::Currency;
+    pub use dedup::{DedupStrategy, dedup};
+    pub use dirty::{DEFAULT_DIRTY_SAMPLES, DirtyCategory, DirtyDataReport};
+    pub use follow::Follower;
+    pub use interned::{ClientDataInterned, InternedRecords, Interner, open_file_interned};
+    pub use kind::TransactionKind;
+    pub use layout::{Column, Layout};
+    pub use located::{Located, LocatedIter, open_file_located};
+    pub use number::NumberFormat;
+    pub use options::{ErrorPolicy, IngestOptions, open_file_with, read_with};
+    pub use recover::{RecoveringReader, SKIP_STEP, SkippedRange, SkippedRanges, open_recovering};
+    pub use report::{FailureKind, IngestReport, open_file_with_report};
+    pub use rows::{Deserialized, Row, RowIter, Value, open_file_as, open_rows};
+    pub use sample::{SAMPLE_DATASET, sample, sample_dataset, sample_with};
+    pub use schema::{
+        Field, FieldType, SNIFF_LINES, Schema, open_file_sniffed, sniff, sniff_lines,
+    };
+    #[cfg(feature = "rayon")]
+    pub use shards::open_dir_parallel;
+    pub(crate) use shards::open_shard;
+    pub use shards::{open_dir, shard_files};
+    pub use spill::{Groups, Spilled, SpilledIter, open_file_spilled};
+
+    #[cfg(feature = "rayon")]
+    mod parallel;
+
+    #[cfg(feature = "rayon")]
+    pub use parallel::{open_file_parallel, open_file_parallel_unordered};
+
+    #[cfg(feature = "mmap")]
+    mod mmap;
+
+    #[cfg(feature = "mmap")]
+    pub use mmap::open_file_mmap;
+
+    mod direct;
+    pub use direct::open_file_direct;
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    mod uring;
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    pub use uring::{URING_QUEUE_DEPTH, URING_READ_SIZE, UringReader, open_file_uring};
+
+    #[cfg(feature = "cache")]
+    mod cache;
+    #[cfg(feature = "csv")]
+    mod csv;
+    #[cfg(feature = "pipeline")]
+    mod pipeline;
+
+    #[cfg(feature = "cache")]
+    pub use cache::{cache_path, open_file_cached};
+    #[cfg(feature = "csv")]
+    pub use csv::{CsvRecordIter, open_file_csv};
+    #[cfg(feature = "pipeline")]
+    pub use pipeline::{PipelineOptions, open_file_pipelined, pipeline};
+
+    #[cfg(feature = "tokio")]
+    mod stream;
+
+    #[cfg(feature = "tokio")]
+    pub use stream::{open_file_async, stream_records};
+
+    #[cfg(feature = "decimal")]
+    mod exact;
+
+    #[cfg(feature = "decimal")]
+    pub use exact::{
+        ClientDataCents, ClientDataDecimal, ClientDataExact, ExactAmount, open_file_exact,
+        parse_line_exact,
+    };
+
+    #[cfg(feature = "arrow")]
+    mod arrow;
+    #[cfg(feature = "parquet")]
+    mod parquet;
+
+    #[cfg(feature = "arrow")]
+    pub use arrow::{BatchBuilder, BatchReader, DEFAULT_BATCH_ROWS, arrow_schema, open_file_arrow};
+
+    #[cfg(feature = "parquet")]
+    pub use parquet::open_file_parquet;
+
+    #[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+    pub struct ClientData {
+        pub id: String,
+        pub from_id: String,
+        pub to_id: String,
+        pub amount: f64,
+        /// unix seconds, only when the file has a `timestamp` column
+        #[serde(default)]
+        pub timestamp: Option<i64>,
+        #[serde(default)]
+        pub kind: TransactionKind,
+        /// only when the file has a `currency` column, `None` is the base currency
+        #[serde(default)]
+        pub currency: Option<Currency>,
+    }
+
+    /// Everything that can go wrong while turning a file into records
+    #[derive(Debug, thiserror::Error)]
+    pub enum IngestError {
+        #[error("Malformed record: expected {expected} fields, got {got}")]
+        FieldCount { expected: usize, got: usize },
+
+        #[error("Invalid amount {value:?}: {source}")]
+        InvalidAmount {
+            value: String,
+            source: std::num::ParseFloatError,
+        },
+
+        #[error("Invalid timestamp {value:?}: {source}")]
+        InvalidTimestamp {
+            value: String,
+            source: std::num::ParseIntError,
+        },
+
+        #[error("Invalid transaction kind {value:?}")]
+        InvalidKind { value: String },
+
+        #[error("Invalid currency {value:?}, expected a three letter ISO code")]
+        InvalidCurrency { value: String },
+
+        #[error("Invalid id {value:?}")]
+        InvalidId { value: String },
+
+        #[error("Invalid {column} {value:?} in the accounts table")]
+        InvalidAccount { column: &'static str, value: String },
+
+        #[error("Invalid {expected} {value:?} in column {column}")]
+        InvalidValue {
+            column: String,
+            value: String,
+            expected: FieldType,
+        },
+
+        #[error("Invalid amount {value:?}: doesn't match the {format} number format")]
+        InvalidNumber { value: String, format: NumberFormat },
+
+        #[error("Invalid amount {value:?}: {reason}")]
+        InvalidCents { value: String, reason: &'static str },
+
+        #[cfg(feature = "decimal")]
+        #[error("Invalid decimal amount {value:?}: {source}")]
+        InvalidDecimal {
+            value: String,
+            source: rust_decimal::Error,
+        },
+
+        #[error("Invalid UTF-8: {0}")]
+        Utf8(#[from] std::str::Utf8Error),
+
+        #[error("I/O error: {0}")]
+        Io(#[from] std::io::Error),
+
+        #[error("Invalid JSON: {0}")]
+        Json(#[from] serde_json::Error),
+
+        #[error("Schema mismatch: {0}")]
+        Schema(String),
+
+        #[error(
+            "No checksum sidecar for {0}, it wasn't generated here or generation got interrupted"
+        )]
+        MissingChecksum(String),
+
+        #[error("Checksum mismatch for {path}: expected {expected}, got {actual}")]
+        ChecksumMismatch {
+            path: String,
+            expected: String,
+            actual: String,
+        },
+
+        #[error("Duplicate id {id}")]
+        DuplicateId { id: String },
+
+        #[cfg(feature = "csv")]
+        #[error("CSV error: {0}")]
+        Csv(#[from] ::csv::Error),
+
+        #[cfg(feature = "parquet")]
+        #[error("Parquet error: {0}")]
+        Parquet(#[from] ::parquet::errors::ParquetError),
+
+        #[cfg(feature = "arrow")]
+        #[error("Arrow error: {0}")]
+        Arrow(#[from] arrow_schema::ArrowError),
+
+        #[cfg(feature = "kafka")]
+        #[error("Kafka error: {0}")]
+        Kafka(#[from] kafka::Error),
+
+        #[cfg(feature = "sqlite")]
+        #[error("SQLite error: {0}")]
+        Sqlite(#[from] rusqlite::Error),
+
+        #[error("Line too long: {len} bytes, a record can't be over {max}", max = MAX_LINE_BYTES)]
+        LineTooLong { len: usize },
+
+        #[error("Error rate {rate:.4} exceeded threshold {threshold} after {lines} lines")]
+        ErrorRateExceeded {
+            rate: f64,
+            threshold: f64,
+            lines: usize,
+        },
+
+        /// Wraps a parse failure with the place it happened in the file
+        #[error("line {line_num}: {line} - {source}")]
+        Line {
+            line_num: usize,
+            line: String,
+            source: Box<IngestError>,
+        },
+    }
+
+    /// Lazy record reader - only the current line is kept in memory
+    pub struct RecordIter<R = Box<dyn BufRead + Send>, T = ClientData> {
+        reader: R,
+        buf: Vec<u8>,
+        line_num: usize,
+        /// where the last line read starts, and where the next one will
+        offset: u64,
+        consumed: u64,
+        header: bool,
+        layout: Layout,
+        parse: fn(&Layout, &str) -> Result<T, IngestError>,
+    }
+
+    impl RecordIter {
+        /// Opens a plain, `.gz` or `.zst` file, compression is detected from the magic bytes.
+        /// `.jsonl` files are read as JSON Lines, everything else as pipe-delimited text
+        pub fn open(path: &str) -> std::io::Result<Self> {
+            Self::open_with_encoding(path, Encoding::Auto)
+        }
+
+        /// [`RecordIter::open`] for a file in a known [`Encoding`], e.g. Latin-1
+        /// which can't be detected
+        pub fn open_with_encoding(path: &str, encoding: Encoding) -> std::io::Result<Self> {
+            let reader = crate::compression::open_reader(path)?;
+            Self::from_reader(reader, Format::from_path(path), encoding)
+        }
+
+        /// [`RecordIter::open_with_encoding`] for input that isn't a file, e.g.
+        /// an upload held in memory. Read as is, compressed input goes through
+        /// [`crate::compression::decompress`] first
+        pub fn from_reader(
+            reader: Box<dyn BufRead + Send>,
+            format: Format,
+            encoding: Encoding,
+        ) -> std::io::Result<Self> {
+            let reader = crate::encoding::decode(reader, encoding)?;
+
+            match format {
+                Format::Delimited => Ok(Self::new(reader)),
+                Format::JsonLines => Ok(Self::json_lines(reader)),
+                Format::Parquet => Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "parquet is not line-based, use open_file_parquet",
+                )),
+            }
+        }
+    }
+
+    impl<R: BufRead> RecordIter<R> {
+        /// Wraps any buffered reader, the first line is treated as the header
+        /// and decides the [`Layout`] of the following ones
+        pub fn new(reader: R) -> Self {
+            Self::with_parser(reader, Layout::parse)
+        }
+
+        /// Newline-delimited JSON, one record object per line and no header
+        pub fn json_lines(reader: R) -> Self {
+            Self::with_parser(reader, |_, line| parse_json_line(line)).has_header(false)
+        }
+
+        /// Headerless input with a known layout
+        pub fn with_layout(reader: R, layout: Layout) -> Self {
+            Self {
+                layout,
+                ..Self::new(reader).has_header(false)
+            }
+        }
+
+        /// For readers positioned past the header, e.g. a chunk in the middle of a file
+        pub fn without_header(reader: R) -> Self {
+            Self {
+                header: false,
+                ..Self::new(reader)
+            }
+        }
+    }
+
+    impl<R: BufRead, T> RecordIter<R, T> {
+        /// Any line -> record function, e.g. for a schema other than [`ClientData`]
+        pub fn with_parser(reader: R, parse: fn(&Layout, &str) -> Result<T, IngestError>) -> Self {
+            Self {
+                reader,
+                buf: Vec::new(),
+                line_num: 0,
+                offset: 0,
+                consumed: 0,
+                header: true,
+                layout: Layout::default(),
+                parse,
+            }
+        }
+
+        pub fn has_header(mut self, header: bool) -> Self {
+            self.header = header;
+            self
+        }
+
+        /// See [`Layout::project`], delimited lines only (JSON records are parsed whole)
+        pub fn project(mut self, columns: &[Column]) -> Self {
+            self.layout = self.layout.project(columns);
+            self
+        }
+
+        /// See [`Layout::with_number_format`], delimited lines only like [`RecordIter::project`]
+        pub fn number_format(mut self, number_format: NumberFormat) -> Self {
+            self.layout = self.layout.with_number_format(number_format);
+            self
+        }
+
+        /// 1-based number of the last line read (header included)
+        pub fn line_num(&self) -> usize {
+            self.line_num
+        }
+
+        /// Where the last line read starts, in bytes from the start of the
+        /// (decompressed) input
+        pub fn byte_offset(&self) -> u64 {
+            self.offset
+        }
+
+        /// Each record with the line and byte offset it came from
+        pub fn located(self) -> LocatedIter<R, T> {
+            LocatedIter::new(self)
+        }
+
+        pub fn layout(&self) -> &Layout {
+            &self.layout
+        }
+    }
+
+    impl<R: BufRead, T> Iterator for RecordIter<R, T> {
+        type Item = Result<T, IngestError>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                // reusing the same buffer, no allocation per line
+                self.buf.clear();
+                let n = match read_line(&mut self.reader, &mut self.buf) {
+                    Ok(0) => {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!(
+                            lines = self.line_num,
+                            bytes = self.consumed,
+                            "end of input"
+                        );
+                        return None;
+                    }
+                    Ok(n) => n,
+                    Err(e) => return Some(Err(e.into())),
+                };
+                self.line_num += 1;
+                self.offset = self.consumed;
+                self.consumed += n as u64;
+                if n > self.buf.len() {
+                    return Some(Err(line_too_long(&self.buf, n, self.line_num)));
+                }
+
+                let line = match decode_line(&self.buf, self.line_num) {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(e)),
+                };
+
+                // header only tells us the column order
+                if self.header && self.line_num == 1 {
+                    let delimiter = self.layout.delimiter();
+                    match Layout::from_names(line.split(delimiter)) {
+                        Ok(layout) => {
+                            self.layout = self.layout.with_header(layout);
+                            continue;
+                        }
+                        Err(e) => {
+                            return Some(Err(IngestError::Line {
+                                line_num: self.line_num,
+                                line: line.to_string(),
+                                source: Box::new(e),
+                            }));
+                        }
+                    }
+                }
+
+                return Some(
+                    (self.parse)(&self.layout, line).map_err(|e| IngestError::Line {
+                        line_num: self.line_num,
+                        line: line.to_string(),
+                        source: Box::new(e),
+                    }),
+                );
+            }
+        }
+    }
+
+    /// Longest line a record can come from, header included. Far past any real
+    /// record, it's there so input without newlines can't take all the memory
+    pub const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+    /// How much of a line over [`MAX_LINE_BYTES`] its error keeps
+    const TOO_LONG_SAMPLE: usize = 256;
+
+    /// `read_until(b'\n')` that keeps at most [`MAX_LINE_BYTES`] of the line in
+    /// `buf`, the rest of a longer one is read past and dropped. Returns the
+    /// bytes the line took up in the input, more than `buf` got when it was cut
+    pub(crate) fn read_line<R: BufRead + ?Sized>(
+        reader: &mut R,
+        buf: &mut Vec<u8>,
+    ) -> std::io::Result<usize> {
+        let mut consumed = 0;
+        loop {
+            let available = match reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            let (taken, done) = match memchr::memchr(b'\n', available) {
+                Some(end) => (end + 1, true),
+                None => (available.len(), false),
+            };
+            let room = MAX_LINE_BYTES.saturating_sub(buf.len());
+            buf.extend_from_slice(&available[..taken.min(room)]);
+            reader.consume(taken);
+            consumed += taken;
+            if done || taken == 0 {
+                return Ok(consumed);
+            }
+        }
+    }
+
+    /// The error for a line [`read_line`] cut short, `len` bytes long in the input
+    pub(crate) fn line_too_long(buf: &[u8], len: usize, line_num: usize) -> IngestError {
+        IngestError::Line {
+            line_num,
+            line: String::from_utf8_lossy(&buf[..buf.len().min(TOO_LONG_SAMPLE)]).into_owned(),
+            source: Box::new(IngestError::LineTooLong { len }),
+        }
+    }
+
+    /// The line in `buf` without its line ending. Invalid UTF-8 is a broken line like
+    /// any other, not a reason to give up on the file
+    pub(crate) fn decode_line(buf: &[u8], line_num: usize) -> Result<&str, IngestError> {
+        match std::str::from_utf8(buf) {
+            Ok(line) => Ok(line.trim_end_matches(['\n', '\r'])),
+            Err(e) => Err(IngestError::Line {
+                line_num,
+                line: String::from_utf8_lossy(buf)
+                    .trim_end_matches(['\n', '\r'])
+                    .to_string(),
+                source: Box::new(e.into()),
+            }),
+        }
+    }
+
+    pub fn open_file(path: &str) -> Result<Vec<ClientData>, IngestError> {
+        // we decided to skip broken records, open_file_with counts them
+        open_file_with(path, IngestOptions::new()).map(|(records, _)| records)
+    }
+
+    /// Checks `path` against the `.sha256` sidecar the generator wrote next to it,
+    /// catches truncated fixtures from an interrupted run before they skew anything
+    pub fn verify(path: &str) -> Result<(), IngestError> {
+        let expected = crate::checksum::read_sidecar(path)?
+            .ok_or_else(|| IngestError::MissingChecksum(path.to_string()))?;
+        let actual = crate::checksum::sha256_file(path)?;
+
+        if actual != expected {
+            return Err(IngestError::ChecksumMismatch {
+                path: path.to_string(),
+                expected,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn parse_line(line: &str) -> Result<ClientData, IngestError> {
+        let parts: Vec<&str> = line.split('|').collect();
+
+        let (id, from_id, to_id, amount) = if parts.len() == 4 {
+            // Well-formed record
+            (parts[0], parts[1], parts[2], parts[3])
+        } else {
+            return Err(IngestError::FieldCount {
+                expected: 4,
+                got: parts.len(),
+            });
+        };
+
+        let record = ClientData {
+            id: id.trim().to_string(),
+            from_id: from_id.trim().to_string(),
+            to_id: to_id.trim().to_string(),
+            amount: parse_amount(amount.trim())?,
+            timestamp: None,
+            kind: TransactionKind::default(),
+            currency: None,
+        };
+
+        Ok(record)
+    }
+
+    /// Same as [`parse_line`] without the `Vec`: memchr finds the three
+    /// delimiters and the fields are sliced out of the line directly
+    pub fn parse_line_fast(line: &str) -> Result<ClientData, IngestError> {
+        let mut delimiters = memchr::memchr_iter(b'|', line.as_bytes());
+
+        let (Some(first), Some(second), Some(third), None) = (
+            delimiters.next(),
+            delimiters.next(),
+            delimiters.next(),
+            delimiters.next(),
+        ) else {
+            return Err(IngestError::FieldCount {
+                expected: 4,
+                got: memchr::memchr_iter(b'|', line.as_bytes()).count() + 1,
+            });
+        };
+
+        // '|' is ascii, so every delimiter position is a char boundary
+        Ok(ClientData {
+            id: line[..first].trim().to_string(),
+            from_id: line[first + 1..second].trim().to_string(),
+            to_id: line[second + 1..third].trim().to_string(),
+            amount: parse_amount(line[third + 1..].trim())?,
+            timestamp: None,
+            kind: TransactionKind::default(),
+            currency: None,
+        })
+    }
+
+    pub(crate) fn parse_amount(amount: &str) -> Result<f64, IngestError> {
+        amount.parse().map_err(|source| IngestError::InvalidAmount {
+            value: amount.to_string(),
+            source,
+        })
+    }
+
+    impl ClientData {
+        /// The record as a line [`parse_line`] reads back, `id|from_id|to_id|amount`
+        /// with the amount in full. See [`Layout::to_line`] for other layouts
+        pub fn to_line(&self) -> String {
+            Layout::default().to_line(self)
+        }
+
+        /// The record as a line [`parse_json_line`] reads back, every field included
+        pub fn to_json_line(&self) -> String {
+            serde_json::to_string(self).expect("a record always serializes")
+        }
+    }
+
+    /// One JSON Lines record, e.g. `{"id":"TXN..","from_id":"ACC..","to_id":"ACC..","amount":1.5}`
+    pub fn parse_json_line(line: &str) -> Result<ClientData, IngestError> {
+        Ok(serde_json::from_str(line)?)
+    }
+}
+
+pub mod analysis {
+    use super::data_ingestion::{ClientColumns, ClientData};
+
+    mod accounts;
+    mod analyzer;
+    pub mod anomaly;
+    #[cfg(feature = "arrow")]
+    mod arrow;
+    mod cancellable;
+    pub mod cardinality;
+    mod cents;
+    mod currency;
+    #[cfg(feature = "rayon")]
+    mod deterministic;
+    #[cfg(feature = "duckdb")]
+    mod duckdb;
+    #[cfg(feature = "decimal")]
+    mod exact;
+    mod follow;
+    pub mod graph;
+    mod heavy_hitters;
+    pub mod join;
+    mod kahan;
+    mod kinds;
+    mod ledger;
+    pub mod query;
+    mod result;
+    pub mod simd;
+    #[cfg(feature = "datafusion")]
+    pub mod sql;
+    pub mod stats;
+    mod streaming;
+    #[cfg(feature = "rayon")]
+    mod tuning;
+    mod windowed;
+
+    #[cfg(feature = "duckdb")]
+    pub use self::duckdb::{analyze_duckdb, totals_by_sender_duckdb};
+    pub use accounts::{
+        AccountStats, aggregate_by_account, aggregate_by_account_compact, top_n_active,
+        top_n_receivers, top_n_senders,
+    };
+    #[cfg(feature = "polars")]
+    pub use analyzer::Polars;
+    #[cfg(feature = "rayon")]
+    pub use analyzer::Rayon;
+    pub use analyzer::{Analyzer, Functional, Greedy, Registry, Threads};
+    #[cfg(feature = "arrow")]
+    pub use arrow::analyze_arrow;
+    #[cfg(feature = "rayon")]
+    pub use cancellable::analyze_rayon_cancellable;
+    pub use cancellable::analyze_threads_cancellable;
+    pub use cents::{CentsError, CentsOverflow, CentsTotals, analyze_file_cents};
+    pub use currency::{MissingRate, RateTable, totals_by_currency};
+    #[cfg(feature = "rayon")]
+    pub use deterministic::{DETERMINISTIC_CHUNK, analyze_rayon_deterministic};
+    pub use follow::{FOLLOW_INTERVAL, LiveStats};
+    pub use heavy_hitters::{
+        COUNTERS_PER_K, HeavyHitter, MIN_COUNTERS, SpaceSaving, TopAccounts, TopK, top_k_accounts,
+    };
+    #[cfg(feature = "rayon")]
+    pub use kahan::analyze_kahan_rayon;
+    pub use kahan::{KahanSum, analyze_kahan};
+    pub use kinds::totals_by_kind;
+    pub use ledger::{ConservationReport, verify_conservation};
+    pub use result::{AnalysisResult, Totals};
+    pub use simd::analyze_simd;
+    #[cfg(feature = "datafusion")]
+    pub use sql::analyze_datafusion;
+    pub use streaming::{
+        StreamStats, analyze_streaming, analyze_streaming_cancellable, analyze_streaming_into,
+    };
+    #[cfg(feature = "rayon")]
+    pub use tuning::{
+        AnalysisOptions, Autotune, CANDIDATE_MIN_LENS, analyze_columnar_rayon_with,
+        analyze_rayon_with, autotune,
+    };
+    pub use windowed::{Window, WindowStats, aggregate_windows};
+
+    #[cfg(feature = "decimal")]
+    pub use exact::{analyze_cents, analyze_decimal};
+    #[cfg(all(feature = "decimal", feature = "rayon"))]
+    pub use exact::{analyze_cents_rayon, analyze_decimal_rayon};
+
+    /// Greedy approach: for loop with mutable accumulators
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = records.len()))
+    )]
+    pub fn analyze_greedy(records: &[ClientData]) -> AnalysisResult {
+        let mut total_amount = 0.0;
+        let mut count = 0;
+        let mut min = f64::INFINITY;
+        let mut max = f64::NEG_INFINITY;
+
+        for record in records {
+            total_amount += record.amount;
+            count += 1;
+            min = min.min(record.amount);
+            max = max.max(record.amount);
+        }
+
+        AnalysisResult::new(total_amount, count, min, max)
+    }
+
+    /// Functional approach: map with fold
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = records.len()))
+    )]
+    pub fn analyze_functional(records: &[ClientData]) -> AnalysisResult {
+        records
+            .iter()
+            .map(|r| r.amount)
+            .fold(Totals::default(), Totals::push)
+            .finish()
+    }
+
+    /// Rayon parallel approach: parallel iterators, a fold per split and a reduce over those
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = records.len()))
+    )]
+    pub fn analyze_rayon(records: &[ClientData]) -> AnalysisResult {
+        use rayon::prelude::*;
+
+        records
+            .par_iter()
+            .map(|r| r.amount)
+            .fold(Totals::default, Totals::push)
+            .reduce(Totals::default, Totals::merge)
+            .finish()
+    }
+
+    /// Hand-rolled threads: one contiguous chunk per thread, summed in
+    /// `std::thread::scope` and added up in chunk order - what rayon does for
+    /// us, minus the work stealing. `n_threads` of 0 counts as 1
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = records.len()))
+    )]
+    pub fn analyze_threads(records: &[ClientData], n_threads: usize) -> AnalysisResult {
+        let chunk_len = records.len().div_ceil(n_threads.max(1)).max(1);
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = records
+                .chunks(chunk_len)
+                .map(|chunk| {
+                    scope.spawn(|| {
+                        chunk
+                            .iter()
+                            .map(|r| r.amount)
+                            .fold(Totals::default(), Totals::push)
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("analysis thread panicked"))
+                .fold(Totals::default(), Totals::merge)
+        })
+        .finish()
+    }
+
+    /// Columnar approach: straight over the contiguous amounts slice
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = columns.len()))
+    )]
+    pub fn analyze_columnar(columns: &ClientColumns) -> AnalysisResult {
+        columns
+            .amounts
+            .iter()
+            .copied()
+            .fold(Totals::default(), Totals::push)
+            .finish()
+    }
+
+    /// Columnar + rayon: parallel over the amounts slice
+    #[cfg(feature = "rayon")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = columns.len()))
+    )]
+    pub fn analyze_columnar_rayon(columns: &ClientColumns) -> AnalysisResult {
+        use rayon::prelude::*;
+
+        columns
+            .amounts
+            .par_iter()
+            .copied()
+            .fold(Totals::default, Totals::push)
+            .reduce(Totals::default, Totals::merge)
+            .finish()
+    }
+
+    /// Polars DataFrame approach
+    #[cfg(feature = "polars")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(records = records.len()))
+    )]
+    pub fn analyze_polars(
+        records: &[ClientData],
+    ) -> Result<AnalysisResult, polars::error::PolarsError> {
+        use polars::prelude::*;
+
+        // Create series from our data
+        let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+        let from_ids: Vec<&str> = records.iter().map(|r| r.from_id.as_str()).collect();
+        let to_ids: Vec<&str> = records.iter().map(|r| r.to_id.as_str()).collect();
+        let amounts: Vec<f64> = records.iter().map(|r| r.amount).collect();
+
+        let df = DataFrame::new(vec![
+            Column::Series(Series::new("id".into(), ids)),
+            Column::Series(Series::new("from_id".into(), from_ids)),
+            Column::Series(Series::new("to_id".into(), to_ids)),
+            Column::Series(Series::new("amount".into(), amounts)),
+        ])?;
+
+        // Perform aggregations - convert Column to Series for sum
+        let amount_col = df.column("amount")?;
+        let amount_series = amount_col.as_materialized_series();
+        let total_amount = amount_series.sum::<f64>().unwrap_or(0.0);
+        let count = df.height();
+        let min = amount_series.min::<f64>()?.unwrap_or(f64::INFINITY);
+        let max = amount_series.max::<f64>()?.unwrap_or(f64::NEG_INFINITY);
+
+        Ok(AnalysisResult::new(total_amount, count, min, max))
+    }
+
+    /// Polars end to end: `scan_csv` does the reading and parsing, so there is no
+    /// `Vec<ClientData>` to convert first.
+    ///
+    /// A corrupted delimiter makes the line one field short, polars fills the
+    /// missing last column with null instead of failing, so those rows get filtered
+    /// out to match what the `ClientData` loaders skip
+    #[cfg(feature = "polars")]
+    #[cfg_attr(feature = "tracing", tracing::instrument)]
+    pub fn analyze_polars_lazy(path: &str) -> Result<AnalysisResult, polars::error::PolarsError> {
+        use polars::prelude::*;
+
+        let amount = Schema::from_iter([Field::new("amount".into(), DataType::Float64)]);
+        let mut scan = LazyCsvReader::new(path)
+            .with_separator(b'|')
+            .with_has_header(true)
+            .with_dtype_overwrite(Some(Arc::new(amount)))
+            .finish()?;
+        let schema = scan.collect_schema()?;
+        let last = schema
+            .iter_names()
+            .last()
+            .cloned()
+            .unwrap_or_else(|| "amount".into());
+
+        let totals = scan
+            .filter(col(last).is_not_null())
+            .select([
+                col("amount").sum().alias("sum"),
+                col("amount").count().alias("count"),
+                col("amount").min().alias("min"),
+                col("amount").max().alias("max"),
+            ])
+            .collect()?;
+
+        let total_amount = totals.column("sum")?.f64()?.get(0).unwrap_or(0.0);
+        let count = totals.column("count")?.idx()?.get(0).unwrap_or(0) as usize;
+        let min = totals.column("min")?.f64()?.get(0).unwrap_or(f64::INFINITY);
+        let max = totals
+            .column("max")?
+            .f64()?
+            .get(0)
+            .unwrap_or(f64::NEG_INFINITY);
+
+        Ok(AnalysisResult::new(total_amount, count, min, max))
+    }
+}
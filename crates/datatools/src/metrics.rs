@@ -0,0 +1,82 @@
+//! Ingestion counters and histograms through the [`metrics`](https://docs.rs/metrics)
+//! facade, with the `metrics` feature. Install a recorder (e.g.
+//! `metrics-exporter-prometheus`) and they show up, without one or without
+//! the feature recording is a no-op.
+//!
+//! Loaders record once per batch (a file, a chunk, a poll...), not per record
+
+use std::io::{Read, Seek, SeekFrom};
+use std::time::Duration;
+
+use crate::data_ingestion::DirtyDataReport;
+
+/// Counter, records that parsed. Label `loader`
+pub const RECORDS_PARSED: &str = "p01_records_parsed_total";
+/// Counter, broken lines. Labels `loader` and `kind` ([`crate::data_ingestion::FailureKind`])
+pub const PARSE_FAILURES: &str = "p01_parse_failures_total";
+/// Counter, bytes read from files and object stores (compressed as stored)
+pub const BYTES_READ: &str = "p01_bytes_read_total";
+/// Histogram, seconds per batch. Label `loader`
+pub const BATCH_SECONDS: &str = "p01_batch_duration_seconds";
+
+/// Bucket bounds for [`BATCH_SECONDS`], from a small poll to a whole big file
+pub const BATCH_BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0, 5.0, 10.0, 60.0];
+
+/// Units and help texts for the exporter, call once after installing the recorder
+#[cfg(feature = "metrics")]
+pub fn describe() {
+    use ::metrics::{Unit, describe_counter, describe_histogram};
+
+    describe_counter!(RECORDS_PARSED, Unit::Count, "Records parsed");
+    describe_counter!(PARSE_FAILURES, Unit::Count, "Lines that failed to parse");
+    describe_counter!(BYTES_READ, Unit::Bytes, "Bytes read from the input");
+    describe_histogram!(
+        BATCH_SECONDS,
+        Unit::Seconds,
+        "Time to read and parse a batch"
+    );
+}
+
+/// One batch done: `records` parsed, `dirty` the lines of this batch only
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_batch(
+    loader: &'static str,
+    records: usize,
+    dirty: &DirtyDataReport,
+    elapsed: Duration,
+) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(RECORDS_PARSED, "loader" => loader).increment(records as u64);
+        for (kind, category) in &dirty.categories {
+            ::metrics::counter!(PARSE_FAILURES, "loader" => loader, "kind" => format!("{kind:?}"))
+                .increment(category.count as u64);
+        }
+        ::metrics::histogram!(BATCH_SECONDS, "loader" => loader).record(elapsed.as_secs_f64());
+    }
+}
+
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub fn record_bytes(bytes: u64) {
+    #[cfg(feature = "metrics")]
+    ::metrics::counter!(BYTES_READ).increment(bytes);
+}
+
+/// Counts what goes through into [`BYTES_READ`], goes under the `BufReader`
+/// so it's once per buffer fill, not per line
+pub(crate) struct Metered<R>(pub R);
+
+impl<R: Read> Read for Metered<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.0.read(buf)?;
+        record_bytes(n as u64);
+        Ok(n)
+    }
+}
+
+// a jump ahead reads nothing, only what's read after it counts
+impl<R: Seek> Seek for Metered<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
@@ -0,0 +1,140 @@
+//! The page cache between a reader and the disk: hints for it, evicting a
+//! file from it, and `O_DIRECT` reads that skip it. The benches use them so
+//! every approach reads the fixture the same way, not whichever runs second
+//! off the pages the first one cached
+
+use std::fs::File;
+use std::io::BufRead;
+
+/// Bytes per `O_DIRECT` read, see [`DirectReader`]
+pub const DIRECT_READ_SIZE: usize = 1024 * 1024;
+
+/// What `O_DIRECT` wants buffers, offsets and lengths aligned to, 4096 covers
+/// the logical block size of every common disk
+#[cfg(target_os = "linux")]
+const DIRECT_ALIGN: usize = 4096;
+
+/// What the kernel gets told about how a file will be read, see posix_fadvise(2)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Advice {
+    /// front to back, so it reads further ahead
+    Sequential,
+    /// not needed again, its clean pages leave the cache
+    DontNeed,
+}
+
+/// `posix_fadvise` over the whole of `file`
+#[cfg(target_os = "linux")]
+pub fn advise(file: &File, advice: Advice) -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let advice = match advice {
+        Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+        Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+    };
+    // safety: the fd is open for as long as `file` is, a length of 0 means to the end
+    match unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, advice) } {
+        0 => Ok(()),
+        errno => Err(std::io::Error::from_raw_os_error(errno)),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn advise(_file: &File, _advice: Advice) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "posix_fadvise hints are Linux only",
+    ))
+}
+
+/// Takes `path` out of the page cache, so the next read of it comes off the disk.
+/// Unlike `/proc/sys/vm/drop_caches` it needs no root and leaves every other
+/// file's pages alone. Dirty pages can't be dropped, so it's synced first
+pub fn evict(path: &str) -> std::io::Result<()> {
+    let file = File::open(path)?;
+    file.sync_data()?;
+    advise(&file, Advice::DontNeed)
+}
+
+/// `File::open` with [`Advice::Sequential`], for readers that go front to back.
+/// It's only a hint, a kernel that won't take it still gets the file opened
+pub fn open_sequential(path: &str) -> std::io::Result<File> {
+    let file = File::open(path)?;
+    let _ = advise(&file, Advice::Sequential);
+    Ok(file)
+}
+
+/// A file read with `O_DIRECT`, past the page cache: every read comes off the
+/// disk however often the file is read, and nothing it reads stays cached for
+/// the next reader. Filesystems without `O_DIRECT` (tmpfs) fail to open
+pub struct DirectReader {
+    file: File,
+    /// over-allocated by [`DIRECT_ALIGN`], the aligned window starts at `start`
+    buf: Vec<u8>,
+    start: usize,
+    /// filled and handed out so far, relative to `start`
+    filled: usize,
+    pos: usize,
+}
+
+impl DirectReader {
+    #[cfg(target_os = "linux")]
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(path)?;
+        let buf = vec![0; DIRECT_READ_SIZE + DIRECT_ALIGN];
+        let start = buf.as_ptr().align_offset(DIRECT_ALIGN);
+        Ok(Self {
+            file,
+            buf,
+            start,
+            filled: 0,
+            pos: 0,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn open(_path: &str) -> std::io::Result<Self> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "O_DIRECT reads are Linux only",
+        ))
+    }
+}
+
+impl BufRead for DirectReader {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        if self.pos >= self.filled {
+            // whole aligned blocks every time, so the file offset stays aligned
+            // too. Only the last read of the file comes back short
+            let window = &mut self.buf[self.start..self.start + DIRECT_READ_SIZE];
+            self.filled = loop {
+                match std::io::Read::read(&mut self.file, window) {
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                    result => break result?,
+                }
+            };
+            self.pos = 0;
+            crate::metrics::record_bytes(self.filled as u64);
+        }
+        Ok(&self.buf[self.start + self.pos..self.start + self.filled])
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.pos = (self.pos + amount).min(self.filled);
+    }
+}
+
+impl std::io::Read for DirectReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
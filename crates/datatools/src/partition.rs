@@ -0,0 +1,346 @@
+//! Hive-style partitioned datasets: records split by a key into
+//! `out/date=2026-01-05/part-00000.csv` directories, so a query that only
+//! wants last week lists the directories and reads the matching ones instead
+//! of scanning everything.
+//!
+//! The part files are complete records, the partition column stays in them
+//! too. Records without a value for the key go to [`DEFAULT_PARTITION`]
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::data_ingestion::{
+    ClientData, Column, DirtyDataReport, IngestError, Layout, RecordIter, open_shard,
+};
+use crate::format::Format;
+use crate::transform::RecordWriter;
+
+/// Hive's name for the partition of records without a value
+pub const DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// Records per part file before the next one is started
+pub const DEFAULT_RECORDS_PER_FILE: usize = 1_000_000;
+
+/// What the records get split by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PartitionKey {
+    /// UTC day of the timestamp, `YYYY-MM-DD`
+    #[default]
+    Date,
+    Kind,
+    Currency,
+}
+
+impl PartitionKey {
+    /// The directory name's `key=` part
+    pub fn name(self) -> &'static str {
+        match self {
+            PartitionKey::Date => "date",
+            PartitionKey::Kind => "kind",
+            PartitionKey::Currency => "currency",
+        }
+    }
+
+    pub fn value(self, record: &ClientData) -> Option<String> {
+        match self {
+            PartitionKey::Date => record.timestamp.map(date_of),
+            PartitionKey::Kind => Some(record.kind.as_str().to_string()),
+            PartitionKey::Currency => record.currency.map(|c| c.as_str().to_string()),
+        }
+    }
+
+    /// The column the value comes from, part files need it to be read back
+    fn column(self) -> Column {
+        match self {
+            PartitionKey::Date => Column::Timestamp,
+            PartitionKey::Kind => Column::Kind,
+            PartitionKey::Currency => Column::Currency,
+        }
+    }
+}
+
+/// `YYYY-MM-DD` of unix seconds in UTC, so comparing the strings compares the days
+pub fn date_of(timestamp: i64) -> String {
+    let (year, month, day) = civil_from_days(timestamp.div_euclid(86_400));
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Year, month and day of a day count since 1970-01-01 (Howard Hinnant's
+/// `civil_from_days`), no date crate for just this
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// What a finished [`PartitionedWriter`] wrote
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PartitionReport {
+    pub records: usize,
+    pub partitions: usize,
+    pub files: usize,
+    /// lines of the input [`partition_file`] couldn't parse, left out
+    pub dirty: DirtyDataReport,
+}
+
+/// The part file a partition is writing to
+struct Part {
+    sink: RecordWriter,
+    /// records in the current part file
+    records: usize,
+    /// part files so far, the current one included
+    files: usize,
+}
+
+/// Writes records into a partitioned dataset under `dir`, one `key=value`
+/// directory per value. Every partition keeps a part file open until the
+/// next one starts, so a key with thousands of values needs as many file handles
+pub struct PartitionedWriter {
+    dir: PathBuf,
+    key: PartitionKey,
+    format: Format,
+    layout: Layout,
+    records_per_file: usize,
+    parts: HashMap<String, Part>,
+    report: PartitionReport,
+}
+
+impl PartitionedWriter {
+    /// Delimited files are written under a header of [`Layout::default`]'s
+    /// columns and the key's column, see [`PartitionedWriter::layout`]
+    pub fn create(
+        dir: impl Into<PathBuf>,
+        key: PartitionKey,
+        format: Format,
+    ) -> std::io::Result<Self> {
+        if format == Format::Parquet && !cfg!(feature = "parquet") {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "parquet support is not compiled in, enable the `parquet` feature",
+            ));
+        }
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            key,
+            format,
+            layout: Layout::default().with(key.column()),
+            records_per_file: DEFAULT_RECORDS_PER_FILE,
+            parts: HashMap::new(),
+            report: PartitionReport::default(),
+        })
+    }
+
+    /// Columns of the part files, the key's column is added if it's missing
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout.with(self.key.column());
+        self
+    }
+
+    pub fn records_per_file(mut self, records: usize) -> Self {
+        self.records_per_file = records.max(1);
+        self
+    }
+
+    pub fn write(&mut self, record: &ClientData) -> std::io::Result<()> {
+        let value = self
+            .key
+            .value(record)
+            .unwrap_or_else(|| DEFAULT_PARTITION.to_string());
+
+        let full = self
+            .parts
+            .get(&value)
+            .is_some_and(|part| part.records >= self.records_per_file);
+        if full {
+            let part = self.parts.remove(&value).expect("checked above");
+            let files = part.files;
+            part.sink.finish()?;
+            let sink = self.open_sink(&value, files)?;
+            self.parts.insert(
+                value.clone(),
+                Part {
+                    sink,
+                    records: 0,
+                    files: files + 1,
+                },
+            );
+        } else if !self.parts.contains_key(&value) {
+            let sink = self.open_sink(&value, 0)?;
+            self.report.partitions += 1;
+            self.parts.insert(
+                value.clone(),
+                Part {
+                    sink,
+                    records: 0,
+                    files: 1,
+                },
+            );
+        }
+
+        let part = self.parts.get_mut(&value).expect("inserted above");
+        part.sink.write(record)?;
+        part.records += 1;
+        self.report.records += 1;
+        Ok(())
+    }
+
+    /// Flushes and closes every part file
+    pub fn finish(mut self) -> std::io::Result<PartitionReport> {
+        for (_, part) in self.parts.drain() {
+            self.report.files += part.files;
+            part.sink.finish()?;
+        }
+        Ok(self.report)
+    }
+
+    fn open_sink(&mut self, value: &str, index: usize) -> std::io::Result<RecordWriter> {
+        let dir = self.dir.join(format!("{}={value}", self.key.name()));
+        std::fs::create_dir_all(&dir)?;
+        let extension = match self.format {
+            Format::Delimited => "csv",
+            Format::JsonLines => "jsonl",
+            Format::Parquet => "parquet",
+        };
+        let path = dir.join(format!("part-{index:05}.{extension}"));
+
+        RecordWriter::create(&path.to_string_lossy(), self.format, &self.layout)
+    }
+}
+
+/// Every record of `input` (delimited or JSON Lines, compressed or not) into
+/// `writer`'s partitioned dataset. Delimited part files get the input's
+/// columns whatever `writer`'s layout was, broken lines are skipped and counted
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(input = %input)))]
+pub fn partition_file(
+    input: &str,
+    mut writer: PartitionedWriter,
+) -> Result<PartitionReport, IngestError> {
+    let json = Format::from_path(input) == Format::JsonLines;
+    // JSON records have every field, a delimited input's header is only known
+    // once the first line is read
+    if json {
+        writer = writer.layout(
+            Layout::default()
+                .with(Column::Timestamp)
+                .with(Column::Kind)
+                .with(Column::Currency),
+        );
+    }
+
+    let mut records = RecordIter::open(input)?;
+    let mut dirty = DirtyDataReport::default();
+    let mut header_seen = json;
+    while let Some(record) = records.next() {
+        if !header_seen {
+            writer = writer.layout(records.layout().clone());
+            header_seen = true;
+        }
+        match record {
+            Ok(record) => writer.write(&record)?,
+            Err(e) => dirty.absorb(e)?,
+        }
+    }
+    let mut report = writer.finish()?;
+    report.dirty = dirty;
+    Ok(report)
+}
+
+/// One `key=value` directory of a partitioned dataset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Partition {
+    pub key: String,
+    pub value: String,
+    pub path: PathBuf,
+}
+
+impl Partition {
+    /// Whether it's the one for records without a value
+    pub fn is_default(&self) -> bool {
+        self.value == DEFAULT_PARTITION
+    }
+
+    /// The data files in it in part order, like [`crate::data_ingestion::shard_files`]
+    pub fn files(&self) -> std::io::Result<Vec<String>> {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(&self.path)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let data = name.trim_end_matches(".gz").trim_end_matches(".zst");
+            if name.starts_with("part-")
+                && [".csv", ".jsonl", ".parquet"]
+                    .iter()
+                    .any(|ext| data.ends_with(ext))
+            {
+                files.push(path.to_string_lossy().into_owned());
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+}
+
+/// The partitions directly under `dir`, by value. Anything that isn't a
+/// `key=value` directory is left out
+pub fn partitions(dir: impl AsRef<Path>) -> std::io::Result<Vec<Partition>> {
+    let mut partitions = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name();
+        let Some((key, value)) = name.to_str().and_then(|n| n.split_once('=')) else {
+            continue;
+        };
+        partitions.push(Partition {
+            key: key.to_string(),
+            value: value.to_string(),
+            path: entry.path(),
+        });
+    }
+    partitions.sort_by(|a, b| a.value.cmp(&b.value));
+    Ok(partitions)
+}
+
+/// The data files of the partitions `predicate` keeps - the pruning, nothing
+/// in the others is opened
+pub fn partition_files(
+    dir: impl AsRef<Path>,
+    predicate: impl Fn(&Partition) -> bool,
+) -> std::io::Result<Vec<String>> {
+    let mut files = Vec::new();
+    for partition in partitions(dir)?.iter().filter(|p| predicate(p)) {
+        files.append(&mut partition.files()?);
+    }
+    Ok(files)
+}
+
+/// The records of the partitions `predicate` keeps, partition by partition in
+/// value order, e.g. the last week of a `date=` dataset:
+/// `open_partitioned(dir, |p| p.value.as_str() >= "2026-01-05")`
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+pub fn open_partitioned(
+    dir: impl AsRef<Path>,
+    predicate: impl Fn(&Partition) -> bool,
+) -> Result<Vec<ClientData>, IngestError> {
+    let mut records = Vec::new();
+    for file in partition_files(dir, predicate)? {
+        records.append(&mut open_shard(&file)?);
+    }
+    Ok(records)
+}
@@ -0,0 +1,229 @@
+/// URI schemes read through `object_store`, anything else is a local path.
+/// `file://` goes the same way, handy to try the ranged reads without a bucket
+const SCHEMES: [&str; 4] = ["s3://", "s3a://", "gs://", "file://"];
+
+/// `s3://bucket/key`, `gs://bucket/key` or `file:///abs/path`
+pub fn is_object_uri(path: &str) -> bool {
+    SCHEMES.iter().any(|scheme| path.starts_with(scheme))
+}
+
+#[cfg(not(feature = "object_store"))]
+fn not_compiled_in(uri: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("{uri} needs the `object_store` feature to be read"),
+    )
+}
+
+#[cfg(feature = "object_store")]
+pub use store::{RemoteFile, RemoteReader};
+
+/// The whole object as a stream of GET chunks, compression is handled by
+/// [`crate::compression::open_reader`] on top like for local files
+#[cfg(feature = "object_store")]
+pub fn open_reader(uri: &str) -> std::io::Result<Box<dyn std::io::BufRead + Send>> {
+    Ok(Box::new(RemoteFile::open(uri)?.reader()))
+}
+
+#[cfg(not(feature = "object_store"))]
+pub fn open_reader(uri: &str) -> std::io::Result<Box<dyn std::io::BufRead + Send>> {
+    Err(not_compiled_in(uri))
+}
+
+/// [`open_reader`] for [`crate::data_ingestion::open_file_async`], the GET
+/// response is read as it arrives without a runtime of its own
+#[cfg(feature = "object_store")]
+pub(crate) async fn open_async_reader(
+    uri: &str,
+) -> std::io::Result<Box<dyn tokio::io::AsyncBufRead + Send + Unpin>> {
+    store::open_async_reader(uri).await
+}
+
+#[cfg(all(feature = "tokio", not(feature = "object_store")))]
+pub(crate) async fn open_async_reader(
+    uri: &str,
+) -> std::io::Result<Box<dyn tokio::io::AsyncBufRead + Send + Unpin>> {
+    Err(not_compiled_in(uri))
+}
+
+#[cfg(feature = "object_store")]
+mod store {
+    use std::io::{BufRead, Read};
+    use std::ops::Range;
+    use std::sync::{Arc, OnceLock};
+
+    use bytes::Bytes;
+    use futures_util::StreamExt;
+    use futures_util::stream::BoxStream;
+    use object_store::path::Path;
+    use object_store::{ObjectStore, ObjectStoreExt};
+    use tokio::runtime::Runtime;
+
+    /// The blocking readers run their requests here. Calling them from inside
+    /// another runtime panics, an async caller wants `spawn_blocking` around them
+    fn runtime() -> &'static Runtime {
+        static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+        RUNTIME.get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .enable_all()
+                .build()
+                .expect("Failed to start the object store runtime")
+        })
+    }
+
+    fn io_error(e: object_store::Error) -> std::io::Error {
+        match e {
+            object_store::Error::NotFound { .. } => {
+                std::io::Error::new(std::io::ErrorKind::NotFound, e)
+            }
+            e => std::io::Error::other(e),
+        }
+    }
+
+    /// One object in a bucket, its size is looked up once when opening
+    #[derive(Debug, Clone)]
+    pub struct RemoteFile {
+        store: Arc<dyn ObjectStore>,
+        path: Path,
+        len: u64,
+    }
+
+    impl RemoteFile {
+        /// Credentials, region and endpoint come from the usual environment
+        /// variables (`AWS_ACCESS_KEY_ID`, `AWS_ENDPOINT`, `GOOGLE_SERVICE_ACCOUNT`...)
+        pub fn open(uri: &str) -> std::io::Result<Self> {
+            let (store, path) = store_for(uri)?;
+            let meta = runtime().block_on(store.head(&path)).map_err(io_error)?;
+            Ok(Self {
+                store,
+                path,
+                len: meta.size,
+            })
+        }
+
+        pub fn len(&self) -> u64 {
+            self.len
+        }
+
+        pub fn is_empty(&self) -> bool {
+            self.len == 0
+        }
+
+        /// One ranged GET, `range` is cut off at the end of the object
+        pub fn read_range(&self, range: Range<u64>) -> std::io::Result<Bytes> {
+            let range = range.start.min(self.len)..range.end.min(self.len);
+            if range.is_empty() {
+                return Ok(Bytes::new());
+            }
+            let bytes = runtime()
+                .block_on(self.store.get_range(&self.path, range))
+                .map_err(io_error)?;
+            crate::metrics::record_bytes(bytes.len() as u64);
+            Ok(bytes)
+        }
+
+        /// Streams the whole object, only the chunk being read is held in memory
+        pub fn reader(&self) -> RemoteReader {
+            let (store, path) = (self.store.clone(), self.path.clone());
+            let chunks = futures_util::stream::once(async move { store.get(&path).await })
+                .map(|result| match result {
+                    Ok(result) => result.into_stream(),
+                    Err(e) => futures_util::stream::once(async move { Err(e) }).boxed(),
+                })
+                .flatten()
+                .boxed();
+            RemoteReader {
+                chunks,
+                chunk: Bytes::new(),
+            }
+        }
+    }
+
+    pub(super) async fn open_async_reader(
+        uri: &str,
+    ) -> std::io::Result<Box<dyn tokio::io::AsyncBufRead + Send + Unpin>> {
+        let (store, path) = store_for(uri)?;
+        let chunks = store
+            .get(&path)
+            .await
+            .map_err(io_error)?
+            .into_stream()
+            .map(|chunk| chunk.map_err(io_error));
+        Ok(Box::new(tokio_util::io::StreamReader::new(chunks)))
+    }
+
+    fn store_for(uri: &str) -> std::io::Result<(Arc<dyn ObjectStore>, Path)> {
+        let invalid = || {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{uri} is not a bucket/key URI"),
+            )
+        };
+
+        if let Some(local) = uri.strip_prefix("file://") {
+            let path = Path::from_filesystem_path(local)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::NotFound, e))?;
+            return Ok((Arc::new(object_store::local::LocalFileSystem::new()), path));
+        }
+
+        let (scheme, rest) = uri.split_once("://").ok_or_else(invalid)?;
+        let (bucket, key) = rest.split_once('/').ok_or_else(invalid)?;
+        if bucket.is_empty() || key.is_empty() {
+            return Err(invalid());
+        }
+        let bucket_url = format!("{scheme}://{bucket}");
+        let store: Arc<dyn ObjectStore> = match scheme {
+            "s3" | "s3a" => Arc::new(
+                object_store::aws::AmazonS3Builder::from_env()
+                    .with_url(bucket_url)
+                    .build()
+                    .map_err(io_error)?,
+            ),
+            "gs" => Arc::new(
+                object_store::gcp::GoogleCloudStorageBuilder::from_env()
+                    .with_url(bucket_url)
+                    .build()
+                    .map_err(io_error)?,
+            ),
+            _ => return Err(invalid()),
+        };
+        Ok((store, Path::from(key)))
+    }
+
+    /// [`RemoteFile::reader`], a blocking [`BufRead`] over the GET response
+    pub struct RemoteReader {
+        chunks: BoxStream<'static, object_store::Result<Bytes>>,
+        chunk: Bytes,
+    }
+
+    impl BufRead for RemoteReader {
+        fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+            // the response chunks are already buffered, no copy into our own buffer
+            while self.chunk.is_empty() {
+                match runtime().block_on(self.chunks.next()) {
+                    Some(chunk) => {
+                        self.chunk = chunk.map_err(io_error)?;
+                        crate::metrics::record_bytes(self.chunk.len() as u64);
+                    }
+                    None => break,
+                }
+            }
+            Ok(&self.chunk)
+        }
+
+        fn consume(&mut self, amount: usize) {
+            let _ = self.chunk.split_to(amount);
+        }
+    }
+
+    impl Read for RemoteReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let chunk = self.fill_buf()?;
+            let n = chunk.len().min(buf.len());
+            buf[..n].copy_from_slice(&chunk[..n]);
+            self.consume(n);
+            Ok(n)
+        }
+    }
+}
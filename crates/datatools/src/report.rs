@@ -0,0 +1,395 @@
+//! What an analysis found, written out for something other than a person to
+//! read: one JSON document, or a directory of CSV tables. The layout is this
+//! module's own, not the analysis types', and [`REPORT_VERSION`] goes up
+//! whenever it changes in a way a reader would notice
+use std::collections::HashMap;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::analysis::anomaly::{AnomalyReport, Reason};
+use crate::analysis::{AccountStats, AnalysisResult};
+use crate::data_ingestion::DirtyDataReport;
+use crate::validation::ValidationReport;
+
+/// Version of the report layout, in every JSON document and every CSV row
+pub const REPORT_VERSION: u32 = 1;
+
+/// How a [`Report`] gets saved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFormat {
+    /// one pretty-printed document
+    #[default]
+    Json,
+    /// a directory with one file per section, see [`Report::write_csv`]
+    Csv,
+}
+
+impl ReportFormat {
+    /// `.json` is JSON, anything else is taken as a directory for the CSV tables
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("json") => ReportFormat::Json,
+            _ => ReportFormat::Csv,
+        }
+    }
+}
+
+/// [`AnalysisResult`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub distinct_accounts: Option<usize>,
+}
+
+/// One account of [`crate::analysis::aggregate_by_account`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccountRow {
+    pub account: String,
+    pub total_sent: f64,
+    pub total_received: f64,
+    pub sent_count: usize,
+    pub received_count: usize,
+    /// received minus sent
+    pub net: f64,
+}
+
+/// A raw line kept as an example of what went wrong
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Sample {
+    pub line: usize,
+    pub text: String,
+}
+
+/// One [`crate::data_ingestion::FailureKind`] of a [`DirtyDataReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MalformedRow {
+    pub kind: String,
+    pub count: usize,
+    pub first_line: Option<usize>,
+    pub last_line: Option<usize>,
+    pub samples: Vec<Sample>,
+}
+
+/// One rule of a [`ValidationReport`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RuleRow {
+    pub rule: String,
+    pub violations: usize,
+    pub samples: Vec<Sample>,
+}
+
+/// One flagged record of an [`AnomalyReport`], the fields its reason doesn't
+/// have are `None`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AnomalyRow {
+    pub id: String,
+    /// [`Reason::name`]
+    pub reason: &'static str,
+    pub zscore: Option<f64>,
+    pub account: Option<String>,
+    pub window_start: Option<i64>,
+    pub count: Option<usize>,
+    pub baseline: Option<f64>,
+}
+
+/// Everything one run found, every section but `version` is optional and
+/// only holds what was added to it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub version: u32,
+    /// the file the findings are about
+    pub source: Option<String>,
+    pub summary: Option<Summary>,
+    /// by account name
+    pub accounts: Vec<AccountRow>,
+    pub malformed: Vec<MalformedRow>,
+    pub rules: Vec<RuleRow>,
+    pub anomalies: Vec<AnomalyRow>,
+}
+
+impl Default for Report {
+    fn default() -> Self {
+        Self {
+            version: REPORT_VERSION,
+            source: None,
+            summary: None,
+            accounts: Vec::new(),
+            malformed: Vec::new(),
+            rules: Vec::new(),
+            anomalies: Vec::new(),
+        }
+    }
+}
+
+fn samples(samples: &[(usize, String)]) -> Vec<Sample> {
+    samples
+        .iter()
+        .map(|(line, text)| Sample {
+            line: *line,
+            text: text.clone(),
+        })
+        .collect()
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn source(mut self, path: impl Into<String>) -> Self {
+        self.source = Some(path.into());
+        self
+    }
+
+    pub fn summary(mut self, result: &AnalysisResult) -> Self {
+        self.summary = Some(Summary {
+            count: result.count,
+            sum: result.sum,
+            min: result.min,
+            max: result.max,
+            mean: result.mean,
+            distinct_accounts: result.distinct_accounts,
+        });
+        self
+    }
+
+    pub fn accounts(mut self, accounts: &HashMap<String, AccountStats>) -> Self {
+        self.accounts = accounts
+            .iter()
+            .map(|(account, stats)| AccountRow {
+                account: account.clone(),
+                total_sent: stats.total_sent,
+                total_received: stats.total_received,
+                sent_count: stats.sent_count,
+                received_count: stats.received_count,
+                net: stats.net(),
+            })
+            .collect();
+        // a HashMap's order changes from run to run, reports of the same data shouldn't
+        self.accounts.sort_by(|a, b| a.account.cmp(&b.account));
+        self
+    }
+
+    pub fn dirty(mut self, dirty: &DirtyDataReport) -> Self {
+        self.malformed = dirty
+            .categories
+            .iter()
+            .map(|(kind, category)| MalformedRow {
+                kind: format!("{kind:?}"),
+                count: category.count,
+                first_line: category.first_line,
+                last_line: category.last_line,
+                samples: samples(&category.samples),
+            })
+            .collect();
+        self
+    }
+
+    pub fn validation(mut self, validation: &ValidationReport) -> Self {
+        self.rules = validation
+            .rules
+            .iter()
+            .map(|rule| RuleRow {
+                rule: rule.name.clone(),
+                violations: rule.violations,
+                samples: samples(&rule.samples),
+            })
+            .collect();
+        self
+    }
+
+    pub fn anomalies(mut self, anomalies: &AnomalyReport) -> Self {
+        self.anomalies = anomalies
+            .flagged
+            .iter()
+            .map(|anomaly| {
+                let mut row = AnomalyRow {
+                    id: anomaly.id.clone(),
+                    reason: anomaly.reason.name(),
+                    zscore: None,
+                    account: None,
+                    window_start: None,
+                    count: None,
+                    baseline: None,
+                };
+                match &anomaly.reason {
+                    Reason::Outlier { zscore } => row.zscore = Some(*zscore),
+                    Reason::VolumeSpike {
+                        account,
+                        window_start,
+                        count,
+                        baseline,
+                    } => {
+                        row.account = Some(account.clone());
+                        row.window_start = Some(*window_start);
+                        row.count = Some(*count);
+                        row.baseline = Some(*baseline);
+                    }
+                    Reason::SelfTransfer | Reason::RoundAmount => {}
+                }
+                row
+            })
+            .collect();
+        self
+    }
+
+    /// Saves to `path`, a file for JSON and a directory (created if needed) for CSV
+    pub fn save(&self, path: &str, format: ReportFormat) -> std::io::Result<()> {
+        match format {
+            ReportFormat::Json => {
+                let mut file = BufWriter::new(std::fs::File::create(path)?);
+                self.write_json(&mut file)?;
+                file.flush()
+            }
+            ReportFormat::Csv => self.write_csv(path),
+        }
+    }
+
+    pub fn write_json(&self, writer: impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// One table per section into `dir`: `summary.csv`, `accounts.csv`,
+    /// `malformed.csv`, `rules.csv`, `anomalies.csv`, and the sample lines of
+    /// the malformed and rules sections in `samples.csv`. Every table is
+    /// written, with just its header when its section is empty, and every row
+    /// starts with the version
+    pub fn write_csv(&self, dir: impl AsRef<Path>) -> std::io::Result<()> {
+        let dir = dir.as_ref();
+        std::fs::create_dir_all(dir)?;
+        let source = self.source.as_deref().unwrap_or_default();
+
+        let mut summary = Table::create(
+            &dir.join("summary.csv"),
+            "version,source,count,sum,min,max,mean,distinct_accounts",
+        )?;
+        if let Some(s) = &self.summary {
+            summary.row(&[
+                field(source),
+                s.count.to_string(),
+                s.sum.to_string(),
+                opt(s.min),
+                opt(s.max),
+                opt(s.mean),
+                opt(s.distinct_accounts),
+            ])?;
+        }
+        summary.finish()?;
+
+        let mut accounts = Table::create(
+            &dir.join("accounts.csv"),
+            "version,account,total_sent,total_received,sent_count,received_count,net",
+        )?;
+        for a in &self.accounts {
+            accounts.row(&[
+                field(&a.account),
+                a.total_sent.to_string(),
+                a.total_received.to_string(),
+                a.sent_count.to_string(),
+                a.received_count.to_string(),
+                a.net.to_string(),
+            ])?;
+        }
+        accounts.finish()?;
+
+        let mut samples =
+            Table::create(&dir.join("samples.csv"), "version,section,name,line,text")?;
+        let mut malformed = Table::create(
+            &dir.join("malformed.csv"),
+            "version,kind,count,first_line,last_line",
+        )?;
+        for m in &self.malformed {
+            malformed.row(&[
+                field(&m.kind),
+                m.count.to_string(),
+                opt(m.first_line),
+                opt(m.last_line),
+            ])?;
+            for sample in &m.samples {
+                samples.row(&[
+                    "malformed".to_string(),
+                    field(&m.kind),
+                    sample.line.to_string(),
+                    field(&sample.text),
+                ])?;
+            }
+        }
+        malformed.finish()?;
+
+        let mut rules = Table::create(&dir.join("rules.csv"), "version,rule,violations")?;
+        for r in &self.rules {
+            rules.row(&[field(&r.rule), r.violations.to_string()])?;
+            for sample in &r.samples {
+                samples.row(&[
+                    "rules".to_string(),
+                    field(&r.rule),
+                    sample.line.to_string(),
+                    field(&sample.text),
+                ])?;
+            }
+        }
+        rules.finish()?;
+        samples.finish()?;
+
+        let mut anomalies = Table::create(
+            &dir.join("anomalies.csv"),
+            "version,id,reason,zscore,account,window_start,count,baseline",
+        )?;
+        for a in &self.anomalies {
+            anomalies.row(&[
+                field(&a.id),
+                a.reason.to_string(),
+                opt(a.zscore),
+                a.account.as_deref().map(field).unwrap_or_default(),
+                opt(a.window_start),
+                opt(a.count),
+                opt(a.baseline),
+            ])?;
+        }
+        anomalies.finish()
+    }
+}
+
+/// A CSV file being written, the version column is put in front of every row
+struct Table(BufWriter<std::fs::File>);
+
+impl Table {
+    fn create(path: &Path, header: &str) -> std::io::Result<Self> {
+        let mut file = BufWriter::new(std::fs::File::create(path)?);
+        writeln!(file, "{header}")?;
+        Ok(Self(file))
+    }
+
+    fn row(&mut self, fields: &[String]) -> std::io::Result<()> {
+        write!(self.0, "{REPORT_VERSION}")?;
+        for value in fields {
+            write!(self.0, ",{value}")?;
+        }
+        writeln!(self.0)
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Quoted, with the quotes doubled, when it would break the row otherwise
+fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Empty for `None`
+fn opt(value: Option<impl ToString>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
@@ -0,0 +1,335 @@
+//! Sorting files that don't fit in memory: sorted runs of at most
+//! [`SortOptions::memory_budget`] bytes are spilled to temp files and merged
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::compression::{CompressedWriter, Compression};
+use crate::data_ingestion::{
+    ClientData, Column, DirtyDataReport, IngestError, Layout, decode_line, parse_json_line,
+};
+use crate::format::Format;
+
+/// Knobs for [`sort_file`] and [`crate::data_ingestion::open_file_spilled`]
+#[derive(Debug, Clone)]
+pub struct SortOptions {
+    pub key: Column,
+    /// roughly how many bytes of lines are held before a run gets spilled
+    pub memory_budget: usize,
+    /// where the runs go, `None` puts them next to the output (in the system
+    /// temp dir for a spill, there's no output)
+    pub temp_dir: Option<PathBuf>,
+}
+
+impl Default for SortOptions {
+    fn default() -> Self {
+        Self {
+            key: Column::Id,
+            memory_budget: 256 * 1024 * 1024,
+            temp_dir: None,
+        }
+    }
+}
+
+impl SortOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Column to sort by. Ids compare by their number, records without a
+    /// timestamp go last
+    pub fn key(mut self, key: Column) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Somewhere with room for a copy of the input, the output's disk if unset
+    pub fn temp_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(dir.into());
+        self
+    }
+}
+
+/// What a finished [`sort_file`] did
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SortReport {
+    pub records: usize,
+    /// lines that didn't parse, left out of the output
+    pub dirty: DirtyDataReport,
+    /// 1 when everything fit in the budget and nothing touched the disk
+    pub runs: usize,
+}
+
+/// One record's value in the sort column
+#[derive(Debug, Clone)]
+pub(crate) enum Key {
+    /// generated ids by their number (as strings `TXN10` would come before `TXN9`),
+    /// and timestamps
+    Number(i64),
+    Amount(f64),
+    /// ids that aren't generated ones sort after those, and kinds
+    Text(String),
+    /// no timestamp, these go last
+    Missing,
+}
+
+impl Key {
+    pub(crate) fn of(record: &ClientData, column: Column) -> Self {
+        match column {
+            Column::Id => Key::id(&record.id, "TXN"),
+            Column::FromId => Key::id(&record.from_id, "ACC"),
+            Column::ToId => Key::id(&record.to_id, "ACC"),
+            Column::Amount => Key::Amount(record.amount),
+            Column::Timestamp => record.timestamp.map_or(Key::Missing, Key::Number),
+            Column::Kind => Key::Text(record.kind.to_string()),
+            Column::Currency => record
+                .currency
+                .map_or(Key::Missing, |currency| Key::Text(currency.to_string())),
+        }
+    }
+
+    fn id(value: &str, prefix: &str) -> Self {
+        match value.strip_prefix(prefix).map(str::parse) {
+            Some(Ok(n)) => Key::Number(n),
+            _ => Key::Text(value.to_string()),
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            Key::Number(_) => 0,
+            Key::Amount(_) => 1,
+            Key::Text(_) => 2,
+            Key::Missing => 3,
+        }
+    }
+}
+
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Key::Number(a), Key::Number(b)) => a.cmp(b),
+            (Key::Amount(a), Key::Amount(b)) => a.total_cmp(b),
+            (Key::Text(a), Key::Text(b)) => a.cmp(b),
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+/// Lines go through unchanged, so a parsed record only lives long enough to give its key
+struct Parser {
+    format: Format,
+    layout: Layout,
+    column: Column,
+}
+
+impl Parser {
+    fn key(&self, line: &str) -> Result<Key, IngestError> {
+        let record = match self.format {
+            Format::JsonLines => parse_json_line(line)?,
+            _ => self.layout.parse(line)?,
+        };
+        Ok(Key::of(&record, self.column))
+    }
+}
+
+/// Writes the records of `input` to `output` ordered by [`SortOptions::key`].
+///
+/// The sort is stable, records with the same key stay in file order, which is
+/// what [`crate::data_ingestion::dedup`] relies on to keep the right one.
+/// Plain, `.gz` and `.zst` input, delimited or JSON Lines; the output is
+/// compressed per its extension and gets a checksum sidecar like generated files
+pub fn sort_file(
+    input: &str,
+    output: &str,
+    options: &SortOptions,
+) -> Result<SortReport, IngestError> {
+    let format = Format::from_path(input);
+    if format == Format::Parquet {
+        return Err(IngestError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "parquet is not line-based, sort it with a query instead",
+        )));
+    }
+
+    let temp_dir = match &options.temp_dir {
+        Some(dir) => {
+            let name = Path::new(output).file_name().unwrap_or_default();
+            dir.join(format!("{}.sort-tmp", name.to_string_lossy()))
+        }
+        None => PathBuf::from(format!("{output}.sort-tmp")),
+    };
+
+    let result = sort_into(input, output, format, &temp_dir, options);
+    // runs left behind by a failed sort would only take up space
+    if temp_dir.exists() {
+        std::fs::remove_dir_all(&temp_dir)?;
+    }
+    result
+}
+
+fn sort_into(
+    input: &str,
+    output: &str,
+    format: Format,
+    temp_dir: &Path,
+    options: &SortOptions,
+) -> Result<SortReport, IngestError> {
+    let mut reader = crate::compression::open_reader(input)?;
+    let mut buf = Vec::new();
+    let mut line_num = 0;
+
+    // the header goes through as it was, and decides how the lines are read
+    let mut header = None;
+    let mut parser = Parser {
+        format,
+        layout: Layout::default(),
+        column: options.key,
+    };
+    if format == Format::Delimited && reader.read_until(b'\n', &mut buf)? > 0 {
+        line_num += 1;
+        let line = decode_line(&buf, line_num)?;
+        parser.layout = Layout::from_header(line)?;
+        header = Some(line.to_string());
+    }
+    if format == Format::Delimited && !parser.layout.contains(options.key) {
+        return Err(IngestError::Schema(format!(
+            "can't sort by {}, the file has no such column",
+            options.key.name()
+        )));
+    }
+
+    let mut report = SortReport::default();
+    let mut runs = Vec::new();
+    let mut chunk: Vec<(Key, String)> = Vec::new();
+    let mut chunk_bytes = 0;
+
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf)? == 0 {
+            break;
+        }
+        line_num += 1;
+
+        let parsed = decode_line(&buf, line_num).and_then(|line| {
+            parser
+                .key(line)
+                .map(|key| (key, line.to_string()))
+                .map_err(|e| IngestError::Line {
+                    line_num,
+                    line: line.to_string(),
+                    source: Box::new(e),
+                })
+        });
+        let (key, line) = match parsed {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                report.dirty.absorb(e)?;
+                continue;
+            }
+        };
+
+        chunk_bytes += line.len() + std::mem::size_of::<(Key, String)>();
+        chunk.push((key, line));
+        report.records += 1;
+
+        if chunk_bytes >= options.memory_budget {
+            runs.push(spill(temp_dir, runs.len(), &mut chunk)?);
+            chunk_bytes = 0;
+        }
+    }
+
+    let mut writer = CompressedWriter::create(output, Compression::from_path(output))?;
+    if let Some(header) = &header {
+        writeln!(writer, "{header}")?;
+    }
+
+    if runs.is_empty() {
+        // sort_by is stable
+        chunk.sort_by(|a, b| a.0.cmp(&b.0));
+        for (_, line) in &chunk {
+            writeln!(writer, "{line}")?;
+        }
+        report.runs = 1;
+    } else {
+        if !chunk.is_empty() {
+            runs.push(spill(temp_dir, runs.len(), &mut chunk)?);
+        }
+        report.runs = runs.len();
+        merge(&runs, &parser, &mut writer)?;
+    }
+
+    writer.finish()?;
+    crate::checksum::write_sidecar(output)?;
+    Ok(report)
+}
+
+/// Sorts the chunk and writes it out as one run, leaving it empty
+fn spill(
+    temp_dir: &Path,
+    index: usize,
+    chunk: &mut Vec<(Key, String)>,
+) -> Result<PathBuf, IngestError> {
+    std::fs::create_dir_all(temp_dir)?;
+    let path = temp_dir.join(format!("run-{index:04}"));
+
+    chunk.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut writer = BufWriter::new(File::create(&path)?);
+    for (_, line) in chunk.drain(..) {
+        writeln!(writer, "{line}")?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// k-way merge of the runs. Ties go to the lower run, which holds the earlier
+/// lines of the input, so the merge keeps the sort stable
+fn merge(runs: &[PathBuf], parser: &Parser, writer: &mut impl Write) -> Result<(), IngestError> {
+    let mut readers = runs
+        .iter()
+        .map(|path| File::open(path).map(|file| BufReader::new(file).lines()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+
+    // runs were parsed once already, every line in them parses again
+    let mut next = |run: usize| -> Result<Option<(Key, usize, String)>, IngestError> {
+        match readers[run].next().transpose()? {
+            Some(line) => Ok(Some((parser.key(&line)?, run, line))),
+            None => Ok(None),
+        }
+    };
+
+    let mut heap = BinaryHeap::with_capacity(runs.len());
+    for run in 0..runs.len() {
+        if let Some(head) = next(run)? {
+            heap.push(Reverse(head));
+        }
+    }
+    while let Some(Reverse((_, run, line))) = heap.pop() {
+        writeln!(writer, "{line}")?;
+        if let Some(head) = next(run)? {
+            heap.push(Reverse(head));
+        }
+    }
+    Ok(())
+}
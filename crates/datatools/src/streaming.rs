@@ -0,0 +1,219 @@
+use std::time::Duration;
+
+use kafka::consumer::{Consumer, FetchOffset, GroupOffsetStorage};
+use kafka::producer::{Producer, Record, RequiredAcks};
+
+use crate::Instant;
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, parse_json_line};
+use crate::format::Format;
+use crate::generator::{
+    GeneratedRecord, GenerationReport, GeneratorConfig, RecordSink, generate_mock_data_into,
+};
+
+/// Messages sent per produce request at most, a throttled run flushes smaller ones
+const PUBLISH_BATCH: usize = 1000;
+
+/// Where [`publish`] sends transactions and [`consume`] reads them from
+#[derive(Debug, Clone, PartialEq)]
+pub struct KafkaConfig {
+    /// `host:port` of one or more brokers, the rest of the cluster is discovered
+    pub brokers: Vec<String>,
+    pub topic: String,
+    /// consumer group, its committed offsets are where [`consume`] carries on
+    pub group: String,
+    /// [`consume`] returns once nothing arrived for this long, `None` keeps it going
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            brokers: vec!["localhost:9092".to_string()],
+            topic: "transactions".to_string(),
+            group: "p01".to_string(),
+            idle_timeout: None,
+        }
+    }
+}
+
+impl KafkaConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn brokers(mut self, brokers: Vec<String>) -> Self {
+        self.brokers = brokers;
+        self
+    }
+
+    pub fn topic(mut self, topic: &str) -> Self {
+        self.topic = topic.to_string();
+        self
+    }
+
+    pub fn group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+}
+
+/// The generator's lines as messages, one per record. A text sink, so the
+/// corrupted lines go out as they are, the consumer has to cope with them
+pub struct KafkaSink {
+    producer: Producer,
+    topic: String,
+    batch: Vec<Vec<u8>>,
+}
+
+impl KafkaSink {
+    pub fn connect(kafka: &KafkaConfig) -> Result<Self, IngestError> {
+        let producer = Producer::from_hosts(kafka.brokers.clone())
+            .with_ack_timeout(Duration::from_secs(1))
+            .with_required_acks(RequiredAcks::One)
+            .create()?;
+        Ok(Self {
+            producer,
+            topic: kafka.topic.clone(),
+            batch: Vec::with_capacity(PUBLISH_BATCH),
+        })
+    }
+
+    fn push(&mut self, mut message: Vec<u8>) -> std::io::Result<()> {
+        if message.last() == Some(&b'\n') {
+            message.pop();
+        }
+        self.batch.push(message);
+        if self.batch.len() == PUBLISH_BATCH {
+            self.send_batch()?;
+        }
+        Ok(())
+    }
+
+    fn send_batch(&mut self) -> std::io::Result<()> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+
+        let records: Vec<_> = self
+            .batch
+            .iter()
+            .map(|line| Record::from_value(self.topic.as_str(), line.as_slice()))
+            .collect();
+        self.producer
+            .send_all(&records)
+            .map_err(std::io::Error::other)?;
+        self.batch.clear();
+        Ok(())
+    }
+}
+
+impl RecordSink for KafkaSink {
+    fn is_text(&self) -> bool {
+        true
+    }
+
+    // the line goes into the batch, the buffer isn't coming back
+    fn write(&mut self, record: GeneratedRecord) -> std::io::Result<Vec<u8>> {
+        self.push(record.line)?;
+        Ok(Vec::new())
+    }
+
+    // the half line at the end is a message of its own
+    fn write_raw(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.push(bytes.to_vec())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.send_batch()
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.send_batch()
+    }
+}
+
+/// Generates per `config` straight into the topic, one JSON record per message,
+/// [`GeneratorConfig::records_per_second`] makes it a live feed. The format is
+/// always JSON Lines, a message has no header to say what its columns are
+pub fn publish(
+    kafka: &KafkaConfig,
+    config: &GeneratorConfig,
+) -> Result<GenerationReport, IngestError> {
+    let sink = KafkaSink::connect(kafka)?;
+    let config = config.clone().format(Format::JsonLines);
+
+    Ok(generate_mock_data_into(sink, &config, |_| {})?)
+}
+
+/// What [`consume`] got through
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsumeReport {
+    pub messages: usize,
+    pub records: usize,
+    /// the message offset stands in for the line number
+    pub dirty: DirtyDataReport,
+}
+
+/// Reads the topic from the group's committed offsets (its start for a new
+/// group) and hands every poll's records to `on_batch`. Offsets are only
+/// committed once `on_batch` returned, so a crash in between means those
+/// messages come again: at-least-once. Broken messages are skipped and counted
+/// in [`ConsumeReport::dirty`]
+pub fn consume(
+    kafka: &KafkaConfig,
+    mut on_batch: impl FnMut(&[ClientData]) -> Result<(), IngestError>,
+) -> Result<ConsumeReport, IngestError> {
+    let mut consumer = Consumer::from_hosts(kafka.brokers.clone())
+        .with_topic(kafka.topic.clone())
+        .with_group(kafka.group.clone())
+        .with_fallback_offset(FetchOffset::Earliest)
+        .with_offset_storage(Some(GroupOffsetStorage::Kafka))
+        .create()?;
+
+    let mut report = ConsumeReport::default();
+    let mut records = Vec::new();
+    let mut last_message = Instant::now();
+    loop {
+        let message_sets = consumer.poll()?;
+        if message_sets.is_empty() {
+            match kafka.idle_timeout {
+                Some(idle) if last_message.elapsed() >= idle => return Ok(report),
+                _ => continue,
+            }
+        }
+        last_message = Instant::now();
+
+        let start = Instant::now();
+        let mut dirty = DirtyDataReport::default();
+        records.clear();
+        for message_set in message_sets.iter() {
+            for message in message_set.messages() {
+                report.messages += 1;
+                crate::metrics::record_bytes(message.value.len() as u64);
+                let parsed = std::str::from_utf8(message.value)
+                    .map_err(IngestError::from)
+                    .and_then(parse_json_line);
+                match parsed {
+                    Ok(record) => records.push(record),
+                    Err(e) => dirty.absorb(IngestError::Line {
+                        line_num: message.offset as usize,
+                        line: String::from_utf8_lossy(message.value).to_string(),
+                        source: Box::new(e),
+                    })?,
+                }
+            }
+            consumer.consume_messageset(message_set)?;
+        }
+
+        crate::metrics::record_batch("kafka", records.len(), &dirty, start.elapsed());
+        report.dirty.merge(dirty);
+        on_batch(&records)?;
+        report.records += records.len();
+        consumer.commit_consumed()?;
+    }
+}
@@ -0,0 +1,361 @@
+//! Cleaning a dataset into a new one: records are read one at a time, go
+//! through a [`Transform`]'s steps in order and are written out in any format,
+//! so only the current record is held (a parquet input is the exception, it's
+//! read whole like everywhere else).
+//!
+//! A `Transform` doesn't change while it runs, the same one can clean any
+//! number of files, from several threads at once too
+use std::io::Write;
+
+use crate::compression::{CompressedWriter, Compression};
+use crate::data_ingestion::{
+    ClientData, Column, Currency, DirtyDataReport, IngestError, Layout, RecordIter, TransactionKind,
+};
+use crate::format::Format;
+
+type MapFn = Box<dyn Fn(ClientData) -> Option<ClientData> + Send + Sync>;
+
+enum Step {
+    NormalizeIds,
+    ClampAmounts { min: f64, max: f64 },
+    FillTimestamp(i64),
+    FillCurrency(Currency),
+    Map(MapFn),
+}
+
+/// What a [`Transform::run`] did
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct TransformReport {
+    /// records out of the input
+    pub records: usize,
+    pub written: usize,
+    /// dropped by [`Transform::map`] or [`Transform::filter`]
+    pub dropped: usize,
+    /// lines that didn't parse, left out of the output
+    pub dirty: DirtyDataReport,
+    /// records [`Transform::normalize_ids`] changed
+    pub normalized: usize,
+    pub clamped: usize,
+    /// records a `fill_*` step gave a missing value
+    pub filled: usize,
+}
+
+/// Steps that clean one record at a time, in the order they're added
+#[derive(Default)]
+pub struct Transform {
+    steps: Vec<Step>,
+    dropped_columns: Vec<Column>,
+}
+
+impl Transform {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trims the ids and upper-cases them, ` acc01000001` becomes `ACC01000001`
+    pub fn normalize_ids(mut self) -> Self {
+        self.steps.push(Step::NormalizeIds);
+        self
+    }
+
+    /// Amounts below `min` become `min`, above `max` become `max`
+    pub fn clamp_amounts(mut self, min: f64, max: f64) -> Self {
+        self.steps.push(Step::ClampAmounts { min, max });
+        self
+    }
+
+    /// Records without a timestamp get this one, and the output a timestamp column
+    pub fn fill_timestamp(mut self, timestamp: i64) -> Self {
+        self.steps.push(Step::FillTimestamp(timestamp));
+        self
+    }
+
+    /// Records without a currency get this one, and the output a currency column
+    pub fn fill_currency(mut self, currency: Currency) -> Self {
+        self.steps.push(Step::FillCurrency(currency));
+        self
+    }
+
+    /// Any change of a record, `None` drops it
+    pub fn map(
+        mut self,
+        f: impl Fn(ClientData) -> Option<ClientData> + Send + Sync + 'static,
+    ) -> Self {
+        self.steps.push(Step::Map(Box::new(f)));
+        self
+    }
+
+    /// Keeps only the records `keep` returns true for
+    pub fn filter(self, keep: impl Fn(&ClientData) -> bool + Send + Sync + 'static) -> Self {
+        self.map(move |record| keep(&record).then_some(record))
+    }
+
+    /// Leaves optional columns (timestamp, kind, currency) out of the output,
+    /// the required ones can't be dropped
+    pub fn drop_columns(mut self, columns: &[Column]) -> Self {
+        self.dropped_columns.extend_from_slice(columns);
+        self
+    }
+
+    /// One record through every step, `None` when a step dropped it
+    pub fn apply(&self, record: ClientData) -> Option<ClientData> {
+        self.apply_counted(record, &mut TransformReport::default())
+    }
+
+    fn apply_counted(
+        &self,
+        mut record: ClientData,
+        report: &mut TransformReport,
+    ) -> Option<ClientData> {
+        for step in &self.steps {
+            match step {
+                Step::NormalizeIds => {
+                    let mut changed = false;
+                    for id in [&mut record.id, &mut record.from_id, &mut record.to_id] {
+                        changed |= normalize_id(id);
+                    }
+                    report.normalized += changed as usize;
+                }
+                Step::ClampAmounts { min, max } => {
+                    let clamped = record.amount.clamp(*min, *max);
+                    // NaN stays NaN, clamp doesn't know where it goes
+                    if clamped != record.amount && !record.amount.is_nan() {
+                        record.amount = clamped;
+                        report.clamped += 1;
+                    }
+                }
+                Step::FillTimestamp(timestamp) => {
+                    if record.timestamp.is_none() {
+                        record.timestamp = Some(*timestamp);
+                        report.filled += 1;
+                    }
+                }
+                Step::FillCurrency(currency) => {
+                    if record.currency.is_none() {
+                        record.currency = Some(*currency);
+                        report.filled += 1;
+                    }
+                }
+                Step::Map(f) => {
+                    record = match f(record) {
+                        Some(record) => record,
+                        None => {
+                            report.dropped += 1;
+                            return None;
+                        }
+                    };
+                }
+            }
+        }
+
+        // dropped columns are gone from JSON output too
+        for column in &self.dropped_columns {
+            match column {
+                Column::Timestamp => record.timestamp = None,
+                Column::Kind => record.kind = TransactionKind::default(),
+                Column::Currency => record.currency = None,
+                Column::Id | Column::FromId | Column::ToId | Column::Amount => {}
+            }
+        }
+        Some(record)
+    }
+
+    /// The output's columns for an input with `input`'s: the filled ones
+    /// added, the dropped ones taken out
+    pub fn layout(&self, input: &Layout) -> Layout {
+        let mut layout = input.clone();
+        for step in &self.steps {
+            match step {
+                Step::FillTimestamp(_) => layout = layout.with(Column::Timestamp),
+                Step::FillCurrency(_) => layout = layout.with(Column::Currency),
+                _ => {}
+            }
+        }
+        let kept: Vec<&str> = layout
+            .columns()
+            .iter()
+            .filter(|c| !self.dropped_columns.contains(c))
+            .map(|c| c.name())
+            .collect();
+        Layout::from_names(kept)
+            .expect("only optional columns are dropped")
+            .with_delimiter(input.delimiter())
+    }
+
+    /// Cleans `input` (delimited, JSON Lines or parquet, compressed or not)
+    /// into `output`, both formats going by the extension
+    pub fn run(&self, input: &str, output: &str) -> Result<TransformReport, IngestError> {
+        self.run_with(input, output, Format::from_path(output))
+    }
+
+    /// [`Transform::run`] into `format` whatever `output`'s extension says.
+    /// Delimited output keeps the input's columns, see [`Transform::layout`]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, fields(input = %input, output = %output))
+    )]
+    pub fn run_with(
+        &self,
+        input: &str,
+        output: &str,
+        format: Format,
+    ) -> Result<TransformReport, IngestError> {
+        if let Some(column) = self.dropped_columns.iter().find(|c| {
+            matches!(
+                c,
+                Column::Id | Column::FromId | Column::ToId | Column::Amount
+            )
+        }) {
+            return Err(IngestError::Schema(format!(
+                "{} is a required column, it can't be dropped",
+                column.name()
+            )));
+        }
+
+        let mut report = TransformReport::default();
+        // JSON and parquet records have every field
+        let every_column = Layout::default()
+            .with(Column::Timestamp)
+            .with(Column::Kind)
+            .with(Column::Currency);
+
+        match Format::from_path(input) {
+            Format::Parquet => {
+                #[cfg(feature = "parquet")]
+                {
+                    let mut writer =
+                        RecordWriter::create(output, format, &self.layout(&every_column))?;
+                    for record in crate::data_ingestion::open_file_parquet(input)? {
+                        self.write(record, &mut writer, &mut report)?;
+                    }
+                    writer.finish()?;
+                }
+                #[cfg(not(feature = "parquet"))]
+                return Err(IngestError::Schema(
+                    "parquet support is not compiled in, enable the `parquet` feature".into(),
+                ));
+            }
+            input_format => {
+                let mut records = RecordIter::open(input)?;
+                // a delimited input's columns are only known once its header is read
+                let mut writer = None;
+                while let Some(record) = records.next() {
+                    let writer = match &mut writer {
+                        Some(writer) => writer,
+                        None => {
+                            let input_layout = match input_format {
+                                Format::JsonLines => &every_column,
+                                _ => records.layout(),
+                            };
+                            writer.insert(RecordWriter::create(
+                                output,
+                                format,
+                                &self.layout(input_layout),
+                            )?)
+                        }
+                    };
+                    match record {
+                        Ok(record) => self.write(record, writer, &mut report)?,
+                        Err(e) => report.dirty.absorb(e)?,
+                    }
+                }
+                // an empty input still gets an (empty) output
+                let writer = match writer {
+                    Some(writer) => writer,
+                    None => RecordWriter::create(output, format, &self.layout(records.layout()))?,
+                };
+                writer.finish()?;
+            }
+        }
+        Ok(report)
+    }
+
+    fn write(
+        &self,
+        record: ClientData,
+        writer: &mut RecordWriter,
+        report: &mut TransformReport,
+    ) -> Result<(), IngestError> {
+        report.records += 1;
+        if let Some(record) = self.apply_counted(record, report) {
+            writer.write(&record)?;
+            report.written += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Trimmed and upper-cased in place, whether that changed anything
+fn normalize_id(id: &mut String) -> bool {
+    let trimmed = id.trim();
+    if trimmed.len() == id.len() && !trimmed.bytes().any(|b| b.is_ascii_lowercase()) {
+        return false;
+    }
+    *id = trimmed.to_ascii_uppercase();
+    true
+}
+
+enum Output {
+    Text(CompressedWriter),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<crate::generator::ParquetSink>),
+}
+
+/// Records into a file one at a time: delimited under a header of the
+/// layout's columns (compressed by the extension, `.gz` or `.zst`), JSON Lines
+/// with every field, or parquet with the layout as its schema
+pub struct RecordWriter {
+    output: Output,
+    format: Format,
+    layout: Layout,
+}
+
+impl RecordWriter {
+    pub fn create(path: &str, format: Format, layout: &Layout) -> std::io::Result<Self> {
+        let output = match format {
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Output::Parquet(Box::new(crate::generator::ParquetSink::create(
+                path, layout,
+            )?)),
+            #[cfg(not(feature = "parquet"))]
+            Format::Parquet => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Unsupported,
+                    "parquet support is not compiled in, enable the `parquet` feature",
+                ));
+            }
+            Format::Delimited | Format::JsonLines => {
+                let mut writer = CompressedWriter::create(path, Compression::from_path(path))?;
+                if format == Format::Delimited {
+                    writeln!(writer, "{}", layout.header(layout.delimiter()))?;
+                }
+                Output::Text(writer)
+            }
+        };
+        Ok(Self {
+            output,
+            format,
+            layout: layout.clone(),
+        })
+    }
+
+    pub fn write(&mut self, record: &ClientData) -> std::io::Result<()> {
+        match &mut self.output {
+            Output::Text(writer) => match self.format {
+                Format::JsonLines => writeln!(writer, "{}", record.to_json_line()),
+                _ => writeln!(writer, "{}", self.layout.to_line(record)),
+            },
+            #[cfg(feature = "parquet")]
+            Output::Parquet(sink) => sink.push(record),
+        }
+    }
+
+    /// Writes the trailer or footer and flushes everything to disk
+    pub fn finish(self) -> std::io::Result<()> {
+        match self.output {
+            Output::Text(writer) => writer.finish(),
+            #[cfg(feature = "parquet")]
+            Output::Parquet(sink) => sink.finish(),
+        }
+    }
+}
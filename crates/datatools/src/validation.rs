@@ -0,0 +1,358 @@
+//! Rules records have to follow beyond parsing: ranges, id formats, no
+//! self-transfers, no repeated ids. A [`Validator`] runs the registered rules
+//! over a file or over records in memory and counts what broke which rule.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::BufRead;
+
+use crate::data_ingestion::{ClientData, IngestError, Layout, decode_line};
+use crate::format::Format;
+
+/// Offending lines kept per rule by default
+pub const DEFAULT_SAMPLES: usize = 5;
+
+/// One check on one record at a time
+pub trait Rule: Send {
+    /// shows up in the report, keep it short
+    fn name(&self) -> String;
+
+    /// `false` when the record breaks the rule
+    fn check(&mut self, record: &ClientData) -> bool;
+
+    /// Drops whatever a previous run left behind, for rules that remember records
+    fn reset(&mut self) {}
+}
+
+/// `min <= amount <= max`, either end can be infinite
+pub struct AmountRange {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl AmountRange {
+    pub fn new(min: f64, max: f64) -> Self {
+        Self { min, max }
+    }
+}
+
+impl Rule for AmountRange {
+    fn name(&self) -> String {
+        match (self.min.is_finite(), self.max.is_finite()) {
+            (true, false) => format!("amount >= {}", self.min),
+            (false, true) => format!("amount <= {}", self.max),
+            _ => format!("amount in {}..={}", self.min, self.max),
+        }
+    }
+
+    fn check(&mut self, record: &ClientData) -> bool {
+        (self.min..=self.max).contains(&record.amount)
+    }
+}
+
+/// The id matches a regex, e.g. `^TXN\d{10}$` for generated ones
+#[cfg(feature = "regex")]
+pub struct IdPattern(pub regex::Regex);
+
+#[cfg(feature = "regex")]
+impl IdPattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        regex::Regex::new(pattern).map(Self)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl Rule for IdPattern {
+    fn name(&self) -> String {
+        format!("id matches {}", self.0.as_str())
+    }
+
+    fn check(&mut self, record: &ClientData) -> bool {
+        self.0.is_match(&record.id)
+    }
+}
+
+/// `from_id != to_id`, nobody sends money to themselves
+pub struct NoSelfTransfer;
+
+impl Rule for NoSelfTransfer {
+    fn name(&self) -> String {
+        "from_id != to_id".to_string()
+    }
+
+    fn check(&mut self, record: &ClientData) -> bool {
+        record.from_id != record.to_id
+    }
+}
+
+/// Every id once, the first one passes and any repeat fails. Keeps every id
+/// it has seen, ~40 bytes a record
+#[derive(Default)]
+pub struct UniqueIds {
+    seen: HashSet<String>,
+}
+
+impl Rule for UniqueIds {
+    fn name(&self) -> String {
+        "unique ids".to_string()
+    }
+
+    fn check(&mut self, record: &ClientData) -> bool {
+        // contains first, so only new ids get cloned
+        !self.seen.contains(&record.id) && self.seen.insert(record.id.clone())
+    }
+
+    fn reset(&mut self) {
+        self.seen.clear();
+    }
+}
+
+/// Any closure as a rule, for checks too specific to deserve a type
+pub struct Check<F> {
+    name: String,
+    check: F,
+}
+
+impl<F: FnMut(&ClientData) -> bool + Send> Rule for Check<F> {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn check(&mut self, record: &ClientData) -> bool {
+        (self.check)(record)
+    }
+}
+
+/// How often one rule broke, with the first few offenders
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleReport {
+    pub name: String,
+    pub violations: usize,
+    /// `(line number, line)`, at most [`Validator::samples`] of them
+    pub samples: Vec<(usize, String)>,
+}
+
+/// What a [`Validator`] run found
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationReport {
+    pub records: usize,
+    /// lines that didn't even parse, no rule sees those (file runs only)
+    pub malformed: usize,
+    /// in registration order
+    pub rules: Vec<RuleReport>,
+}
+
+impl ValidationReport {
+    pub fn violations(&self) -> usize {
+        self.rules.iter().map(|rule| rule.violations).sum()
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.malformed == 0 && self.violations() == 0
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "records: {}, malformed: {}",
+            self.records, self.malformed
+        )?;
+        for rule in &self.rules {
+            writeln!(f, "{}: {} violations", rule.name, rule.violations)?;
+            for (line_num, line) in &rule.samples {
+                writeln!(f, "  line {line_num}: {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The registered rules, run in order on every record
+pub struct Validator {
+    rules: Vec<Box<dyn Rule>>,
+    pub samples: usize,
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            samples: DEFAULT_SAMPLES,
+        }
+    }
+}
+
+impl Validator {
+    /// No rules yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What a generated file should pass: positive amounts, `TXN` + 10 digit
+    /// ids (with the regex feature), no self-transfers, no repeated ids
+    pub fn standard() -> Self {
+        let validator = Self::new().rule(AmountRange::new(0.0, f64::INFINITY));
+        #[cfg(feature = "regex")]
+        let validator = validator.rule(IdPattern::new(r"^TXN\d{10}$").expect("valid regex"));
+        validator.rule(NoSelfTransfer).rule(UniqueIds::default())
+    }
+
+    pub fn rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    /// [`Check`] under `name`, e.g. `.check("no fees", |r| r.kind != TransactionKind::Fee)`
+    pub fn check(
+        self,
+        name: &str,
+        check: impl FnMut(&ClientData) -> bool + Send + 'static,
+    ) -> Self {
+        self.rule(Check {
+            name: name.to_string(),
+            check,
+        })
+    }
+
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// Runs over records in memory. They have no lines, so the samples are
+    /// numbered from 1 in slice order and show the record pipe-delimited
+    pub fn validate(&mut self, records: &[ClientData]) -> ValidationReport {
+        let mut run = self.start();
+        for record in records {
+            run.check(record);
+        }
+        run.finish()
+    }
+
+    /// Checks records handed over one at a time, for ones that come from
+    /// neither a file nor a slice. Numbered and shown like [`Validator::validate`]
+    pub fn start(&mut self) -> ValidationRun<'_> {
+        ValidationRun(Run::start(self))
+    }
+
+    /// Streams a delimited or JSON Lines file (plain, `.gz` or `.zst`), the
+    /// samples are its raw lines with their line numbers
+    pub fn validate_file(&mut self, path: &str) -> Result<ValidationReport, IngestError> {
+        let format = Format::from_path(path);
+        if format == Format::Parquet {
+            return Err(IngestError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "parquet is not line-based, validate the records with Validator::validate",
+            )));
+        }
+
+        let mut reader = crate::compression::open_reader(path)?;
+        let mut layout = Layout::default();
+        let mut buf = Vec::new();
+        let mut line_num = 0;
+        let mut run = Run::start(self);
+
+        loop {
+            buf.clear();
+            if reader.read_until(b'\n', &mut buf)? == 0 {
+                break;
+            }
+            line_num += 1;
+
+            let Ok(line) = decode_line(&buf, line_num) else {
+                run.report.malformed += 1;
+                continue;
+            };
+            if format == Format::Delimited && line_num == 1 {
+                layout = Layout::from_header(line)?;
+                continue;
+            }
+            let record = match format {
+                Format::JsonLines => crate::data_ingestion::parse_json_line(line),
+                _ => layout.parse(line),
+            };
+            match record {
+                Ok(record) => {
+                    run.check(&record, line_num, || line.to_string());
+                }
+                Err(_) => run.report.malformed += 1,
+            }
+        }
+        Ok(run.report)
+    }
+}
+
+/// [`Validator::start`]
+pub struct ValidationRun<'v>(Run<'v>);
+
+impl ValidationRun<'_> {
+    /// `false` when the record broke any rule
+    pub fn check(&mut self, record: &ClientData) -> bool {
+        let line_num = self.0.report.records + 1;
+        self.0.check(record, line_num, || {
+            format!(
+                "{}|{}|{}|{}",
+                record.id, record.from_id, record.to_id, record.amount
+            )
+        })
+    }
+
+    pub fn report(&self) -> &ValidationReport {
+        &self.0.report
+    }
+
+    pub fn finish(self) -> ValidationReport {
+        self.0.report
+    }
+}
+
+/// A validation in progress, the rules borrowed from the validator
+struct Run<'v> {
+    rules: &'v mut [Box<dyn Rule>],
+    samples: usize,
+    report: ValidationReport,
+}
+
+impl<'v> Run<'v> {
+    fn start(validator: &'v mut Validator) -> Self {
+        let rules = &mut validator.rules[..];
+        for rule in rules.iter_mut() {
+            rule.reset();
+        }
+        let report = ValidationReport {
+            rules: rules
+                .iter()
+                .map(|rule| RuleReport {
+                    name: rule.name(),
+                    violations: 0,
+                    samples: Vec::new(),
+                })
+                .collect(),
+            ..ValidationReport::default()
+        };
+        Self {
+            rules,
+            samples: validator.samples,
+            report,
+        }
+    }
+
+    /// `false` when any rule broke, every rule sees every record either way
+    fn check(&mut self, record: &ClientData, line_num: usize, line: impl Fn() -> String) -> bool {
+        self.report.records += 1;
+        let mut passed = true;
+        for (rule, report) in self.rules.iter_mut().zip(&mut self.report.rules) {
+            if rule.check(record) {
+                continue;
+            }
+            passed = false;
+            report.violations += 1;
+            if report.samples.len() < self.samples {
+                report.samples.push((line_num, line()));
+            }
+        }
+        passed
+    }
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+/// Heap usage of one call, only filled in with `--features alloc-tracking`
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct AllocStats {
+    /// highest live heap above what was live before the call
+    pub peak_bytes: usize,
+    /// everything allocated during the call, freed or not
+    pub allocated_bytes: usize,
+    pub allocations: usize,
+}
+
+#[cfg(feature = "alloc-tracking")]
+mod tracking {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+    pub static CURRENT: AtomicUsize = AtomicUsize::new(0);
+    pub static PEAK: AtomicUsize = AtomicUsize::new(0);
+    pub static TOTAL: AtomicUsize = AtomicUsize::new(0);
+    pub static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    /// `System` plus a few relaxed counters, costs a bit on every allocation
+    pub struct TrackingAlloc;
+
+    fn grow(size: usize) {
+        let current = CURRENT.fetch_add(size, Relaxed) + size;
+        PEAK.fetch_max(current, Relaxed);
+        TOTAL.fetch_add(size, Relaxed);
+        COUNT.fetch_add(1, Relaxed);
+    }
+
+    unsafe impl GlobalAlloc for TrackingAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let ptr = unsafe { System.alloc(layout) };
+            if !ptr.is_null() {
+                grow(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+            let ptr = unsafe { System.alloc_zeroed(layout) };
+            if !ptr.is_null() {
+                grow(layout.size());
+            }
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) };
+            CURRENT.fetch_sub(layout.size(), Relaxed);
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+            let new = unsafe { System.realloc(ptr, layout, new_size) };
+            if !new.is_null() {
+                // counted as freeing the old block and allocating the new one
+                CURRENT.fetch_sub(layout.size(), Relaxed);
+                grow(new_size);
+            }
+            new
+        }
+    }
+}
+
+#[cfg(feature = "alloc-tracking")]
+#[global_allocator]
+static GLOBAL: tracking::TrackingAlloc = tracking::TrackingAlloc;
+
+/// Runs `f` once and reports what it allocated, `None` without the feature
+#[cfg(feature = "alloc-tracking")]
+pub fn measure<T>(f: impl FnOnce() -> T) -> Option<AllocStats> {
+    use std::sync::atomic::Ordering::Relaxed;
+    use tracking::*;
+
+    let baseline = CURRENT.load(Relaxed);
+    PEAK.store(baseline, Relaxed);
+    let (total, count) = (TOTAL.load(Relaxed), COUNT.load(Relaxed));
+
+    std::hint::black_box(f());
+
+    Some(AllocStats {
+        peak_bytes: PEAK.load(Relaxed).saturating_sub(baseline),
+        allocated_bytes: TOTAL.load(Relaxed) - total,
+        allocations: COUNT.load(Relaxed) - count,
+    })
+}
+
+#[cfg(not(feature = "alloc-tracking"))]
+pub fn measure<T>(_f: impl FnOnce() -> T) -> Option<AllocStats> {
+    None
+}
+
+/// Resets the kernel's peak RSS counter so the next [`peak_rss`] only covers what comes after.
+/// Linux only, a no-op elsewhere
+pub fn reset_peak_rss() {
+    // "5" resets VmHWM, see proc(5)
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+/// Peak resident set size in bytes (VmHWM), Linux only
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
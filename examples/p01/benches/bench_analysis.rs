@@ -1,121 +1,621 @@
 use p01::analysis::*;
-use p01::data_ingestion::open_file;
-use std::time::Instant;
+use p01::data_ingestion::{ClientColumns, ClientData};
+use p01::format::Format;
+use p01::generator::parse_size;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
+mod alloc_tracker;
 mod fixture;
-use fixture::init_fixture;
+mod harness;
+mod plot;
+mod profiler;
+mod results;
+use fixture::{FixtureConfig, init_fixture};
+use harness::{Bench, Measurement};
+use results::BenchRun;
 
-fn main() {
-    println!("=== Benchmark: Data Analysis Approaches ===\n");
+const USAGE: &str = "usage: bench_analysis [--json <out>] [--profile] [--scaling] \
+    | compare <old.json> <new.json> | plot <results.json> [<dir>]";
 
-    let file_path = init_fixture();
-    let records = open_file(file_path).expect("Failed to load data");
+/// Fixture sizes of `--scaling`, `BENCH_SCALING=100mb,1gb` for others
+const SCALING_SIZES: &str = "100mb,1gb,4gb";
 
-    println!("--- Greedy Approach (for loop with mut) ---");
-    let iterations = 100;
-    let start = Instant::now();
-    let mut sum_check = 0.0;
+// usage: bench_analysis [--json <out>] [--profile] [--scaling]
+//        bench_analysis compare <old.json> <new.json>
+//        bench_analysis plot <results.json> [<dir>]
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["compare", old, new] => {
+            let old = BenchRun::read(old).expect("Failed to read old results");
+            let new = BenchRun::read(new).expect("Failed to read new results");
+            let regressions = results::compare(&old, &new);
+            if regressions > 0 {
+                println!("\n{regressions} regression(s)");
+                return ExitCode::FAILURE;
+            }
+        }
+        ["plot", json, dir @ ..] if dir.len() <= 1 => {
+            if let Err(e) = plot::plot(json, dir.first().copied()) {
+                eprintln!("Plot failed: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+        flags => {
+            let (mut json_out, mut profile, mut scaling) = (None, false, false);
+            let mut flags = flags.iter();
+            while let Some(flag) = flags.next() {
+                match *flag {
+                    "--json" => match flags.next() {
+                        Some(out) => json_out = Some(*out),
+                        None => {
+                            eprintln!("{USAGE}");
+                            return ExitCode::FAILURE;
+                        }
+                    },
+                    "--profile" => profile = true,
+                    "--scaling" => scaling = true,
+                    _ => {
+                        eprintln!("{USAGE}");
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            if profile && cfg!(not(feature = "pprof")) {
+                eprintln!("--profile needs the pprof feature");
+                return ExitCode::FAILURE;
+            }
+            let bench = Bench {
+                profile,
+                ..Bench::from_env()
+            };
+            let results = match scaling {
+                true => run_scaling(bench),
+                false => run(bench),
+            };
+            finish(&results, json_out, profile);
+        }
+    }
+    ExitCode::SUCCESS
+}
 
-    for _ in 0..iterations {
-        let (total, _) = analyze_greedy(&records);
-        sum_check += total;
+/// Where `--profile` puts its SVGs: `results_flamegraphs/` next to `results.json`,
+/// `flamegraphs/` without `--json`
+fn flamegraph_dir(json_out: Option<&str>) -> PathBuf {
+    match json_out.map(Path::new) {
+        Some(out) => {
+            let stem = out.file_stem().unwrap_or_default().to_string_lossy();
+            out.with_file_name(format!("{stem}_flamegraphs"))
+        }
+        None => PathBuf::from("flamegraphs"),
     }
+}
 
-    let elapsed = start.elapsed();
-    let avg_time = elapsed / iterations;
+/// One SVG per profiled approach, `Columnar + Rayon Approach` goes to
+/// `columnar_rayon_approach.svg`
+#[cfg(feature = "pprof")]
+fn write_flamegraphs(results: &[Measurement], dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for measurement in results {
+        let Some(profile) = &measurement.profile else {
+            continue;
+        };
+        let name = measurement
+            .name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>()
+            .join("_");
+        let path = dir.join(format!("{name}.svg"));
+        let writer = std::io::BufWriter::new(std::fs::File::create(&path)?);
+        profile
+            .report
+            .flamegraph(writer)
+            .map_err(std::io::Error::other)?;
+        println!("Flamegraph: {}", path.display());
+    }
+    Ok(())
+}
 
-    println!("Total time: {:?}", elapsed);
-    println!("Average: {:?} per iteration", avg_time);
-    println!(
-        "Throughput: {:.2} million records/sec",
-        (records.len() as f64 / avg_time.as_secs_f64()) / 1_000_000.0
-    );
-    println!("(checksum: {:.2})\n", sum_check);
+#[cfg(not(feature = "pprof"))]
+fn write_flamegraphs(_results: &[Measurement], _dir: &Path) -> std::io::Result<()> {
+    Ok(())
+}
 
-    println!("--- Functional Approach (map + sum) ---");
-    let start = Instant::now();
-    let mut sum_check = 0.0;
+/// The fixture as records, read back from its binary cache with --features cache
+fn load(file_path: &str) -> Vec<ClientData> {
+    if Format::from_path(file_path) == Format::Parquet {
+        #[cfg(feature = "parquet")]
+        return p01::data_ingestion::open_file_parquet(file_path).expect("Failed to load data");
+        #[cfg(not(feature = "parquet"))]
+        panic!("a parquet fixture needs the `parquet` feature");
+    }
 
-    for _ in 0..iterations {
-        let (total, _) = analyze_functional(&records);
-        sum_check += total;
+    // parsing the fixture again on every run takes longer than some of the benches
+    #[cfg(feature = "cache")]
+    let records = p01::data_ingestion::open_file_cached(file_path);
+    #[cfg(not(feature = "cache"))]
+    let records = p01::data_ingestion::open_file(file_path);
+    records.expect("Failed to load data")
+}
+
+/// `--json` and `--profile` output, once everything ran
+fn finish(results: &[Measurement], json_out: Option<&str>, profile: bool) {
+    if let Some(out) = json_out {
+        BenchRun::new(results)
+            .write(out)
+            .expect("Failed to write results");
+        println!("Results written to {out}\n");
     }
+    if profile {
+        write_flamegraphs(results, &flamegraph_dir(json_out)).expect("Failed to write flamegraphs");
+        println!();
+    }
+}
 
-    let elapsed = start.elapsed();
-    let avg_time = elapsed / iterations;
-    println!("Total time: {:?}", elapsed);
-    println!("Average: {:?} per iteration", avg_time);
-    println!(
-        "Throughput: {:.2} million records/sec",
-        (records.len() as f64 / avg_time.as_secs_f64()) / 1_000_000.0
+/// The in-memory approaches on fixtures of every size in `BENCH_SCALING`, one
+/// loaded at a time. Names get an ` @ 1gb` so runs still compare per approach
+fn run_scaling(bench: Bench) -> Vec<Measurement> {
+    println!("=== Benchmark: Scaling of the Analysis Approaches ===\n");
+
+    let sizes = std::env::var("BENCH_SCALING").unwrap_or_else(|_| SCALING_SIZES.to_string());
+    let sizes: Vec<usize> = sizes
+        .split(',')
+        .map(|size| parse_size(size).expect("Invalid BENCH_SCALING"))
+        .collect();
+
+    let mut results = Vec::new();
+    for &bytes in &sizes {
+        let label = fixture::size_label(bytes);
+        let file_path = FixtureConfig::from_env().bytes(bytes).ensure();
+        let records = load(&file_path);
+        let n = records.len();
+        println!("=== {label} ({n} records) ===\n");
+
+        for analyzer in Registry::builtin().iter() {
+            let bench = match analyzer.expensive() {
+                true => bench.samples(bench.samples.min(10)),
+                false => bench,
+            };
+            let name = format!("{} @ {label}", analyzer.name());
+            results.push(bench.run(&name, n, || analyzer.analyze(&records)));
+        }
+
+        let columns = ClientColumns::from(records.as_slice());
+        results.push(bench.run(&format!("Columnar Approach @ {label}"), n, || {
+            analyze_columnar(&columns)
+        }));
+
+        // the one expected to fall behind: the map outgrows the caches as accounts pile up
+        let group_by = bench.samples(bench.samples.min(10));
+        results.push(group_by.run(&format!("Group by account @ {label}"), n, || {
+            aggregate_by_account(&records)
+        }));
+    }
+
+    print_scaling(&results, &sizes);
+    results
+}
+
+/// Million records/s per approach (rows) and size (columns)
+fn print_scaling(results: &[Measurement], sizes: &[usize]) {
+    let labels: Vec<String> = sizes
+        .iter()
+        .map(|&bytes| fixture::size_label(bytes))
+        .collect();
+    let mut approaches: Vec<&str> = Vec::new();
+    for measurement in results {
+        if let Some((approach, _)) = measurement.name.rsplit_once(" @ ")
+            && !approaches.contains(&approach)
+        {
+            approaches.push(approach);
+        }
+    }
+
+    println!("--- Scaling (million records/sec) ---");
+    print!("{:<45}", "");
+    labels.iter().for_each(|label| print!(" {label:>10}"));
+    println!();
+    for approach in approaches {
+        print!("{approach:<45}");
+        for label in &labels {
+            let name = format!("{approach} @ {label}");
+            match results.iter().find(|m| m.name == name) {
+                Some(measurement) => print!(" {:>10.2}", measurement.throughput()),
+                None => print!(" {:>10}", "-"),
+            }
+        }
+        println!();
+    }
+    println!();
+}
+
+fn run(bench: Bench) -> Vec<Measurement> {
+    println!("=== Benchmark: Data Analysis Approaches ===\n");
+
+    let file_path = init_fixture(FixtureConfig::from_env());
+    // parsing the fixture again on every run takes longer than some of the benches
+    let records = load(file_path);
+    let n = records.len();
+    // the end to end benches read the file themselves, as delimited text
+    let text = Format::from_path(file_path) == Format::Delimited;
+    if !text {
+        println!("Not a delimited fixture, skipping the end to end benches\n");
+    }
+    let mut results = Vec::new();
+
+    // greedy, functional, rayon, scoped threads, polars - whatever is compiled in
+    for analyzer in Registry::builtin().iter() {
+        let bench = match analyzer.expensive() {
+            true => bench.samples(bench.samples.min(10)),
+            false => bench,
+        };
+        results.push(bench.run(analyzer.name(), n, || analyzer.analyze(&records)));
+    }
+
+    // bench 3: compensated (Kahan) summation, trades speed for accuracy
+    results.push(bench.run("Kahan Approach (compensated sum)", n, || {
+        analyze_kahan(&records)
+    }));
+
+    // bench 4: columnar (struct-of-arrays) approach, same data laid out per field
+    let columns = ClientColumns::from(records.as_slice());
+    results.push(
+        bench.run("Columnar Approach (sum over amounts slice)", n, || {
+            analyze_columnar(&columns)
+        }),
     );
-    println!("(checksum: {:.2})\n", sum_check);
 
-    /// bench 3: rayon parallel approach (enable with --features rayon or --features all)
+    // bench 4a: same slice in 8 lanes, avx intrinsics against the portable array the
+    // optimizer is left to vectorize - if they match, auto-vectorization is happening
+    results.push(bench.run("SIMD Approach (8 lanes, avx if present)", n, || {
+        analyze_simd(&columns)
+    }));
+    results.push(bench.run("SIMD Approach (8 lanes, portable)", n, || {
+        simd::totals_lanes(&columns.amounts)
+    }));
+
+    // bench 4b: arrow batches, summed with arrow's compute kernel (enable with --features arrow)
+    #[cfg(feature = "arrow")]
+    let batches: Vec<_> = if text {
+        use p01::data_ingestion::{DEFAULT_BATCH_ROWS, open_file_arrow};
+
+        let batches = open_file_arrow(file_path, DEFAULT_BATCH_ROWS)
+            .and_then(|reader| reader.collect::<Result<Vec<_>, _>>())
+            .expect("Failed to load data");
+        results.push(
+            bench.run("Arrow Approach (compute::sum over batches)", n, || {
+                analyze_arrow(&batches).expect("Arrow analysis failed")
+            }),
+        );
+        batches
+    } else {
+        Vec::new()
+    };
+
+    // bench 5: rayon parallel approach (enable with --features rayon or --features all)
     #[cfg(feature = "rayon")]
     {
-        println!("--- Rayon Parallel Approach ---");
-        let start = Instant::now();
-        let mut sum_check = 0.0;
+        results.push(bench.run("Columnar + Rayon Approach", n, || {
+            analyze_columnar_rayon(&columns)
+        }));
+        results.push(bench.run("Kahan + Rayon Approach", n, || {
+            analyze_kahan_rayon(&records)
+        }));
 
-        for _ in 0..iterations {
-            let (total, _) = analyze_rayon(&records);
-            sum_check += total;
-        }
+        // bench 5a: rayon's default splitting against the chunk size that won a sweep on a sample
+        let tuned = autotune(&records, 1_000_000);
+        println!("autotuned min_len: {}", tuned.options.min_len);
+        results.push(bench.run("Rayon Approach (autotuned min_len)", n, || {
+            analyze_rayon_with(&records, &tuned.options)
+        }));
 
-        let elapsed = start.elapsed();
-        let avg_time = elapsed / iterations;
-        println!("Total time: {:?}", elapsed);
-        println!("Average: {:?} per iteration", avg_time);
-        println!(
-            "Throughput: {:.2} million records/sec",
-            (records.len() as f64 / avg_time.as_secs_f64()) / 1_000_000.0
-        );
-        println!("(checksum: {:.2})\n", sum_check);
+        // bench 5b: what the fixed chunks and reduction tree cost against the work stealing
+        results.push(bench.run("Rayon Approach (deterministic)", n, || {
+            analyze_rayon_deterministic(&records)
+        }));
     }
 
-    // bench 4: polars df approach
+    // bench 6: polars df approach
     #[cfg(feature = "polars")]
+    if text {
+        use p01::data_ingestion::open_file;
+
+        // like-for-like end to end: both read the file on every call, polars into
+        // its own columns, we into Vec<ClientData>
+        let end_to_end = bench.samples(bench.samples.min(10));
+        results.push(end_to_end.run("Polars Lazy (scan_csv, end to end)", n, || {
+            analyze_polars_lazy(file_path).expect("Polars analysis failed")
+        }));
+        results.push(
+            end_to_end.run("open_file + Functional (end to end)", n, || {
+                analyze_functional(&open_file(file_path).expect("Failed to load data"))
+            }),
+        );
+    }
+
+    // bench 6c: datafusion SQL, also end to end (enable with --features datafusion)
+    #[cfg(feature = "datafusion")]
+    if text {
+        results.push(bench.samples(bench.samples.min(10)).run(
+            "DataFusion SQL (end to end)",
+            n,
+            || analyze_datafusion(file_path).expect("DataFusion query failed"),
+        ));
+    }
+
+    // bench 6d: duckdb reading the csv itself (enable with --features duckdb)
+    #[cfg(feature = "duckdb")]
+    if text {
+        use p01::data_ingestion::open_file;
+
+        let end_to_end = bench.samples(bench.samples.min(10));
+        results.push(end_to_end.run("DuckDB sum/count (end to end)", n, || {
+            analyze_duckdb(file_path).expect("DuckDB query failed")
+        }));
+        results.push(
+            end_to_end.run("DuckDB group by sender (end to end)", n, || {
+                totals_by_sender_duckdb(file_path).expect("DuckDB query failed")
+            }),
+        );
+        results.push(
+            end_to_end.run("open_file + aggregate_by_account (end to end)", n, || {
+                aggregate_by_account(&open_file(file_path).expect("Failed to load data"))
+            }),
+        );
+    }
+
+    // bench 7: exact money, integer cents and decimals (enable with --features decimal)
+    #[cfg(feature = "decimal")]
+    if text {
+        use p01::data_ingestion::{ClientDataCents, ClientDataDecimal, open_file_exact};
+
+        let (cents, _): (Vec<ClientDataCents>, _) =
+            open_file_exact(file_path).expect("Failed to load data");
+        results.push(bench.run("Integer Cents Approach", cents.len(), || {
+            analyze_cents(&cents)
+        }));
+        drop(cents);
+
+        let (decimals, _): (Vec<ClientDataDecimal>, _) =
+            open_file_exact(file_path).expect("Failed to load data");
+        results.push(bench.run("Decimal Approach", decimals.len(), || {
+            analyze_decimal(&decimals)
+        }));
+
+        let (exact_sum, _) = analyze_decimal(&decimals);
+        println!("Exact sum: {}\n", exact_sum);
+    }
+
+    // bench 8: group by account, String keys against the compact schema's u32 ids
     {
-        println!("--- Polars DataFrame Approach ---");
+        use p01::data_ingestion::open_file_compact;
 
-        // Polars has conversion overhead, so run fewer iterations
-        let polars_iterations = 10;
-        let start = Instant::now();
-        let mut sum_check = 0.0;
+        let group_by = bench.samples(bench.samples.min(10));
+        results.push(group_by.run("Group by account (String keys)", n, || {
+            aggregate_by_account(&records)
+        }));
 
-        for _ in 0..polars_iterations {
-            let (total, _) = analyze_polars(&records).expect("Polars analysis failed");
-            sum_check += total;
+        if text {
+            let (compact, _) = open_file_compact(file_path).expect("Failed to load data");
+            results.push(group_by.run("Group by account (compact u32 keys)", n, || {
+                aggregate_by_account_compact(&compact)
+            }));
         }
 
-        let elapsed = start.elapsed();
-        let avg_time = elapsed / polars_iterations;
-        println!("Total time: {:?}", elapsed);
-        println!("Average: {:?} per iteration", avg_time);
-        println!(
-            "Throughput: {:.2} million records/sec",
-            (records.len() as f64 / avg_time.as_secs_f64()) / 1_000_000.0
+        // the same group by through the query engine, what the generality costs
+        let by_sender = query::Query::new()
+            .group_by("from_id")
+            .agg(query::sum("amount"))
+            .agg(query::count());
+        results.push(group_by.run("Group by sender (query engine)", n, || {
+            by_sender.run(&records).expect("Query failed")
+        }));
+    }
+
+    // bench 9: production-shaped data, zipfian accounts with a few hot ones and
+    // log-normal amounts - the hot keys stay in cache, the long tail doesn't
+    {
+        use p01::generator::{
+            AccountDistribution, AmountDistribution, GeneratorConfig, HotAccounts,
+        };
+
+        let skewed_path = FixtureConfig::from_env().dir.join("bench_data_skewed.csv");
+        let skewed_path = skewed_path.to_string_lossy();
+        let config = GeneratorConfig::new()
+            .target_bytes(1024 * 1024 * 1024)
+            .account_distribution(AccountDistribution::Zipf { exponent: 1.0 })
+            .hot_accounts(HotAccounts::default())
+            .amounts(AmountDistribution::LogNormal {
+                median: 250.0,
+                sigma: 1.2,
+            });
+        fixture::ensure_fixture(&skewed_path, &config);
+
+        let skewed = load(&skewed_path);
+        let group_by = bench.samples(bench.samples.min(10));
+        results.push(group_by.run(
+            "Group by account, skewed (String keys)",
+            skewed.len(),
+            || aggregate_by_account(&skewed),
+        ));
+        results.push(group_by.run("Anomaly detection, skewed", skewed.len(), || {
+            anomaly::detect_anomalies(&skewed, anomaly::AnomalyConfig::default())
+        }));
+
+        // the exact top 10 needs a counter per account, the sketch a fixed 1024
+        results.push(
+            group_by.run("Top 10 accounts, skewed (group by)", skewed.len(), || {
+                let accounts = aggregate_by_account(&skewed);
+                top_n_active(&accounts, 10)
+                    .into_iter()
+                    .map(|(id, _)| id.to_string())
+                    .collect::<Vec<_>>()
+            }),
+        );
+        results.push(group_by.run(
+            "Top 10 accounts, skewed (Space-Saving)",
+            skewed.len(),
+            || {
+                let mut top = TopAccounts::new(10);
+                skewed.iter().for_each(|r| top.add(r));
+                top.result()
+            },
+        ));
+
+        let accounts = aggregate_by_account(&skewed);
+        let exact: Vec<&str> = top_n_active(&accounts, 10)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let mut top = TopAccounts::new(10);
+        skewed.iter().for_each(|r| top.add(r));
+        let found = top
+            .result()
+            .by_count
+            .iter()
+            .filter(|h| exact.contains(&h.account.as_str()))
+            .count();
+        println!("Space-Saving found {found} of the exact top 10 accounts\n");
+    }
+
+    // bench 10: one pass straight off the file against load + group by, end to end
+    if text {
+        use p01::data_ingestion::open_file;
+
+        let end_to_end = bench.samples(bench.samples.min(3));
+        results.push(
+            end_to_end.run("Streaming (one pass, file to totals)", n, || {
+                let reader = p01::compression::open_reader(file_path).unwrap();
+                analyze_streaming(reader).unwrap()
+            }),
+        );
+        results.push(end_to_end.run("Load + group by (file to totals)", n, || {
+            aggregate_by_account(&open_file(file_path).unwrap())
+        }));
+    }
+
+    // bench 11: joined with the accounts table, the hash table against the
+    // grace join that partitions both files to disk first
+    if text {
+        use p01::analysis::join::{
+            JoinOptions, JoinSide, totals_by_country, totals_by_country_files,
+        };
+
+        let accounts_path = FixtureConfig::from_env().dir.join("bench_accounts.csv");
+        let accounts_path = accounts_path.to_string_lossy();
+        if !Path::new(accounts_path.as_ref()).exists() {
+            println!("Generating the accounts table ({accounts_path})...");
+            p01::generator::generate_accounts(
+                &accounts_path,
+                &p01::generator::AccountsConfig::new(),
+            )
+            .expect("Failed to generate the accounts table");
+        }
+        let (accounts, _) = p01::data_ingestion::load_accounts(&accounts_path).unwrap();
+
+        let join = bench.samples(bench.samples.min(3));
+        results.push(join.run("Join accounts, in memory (per country)", n, || {
+            totals_by_country(&records, &accounts, JoinSide::Sender)
+        }));
+        results.push(
+            join.run("Join accounts, grace hash (16 partitions)", n, || {
+                let options = JoinOptions::new().partitions(16);
+                totals_by_country_files(file_path, &accounts_path, &options).unwrap()
+            }),
         );
-        println!("(checksum: {:.2})\n", sum_check);
     }
 
     println!("--- Verification ---");
-    let (greedy_sum, greedy_count) = analyze_greedy(&records);
-    let (func_sum, func_count) = analyze_functional(&records);
+    let greedy = analyze_greedy(&records);
+    let func = analyze_functional(&records);
 
-    println!("Greedy:     sum={:.2}, count={}", greedy_sum, greedy_count);
-    println!("Functional: sum={:.2}, count={}", func_sum, func_count);
+    println!("Greedy:     sum={:.2}, count={}", greedy.sum, greedy.count);
+    println!("Functional: sum={:.2}, count={}", func.sum, func.count);
 
-    #[cfg(feature = "rayon")]
-    {
-        let (rayon_sum, rayon_count) = analyze_rayon(&records);
-        println!("Rayon:      sum={:.2}, count={}", rayon_sum, rayon_count);
+    let col = analyze_columnar(&columns);
+    println!("Columnar:   sum={:.2}, count={}", col.sum, col.count);
+
+    let simd = analyze_simd(&columns);
+    println!("SIMD:       sum={:.2}, count={}", simd.sum, simd.count);
+
+    let kahan = analyze_kahan(&records);
+    println!(
+        "Kahan:      sum={:.2}, count={}",
+        kahan.sum(),
+        kahan.count()
+    );
+
+    #[cfg(feature = "arrow")]
+    if text {
+        let arrow = analyze_arrow(&batches).unwrap();
+        println!("Arrow:      sum={:.2}, count={}", arrow.sum, arrow.count);
     }
 
+    if text {
+        let streamed =
+            analyze_streaming(p01::compression::open_reader(file_path).unwrap()).unwrap();
+        println!(
+            "Streaming:  sum={:.2}, count={}",
+            streamed.sum, streamed.count
+        );
+    }
+
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let threads = analyze_threads(&records, threads);
+    println!(
+        "Threads:    sum={:.2}, count={}",
+        threads.sum, threads.count
+    );
+
+    // the plain rayon sum moves in the last bits from run to run
+    #[cfg(feature = "rayon")]
+    let rayon = analyze_rayon_deterministic(&records);
+    #[cfg(feature = "rayon")]
+    println!("Rayon:      sum={:.2}, count={}", rayon.sum, rayon.count);
+
     #[cfg(feature = "polars")]
     {
-        let (polars_sum, polars_count) = analyze_polars(&records).unwrap();
-        println!("Polars:     sum={:.2}, count={}", polars_sum, polars_count);
+        let polars = analyze_polars(&records).unwrap();
+        println!("Polars:     sum={:.2}, count={}", polars.sum, polars.count);
+
+        if text {
+            let lazy = analyze_polars_lazy(file_path).unwrap();
+            println!("Polars lazy: sum={:.2}, count={}", lazy.sum, lazy.count);
+        }
+    }
+
+    #[cfg(feature = "datafusion")]
+    if text {
+        let df = analyze_datafusion(file_path).unwrap();
+        println!("DataFusion: sum={:.2}, count={}", df.sum, df.count);
     }
+
+    #[cfg(feature = "duckdb")]
+    if text {
+        let duck = analyze_duckdb(file_path).unwrap();
+        println!("DuckDB:     sum={:.2}, count={}", duck.sum, duck.count);
+    }
+
+    // kahan is the reference, anything past its bound is accumulated rounding error
+    println!("\n--- Drift vs Kahan ---");
+    println!(
+        "Greedy:     {:+.6} (naive bound ±{:.6})",
+        greedy.sum - kahan.sum(),
+        kahan.naive_error_bound()
+    );
+    println!("Functional: {:+.6}", func.sum - kahan.sum());
+    println!("SIMD:       {:+.6}", simd.sum - kahan.sum());
+    #[cfg(feature = "rayon")]
+    println!("Rayon:      {:+.6}", rayon.sum - kahan.sum());
+    println!("Kahan bound: ±{:.9}", kahan.error_bound());
+
+    results
 }
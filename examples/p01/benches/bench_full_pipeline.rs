@@ -0,0 +1,207 @@
+use p01::analysis::*;
+use p01::data_ingestion::*;
+use p01::format::Format;
+use std::process::ExitCode;
+
+mod alloc_tracker;
+mod fixture;
+mod harness;
+mod plot;
+mod profiler;
+mod results;
+use fixture::{FixtureConfig, init_fixture};
+use harness::Bench;
+use results::BenchRun;
+
+// usage: bench_full_pipeline [--json <out>]
+//        bench_full_pipeline compare <old.json> <new.json>
+//        bench_full_pipeline plot <results.json> [<dir>]
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args
+        .iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        ["compare", old, new] => {
+            let old = BenchRun::read(old).expect("Failed to read old results");
+            let new = BenchRun::read(new).expect("Failed to read new results");
+            let regressions = results::compare(&old, &new);
+            if regressions > 0 {
+                println!("\n{regressions} regression(s)");
+                return ExitCode::FAILURE;
+            }
+        }
+        ["plot", json, dir @ ..] if dir.len() <= 1 => {
+            if let Err(e) = plot::plot(json, dir.first().copied()) {
+                eprintln!("Plot failed: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+        ["--json", out] => run(Some(out)),
+        [] => run(None),
+        _ => {
+            eprintln!(
+                "usage: bench_full_pipeline [--json <out>] | compare <old.json> <new.json> \
+                 | plot <results.json> [<dir>]"
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+/// Every sample is the whole way from the file to the totals: open, parse,
+/// aggregate. Once the first call pulled the file into the page cache that's
+/// the warm path, `BENCH_COLD=1` drops the cache before every sample
+fn run(json_out: Option<&str>) {
+    println!("=== Benchmark: Full Pipeline (file to answer) ===\n");
+
+    // all of them read delimited text, whatever BENCH_FORMAT says
+    let file_path = init_fixture(FixtureConfig::from_env().format(Format::Delimited));
+    let n = open_file(file_path).expect("Failed to load data").len();
+    let env = Bench::from_env();
+    // a sample is a full read of the fixture, seconds rather than microseconds
+    let bench = Bench {
+        fixture: Some(file_path),
+        ..env.samples(env.samples.min(5))
+    };
+    if bench.cold {
+        println!("Cold: {file_path} evicted from the page cache before every sample\n");
+    }
+    let mut results = Vec::new();
+
+    // the same aggregation after every loader, so the difference is the ingestion
+    results.push(bench.run("Lines (BufReader) + Greedy", n, || {
+        analyze_greedy(&open_file(file_path).expect("Failed to load data"))
+    }));
+
+    // no Vec<ClientData> at all, each record is folded in and dropped
+    results.push(bench.run("Streaming (one pass, no records kept)", n, || {
+        let reader = p01::compression::open_reader(file_path).expect("Failed to open data");
+        analyze_streaming(reader).expect("Failed to load data")
+    }));
+
+    // the amounts alone, as f64 and as integer cents: the same pass, only the number parsing differs
+    results.push(bench.run("Amounts only, f64", n, || {
+        let reader = p01::compression::open_reader(file_path).expect("Failed to open data");
+        RecordIter::with_parser(reader, |_, line| parse_line_ref(line).map(|r| r.amount))
+            .filter_map(Result::ok)
+            .fold(Totals::default(), Totals::push)
+            .finish()
+    }));
+    results.push(bench.run("Amounts only, i64 cents (checked)", n, || {
+        analyze_file_cents(file_path).expect("Failed to load data")
+    }));
+
+    // memory-mapped approach (enable with --features mmap or --features all)
+    #[cfg(feature = "mmap")]
+    results.push(bench.run("Mmap (memmap2) + Greedy", n, || {
+        analyze_greedy(&open_file_mmap(file_path).expect("Failed to load data").0)
+    }));
+
+    // BufReader's lines again past the page cache, always cold. Not on tmpfs, it has no O_DIRECT
+    let direct = p01::page_cache::DirectReader::open(file_path).map(drop);
+    match &direct {
+        Ok(_) => results.push(bench.run("Lines (O_DIRECT) + Greedy", n, || {
+            analyze_greedy(&open_file_direct(file_path).expect("Failed to load data").0)
+        })),
+        Err(e) => {
+            println!("--- Lines (O_DIRECT) + Greedy ---\nSkipped, can't read with O_DIRECT: {e}\n")
+        }
+    }
+
+    // BufReader's lines again, the reads queued ahead through io_uring (enable with --features io-uring)
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    results.push(bench.run("io_uring + Greedy", n, || {
+        analyze_greedy(&open_file_uring(file_path).expect("Failed to load data").0)
+    }));
+
+    // chunks parsed on every core, then summed on every core too (enable with --features rayon)
+    #[cfg(feature = "rayon")]
+    {
+        results.push(bench.run("Rayon Chunked + Greedy", n, || {
+            analyze_greedy(
+                &open_file_parallel(file_path)
+                    .expect("Failed to load data")
+                    .0,
+            )
+        }));
+        results.push(bench.run("Rayon Chunked + Rayon", n, || {
+            analyze_rayon(
+                &open_file_parallel(file_path)
+                    .expect("Failed to load data")
+                    .0,
+            )
+        }));
+    }
+
+    // csv crate reader, pays for quote handling (enable with --features csv)
+    #[cfg(feature = "csv")]
+    results.push(bench.run("csv crate + Greedy", n, || {
+        analyze_greedy(&open_file_csv(file_path).expect("Failed to load data"))
+    }));
+
+    // polars reads, parses and sums in its own columns (enable with --features polars)
+    #[cfg(feature = "polars")]
+    results.push(bench.run("Polars Lazy (scan_csv)", n, || {
+        analyze_polars_lazy(file_path).expect("Polars analysis failed")
+    }));
+
+    if let Some(out) = json_out {
+        BenchRun::new(&results)
+            .write(out)
+            .expect("Failed to write results");
+        println!("Results written to {out}\n");
+    }
+
+    println!("--- Verification ---");
+    let lines = analyze_greedy(&open_file(file_path).expect("Failed to load data"));
+    println!("Lines:      sum={:.2}, count={}", lines.sum, lines.count);
+
+    let streamed = analyze_streaming(p01::compression::open_reader(file_path).unwrap()).unwrap();
+    println!(
+        "Streaming:  sum={:.2}, count={}",
+        streamed.sum, streamed.count
+    );
+
+    // exact, the others are off from it by their rounding
+    let cents = analyze_file_cents(file_path).unwrap();
+    println!("Cents:      {cents}");
+
+    #[cfg(feature = "mmap")]
+    {
+        let mmap = analyze_greedy(&open_file_mmap(file_path).unwrap().0);
+        println!("Mmap:       sum={:.2}, count={}", mmap.sum, mmap.count);
+    }
+
+    if direct.is_ok() {
+        let direct = analyze_greedy(&open_file_direct(file_path).unwrap().0);
+        println!("O_DIRECT:   sum={:.2}, count={}", direct.sum, direct.count);
+    }
+
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    {
+        let uring = analyze_greedy(&open_file_uring(file_path).unwrap().0);
+        println!("io_uring:   sum={:.2}, count={}", uring.sum, uring.count);
+    }
+
+    #[cfg(feature = "rayon")]
+    {
+        let rayon = analyze_rayon_deterministic(&open_file_parallel(file_path).unwrap().0);
+        println!("Rayon:      sum={:.2}, count={}", rayon.sum, rayon.count);
+    }
+
+    #[cfg(feature = "csv")]
+    {
+        let csv = analyze_greedy(&open_file_csv(file_path).unwrap());
+        println!("csv:        sum={:.2}, count={}", csv.sum, csv.count);
+    }
+
+    #[cfg(feature = "polars")]
+    {
+        let polars = analyze_polars_lazy(file_path).unwrap();
+        println!("Polars:     sum={:.2}, count={}", polars.sum, polars.count);
+    }
+}
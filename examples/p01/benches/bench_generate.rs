@@ -0,0 +1,127 @@
+use p01::generator::{GeneratorConfig, WriteStrategy, generate_mock_data_to, parse_size};
+
+mod alloc_tracker;
+mod harness;
+mod profiler;
+use harness::Bench;
+
+/// Lines per `write_vectored` for the vectored runs
+const BATCH_LINES: usize = 256;
+
+// usage: bench_generate, BENCH_SIZE (128mb by default) and BENCH_DIR pick
+// how much gets written where
+fn main() {
+    println!("=== Benchmark: Generator Write Strategies ===\n");
+
+    let bytes = std::env::var("BENCH_SIZE")
+        .map(|size| parse_size(&size).expect("Invalid BENCH_SIZE"))
+        .unwrap_or(128 * 1024 * 1024);
+    let dir = std::env::var("BENCH_DIR").unwrap_or_else(|_| ".".to_string());
+    let out = std::path::Path::new(&dir).join("bench_generate.tmp");
+    // the same records every time, only the way they're written changes
+    let config = GeneratorConfig::new().target_bytes(bytes).seed(42);
+
+    let generate = |config: &GeneratorConfig| {
+        // a real file rather than io::sink, the syscalls are half of what's compared
+        let file = std::fs::File::create(&out).expect("Failed to create output");
+        generate_mock_data_to(file, config, |_| {}).expect("Failed to generate data")
+    };
+    let n = generate(&config).records;
+
+    // a sample writes the whole target, seconds rather than microseconds
+    let env = Bench::from_env();
+    let bench = env.samples(env.samples.min(5));
+
+    let strategies = [
+        ("per-line", WriteStrategy::PerLine),
+        ("reused buffer", WriteStrategy::ReusedBuffer),
+        ("vectored", WriteStrategy::Vectored(BATCH_LINES)),
+    ];
+    let capacities = [8 * 1024, 64 * 1024, 1024 * 1024];
+    let mut digests = Vec::new();
+    for (name, strategy) in strategies {
+        for capacity in capacities {
+            let config = config
+                .clone()
+                .write_strategy(strategy)
+                .buffer_capacity(capacity);
+            bench.run(&format!("{name}, {}kb buffer", capacity / 1024), n, || {
+                generate(&config)
+            });
+            digests.push(digest(&out));
+        }
+    }
+
+    println!("--- Verification ---");
+    // a seeded run is the same bytes however it's written
+    let mismatches = digests.iter().filter(|d| **d != digests[0]).count();
+    println!("Outputs differing from per-line, 8kb buffer: {mismatches}");
+
+    if let Err(e) = std::fs::remove_file(&out) {
+        eprintln!("Warning: Failed to remove {}: {e}", out.display());
+    }
+
+    #[cfg(feature = "zstd")]
+    bench_compressed(&bench, &config, &dir, n);
+}
+
+/// zstd on the writing thread against worker threads compressing blocks
+#[cfg(feature = "zstd")]
+fn bench_compressed(bench: &Bench, config: &GeneratorConfig, dir: &str, n: usize) {
+    use p01::generator::{
+        ParallelCompression, generate_mock_data_pipelined, generate_mock_data_with,
+    };
+
+    println!("\n--- Compressed (zstd) ---");
+    let out = std::path::Path::new(dir).join("bench_generate.tmp.zst");
+    let path = out.to_string_lossy().into_owned();
+    let config = config.clone().buffer_capacity(1024 * 1024);
+
+    bench.run("zstd, writing thread", n, || {
+        generate_mock_data_with(&path, &config).expect("Failed to generate data")
+    });
+    let expected = digest_decompressed(&path);
+
+    let cores = std::thread::available_parallelism().map_or(4, |n| n.get());
+    let mut threads = vec![1, 2, cores.saturating_sub(1).max(1)];
+    threads.sort_unstable();
+    threads.dedup();
+    let mut mismatches = 0;
+    for threads in threads {
+        let options = ParallelCompression::new().threads(threads);
+        bench.run(&format!("zstd, {threads} compressing threads"), n, || {
+            generate_mock_data_pipelined(&path, &config, &options).expect("Failed to generate data")
+        });
+        mismatches += (digest_decompressed(&path) != expected) as usize;
+    }
+    // blocks are frames of their own, decompressed it's the same text
+    println!("Pipelined outputs decompressing to something else: {mismatches}");
+
+    for path in [out.clone(), out.with_extension("zst.sha256")] {
+        if let Err(e) = std::fs::remove_file(&path) {
+            eprintln!("Warning: Failed to remove {}: {e}", path.display());
+        }
+    }
+}
+
+/// [`digest`] of what the file decompresses to
+#[cfg(feature = "zstd")]
+fn digest_decompressed(path: &str) -> u64 {
+    use std::hash::{DefaultHasher, Hasher};
+    use std::io::Read;
+    let mut text = Vec::new();
+    p01::compression::open_reader(path)
+        .and_then(|mut reader| reader.read_to_end(&mut text))
+        .expect("Failed to read output");
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&text);
+    hasher.finish()
+}
+
+/// Hash of the file's bytes, to tell the strategies' outputs apart
+fn digest(path: &std::path::Path) -> u64 {
+    use std::hash::{DefaultHasher, Hasher};
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&std::fs::read(path).expect("Failed to read output"));
+    hasher.finish()
+}
@@ -0,0 +1,392 @@
+use p01::data_ingestion::*;
+use p01::format::Format;
+use p01::page_cache;
+use std::time::Instant;
+
+mod fixture;
+use fixture::{FixtureConfig, init_fixture};
+
+fn main() {
+    println!("=== Benchmark: File Ingestion Approaches ===\n");
+
+    let file_path = init_fixture(FixtureConfig::from_env());
+    // everything below reads delimited text, the other formats only have their own loader
+    let format = Format::from_path(file_path);
+    if format != Format::Delimited {
+        return bench_format(file_path, format);
+    }
+
+    println!("--- Sequential (BufReader lines) ---");
+    go_cold(file_path);
+    let start = Instant::now();
+    let records = open_file(file_path).expect("Failed to load data");
+    let elapsed = start.elapsed();
+
+    println!("Total time: {:?}", elapsed);
+    println!(
+        "Throughput: {:.2} million records/sec",
+        (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+    );
+    println!("(records: {})\n", records.len());
+
+    // memory-mapped approach (enable with --features mmap or --features all)
+    #[cfg(feature = "mmap")]
+    {
+        println!("--- Memory-mapped (memmap2) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let (records, _) = open_file_mmap(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", records.len());
+    }
+
+    // the same line parsing again, past the page cache so it's cold however often it runs.
+    // tmpfs and a few others have no O_DIRECT, the open fails there
+    {
+        println!(
+            "--- O_DIRECT ({} KB aligned reads) ---",
+            page_cache::DIRECT_READ_SIZE / 1024
+        );
+        go_cold(file_path);
+        let start = Instant::now();
+        match open_file_direct(file_path) {
+            Ok((records, _)) => {
+                let elapsed = start.elapsed();
+                println!("Total time: {:?}", elapsed);
+                println!(
+                    "Throughput: {:.2} million records/sec",
+                    (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+                );
+                println!("(records: {})\n", records.len());
+            }
+            Err(e) => println!("Skipped, can't read with O_DIRECT: {e}\n"),
+        }
+    }
+
+    // the same line parsing as the first one, big reads queued ahead through io_uring (enable with --features io-uring)
+    #[cfg(all(feature = "io-uring", target_os = "linux"))]
+    {
+        println!(
+            "--- io_uring ({URING_QUEUE_DEPTH} x {} KB reads in flight) ---",
+            URING_READ_SIZE / 1024
+        );
+        go_cold(file_path);
+        let start = Instant::now();
+        let (records, _) = open_file_uring(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", records.len());
+    }
+
+    // csv crate reader, pays for quote handling the plain split doesn't do (enable with --features csv)
+    #[cfg(feature = "csv")]
+    {
+        println!("--- csv crate (quoting aware) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let records = open_file_csv(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", records.len());
+    }
+
+    // arrow batches straight from the lines, no ClientData (enable with --features arrow)
+    #[cfg(feature = "arrow")]
+    {
+        println!("--- Arrow batches (lines into column builders) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let rows: usize = open_file_arrow(file_path, DEFAULT_BATCH_ROWS)
+            .expect("Failed to open data")
+            .map(|batch| batch.expect("Failed to load data").num_rows())
+            .sum();
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (rows as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", rows);
+    }
+
+    // borrowed records out of one reused line buffer, summed on the fly since they can't be kept
+    {
+        println!("--- Borrowed (ClientDataRef, no allocation per record) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let mut reader = open_file_ref(file_path).expect("Failed to open data");
+        let (mut total, mut count) = (0.0, 0);
+        while let Some(record) = reader.next_record() {
+            if let Ok(record) = record {
+                total += record.amount;
+                count += 1;
+            }
+        }
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (count as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {}, checksum: {:.2})\n", count, total);
+    }
+
+    // account ids shared through an interner, ~9M accounts against tens of millions of records
+    {
+        println!("--- Interned account ids (Arc<str>) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let interned = open_file_interned(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (interned.records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!(
+            "(records: {}, distinct accounts: {})\n",
+            interned.records.len(),
+            interned.distinct_accounts()
+        );
+    }
+
+    // amounts over 10000 only (~90% of the uniform fixture) and no ids, against the full load above
+    {
+        println!("--- Filtered + projected (amount > 10000, amount column only) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let options = IngestOptions::new()
+            .filter(|record| record.amount > 10_000.0)
+            .project(&[Column::Amount]);
+        let (records, report) = open_file_with(file_path, options).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million lines/sec",
+            (report.lines as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!(
+            "(records: {}, filtered out: {})\n",
+            records.len(),
+            report.filtered
+        );
+    }
+
+    // 16 more bytes a record for where it came from, against the sequential load
+    {
+        println!("--- Located (line + byte offset per record) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let (records, _) = open_file_located(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!(
+            "(records: {}, last at byte {})\n",
+            records.len(),
+            records.last().map_or(0, |r| r.byte_offset)
+        );
+    }
+
+    // a 100k sample to iterate on, still one full pass but only the sample is kept
+    {
+        println!("--- Reservoir sample (100k records) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let records = sample(file_path, 100_000, 42).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!("(records: {})\n", records.len());
+    }
+
+    // tokio::fs + a Stream on a single-threaded runtime, what an async service gets
+    // without spawn_blocking (enable with --features tokio or --features all)
+    #[cfg(feature = "tokio")]
+    {
+        use futures_util::StreamExt;
+
+        println!("--- Async (tokio::fs, Stream) ---");
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .expect("Failed to start the runtime");
+        go_cold(file_path);
+        let start = Instant::now();
+        let count = runtime.block_on(async {
+            let records = open_file_async(file_path)
+                .await
+                .expect("Failed to open data");
+            let mut records = std::pin::pin!(records);
+            let mut count = 0;
+            while let Some(record) = records.next().await {
+                if record.is_ok() {
+                    count += 1;
+                }
+            }
+            count
+        });
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (count as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", count);
+    }
+
+    // compressed fixtures, streaming decompression (enable with --features gzip/zstd or --features all)
+    #[cfg(feature = "gzip")]
+    {
+        println!("--- Gzip (flate2, streaming) ---");
+        let records = bench_compressed("bench_data.csv.gz");
+        println!("(records: {})\n", records);
+    }
+
+    #[cfg(feature = "zstd")]
+    {
+        println!("--- Zstd (streaming) ---");
+        let records = bench_compressed("bench_data.csv.zst");
+        println!("(records: {})\n", records);
+    }
+
+    // rayon chunked approaches (enable with --features rayon or --features all)
+    #[cfg(feature = "rayon")]
+    {
+        println!("--- Rayon Chunked (ordered) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let (records, _) = open_file_parallel(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", records.len());
+
+        println!("--- Rayon Chunked (unordered) ---");
+        go_cold(file_path);
+        let start = Instant::now();
+        let (records, _) = open_file_parallel_unordered(file_path).expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?}", elapsed);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {})\n", records.len());
+    }
+
+    // reader thread -> parser threads -> consumer summing, over bounded channels
+    // (enable with --features pipeline or --features all), against the rayon chunks above
+    #[cfg(feature = "pipeline")]
+    for batch_lines in [1024, 8192, 65536] {
+        println!("--- Pipeline (batches of {batch_lines} lines) ---");
+        let options = PipelineOptions::new().batch_lines(batch_lines);
+        go_cold(file_path);
+        let start = Instant::now();
+        let (mut sum, mut count) = (0.0, 0);
+        pipeline(file_path, &options, |records| {
+            sum += records.iter().map(|r| r.amount).sum::<f64>();
+            count += records.len();
+        })
+        .expect("Failed to load data");
+        let elapsed = start.elapsed();
+
+        println!("Total time: {:?} ({} parsers)", elapsed, options.parsers);
+        println!(
+            "Throughput: {:.2} million records/sec",
+            (count as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+        );
+        println!("(records: {count}, sum: {sum:.2})\n");
+    }
+}
+
+/// A JSON Lines or parquet fixture (`BENCH_FORMAT=jsonl|parquet`)
+/// `BENCH_COLD=1` takes the fixture out of the page cache before every approach,
+/// or the second one always reads the pages the first one pulled in
+fn go_cold(file_path: &str) {
+    if std::env::var("BENCH_COLD").is_ok_and(|cold| cold == "1")
+        && let Err(e) = page_cache::evict(file_path)
+    {
+        eprintln!("Warning: Failed to evict {file_path} from the page cache: {e}");
+    }
+}
+
+fn bench_format(file_path: &str, format: Format) {
+    println!("--- {format:?} ---");
+    go_cold(file_path);
+    let start = Instant::now();
+    let records = match format {
+        #[cfg(feature = "parquet")]
+        Format::Parquet => open_file_parquet(file_path),
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => panic!("a parquet fixture needs the `parquet` feature"),
+        Format::JsonLines | Format::Delimited => open_file(file_path),
+    }
+    .expect("Failed to load data");
+    let elapsed = start.elapsed();
+
+    println!("Total time: {:?}", elapsed);
+    println!(
+        "Throughput: {:.2} million records/sec",
+        (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+    );
+    println!(
+        "(records: {}, {:.2} MB on disk)\n",
+        records.len(),
+        std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0) as f64 / 1e6
+    );
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+fn bench_compressed(file_path: &str) -> usize {
+    if !std::path::Path::new(file_path).exists() {
+        println!("Generating compressed benchmark data...");
+        p01::generate_mock_data(file_path, 1).expect("Failed to generate benchmark data");
+    }
+
+    go_cold(file_path);
+    let start = Instant::now();
+    let records = open_file(file_path).expect("Failed to load data");
+    let elapsed = start.elapsed();
+
+    println!("Total time: {:?}", elapsed);
+    println!(
+        "Throughput: {:.2} million records/sec",
+        (records.len() as f64 / elapsed.as_secs_f64()) / 1_000_000.0
+    );
+    println!(
+        "Compressed size: {:.2} GB",
+        std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0) as f64 / 1e9
+    );
+
+    records.len()
+}
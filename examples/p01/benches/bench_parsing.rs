@@ -0,0 +1,115 @@
+use p01::data_ingestion::{Layout, parse_line, parse_line_fast, parse_line_ref};
+use p01::format::Format;
+
+mod alloc_tracker;
+mod fixture;
+mod harness;
+mod profiler;
+use fixture::{FixtureConfig, init_fixture};
+use harness::{Bench, Measurement};
+
+fn main() {
+    println!("=== Benchmark: Line Parsing ===\n");
+
+    // the whole file in memory, so only the parsing gets timed: no I/O and no
+    // aggregation. These are delimited line parsers, whatever BENCH_FORMAT says
+    let file_path = init_fixture(FixtureConfig::from_env().format(Format::Delimited));
+    let content = std::fs::read_to_string(file_path).expect("Failed to read data");
+    let lines: Vec<&str> = content.lines().skip(1).collect();
+    let n = lines.len();
+
+    // every sample parses every line, a lot slower than a sum
+    let env = Bench::from_env();
+    let bench = env.samples(env.samples.min(10));
+    let mut results: Vec<Measurement> = Vec::new();
+
+    results.push(bench.run("parse_line (split + collect into a Vec)", n, || {
+        lines.iter().filter_map(|l| parse_line(l).ok()).count()
+    }));
+
+    results.push(bench.run("parse_line_fast (memchr, no Vec)", n, || {
+        lines.iter().filter_map(|l| parse_line_fast(l).ok()).count()
+    }));
+
+    results.push(
+        bench.run("parse_line_ref (borrowed, no allocation)", n, || {
+            lines.iter().filter_map(|l| parse_line_ref(l).ok()).count()
+        }),
+    );
+
+    let layout = Layout::default();
+    results.push(bench.run("Layout::parse (what the loaders call)", n, || {
+        lines.iter().filter_map(|l| layout.parse(l).ok()).count()
+    }));
+
+    // the csv crate gets the same buffer in one piece, it finds the lines itself
+    #[cfg(feature = "csv")]
+    {
+        let reader = || {
+            csv::ReaderBuilder::new()
+                .delimiter(b'|')
+                .flexible(true)
+                .from_reader(content.as_bytes())
+        };
+
+        results.push(
+            bench.run("csv crate (StringRecord + Layout::parse_fields)", n, || {
+                let mut reader = reader();
+                let mut record = csv::StringRecord::new();
+                let mut count = 0;
+                while reader.read_record(&mut record).unwrap_or(false) {
+                    let fields: Vec<&str> = record.iter().collect();
+                    count += layout.parse_fields(&fields).is_ok() as usize;
+                }
+                count
+            }),
+        );
+
+        results.push(
+            bench.run("csv crate + serde (deserialize into ClientData)", n, || {
+                reader()
+                    .deserialize::<p01::data_ingestion::ClientData>()
+                    .filter_map(Result::ok)
+                    .count()
+            }),
+        );
+    }
+    #[cfg(not(feature = "csv"))]
+    println!("(csv crate and serde left out, they need --features csv)\n");
+
+    // just finding the fields, the floor for anything that builds records
+    results.push(bench.run("memchr delimiters only (no allocation)", n, || {
+        lines
+            .iter()
+            .map(|l| memchr::memchr_iter(b'|', l.as_bytes()).count())
+            .sum::<usize>()
+    }));
+
+    println!("--- Summary ---");
+    println!(
+        "{:<50} {:>14} {:>12}",
+        "parser", "M lines/sec", "allocs/line"
+    );
+    for result in &results {
+        // allocations are only counted with --features alloc-tracking
+        let per_line = result.alloc.map_or("-".to_string(), |alloc| {
+            format!("{:.2}", alloc.allocations as f64 / n as f64)
+        });
+        println!(
+            "{:<50} {:>14.2} {:>12}",
+            result.name,
+            result.throughput(),
+            per_line
+        );
+    }
+    println!();
+
+    println!("--- Verification ---");
+    let slow = lines.iter().filter_map(|l| parse_line(l).ok());
+    let fast = lines.iter().filter_map(|l| parse_line_fast(l).ok());
+    let mismatches = slow
+        .zip(fast)
+        .filter(|(a, b)| a.id != b.id || a.to_id != b.to_id || a.amount != b.amount)
+        .count();
+    println!("Mismatches between parse_line and parse_line_fast: {mismatches}");
+}
@@ -0,0 +1,99 @@
+use p01::data_ingestion::{ClientData, RecordIter};
+use p01::export::{SqliteOptions, from_sqlite, to_sqlite_with};
+
+mod alloc_tracker;
+mod fixture;
+mod harness;
+mod profiler;
+use fixture::{FixtureConfig, init_fixture};
+use harness::Bench;
+
+/// Rows per export, plenty to tell the batching strategies apart
+const ROWS: usize = 1_000_000;
+
+/// A commit per row costs a journal write per row, a million of them takes minutes
+const AUTOCOMMIT_ROWS: usize = 10_000;
+
+// usage: bench_sqlite (needs the sqlite feature), the database goes next to
+// the fixture in BENCH_DIR
+fn main() {
+    println!("=== Benchmark: SQLite Export ===\n");
+
+    let config = FixtureConfig::from_env();
+    let db = config.dir.join("bench_sqlite.db");
+    let db = db.to_str().expect("BENCH_DIR isn't UTF-8");
+    let file_path = init_fixture(config);
+    let records: Vec<ClientData> = RecordIter::open(file_path)
+        .expect("Failed to open data")
+        .take(ROWS)
+        .filter_map(Result::ok)
+        .collect();
+    let n = records.len();
+    let few = &records[..n.min(AUTOCOMMIT_ROWS)];
+
+    // every sample writes a whole database
+    let env = Bench::from_env();
+    let bench = env.samples(env.samples.min(5));
+    let export = |records: &[ClientData], options: SqliteOptions| {
+        to_sqlite_with(records, db, &options).expect("Failed to export")
+    };
+
+    bench.run("autocommit, 1 row per INSERT", few.len(), || {
+        export(few, SqliteOptions::new().batch_rows(1))
+    });
+    for batch_rows in [1_000, 100_000] {
+        bench.run(
+            &format!("{batch_rows} rows per transaction, 1 row per INSERT"),
+            n,
+            || export(&records, SqliteOptions::new().batch_rows(batch_rows)),
+        );
+    }
+    for rows_per_insert in [16, 256] {
+        bench.run(
+            &format!("100000 rows per transaction, {rows_per_insert} rows per INSERT"),
+            n,
+            || {
+                export(
+                    &records,
+                    SqliteOptions::new().rows_per_insert(rows_per_insert),
+                )
+            },
+        );
+    }
+    // what the four indexes cost on top
+    bench.run(
+        "100000 rows per transaction, 256 rows per INSERT, no indexes",
+        n,
+        || {
+            export(
+                &records,
+                SqliteOptions::new().rows_per_insert(256).indexes(false),
+            )
+        },
+    );
+
+    println!("--- Verification ---");
+    export(&records, SqliteOptions::new().rows_per_insert(256));
+    let back = from_sqlite(db).expect("Failed to read the database back");
+    let mismatches = records
+        .iter()
+        .zip(&back)
+        .filter(|(a, b)| {
+            a.id != b.id
+                || a.from_id != b.from_id
+                || a.to_id != b.to_id
+                || a.amount != b.amount
+                || a.timestamp != b.timestamp
+                || a.kind != b.kind
+                || a.currency != b.currency
+        })
+        .count();
+    println!(
+        "Read back {} of {n} records, {mismatches} differing",
+        back.len()
+    );
+
+    if let Err(e) = std::fs::remove_file(db) {
+        eprintln!("Warning: Failed to remove {db}: {e}");
+    }
+}
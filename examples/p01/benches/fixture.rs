@@ -1,17 +1,203 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 
+use p01::GeneratorConfig;
+use p01::format::Format;
+use p01::generator::parse_size;
+
 static DATA_FILE: OnceLock<String> = OnceLock::new();
 
-pub fn init_fixture() -> &'static str {
-    DATA_FILE.get_or_init(|| {
-        let file_path = "bench_data.csv";
+/// What the benches run on. Every distinct one gets its own file name, so
+/// fixtures of different sizes, formats and seeds sit side by side in `dir`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixtureConfig {
+    /// roughly, like [`GeneratorConfig::target_bytes`]
+    pub bytes: usize,
+    pub format: Format,
+    /// `None` is a random fixture, generated once and then reused
+    pub seed: Option<u64>,
+    /// where fixtures are generated and kept
+    pub dir: PathBuf,
+}
+
+impl Default for FixtureConfig {
+    fn default() -> Self {
+        Self {
+            bytes: 1024 * 1024 * 1024,
+            format: Format::Delimited,
+            seed: None,
+            dir: PathBuf::from("."),
+        }
+    }
+}
+
+impl FixtureConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults, overridden by `BENCH_SIZE` (`100mb`, `4gb`...), `BENCH_FORMAT`
+    /// (`csv`, `jsonl`, `parquet`), `BENCH_SEED` and `BENCH_DIR` when they're set
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+
+        let mut config = Self::new();
+        if let Some(size) = var("BENCH_SIZE") {
+            config = config.bytes(parse_size(&size).expect("Invalid BENCH_SIZE"));
+        }
+        if let Some(format) = var("BENCH_FORMAT") {
+            config = config.format(parse_format(&format).expect("Invalid BENCH_FORMAT"));
+        }
+        if let Some(seed) = var("BENCH_SEED") {
+            config = config.seed(seed.parse().expect("Invalid BENCH_SEED"));
+        }
+        if let Some(dir) = var("BENCH_DIR") {
+            config = config.dir(dir);
+        }
+        config
+    }
+
+    pub fn bytes(mut self, bytes: usize) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = dir.into();
+        self
+    }
 
-        if !Path::new(file_path).exists() {
-            println!("Generating benchmark data...");
-            p01::generate_mock_data(file_path, 1).expect("Failed to generate benchmark data");
+    /// e.g. `bench_data_1gb.csv`, `bench_data_100mb_seed7.parquet`
+    pub fn path(&self) -> String {
+        let seed = self
+            .seed
+            .map(|seed| format!("_seed{seed}"))
+            .unwrap_or_default();
+        let extension = match self.format {
+            Format::Delimited => "csv",
+            Format::JsonLines => "jsonl",
+            Format::Parquet => "parquet",
+        };
+        let name = format!("bench_data_{}{seed}.{extension}", size_label(self.bytes));
+        self.dir.join(name).to_string_lossy().into_owned()
+    }
+
+    pub fn generator_config(&self) -> GeneratorConfig {
+        let config = GeneratorConfig::new()
+            .target_bytes(self.bytes)
+            .format(self.format);
+        match self.seed {
+            Some(seed) => config.seed(seed),
+            None => config,
         }
+    }
+
+    /// The fixture's path, generated first unless an intact copy is already there
+    pub fn ensure(&self) -> String {
+        std::fs::create_dir_all(&self.dir).expect("Failed to create the fixture directory");
+        let file_path = self.path();
+        ensure_fixture(&file_path, &self.generator_config());
+        file_path
+    }
+}
+
+/// `csv` (or `delimited`), `jsonl` (or `ndjson`), `parquet`
+pub fn parse_format(format: &str) -> Result<Format, String> {
+    match format.to_ascii_lowercase().as_str() {
+        "csv" | "delimited" => Ok(Format::Delimited),
+        "jsonl" | "ndjson" => Ok(Format::JsonLines),
+        "parquet" => Ok(Format::Parquet),
+        other => Err(format!("unknown format `{other}`")),
+    }
+}
 
-        file_path.to_string()
+/// `100mb`, `1gb`... the largest binary unit that divides it, for file names and labels
+pub fn size_label(bytes: usize) -> String {
+    const UNITS: [(usize, &str); 3] = [(1 << 30, "gb"), (1 << 20, "mb"), (1 << 10, "kb")];
+    UNITS
+        .iter()
+        .find(|(unit, _)| bytes >= *unit && bytes.is_multiple_of(*unit))
+        .map_or_else(
+            || format!("{bytes}b"),
+            |(unit, label)| format!("{}{label}", bytes / unit),
+        )
+}
+
+/// The fixture per `config` (usually [`FixtureConfig::from_env`]), the first
+/// one asked for is the one for the rest of the run
+pub fn init_fixture(config: FixtureConfig) -> &'static str {
+    DATA_FILE.get_or_init(|| {
+        // BENCH_DATA=s3://bucket/key benches a shared fixture instead of a fresh one
+        if let Ok(uri) = std::env::var("BENCH_DATA") {
+            return fetch_fixture(&uri);
+        }
+        config.ensure()
     })
 }
+
+/// Generates `file_path` per `config` unless an intact copy is already there
+pub fn ensure_fixture(file_path: &str, config: &GeneratorConfig) {
+    // a fixture left truncated by an interrupted run would skew every number
+    if Path::new(file_path).exists()
+        && let Err(e) = p01::data_ingestion::verify(file_path)
+    {
+        println!("{e}, regenerating benchmark data...");
+        std::fs::remove_file(file_path).expect("Failed to remove stale benchmark data");
+    }
+
+    if !Path::new(file_path).exists() {
+        println!("Generating benchmark data ({file_path})...");
+        let report = p01::generate_mock_data_with_progress(file_path, config, |progress| {
+            println!("  {:.0}%", progress.fraction() * 100.0);
+        })
+        .expect("Failed to generate benchmark data");
+        println!(
+            "Generated {} records in {:?}\n",
+            report.records, report.elapsed
+        );
+    }
+}
+
+/// Local copy of an object store fixture, downloaded once. Most approaches need
+/// a local file to seek or map, so the benches don't read the bucket directly
+#[cfg(feature = "object_store")]
+fn fetch_fixture(uri: &str) -> String {
+    if !p01::remote::is_object_uri(uri) {
+        return uri.to_string();
+    }
+
+    let file_path = uri
+        .rsplit('/')
+        .next()
+        .unwrap_or("bench_data.csv")
+        .to_string();
+    if !Path::new(&file_path).exists() {
+        println!("Downloading benchmark data ({uri})...");
+        let remote = p01::remote::RemoteFile::open(uri).expect("Failed to open benchmark data");
+        let partial = format!("{file_path}.partial");
+        let mut file = std::fs::File::create(&partial).expect("Failed to create benchmark data");
+        std::io::copy(&mut remote.reader(), &mut file).expect("Failed to download benchmark data");
+        // renamed only once complete, an interrupted download is started over
+        std::fs::rename(&partial, &file_path).expect("Failed to save benchmark data");
+    }
+    file_path
+}
+
+#[cfg(not(feature = "object_store"))]
+fn fetch_fixture(uri: &str) -> String {
+    assert!(
+        !p01::remote::is_object_uri(uri),
+        "BENCH_DATA={uri} needs the `object_store` feature"
+    );
+    uri.to_string()
+}
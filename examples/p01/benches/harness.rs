@@ -0,0 +1,228 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use crate::alloc_tracker::{self, AllocStats};
+use crate::profiler::{self, Profile};
+
+/// How a benchmark gets run: warm up for a while, then take a fixed number of timed samples
+#[derive(Debug, Clone, Copy)]
+pub struct Bench {
+    pub warmup: Duration,
+    pub samples: usize,
+    /// one more untimed stretch under the CPU profiler, see [`Measurement::profile`]
+    pub profile: bool,
+    /// take the file out of the page cache before every timed call, so it comes
+    /// off the disk each time
+    pub cold: bool,
+    /// with `cold`, the file that gets evicted (no root needed), the whole page
+    /// cache gets dropped without one (root only)
+    pub fixture: Option<&'static str>,
+}
+
+impl Default for Bench {
+    fn default() -> Self {
+        Self {
+            warmup: Duration::from_secs(1),
+            samples: 100,
+            profile: false,
+            cold: false,
+            fixture: None,
+        }
+    }
+}
+
+impl Bench {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Defaults, overridden by `BENCH_SAMPLES` and `BENCH_WARMUP_MS` when they're
+    /// set. `BENCH_COLD=1` for cold page cache runs, Linux only
+    pub fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u64>().ok());
+
+        let mut bench = Self::new();
+        if let Some(samples) = var("BENCH_SAMPLES") {
+            bench = bench.samples(samples as usize);
+        }
+        if let Some(ms) = var("BENCH_WARMUP_MS") {
+            bench = bench.warmup(Duration::from_millis(ms));
+        }
+        bench.cold = var("BENCH_COLD") == Some(1);
+        bench
+    }
+
+    pub fn samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn warmup(mut self, warmup: Duration) -> Self {
+        self.warmup = warmup;
+        self
+    }
+
+    /// Times `f`, `records` is what one call processes (for the throughput)
+    pub fn run<T>(&self, name: &str, records: usize, mut f: impl FnMut() -> T) -> Measurement {
+        // caches, branch predictors and the allocator settle during the warmup,
+        // at least one call even if it's slower than the whole warmup
+        let start = Instant::now();
+        loop {
+            black_box(f());
+            if start.elapsed() >= self.warmup {
+                break;
+            }
+        }
+
+        let mut cold = self.cold;
+        let mut samples: Vec<Duration> = (0..self.samples)
+            .map(|_| {
+                if cold && let Err(e) = self.go_cold() {
+                    eprintln!("Warning: BENCH_COLD ignored for {name}: {e}");
+                    cold = false;
+                }
+                let start = Instant::now();
+                black_box(f());
+                start.elapsed()
+            })
+            .collect();
+        samples.sort_unstable();
+
+        // one more untimed call for the memory numbers, so the bookkeeping stays out of the timings
+        alloc_tracker::reset_peak_rss();
+        let alloc = alloc_tracker::measure(&mut f);
+        let peak_rss = alloc_tracker::peak_rss();
+        let profile = match self.profile {
+            true => profiler::capture(&mut f),
+            false => None,
+        };
+
+        let total = samples.len();
+        let samples = reject_outliers(samples);
+        let measurement = Measurement {
+            name: name.to_string(),
+            records,
+            outliers: total - samples.len(),
+            samples,
+            alloc,
+            peak_rss,
+            profile,
+        };
+        measurement.print();
+        measurement
+    }
+}
+
+impl Bench {
+    /// Evicts the fixture, every clean page without one
+    fn go_cold(&self) -> std::io::Result<()> {
+        match self.fixture {
+            Some(path) => p01::page_cache::evict(path),
+            None => drop_page_cache(),
+        }
+    }
+}
+
+/// Evicts clean pages (the fixture's included) from the kernel's cache, see proc(5)
+fn drop_page_cache() -> std::io::Result<()> {
+    std::fs::write("/proc/sys/vm/drop_caches", "1")
+}
+
+/// Drops samples outside the Tukey fences (1.5 IQR past the quartiles), input is sorted
+fn reject_outliers(samples: Vec<Duration>) -> Vec<Duration> {
+    // too few samples for quartiles to mean anything
+    if samples.len() < 8 {
+        return samples;
+    }
+
+    let q1 = percentile(&samples, 0.25).as_secs_f64();
+    let q3 = percentile(&samples, 0.75).as_secs_f64();
+    let iqr = q3 - q1;
+    let (low, high) = (q1 - 1.5 * iqr, q3 + 1.5 * iqr);
+
+    samples
+        .into_iter()
+        .filter(|s| (low..=high).contains(&s.as_secs_f64()))
+        .collect()
+}
+
+/// Nearest rank on sorted samples
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    let rank = (p * (sorted.len() - 1) as f64).round() as usize;
+    sorted[rank]
+}
+
+/// Timings of one benchmark, outliers already taken out
+pub struct Measurement {
+    pub name: String,
+    pub records: usize,
+    /// sorted
+    pub samples: Vec<Duration>,
+    pub outliers: usize,
+    pub alloc: Option<AllocStats>,
+    /// of the whole process, data loaded before the benchmark included
+    pub peak_rss: Option<u64>,
+    /// `None` unless profiling, which needs `--features pprof`
+    pub profile: Option<Profile>,
+}
+
+impl Measurement {
+    pub fn median(&self) -> Duration {
+        percentile(&self.samples, 0.5)
+    }
+
+    pub fn p95(&self) -> Duration {
+        percentile(&self.samples, 0.95)
+    }
+
+    pub fn mean(&self) -> Duration {
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    pub fn stddev(&self) -> Duration {
+        let mean = self.mean().as_secs_f64();
+        let variance = self
+            .samples
+            .iter()
+            .map(|s| (s.as_secs_f64() - mean).powi(2))
+            .sum::<f64>()
+            / self.samples.len() as f64;
+        Duration::from_secs_f64(variance.sqrt())
+    }
+
+    /// Million records per second, at the median
+    pub fn throughput(&self) -> f64 {
+        (self.records as f64 / self.median().as_secs_f64()) / 1_000_000.0
+    }
+
+    pub fn print(&self) {
+        println!("--- {} ---", self.name);
+        println!(
+            "Median: {:?} (mean {:?}, p95 {:?}, stddev {:?})",
+            self.median(),
+            self.mean(),
+            self.p95(),
+            self.stddev()
+        );
+        println!(
+            "Samples: {} ({} outliers dropped)",
+            self.samples.len(),
+            self.outliers
+        );
+        if let Some(alloc) = self.alloc {
+            println!(
+                "Heap: peak +{:.2} MB, {:.2} MB in {} allocations",
+                alloc.peak_bytes as f64 / 1e6,
+                alloc.allocated_bytes as f64 / 1e6,
+                alloc.allocations
+            );
+        }
+        if let Some(rss) = self.peak_rss {
+            println!("Peak RSS: {:.2} MB", rss as f64 / 1e6);
+        }
+        if let Some(profile) = &self.profile {
+            println!("Profile: {} samples", profiler::samples(profile));
+        }
+        println!("Throughput: {:.2} million records/sec\n", self.throughput());
+    }
+}
@@ -0,0 +1,219 @@
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+use crate::results::BenchRun;
+
+/// `results_charts/` next to `results.json`, where `plot` writes by default
+fn chart_dir(json: &str) -> PathBuf {
+    let json = Path::new(json);
+    let stem = json.file_stem().unwrap_or_default().to_string_lossy();
+    json.with_file_name(format!("{stem}_charts"))
+}
+
+/// `plot <results.json> [<dir>]`: every chart the run has numbers for, as SVG.
+/// Throughput and memory bars per approach, a line per approach for a
+/// `--scaling` run
+pub fn plot(json: &str, dir: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let run = BenchRun::read(json)?;
+    let dir = dir.map_or_else(|| chart_dir(json), PathBuf::from);
+    std::fs::create_dir_all(&dir)?;
+
+    for chart in charts::write_all(&run, &dir)? {
+        println!("Chart: {}", chart.display());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "plot")]
+mod charts {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+
+    use p01::generator::parse_size;
+    use plotters::prelude::*;
+
+    use crate::results::BenchRun;
+
+    const WIDTH: u32 = 1000;
+    /// the approach names go here, some are long
+    const LABELS: u32 = 380;
+    const ROW: u32 = 28;
+
+    pub fn write_all(run: &BenchRun, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        let mut written = Vec::new();
+
+        let throughput: Vec<(&str, f64)> = run
+            .results
+            .iter()
+            .map(|r| (r.approach.as_str(), r.records_per_sec / 1e6))
+            .collect();
+        let path = dir.join("throughput.svg");
+        bars(&path, "Throughput", "million records/sec", &throughput)?;
+        written.push(path);
+
+        // the heap of one call is the approach's own, peak RSS is the whole process
+        let heap: Vec<(&str, f64)> = run
+            .results
+            .iter()
+            .filter_map(|r| Some((r.approach.as_str(), r.alloc?.peak_bytes as f64 / 1e6)))
+            .collect();
+        let rss: Vec<(&str, f64)> = run
+            .results
+            .iter()
+            .filter_map(|r| Some((r.approach.as_str(), r.peak_rss_bytes? as f64 / 1e6)))
+            .collect();
+        let memory = match (heap.is_empty(), rss.is_empty()) {
+            (false, _) => Some(("Peak heap per call", heap)),
+            (true, false) => Some(("Peak RSS (whole process)", rss)),
+            (true, true) => None,
+        };
+        if let Some((title, memory)) = memory {
+            let path = dir.join("memory.svg");
+            bars(&path, title, "MB", &memory)?;
+            written.push(path);
+        }
+
+        let scaling = scaling_series(run);
+        if scaling.iter().any(|(_, points)| points.len() > 1) {
+            let path = dir.join("scaling.svg");
+            lines(&path, &scaling)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// `Greedy Approach @ 1gb` results as (approach, [(MB, million records/s)])
+    fn scaling_series(run: &BenchRun) -> Vec<(&str, Vec<(f64, f64)>)> {
+        let mut series: Vec<(&str, Vec<(f64, f64)>)> = Vec::new();
+        for result in &run.results {
+            let Some((approach, size)) = result.approach.rsplit_once(" @ ") else {
+                continue;
+            };
+            let Ok(bytes) = parse_size(size) else {
+                continue;
+            };
+            let point = (bytes as f64 / 1e6, result.records_per_sec / 1e6);
+            match series.iter_mut().find(|(name, _)| *name == approach) {
+                Some((_, points)) => points.push(point),
+                None => series.push((approach, vec![point])),
+            }
+        }
+        series
+    }
+
+    /// Horizontal bars, the first one on top
+    fn bars(
+        path: &Path,
+        title: &str,
+        unit: &str,
+        bars: &[(&str, f64)],
+    ) -> Result<(), Box<dyn Error>> {
+        let height = 100 + ROW * bars.len() as u32;
+        let root = SVGBackend::new(path, (WIDTH, height)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        let max = bars.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+        let mut chart = ChartBuilder::on(&root)
+            .caption(title, ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(LABELS)
+            .build_cartesian_2d(
+                0.0..(max * 1.1).max(f64::EPSILON),
+                (0..bars.len()).into_segmented(),
+            )?;
+
+        // segment 0 is at the bottom
+        let name_of = |y: &SegmentValue<usize>| match y {
+            SegmentValue::CenterOf(i) if *i < bars.len() => bars[bars.len() - 1 - i].0.to_string(),
+            _ => String::new(),
+        };
+        chart
+            .configure_mesh()
+            .disable_y_mesh()
+            .y_labels(bars.len())
+            .y_label_formatter(&name_of)
+            .x_desc(unit)
+            .draw()?;
+
+        let color = Palette99::pick(0).mix(0.8).filled();
+        chart.draw_series(bars.iter().rev().enumerate().map(
+            |(i, (_, value))| -> Rectangle<(f64, SegmentValue<usize>)> {
+                let mut bar = Rectangle::new(
+                    [
+                        (0.0, SegmentValue::Exact(i)),
+                        (*value, SegmentValue::Exact(i + 1)),
+                    ],
+                    color,
+                );
+                bar.set_margin(4, 4, 0, 0);
+                bar
+            },
+        ))?;
+
+        root.present()?;
+        Ok(())
+    }
+
+    /// Throughput against fixture size, log scale since the sizes are 10x apart
+    fn lines(path: &Path, series: &[(&str, Vec<(f64, f64)>)]) -> Result<(), Box<dyn Error>> {
+        let root = SVGBackend::new(path, (WIDTH, 600)).into_drawing_area();
+        root.fill(&WHITE)?;
+
+        let points = || series.iter().flat_map(|(_, points)| points);
+        let min_x = points().map(|p| p.0).fold(f64::INFINITY, f64::min);
+        let max_x = points().map(|p| p.0).fold(0.0, f64::max);
+        let max_y = points().map(|p| p.1).fold(0.0, f64::max);
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Throughput by fixture size", ("sans-serif", 20))
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(60)
+            .build_cartesian_2d(
+                (min_x * 0.8..max_x * 1.25).log_scale(),
+                0.0..(max_y * 1.1).max(f64::EPSILON),
+            )?;
+        chart
+            .configure_mesh()
+            .x_desc("fixture size (MB)")
+            .y_desc("million records/sec")
+            .draw()?;
+
+        for (i, (approach, points)) in series.iter().enumerate() {
+            let color = Palette99::pick(i).to_rgba();
+            chart
+                .draw_series(LineSeries::new(
+                    points.iter().copied(),
+                    color.stroke_width(2),
+                ))?
+                .label(*approach)
+                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+            chart.draw_series(
+                points
+                    .iter()
+                    .map(|point| Circle::new(*point, 3, color.filled())),
+            )?;
+        }
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "plot"))]
+mod charts {
+    use std::error::Error;
+    use std::path::{Path, PathBuf};
+
+    use crate::results::BenchRun;
+
+    pub fn write_all(_run: &BenchRun, _dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        Err("plot needs the `plot` feature (or --features all)".into())
+    }
+}
@@ -0,0 +1,61 @@
+/// A CPU profile of one benchmark, only ever taken with `--features pprof`
+#[cfg(feature = "pprof")]
+pub struct Profile {
+    pub report: pprof::Report,
+}
+
+/// Nothing to profile with, never made
+#[cfg(not(feature = "pprof"))]
+pub enum Profile {}
+
+/// How long one benchmark gets sampled, a fast one is called over and over until then
+#[cfg(feature = "pprof")]
+const PROFILE_FOR: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Samples per second, prime so it doesn't run in step with a loop in the code
+#[cfg(feature = "pprof")]
+const FREQUENCY: i32 = 997;
+
+/// Calls `f` under the sampling profiler for a couple of seconds (at least
+/// once), `None` when the profiler couldn't start
+#[cfg(feature = "pprof")]
+pub fn capture<T>(mut f: impl FnMut() -> T) -> Option<Profile> {
+    // the profiler's own signal handler and unwinding, not what we're after
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(FREQUENCY)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+        .map_err(|e| eprintln!("Warning: Failed to start the profiler: {e}"))
+        .ok()?;
+
+    let start = std::time::Instant::now();
+    loop {
+        std::hint::black_box(f());
+        if start.elapsed() >= PROFILE_FOR {
+            break;
+        }
+    }
+
+    let report = guard
+        .report()
+        .build()
+        .map_err(|e| eprintln!("Warning: Failed to build the profile: {e}"))
+        .ok()?;
+    Some(Profile { report })
+}
+
+/// How many stacks the profiler caught
+#[cfg(feature = "pprof")]
+pub fn samples(profile: &Profile) -> isize {
+    profile.report.data.values().sum()
+}
+
+#[cfg(not(feature = "pprof"))]
+pub fn samples(profile: &Profile) -> isize {
+    match *profile {}
+}
+
+#[cfg(not(feature = "pprof"))]
+pub fn capture<T>(_f: impl FnMut() -> T) -> Option<Profile> {
+    None
+}
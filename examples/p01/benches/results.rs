@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::alloc_tracker::AllocStats;
+use crate::harness::Measurement;
+
+/// Anything within this many percent of the old number is noise
+const NOISE_PERCENT: f64 = 5.0;
+
+/// One approach of one run, times in nanoseconds
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub approach: String,
+    pub records: usize,
+    pub records_per_sec: f64,
+    pub median_ns: u64,
+    pub mean_ns: u64,
+    pub p95_ns: u64,
+    pub stddev_ns: u64,
+    pub samples: usize,
+    /// missing in runs without `alloc-tracking`
+    #[serde(default)]
+    pub alloc: Option<AllocStats>,
+    #[serde(default)]
+    pub peak_rss_bytes: Option<u64>,
+}
+
+impl From<&Measurement> for BenchResult {
+    fn from(m: &Measurement) -> Self {
+        Self {
+            approach: m.name.clone(),
+            records: m.records,
+            records_per_sec: m.throughput() * 1_000_000.0,
+            median_ns: m.median().as_nanos() as u64,
+            mean_ns: m.mean().as_nanos() as u64,
+            p95_ns: m.p95().as_nanos() as u64,
+            stddev_ns: m.stddev().as_nanos() as u64,
+            samples: m.samples.len(),
+            alloc: m.alloc,
+            peak_rss_bytes: m.peak_rss,
+        }
+    }
+}
+
+/// What `--json` writes, one file per run
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchRun {
+    /// `git rev-parse --short HEAD`, if we're in a git checkout
+    pub commit: Option<String>,
+    pub unix_time: u64,
+    pub results: Vec<BenchResult>,
+}
+
+impl BenchRun {
+    pub fn new(measurements: &[Measurement]) -> Self {
+        let commit = std::process::Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|out| out.status.success())
+            .map(|out| String::from_utf8_lossy(&out.stdout).trim().to_string());
+
+        Self {
+            commit,
+            unix_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            results: measurements.iter().map(BenchResult::from).collect(),
+        }
+    }
+
+    pub fn write(&self, path: &str) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    pub fn read(path: &str) -> std::io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// Prints old vs new throughput per approach, returns how many approaches got slower
+pub fn compare(old: &BenchRun, new: &BenchRun) -> usize {
+    let label = |run: &BenchRun| run.commit.clone().unwrap_or_else(|| "?".to_string());
+    println!("=== {} -> {} ===\n", label(old), label(new));
+
+    let old_results: HashMap<&str, &BenchResult> = old
+        .results
+        .iter()
+        .map(|r| (r.approach.as_str(), r))
+        .collect();
+
+    let mut regressions = 0;
+    for result in &new.results {
+        let Some(before) = old_results.get(result.approach.as_str()) else {
+            println!("{:<45} new", result.approach);
+            continue;
+        };
+
+        // throughput, so positive is faster
+        let change = (result.records_per_sec / before.records_per_sec - 1.0) * 100.0;
+        let verdict = if change < -NOISE_PERCENT {
+            regressions += 1;
+            "REGRESSION"
+        } else if change > NOISE_PERCENT {
+            "improvement"
+        } else {
+            "~"
+        };
+
+        println!(
+            "{:<45} {:>10.2} -> {:>10.2} M rec/s  {:+7.2}%  {verdict}",
+            result.approach,
+            before.records_per_sec / 1e6,
+            result.records_per_sec / 1e6,
+            change
+        );
+    }
+
+    for before in &old.results {
+        if !new.results.iter().any(|r| r.approach == before.approach) {
+            println!("{:<45} gone", before.approach);
+        }
+    }
+
+    regressions
+}
@@ -0,0 +1,21 @@
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    ffi_header();
+}
+
+/// include/p01.h from src/ffi.rs alone, the rest of the crate has nothing for C
+#[cfg(feature = "ffi")]
+fn ffi_header() {
+    println!("cargo::rerun-if-changed=src/ffi.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+
+    let config =
+        cbindgen::Config::from_file("cbindgen.toml").expect("Failed to read cbindgen.toml");
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src("src/ffi.rs")
+        .generate()
+        .expect("Failed to generate include/p01.h")
+        .write_to_file("include/p01.h");
+}
@@ -0,0 +1,118 @@
+//! Writes the seed corpus, one small generated file per corruption mode.
+//! `cargo run --example seed_corpus` from `fuzz/`, the output is checked in
+use std::fs;
+use std::path::Path;
+
+use p01::format::Format;
+use p01::generator::CorruptionProfile;
+use p01::{GeneratorConfig, generate_mock_data_to};
+
+const RECORDS: usize = 24;
+/// lines of each file that also go in the `parse_line` corpus
+const LINES: usize = 4;
+
+fn main() -> std::io::Result<()> {
+    let none = CorruptionProfile::none;
+    let modes = [
+        ("clean", none()),
+        (
+            "delimiter",
+            CorruptionProfile {
+                delimiter: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "missing_field",
+            CorruptionProfile {
+                missing_field: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "extra_field",
+            CorruptionProfile {
+                extra_field: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "non_numeric_amount",
+            CorruptionProfile {
+                non_numeric_amount: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "negative_amount",
+            CorruptionProfile {
+                negative_amount: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "empty_line",
+            CorruptionProfile {
+                empty_line: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "invalid_utf8",
+            CorruptionProfile {
+                invalid_utf8: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "duplicate_id",
+            CorruptionProfile {
+                duplicate_id: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "crlf",
+            CorruptionProfile {
+                crlf: 0.3,
+                ..none()
+            },
+        ),
+        (
+            "truncated_last_line",
+            CorruptionProfile {
+                truncated_last_line: true,
+                ..none()
+            },
+        ),
+        ("all", CorruptionProfile::all(0.1)),
+    ];
+
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    for target in ["parse_line", "sniff", "stream"] {
+        fs::create_dir_all(corpus.join(target))?;
+    }
+
+    for (name, profile) in modes {
+        for format in [Format::Delimited, Format::JsonLines] {
+            let config = GeneratorConfig::new()
+                .target_records(RECORDS)
+                .corruption(profile)
+                .seed(589)
+                .format(format);
+            let mut data = Vec::new();
+            generate_mock_data_to(&mut data, &config, |_| {})?;
+
+            if format == Format::JsonLines {
+                fs::write(corpus.join("stream").join(format!("{name}.jsonl")), &data)?;
+                continue;
+            }
+            fs::write(corpus.join("stream").join(name), &data)?;
+            fs::write(corpus.join("sniff").join(name), &data)?;
+            for (i, line) in data.split(|&b| b == b'\n').take(LINES + 1).enumerate() {
+                fs::write(corpus.join("parse_line").join(format!("{name}-{i}")), line)?;
+            }
+        }
+    }
+    Ok(())
+}
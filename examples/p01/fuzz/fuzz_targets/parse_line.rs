@@ -0,0 +1,22 @@
+//! The three line parsers on one line each, they have to agree on what's a
+//! record and never panic doing it
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p01::data_ingestion::{parse_line, parse_line_fast, parse_line_ref};
+
+fuzz_target!(|line: &str| {
+    let parsed = parse_line(line);
+    let fast = parse_line_fast(line);
+    let borrowed = parse_line_ref(line);
+    assert_eq!(
+        parsed.is_ok(),
+        fast.is_ok(),
+        "parse_line and parse_line_fast disagree"
+    );
+    assert_eq!(
+        parsed.is_ok(),
+        borrowed.is_ok(),
+        "parse_line and parse_line_ref disagree"
+    );
+});
@@ -0,0 +1,24 @@
+//! The schema sniffer on the first lines of the input, then the whole input
+//! read with the layout it came up with
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p01::data_ingestion::{RecordIter, SNIFF_LINES, sniff_lines};
+
+fuzz_target!(|data: &[u8]| {
+    let text = String::from_utf8_lossy(data);
+    let lines: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SNIFF_LINES)
+        .collect();
+    let Ok(schema) = sniff_lines(&lines) else {
+        return;
+    };
+    let Ok(layout) = schema.layout() else {
+        return;
+    };
+    for record in RecordIter::with_layout(data, layout).has_header(schema.has_header) {
+        let _ = record;
+    }
+});
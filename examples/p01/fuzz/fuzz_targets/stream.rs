@@ -0,0 +1,21 @@
+//! The streaming ingestion loops over untrusted bytes: owned and borrowed
+//! records from delimited text, and JSON Lines
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use p01::data_ingestion::{RecordIter, RefReader};
+
+fuzz_target!(|data: &[u8]| {
+    let owned = RecordIter::new(data).filter(Result::is_ok).count();
+
+    let mut reader = RefReader::new(data);
+    let mut borrowed = 0;
+    while let Some(record) = reader.next_record() {
+        borrowed += record.is_ok() as usize;
+    }
+    assert_eq!(owned, borrowed, "RecordIter and RefReader disagree");
+
+    for record in RecordIter::json_lines(data) {
+        let _ = record;
+    }
+});
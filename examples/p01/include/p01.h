@@ -0,0 +1,108 @@
+/* Generated by cbindgen from src/ffi.rs with the `ffi` feature, don't edit. */
+
+#ifndef P01_H
+#define P01_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What every function returns
+typedef enum P01Status {
+  P01_STATUS_OK = 0,
+  // a pointer argument was null
+  P01_STATUS_NULL_POINTER = 1,
+  // an index past the end of the records
+  P01_STATUS_OUT_OF_RANGE = 2,
+} P01Status;
+
+// Records parsed by [`p01_parse`], opaque to C
+typedef struct P01Records P01Records;
+
+// Totals of a buffer, see [`p01_analyze`]
+typedef struct P01Stats {
+  uint64_t count;
+  double sum;
+  // NaN when `count` is 0, like `max` and `mean`
+  double min;
+  double max;
+  double mean;
+  uint64_t distinct_accounts;
+  // lines that didn't parse
+  uint64_t malformed;
+} P01Stats;
+
+// A borrowed string, `len` bytes of UTF-8 at `ptr`
+typedef struct P01Str {
+  const uint8_t *ptr;
+  size_t len;
+} P01Str;
+
+// One parsed record, its strings live as long as the [`P01Records`] it came from
+typedef struct P01Record {
+  struct P01Str id;
+  struct P01Str from_id;
+  struct P01Str to_id;
+  double amount;
+  // unix seconds, only meaningful when `has_timestamp`
+  int64_t timestamp;
+  bool has_timestamp;
+  // e.g. `transfer`
+  struct P01Str kind;
+  // three letter ISO code, empty for the base currency
+  struct P01Str currency;
+} P01Record;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Sum, count, min, max, mean and distinct accounts of the `len` bytes at
+// `data` into `out`, in one pass and without copying a record
+//
+// # Safety
+// `data` has to point to `len` readable bytes and `out` to a writable [`P01Stats`]
+enum P01Status p01_analyze(const uint8_t *data, size_t len, struct P01Stats *out);
+
+// Parses the `len` bytes at `data` into `*out`, which has to be freed with
+// [`p01_records_free`]. The buffer can be freed as soon as this returns
+//
+// # Safety
+// `data` has to point to `len` readable bytes and `out` to a writable pointer
+enum P01Status p01_parse(const uint8_t *data, size_t len, struct P01Records **out);
+
+// How many records parsed, 0 for null
+//
+// # Safety
+// `records` has to come from [`p01_parse`] and not be freed yet
+size_t p01_records_len(const struct P01Records *records);
+
+// How many lines didn't parse, 0 for null
+//
+// # Safety
+// `records` has to come from [`p01_parse`] and not be freed yet
+size_t p01_records_malformed(const struct P01Records *records);
+
+// The `index`th record into `out`
+//
+// # Safety
+// `records` has to come from [`p01_parse`] and not be freed yet, `out` has
+// to point to a writable [`P01Record`]
+enum P01Status p01_records_get(const struct P01Records *records,
+                               size_t index,
+                               struct P01Record *out);
+
+// Frees what [`p01_parse`] returned, null is a no-op
+//
+// # Safety
+// `records` has to come from [`p01_parse`] and not be freed already, none of
+// its strings can be used afterwards
+void p01_records_free(struct P01Records *records);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* P01_H */
@@ -0,0 +1,140 @@
+//! proptest strategies for records and layouts, with field content picked to
+//! break parsers: every format's delimiters and quotes, line breaks, BOMs,
+//! NULs, non-breaking spaces and anything else `any::<String>()` turns up.
+//! The [`crate::roundtrip`] tests run them through a write and a read
+use proptest::collection::vec;
+use proptest::prelude::*;
+use proptest::sample::{select, subsequence};
+
+use crate::data_ingestion::{ClientData, Column, Currency, Layout, TransactionKind};
+use crate::format::Format;
+
+/// Pieces [`nasty_text`] glues together
+const NASTY: &[&str] = &[
+    "|",
+    ",",
+    ";",
+    "\t",
+    "\"",
+    "'",
+    "\\",
+    "\n",
+    "\r",
+    "\r\n",
+    " ",
+    "\u{a0}",
+    "\u{feff}",
+    "\0",
+    "{",
+    "}",
+    "[",
+    ":",
+    "null",
+    "N/A",
+    "-",
+    "1e309",
+    "NaN",
+    "é",
+    "漢",
+    "🦀",
+    "\u{202e}",
+    "ACC",
+    "TXN0000000001",
+];
+
+/// Any text at all, or runs of characters the formats give a meaning to
+pub fn nasty_text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        any::<String>(),
+        vec(select(NASTY), 0..12).prop_map(|pieces| pieces.concat()),
+    ]
+}
+
+/// [`nasty_text`] cut down to what a `format` field can hold: delimited
+/// fields lose the delimiter and line breaks and are trimmed like the parser
+/// trims them, JSON and parquet strings take anything
+pub fn text_for(format: Format, delimiter: char) -> BoxedStrategy<String> {
+    match format {
+        Format::Delimited => nasty_text()
+            .prop_map(move |text| {
+                let text = text.replace([delimiter, '\r', '\n'], "");
+                text.trim().to_string()
+            })
+            .boxed(),
+        Format::JsonLines | Format::Parquet => nasty_text().boxed(),
+    }
+}
+
+/// Finite amounts of any size, subnormals and both zeros included. NaN
+/// isn't equal to itself and JSON has no infinity, neither is a real amount
+pub fn amount() -> impl Strategy<Value = f64> {
+    use proptest::num::f64::{NEGATIVE, NORMAL, POSITIVE, SUBNORMAL, ZERO};
+    POSITIVE | NEGATIVE | NORMAL | SUBNORMAL | ZERO
+}
+
+pub fn currency() -> impl Strategy<Value = Currency> {
+    "[A-Z]{3}".prop_map(|code| Currency::new(&code).expect("three uppercase letters"))
+}
+
+/// Records with every field filled in from its whole range
+pub fn client_data() -> impl Strategy<Value = ClientData> {
+    record(nasty_text().boxed())
+}
+
+/// Records `format` can carry under `layout`: text per [`text_for`], and the
+/// optional columns the layout doesn't have left at their default since
+/// nothing gets written for them. JSON Lines always carry every field
+pub fn client_data_for(format: Format, layout: &Layout) -> BoxedStrategy<ClientData> {
+    let layout = layout.clone();
+    record(text_for(format, layout.delimiter()))
+        .prop_map(move |mut record| {
+            if format != Format::JsonLines {
+                if !layout.contains(Column::Timestamp) {
+                    record.timestamp = None;
+                }
+                if !layout.contains(Column::Kind) {
+                    record.kind = TransactionKind::default();
+                }
+                if !layout.contains(Column::Currency) {
+                    record.currency = None;
+                }
+            }
+            record
+        })
+        .boxed()
+}
+
+fn record(text: BoxedStrategy<String>) -> impl Strategy<Value = ClientData> {
+    (
+        (text.clone(), text.clone(), text),
+        amount(),
+        any::<Option<i64>>(),
+        select(TransactionKind::ALL.to_vec()),
+        proptest::option::of(currency()),
+    )
+        .prop_map(
+            |((id, from_id, to_id), amount, timestamp, kind, currency)| ClientData {
+                id,
+                from_id,
+                to_id,
+                amount,
+                timestamp,
+                kind,
+                currency,
+            },
+        )
+}
+
+/// The four core columns and any of the optional ones, in any order
+pub fn layout() -> impl Strategy<Value = Layout> {
+    let optional = vec![Column::Timestamp, Column::Kind, Column::Currency];
+    subsequence(optional, 0..=3)
+        .prop_flat_map(|optional| {
+            let mut columns = Layout::default().columns().to_vec();
+            columns.extend(optional);
+            Just(columns).prop_shuffle()
+        })
+        .prop_map(|columns| {
+            Layout::from_names(columns.iter().map(|c| c.name())).expect("core columns are there")
+        })
+}
@@ -0,0 +1,185 @@
+//! The streaming pipeline over HTTP: uploads are parsed as they arrive and
+//! folded into one set of running totals.
+//!
+//! ```text
+//! cargo run --release --features server --bin p01-server -- --addr 127.0.0.1:3000
+//! curl --data-binary @data.csv localhost:3000/ingest
+//! curl localhost:3000/stats
+//! curl 'localhost:3000/accounts/top?n=10&by=received'
+//! curl localhost:3000/metrics
+//! ```
+
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::Json;
+use axum::Router;
+use axum::body::Body;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use clap::Parser;
+use futures_util::{StreamExt, TryStreamExt};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder};
+use p01::analysis::{AccountStats, AnalysisResult, StreamStats, top_n_receivers, top_n_senders};
+use p01::data_ingestion::{IngestError, stream_records};
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser)]
+#[command(name = "p01-server", about = "Mock transaction analysis over HTTP")]
+struct Cli {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:3000")]
+    addr: String,
+}
+
+/// Everything ingested since the server started
+#[derive(Default)]
+struct Totals {
+    stats: StreamStats,
+    uploads: usize,
+}
+
+type Shared = Arc<Mutex<Totals>>;
+
+type Rejection = (StatusCode, String);
+
+#[derive(Serialize)]
+struct Stats {
+    #[serde(flatten)]
+    result: AnalysisResult,
+    malformed: usize,
+    uploads: usize,
+}
+
+impl Stats {
+    fn new(stats: &StreamStats, uploads: usize) -> Self {
+        Self {
+            result: stats.result(),
+            malformed: stats.dirty.total(),
+            uploads,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Ranking {
+    #[default]
+    Sent,
+    Received,
+}
+
+#[derive(Deserialize)]
+struct TopQuery {
+    #[serde(default = "default_top_n")]
+    n: usize,
+    #[serde(default)]
+    by: Ranking,
+}
+
+fn default_top_n() -> usize {
+    10
+}
+
+#[derive(Serialize)]
+struct TopAccount {
+    account: String,
+    #[serde(flatten)]
+    stats: AccountStats,
+}
+
+/// `POST /ingest`: delimited text with a header, as `generate` writes it. The
+/// body is parsed while it streams in, broken lines are skipped and counted.
+/// Responds with the stats of this upload, they only join the totals once the
+/// whole body made it
+async fn ingest(State(totals): State<Shared>, body: Body) -> Result<Json<Stats>, Rejection> {
+    let start = Instant::now();
+    let chunks = body
+        .into_data_stream()
+        .inspect_ok(|chunk| p01::metrics::record_bytes(chunk.len() as u64))
+        .map_err(std::io::Error::other);
+    let reader = tokio_util::io::StreamReader::new(chunks);
+    let mut records = pin!(stream_records(reader));
+
+    let mut upload = StreamStats::default();
+    while let Some(record) = records.next().await {
+        match record {
+            Ok(record) => upload.add(&record),
+            Err(IngestError::Io(e)) => {
+                return Err((StatusCode::BAD_REQUEST, format!("Upload failed: {e}")));
+            }
+            Err(e) => {
+                eprintln!("Warning: Failed to parse {e}");
+                upload.dirty.record(&e);
+            }
+        }
+    }
+
+    p01::metrics::record_batch("http", upload.count, &upload.dirty, start.elapsed());
+    let response = Stats::new(&upload, 1);
+    let mut totals = totals.lock().expect("totals lock poisoned");
+    totals.stats.merge(upload);
+    totals.uploads += 1;
+    Ok(Json(response))
+}
+
+/// `GET /stats`: sum, count, min, max, mean and distinct accounts over every upload
+async fn stats(State(totals): State<Shared>) -> Json<Stats> {
+    let totals = totals.lock().expect("totals lock poisoned");
+    Json(Stats::new(&totals.stats, totals.uploads))
+}
+
+/// `GET /accounts/top?n=10&by=sent|received`: the accounts that moved the most
+/// money, biggest first
+async fn top_accounts(
+    State(totals): State<Shared>,
+    Query(query): Query<TopQuery>,
+) -> Json<Vec<TopAccount>> {
+    let totals = totals.lock().expect("totals lock poisoned");
+    let accounts = &totals.stats.accounts;
+    let top = match query.by {
+        Ranking::Sent => top_n_senders(accounts, query.n),
+        Ranking::Received => top_n_receivers(accounts, query.n),
+    };
+
+    Json(
+        top.into_iter()
+            .map(|(account, stats)| TopAccount {
+                account: account.to_string(),
+                stats: *stats,
+            })
+            .collect(),
+    )
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    // buckets make it a histogram, the exporter's default is a summary
+    let metrics = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(p01::metrics::BATCH_SECONDS.to_string()),
+            p01::metrics::BATCH_BUCKETS,
+        )?
+        .install_recorder()?;
+    p01::metrics::describe();
+
+    let app = Router::new()
+        .route("/ingest", post(ingest))
+        .route("/stats", get(stats))
+        .route("/accounts/top", get(top_accounts))
+        // `GET /metrics`: the ingestion counters in the Prometheus text format
+        .route(
+            "/metrics",
+            get(move || std::future::ready(metrics.render())),
+        )
+        .with_state(Shared::default());
+
+    let listener = tokio::net::TcpListener::bind(&cli.addr).await?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
@@ -0,0 +1,321 @@
+//! `p01.toml`: a whole run in one file, from generating the data to writing the
+//! report, so a run can be repeated exactly instead of pieced together from
+//! flags. `p01 run` reads it, [`Pipeline::from_config`] does the same from code
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::anomaly::AnomalyConfig;
+use crate::analysis::{Analyzer, Functional, Greedy, Threads};
+use crate::format::Format;
+use crate::generator::{
+    AccountDistribution, AmountDistribution, CorruptionProfile, CurrencyMix, Disorder,
+    GeneratorConfig, HotAccounts, KindMix, Ledger, Timestamps, parse_size,
+};
+use crate::pipeline::{Clean, OnError, Pipeline, file, generate_to, report, totals};
+use crate::validation::Validator;
+
+/// Where `p01 run` looks without `--config`
+pub const DEFAULT_CONFIG: &str = "p01.toml";
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid config: {0}")]
+    Parse(#[from] toml::de::Error),
+
+    #[error("Invalid config: {0}")]
+    Invalid(String),
+}
+
+/// The whole file. Every section can be left out, `[generate]` only runs when it's there
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub generate: Option<GenerateConfig>,
+    pub ingest: IngestConfig,
+    pub analyze: AnalyzeConfig,
+    pub output: OutputConfig,
+}
+
+/// Same knobs as `p01 generate`, by the same names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GenerateConfig {
+    pub out: String,
+    /// e.g. `"500mb"`, 1gb when neither this nor `records` is set
+    pub size: Option<String>,
+    pub records: Option<usize>,
+    /// `csv`, `jsonl` or `parquet`, picked from the extension of `out` by default
+    pub format: Option<String>,
+    pub seed: Option<u64>,
+    pub accounts: Option<u32>,
+    pub corruption_rate: Option<f64>,
+    pub corrupt_all: Option<f64>,
+    pub crlf: bool,
+    pub timestamps: bool,
+    pub kinds: bool,
+    pub currencies: bool,
+    pub zipf: Option<f64>,
+    pub hot_accounts: bool,
+    pub lognormal: bool,
+    pub ledger: bool,
+    pub duplicates: Option<f64>,
+    pub shuffle: Option<f64>,
+    /// e.g. `"64kb"`
+    pub buffer_size: Option<String>,
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        Self {
+            out: "data.csv".to_string(),
+            size: None,
+            records: None,
+            format: None,
+            seed: None,
+            accounts: None,
+            corruption_rate: None,
+            corrupt_all: None,
+            crlf: false,
+            timestamps: false,
+            kinds: false,
+            currencies: false,
+            zipf: None,
+            hot_accounts: false,
+            lognormal: false,
+            ledger: false,
+            duplicates: None,
+            shuffle: None,
+            buffer_size: None,
+        }
+    }
+}
+
+impl GenerateConfig {
+    /// What this describes, the way `p01 generate` builds it from its flags
+    pub fn generator_config(&self) -> Result<GeneratorConfig, ConfigError> {
+        let mut config = match (&self.size, self.records) {
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::Invalid(
+                    "generate: `size` and `records` can't both be set".into(),
+                ));
+            }
+            (_, Some(records)) => GeneratorConfig::new().target_records(records),
+            (size, None) => GeneratorConfig::new().target_bytes(match size {
+                Some(size) => parse_size(size).map_err(ConfigError::Invalid)?,
+                None => 1024 * 1024 * 1024,
+            }),
+        };
+        config.format = self.format.as_deref().map(parse_format).transpose()?;
+        config.seed = self.seed;
+        config.crlf = self.crlf;
+        if let Some(accounts) = self.accounts {
+            config = config.accounts(accounts);
+        }
+        if let Some(size) = &self.buffer_size {
+            config = config.buffer_capacity(parse_size(size).map_err(ConfigError::Invalid)?);
+        }
+        match (self.corruption_rate, self.corrupt_all) {
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::Invalid(
+                    "generate: `corruption_rate` and `corrupt_all` can't both be set".into(),
+                ));
+            }
+            (Some(rate), None) => config = config.corruption_probability(rate),
+            (None, Some(rate)) => config = config.corruption(CorruptionProfile::all(rate)),
+            (None, None) => {}
+        }
+        if self.timestamps {
+            config = config.timestamps(Timestamps::default());
+        }
+        if self.kinds {
+            config = config.kinds(KindMix::default());
+        }
+        if self.currencies {
+            config = config.currencies(CurrencyMix::default());
+        }
+        if self.ledger {
+            config = config.ledger(Ledger::default());
+        }
+        if self.duplicates.is_some() || self.shuffle.is_some() {
+            config = config.disorder(Disorder {
+                duplicate: self.duplicates.unwrap_or(0.0),
+                shuffle: self.shuffle.unwrap_or(0.0),
+                ..Disorder::default()
+            });
+        }
+        if let Some(exponent) = self.zipf {
+            config = config.account_distribution(AccountDistribution::Zipf { exponent });
+        }
+        if self.hot_accounts {
+            config = config.hot_accounts(HotAccounts::default());
+        }
+        if self.lognormal {
+            config = config.amounts(AmountDistribution::LogNormal {
+                median: 250.0,
+                sigma: 1.2,
+            });
+        }
+        Ok(config)
+    }
+}
+
+fn parse_format(format: &str) -> Result<Format, ConfigError> {
+    match format {
+        "csv" => Ok(Format::Delimited),
+        "jsonl" => Ok(Format::JsonLines),
+        "parquet" => Ok(Format::Parquet),
+        _ => Err(ConfigError::Invalid(format!(
+            "unknown format {format:?}, expected csv, jsonl or parquet"
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IngestConfig {
+    /// what gets read without a `[generate]`, with one it's `generate.out`
+    pub path: Option<String>,
+    /// `warn`, `skip` or `fail`
+    pub on_error: OnError,
+    /// give up once this fraction of the lines failed, e.g. `0.001`
+    pub max_error_rate: Option<f64>,
+    /// drop records with an id seen before
+    pub dedup: bool,
+    /// drop records that break [`Validator::standard`]'s rules
+    pub validate: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnalyzeConfig {
+    /// run in this order: `greedy`, `functional`, `rayon`, `threads`, `polars`
+    pub approaches: Vec<String>,
+    /// per-account totals into the report
+    pub accounts: bool,
+    /// anomalies with the default thresholds into the report
+    pub anomalies: bool,
+}
+
+impl Default for AnalyzeConfig {
+    fn default() -> Self {
+        Self {
+            approaches: vec!["functional".to_string()],
+            accounts: false,
+            anomalies: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// a `.json` file, or a directory for CSV tables, see [`crate::report::ReportFormat::from_path`]
+    pub report: Option<String>,
+}
+
+/// The approach `name` stands for in `analyze.approaches`
+pub(crate) fn analyzer(name: &str) -> Result<Box<dyn Analyzer>, ConfigError> {
+    // only there for the approaches left out of this build
+    #[cfg(any(not(feature = "rayon"), not(feature = "polars")))]
+    let not_compiled_in = |feature: &str| {
+        ConfigError::Invalid(format!("approach {name:?} needs the `{feature}` feature"))
+    };
+    match name {
+        "greedy" => Ok(Box::new(Greedy)),
+        "functional" => Ok(Box::new(Functional)),
+        #[cfg(feature = "rayon")]
+        "rayon" => Ok(Box::new(crate::analysis::Rayon)),
+        #[cfg(not(feature = "rayon"))]
+        "rayon" => Err(not_compiled_in("rayon")),
+        "threads" => Ok(Box::new(Threads)),
+        #[cfg(feature = "polars")]
+        "polars" => Ok(Box::new(crate::analysis::Polars)),
+        #[cfg(not(feature = "polars"))]
+        "polars" => Err(not_compiled_in("polars")),
+        _ => Err(ConfigError::Invalid(format!(
+            "unknown approach {name:?}, expected greedy, functional, rayon, threads or polars"
+        ))),
+    }
+}
+
+impl PipelineConfig {
+    /// The [`Pipeline`] this describes. Fails on anything that would only
+    /// fail halfway through the run: sizes and formats that don't parse,
+    /// approaches that aren't compiled in, nothing to read
+    pub fn pipeline(&self) -> Result<Pipeline, ConfigError> {
+        let source = match (&self.generate, &self.ingest.path) {
+            (Some(_), Some(_)) => {
+                return Err(ConfigError::Invalid(
+                    "`ingest.path` can't be set next to a `[generate]`, what's generated is read"
+                        .into(),
+                ));
+            }
+            (Some(generate), None) if generate.out == "-" => {
+                return Err(ConfigError::Invalid(
+                    "generate: `out` has to be a file, the pipeline reads it back".into(),
+                ));
+            }
+            (Some(generate), None) => generate_to(&generate.out, generate.generator_config()?),
+            (None, Some(path)) => file(path),
+            (None, None) => {
+                return Err(ConfigError::Invalid(
+                    "nothing to read, set `ingest.path` or add a `[generate]` section".into(),
+                ));
+            }
+        };
+
+        let mut clean = Clean::new();
+        if self.ingest.dedup {
+            clean = clean.dedup();
+        }
+        if self.ingest.validate {
+            clean = clean.validate(Validator::standard());
+        }
+        let mut analyze = totals();
+        for name in &self.analyze.approaches {
+            analyze = analyze.approach_boxed(analyzer(name)?);
+        }
+        if self.analyze.accounts {
+            analyze = analyze.group_by_account();
+        }
+        if self.analyze.anomalies {
+            analyze = analyze.anomalies(AnomalyConfig::default());
+        }
+
+        let mut pipeline = Pipeline::new()
+            .source(source)
+            .on_error(self.ingest.on_error)
+            .clean(clean)
+            .analyze(analyze);
+        if let Some(rate) = self.ingest.max_error_rate {
+            pipeline = pipeline.max_error_rate(rate);
+        }
+        if let Some(path) = &self.output.report {
+            pipeline = pipeline.sink(report(path.as_str()));
+        }
+        Ok(pipeline)
+    }
+}
+
+impl Pipeline {
+    /// Reads a TOML file like [`DEFAULT_CONFIG`], see [`PipelineConfig::pipeline`]
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let toml = std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::from_toml(&toml)
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<PipelineConfig>(toml)?.pipeline()
+    }
+}
@@ -0,0 +1,278 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+
+use crate::analysis::{AnalysisResult, StreamStats};
+use crate::compression::Compression;
+use crate::data_ingestion::{IngestError, RecordIter};
+use crate::generator::{GenerationReport, GeneratorConfig, generate_mock_data_with_progress};
+use crate::remote::is_object_uri;
+
+/// Records between two snapshots sent to the dashboard
+pub const SNAPSHOT_EVERY: usize = 10_000;
+
+/// How often the screen is redrawn when no snapshot arrives
+const REDRAW: Duration = Duration::from_millis(100);
+
+/// Throughput is measured over at least this long, shorter gets jumpy
+const SAMPLE: Duration = Duration::from_millis(250);
+
+/// Throughput samples kept for the chart, newest last
+const HISTORY: usize = 240;
+
+/// Where a job is at, sent by the job thread to the screen
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Snapshot {
+    pub records: usize,
+    pub bytes: u64,
+    /// 0.0..=1.0, `None` when the end isn't known (e.g. a compressed input)
+    pub fraction: Option<f64>,
+    pub malformed: usize,
+    pub elapsed: Duration,
+    /// running totals, ingestion only
+    pub stats: Option<AnalysisResult>,
+}
+
+/// Runs `job` on its own thread and shows the snapshots it sends until it's
+/// done. `None` when `q` (or Esc, Ctrl-C) was pressed first: the job is left
+/// running and ends with the process
+pub fn run<T: Send + 'static>(
+    title: &str,
+    job: impl FnOnce(Sender<Snapshot>) -> T + Send + 'static,
+) -> std::io::Result<Option<T>> {
+    let (sender, snapshots) = mpsc::channel();
+    let worker = std::thread::spawn(move || job(sender));
+
+    let mut terminal = ratatui::try_init()?;
+    let mut view = View::new(title);
+    // restore the terminal whatever happens in there
+    let result = (|| loop {
+        match snapshots.recv_timeout(REDRAW) {
+            Ok(snapshot) => {
+                view.push(snapshot);
+                snapshots
+                    .try_iter()
+                    .for_each(|snapshot| view.push(snapshot));
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                let done = worker.join().expect("dashboard job panicked");
+                return Ok(Some(done));
+            }
+        }
+        terminal.draw(|frame| view.draw(frame))?;
+
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                    || key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL))
+            {
+                return Ok(None);
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
+
+/// [`generate_mock_data_with_progress`] on a dashboard
+pub fn generate(path: &str, config: &GeneratorConfig) -> std::io::Result<Option<GenerationReport>> {
+    let (path, config) = (path.to_string(), config.clone());
+    let title = format!("generate -> {path}");
+    let done = run(&title, move |snapshots| {
+        let config = config.progress_every(SNAPSHOT_EVERY);
+        generate_mock_data_with_progress(&path, &config, |progress| {
+            let _ = snapshots.send(Snapshot {
+                records: progress.records,
+                bytes: progress.bytes as u64,
+                fraction: Some(progress.fraction()),
+                malformed: progress.malformed_records,
+                elapsed: progress.elapsed,
+                stats: None,
+            });
+        })
+    })?;
+    done.transpose()
+}
+
+/// [`crate::analysis::analyze_streaming`] over a file on a dashboard, with
+/// the totals so far on screen. Broken lines are counted instead of warned
+/// about, the warnings would scroll through the screen
+pub fn ingest(path: &str) -> Result<Option<StreamStats>, IngestError> {
+    // the read offset is in decompressed bytes, only comparable to a plain file's size
+    let len = match is_object_uri(path) || Compression::detect(path)? != Compression::None {
+        true => None,
+        false => Some(std::fs::metadata(path)?.len()),
+    };
+    let mut records = RecordIter::open(path)?;
+
+    let title = format!("ingest <- {path}");
+    let done = run(&title, move |snapshots| {
+        let start = Instant::now();
+        let mut stats = StreamStats::default();
+        let mut lines = 0usize;
+        let snapshot = |stats: &StreamStats, bytes: u64| Snapshot {
+            records: stats.count,
+            bytes,
+            fraction: len.map(|len| (bytes as f64 / len.max(1) as f64).min(1.0)),
+            malformed: stats.dirty.total(),
+            elapsed: start.elapsed(),
+            stats: Some(stats.result()),
+        };
+
+        while let Some(record) = records.next() {
+            match record {
+                Ok(record) => stats.add(&record),
+                Err(IngestError::Io(e)) => return Err(e.into()),
+                Err(e) => stats.dirty.record(&e),
+            }
+            lines += 1;
+            if lines.is_multiple_of(SNAPSHOT_EVERY) {
+                let _ = snapshots.send(snapshot(&stats, records.byte_offset()));
+            }
+        }
+        Ok(stats)
+    })?;
+    done.transpose()
+}
+
+/// What's on screen, built up from the snapshots
+struct View {
+    title: String,
+    latest: Snapshot,
+    /// where the current throughput sample started
+    sample: Snapshot,
+    /// records/s and bytes/s per sample
+    history: VecDeque<(u64, u64)>,
+}
+
+impl View {
+    fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            latest: Snapshot::default(),
+            sample: Snapshot::default(),
+            history: VecDeque::with_capacity(HISTORY),
+        }
+    }
+
+    fn push(&mut self, snapshot: Snapshot) {
+        self.latest = snapshot;
+        let span = snapshot.elapsed.saturating_sub(self.sample.elapsed);
+        if span < SAMPLE {
+            return;
+        }
+        let secs = span.as_secs_f64();
+        let records = snapshot.records.saturating_sub(self.sample.records) as f64 / secs;
+        let bytes = snapshot.bytes.saturating_sub(self.sample.bytes) as f64 / secs;
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back((records as u64, bytes as u64));
+        self.sample = snapshot;
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [progress, throughput, chart, malformed, totals, help] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let latest = &self.latest;
+
+        let label = match latest.fraction {
+            Some(fraction) => format!("{:.1}%", fraction * 100.0),
+            None => format!("{:.1} MB", latest.bytes as f64 / 1e6),
+        };
+        let gauge = Gauge::default()
+            .block(Block::bordered().title(self.title.as_str()))
+            .ratio(latest.fraction.unwrap_or(0.0).clamp(0.0, 1.0))
+            .label(label);
+        frame.render_widget(gauge, progress);
+
+        let secs = latest.elapsed.as_secs_f64().max(f64::EPSILON);
+        let (records_now, bytes_now) = self.history.back().copied().unwrap_or_default();
+        let eta = match latest.fraction {
+            Some(fraction) if fraction > 0.0 => {
+                format!("{:.0}s", secs * (1.0 - fraction) / fraction)
+            }
+            _ => "-".to_string(),
+        };
+        let lines = vec![
+            Line::from(format!(
+                "records/s: {records_now} now, {:.0} average",
+                latest.records as f64 / secs
+            )),
+            Line::from(format!(
+                "MB/s: {:.1} now, {:.1} average",
+                bytes_now as f64 / 1e6,
+                latest.bytes as f64 / 1e6 / secs
+            )),
+            Line::from(format!(
+                "records: {}, {:.1} MB",
+                latest.records,
+                latest.bytes as f64 / 1e6
+            )),
+            Line::from(format!("elapsed: {:.1}s, eta: {eta}", secs)),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Throughput")),
+            throughput,
+        );
+
+        // newest on the right, as many as fit
+        let width = chart.width.saturating_sub(2) as usize;
+        let rates: Vec<u64> = self.history.iter().map(|(records, _)| *records).collect();
+        let sparkline = Sparkline::default()
+            .block(Block::bordered().title("records/s"))
+            .data(&rates[rates.len().saturating_sub(width)..]);
+        frame.render_widget(sparkline, chart);
+
+        let seen = latest.records + latest.malformed;
+        let share = latest.malformed as f64 / seen.max(1) as f64 * 100.0;
+        frame.render_widget(
+            Paragraph::new(format!("{} records ({share:.3}%)", latest.malformed))
+                .block(Block::bordered().title("Malformed")),
+            malformed,
+        );
+
+        let lines = match &latest.stats {
+            Some(stats) => {
+                let or_dash = |value: Option<f64>| match value {
+                    Some(value) => format!("{value:.2}"),
+                    None => "-".to_string(),
+                };
+                vec![
+                    Line::from(format!("sum: {:.2}", stats.sum)),
+                    Line::from(format!("count: {}", stats.count)),
+                    Line::from(format!("min: {}", or_dash(stats.min))),
+                    Line::from(format!("max: {}", or_dash(stats.max))),
+                    Line::from(format!("mean: {}", or_dash(stats.mean))),
+                    Line::from(format!(
+                        "accounts: {}",
+                        stats.distinct_accounts.unwrap_or_default()
+                    )),
+                ]
+            }
+            None => vec![Line::from("no running totals while generating")],
+        };
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Totals")),
+            totals,
+        );
+
+        frame.render_widget(Line::from("q to quit"), help);
+    }
+}
@@ -0,0 +1,233 @@
+//! `extern "C"` functions over the delimited parser and the streaming totals,
+//! for callers that can link a C library but don't speak serde. Build one with
+//! `cargo rustc --release --lib --features ffi --crate-type staticlib` (or
+//! `cdylib`), the header is `include/p01.h`, regenerated from this file by
+//! `build.rs` whenever the feature is on.
+//!
+//! Buffers are pipe-delimited text with the header line first, like the files
+//! `p01 generate` writes. Lines that don't parse are skipped and counted, never
+//! an error. Strings handed out point into memory owned by the library and are
+//! not NUL-terminated
+use crate::analysis::StreamStats;
+use crate::data_ingestion::{ClientData, RecordIter, RefReader};
+
+/// What every function returns
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum P01Status {
+    Ok = 0,
+    /// a pointer argument was null
+    NullPointer = 1,
+    /// an index past the end of the records
+    OutOfRange = 2,
+}
+
+/// Totals of a buffer, see [`p01_analyze`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct P01Stats {
+    pub count: u64,
+    pub sum: f64,
+    /// NaN when `count` is 0, like `max` and `mean`
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub distinct_accounts: u64,
+    /// lines that didn't parse
+    pub malformed: u64,
+}
+
+/// A borrowed string, `len` bytes of UTF-8 at `ptr`
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct P01Str {
+    pub ptr: *const u8,
+    pub len: usize,
+}
+
+impl P01Str {
+    fn new(s: &str) -> Self {
+        Self {
+            ptr: s.as_ptr(),
+            len: s.len(),
+        }
+    }
+}
+
+/// One parsed record, its strings live as long as the [`P01Records`] it came from
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct P01Record {
+    pub id: P01Str,
+    pub from_id: P01Str,
+    pub to_id: P01Str,
+    pub amount: f64,
+    /// unix seconds, only meaningful when `has_timestamp`
+    pub timestamp: i64,
+    pub has_timestamp: bool,
+    /// e.g. `transfer`
+    pub kind: P01Str,
+    /// three letter ISO code, empty for the base currency
+    pub currency: P01Str,
+}
+
+/// Records parsed by [`p01_parse`], opaque to C
+pub struct P01Records {
+    records: Vec<ClientData>,
+    malformed: usize,
+}
+
+/// # Safety
+/// `data` has to point to `len` readable bytes, or be null with `len` 0
+unsafe fn buffer<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        return Some(&[]);
+    }
+    // safety: non-null and `len` bytes long, the caller promises
+    (!data.is_null()).then(|| unsafe { std::slice::from_raw_parts(data, len) })
+}
+
+/// Sum, count, min, max, mean and distinct accounts of the `len` bytes at
+/// `data` into `out`, in one pass and without copying a record
+///
+/// # Safety
+/// `data` has to point to `len` readable bytes and `out` to a writable [`P01Stats`]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_analyze(data: *const u8, len: usize, out: *mut P01Stats) -> P01Status {
+    // safety: the caller's promise
+    let Some(data) = (unsafe { buffer(data, len) }) else {
+        return P01Status::NullPointer;
+    };
+    if out.is_null() {
+        return P01Status::NullPointer;
+    }
+
+    let mut reader = RefReader::new(data);
+    let mut stats = StreamStats::default();
+    while let Some(record) = reader.next_record() {
+        match record {
+            Ok(record) => stats.push(record.from_id, record.to_id, record.amount),
+            // a slice can't fail to read, everything else is a broken line
+            Err(e) => stats.dirty.record(&e),
+        }
+    }
+
+    let result = stats.result();
+    // safety: checked for null above, writable the caller promises
+    unsafe {
+        out.write(P01Stats {
+            count: result.count as u64,
+            sum: result.sum,
+            min: result.min.unwrap_or(f64::NAN),
+            max: result.max.unwrap_or(f64::NAN),
+            mean: result.mean.unwrap_or(f64::NAN),
+            distinct_accounts: stats.accounts.len() as u64,
+            malformed: stats.dirty.total() as u64,
+        })
+    };
+    P01Status::Ok
+}
+
+/// Parses the `len` bytes at `data` into `*out`, which has to be freed with
+/// [`p01_records_free`]. The buffer can be freed as soon as this returns
+///
+/// # Safety
+/// `data` has to point to `len` readable bytes and `out` to a writable pointer
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_parse(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut P01Records,
+) -> P01Status {
+    // safety: the caller's promise
+    let Some(data) = (unsafe { buffer(data, len) }) else {
+        return P01Status::NullPointer;
+    };
+    if out.is_null() {
+        return P01Status::NullPointer;
+    }
+
+    let mut parsed = P01Records {
+        records: Vec::new(),
+        malformed: 0,
+    };
+    for record in RecordIter::new(data) {
+        match record {
+            Ok(record) => parsed.records.push(record),
+            Err(_) => parsed.malformed += 1,
+        }
+    }
+    // safety: checked for null above, writable the caller promises
+    unsafe { out.write(Box::into_raw(Box::new(parsed))) };
+    P01Status::Ok
+}
+
+/// How many records parsed, 0 for null
+///
+/// # Safety
+/// `records` has to come from [`p01_parse`] and not be freed yet
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_records_len(records: *const P01Records) -> usize {
+    // safety: the caller's promise
+    unsafe { records.as_ref() }.map_or(0, |r| r.records.len())
+}
+
+/// How many lines didn't parse, 0 for null
+///
+/// # Safety
+/// `records` has to come from [`p01_parse`] and not be freed yet
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_records_malformed(records: *const P01Records) -> usize {
+    // safety: the caller's promise
+    unsafe { records.as_ref() }.map_or(0, |r| r.malformed)
+}
+
+/// The `index`th record into `out`
+///
+/// # Safety
+/// `records` has to come from [`p01_parse`] and not be freed yet, `out` has
+/// to point to a writable [`P01Record`]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_records_get(
+    records: *const P01Records,
+    index: usize,
+    out: *mut P01Record,
+) -> P01Status {
+    // safety: the caller's promise
+    let Some(records) = (unsafe { records.as_ref() }) else {
+        return P01Status::NullPointer;
+    };
+    if out.is_null() {
+        return P01Status::NullPointer;
+    }
+    let Some(record) = records.records.get(index) else {
+        return P01Status::OutOfRange;
+    };
+
+    let record = P01Record {
+        id: P01Str::new(&record.id),
+        from_id: P01Str::new(&record.from_id),
+        to_id: P01Str::new(&record.to_id),
+        amount: record.amount,
+        timestamp: record.timestamp.unwrap_or_default(),
+        has_timestamp: record.timestamp.is_some(),
+        kind: P01Str::new(record.kind.as_str()),
+        currency: P01Str::new(record.currency.as_ref().map_or("", |c| c.as_str())),
+    };
+    // safety: checked for null above, writable the caller promises
+    unsafe { out.write(record) };
+    P01Status::Ok
+}
+
+/// Frees what [`p01_parse`] returned, null is a no-op
+///
+/// # Safety
+/// `records` has to come from [`p01_parse`] and not be freed already, none of
+/// its strings can be used afterwards
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn p01_records_free(records: *mut P01Records) {
+    if !records.is_null() {
+        // safety: made by Box::into_raw in p01_parse, freed once the caller promises
+        drop(unsafe { Box::from_raw(records) });
+    }
+}
@@ -1,9 +1,1663 @@
+use std::process::ExitCode;
+use std::time::Instant;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use p01::analysis::anomaly::{AnomalyConfig, detect_anomalies};
+use p01::analysis::join::{JoinOptions, JoinSide, totals_by_country_files};
+use p01::analysis::stats::{DEFAULT_PERCENTILES, StreamingStats};
+use p01::analysis::*;
+use p01::cancel::CancellationToken;
 use p01::data_ingestion::*;
-use p01::generate_mock_data;
+use p01::diff::{DiffOptions, compare_with};
+use p01::format::Format;
+use p01::generator::{
+    AccountDistribution, AccountsConfig, AmountDistribution, CorruptionProfile, CurrencyMix,
+    Disorder, GeneratorConfig, HotAccounts, KindMix, Ledger, ParallelCompression, Timestamps,
+    WriteStrategy, generate_accounts, generate_mock_data_pipelined_with_progress,
+    generate_mock_data_sharded_with, parse_size,
+};
+use p01::partition::{
+    Partition, PartitionKey, PartitionedWriter, open_partitioned, partition_file,
+};
+use p01::report::{Report, ReportFormat};
+use p01::sort::{SortOptions, sort_file};
+use p01::transform::Transform;
+use p01::validation::Validator;
+use p01::{
+    Progress, generate_mock_data_to, generate_mock_data_with_progress, generate_mock_records,
+};
+
+#[derive(Parser)]
+#[command(
+    name = "p01",
+    about = "Mock transaction data: generate, ingest, analyze"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate a mock dataset
+    Generate {
+        /// Target size, e.g. `2gb`, `500mb`, `1024` (bytes)
+        #[arg(long, default_value = "1gb", value_parser = parse_size, conflicts_with = "records")]
+        size: usize,
+        /// Exact number of records instead of a size
+        #[arg(long)]
+        records: Option<usize>,
+        /// `-` writes to stdout to pipe into another tool (text formats only)
+        #[arg(long, default_value = "data.csv")]
+        out: String,
+        /// Same as `--out`, e.g. `generate --size 1gb - | gzip > data.csv.gz`
+        #[arg(conflicts_with = "out")]
+        dest: Option<String>,
+        /// Output format, picked from the extension by default
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        /// Chance of a record getting a corrupted delimiter
+        #[arg(long)]
+        corruption_rate: Option<f64>,
+        /// Every corruption mode (missing/extra fields, bad amounts, blank lines, invalid UTF-8,
+        /// duplicate ids, CRLF...) at this rate, plus a truncated last line
+        #[arg(long, conflicts_with = "corruption_rate")]
+        corrupt_all: Option<f64>,
+        #[arg(long)]
+        seed: Option<u64>,
+        /// End lines with `\r\n` instead of `\n`
+        #[arg(long)]
+        crlf: bool,
+        /// Add a `timestamp` column (unix seconds, ~1s apart)
+        #[arg(long)]
+        timestamps: bool,
+        /// Add a `kind` column (mostly transfers, some deposits/withdrawals/fees)
+        #[arg(long)]
+        kinds: bool,
+        /// Add a `currency` column (mostly USD and EUR, some GBP/JPY/CHF)
+        #[arg(long)]
+        currencies: bool,
+        /// Zipfian account popularity with this exponent (~1.0) instead of uniform
+        #[arg(long)]
+        zipf: Option<f64>,
+        /// Send 20% of all traffic through 100 hot accounts
+        #[arg(long)]
+        hot_accounts: bool,
+        /// Accounts the records are between (8999999 by default), pass the
+        /// same to `generate-accounts` to join them
+        #[arg(long)]
+        accounts: Option<u32>,
+        /// Log-normal amounts (median 250, long tail) instead of uniform 1..100000
+        #[arg(long)]
+        lognormal: bool,
+        /// Simulate balances (10000 per account to start) so nothing overdraws, check with `verify-ledger`
+        #[arg(long)]
+        ledger: bool,
+        /// Chance of a record being written again a few records later (exact copy)
+        #[arg(long)]
+        duplicates: Option<f64>,
+        /// Chance of a record being written a few records late, out of id order
+        #[arg(long)]
+        shuffle: Option<f64>,
+        /// Continue an interrupted run on `--out` (plain text only, pass its `--seed` for identical output)
+        #[arg(long, conflicts_with = "shards")]
+        append: bool,
+        /// Split the output over this many files generated in parallel, `--out` is then a directory
+        #[arg(long)]
+        shards: Option<usize>,
+        /// Write `--out/<key>=<value>/part-*` directories instead of one file (records come
+        /// clean, corruption only exists in a single text file). `date` needs `--timestamps`
+        #[arg(long, value_enum, conflicts_with_all = ["shards", "append", "rate"])]
+        partition_by: Option<PartitionKeyArg>,
+        /// Records per second, written out as they go like a live feed (see `analyze --follow`)
+        #[arg(long)]
+        rate: Option<f64>,
+        /// Capacity of the write buffer for text output, e.g. `64kb` (8kb by default)
+        #[arg(long, value_parser = parse_size)]
+        buffer_size: Option<usize>,
+        /// How formatted lines are handed to the writer
+        #[arg(long, value_enum, default_value = "per-line")]
+        write_strategy: WriteStrategyArg,
+        /// Lines per `write_vectored` call with `--write-strategy vectored`
+        #[arg(long, default_value_t = 256)]
+        batch_lines: usize,
+        /// Compress `.gz` / `.zst` output in blocks on this many threads instead of
+        /// on the writing one (the file decompresses the same)
+        #[arg(long, conflicts_with_all = ["shards", "append", "partition_by"])]
+        compress_threads: Option<usize>,
+        /// The embedded sample dataset's records, accounts, columns and seed,
+        /// `--out - > crates/datatools/data/sample.csv` regenerates it
+        #[arg(long, conflicts_with_all = ["size", "records", "seed", "accounts"])]
+        sample_dataset: bool,
+    },
+    /// Generate the accounts table (id, country, opening date, risk tier) the
+    /// transactions' ids can be joined with
+    GenerateAccounts {
+        /// Same as `generate --accounts`
+        #[arg(long, default_value_t = GeneratorConfig::default().accounts)]
+        accounts: u32,
+        #[arg(long, default_value = "accounts.csv")]
+        out: String,
+        #[arg(long)]
+        seed: Option<u64>,
+    },
+    /// Generate, ingest, analyze and write the report as a TOML file describes
+    Run {
+        #[arg(long, default_value = p01::config::DEFAULT_CONFIG)]
+        config: String,
+    },
+    /// Load a dataset and aggregate it
+    Analyze {
+        #[arg(long, value_enum, default_value = "functional")]
+        approach: Approach,
+        /// `.parquet`, `.jsonl` or pipe-delimited, optionally `.gz` / `.zst` compressed,
+        /// a directory of shards, or `-` to stream delimited text (optionally compressed)
+        /// from stdin in one pass, e.g. `generate - | analyze -`
+        path: String,
+        /// Rayon only: smallest chunk it splits the records into
+        #[arg(long)]
+        min_len: Option<usize>,
+        /// Rayon only: time a few chunk sizes on a sample first and use the fastest
+        #[arg(long, conflicts_with = "min_len")]
+        autotune: bool,
+        /// Convert every amount into `--base` before analyzing, e.g. `EUR=1.08,GBP=1.27`
+        /// (what one unit is worth in the base currency)
+        #[arg(long, value_delimiter = ',', value_parser = parse_rate)]
+        rates: Vec<(Currency, f64)>,
+        #[arg(long, default_value = "USD", value_parser = parse_currency)]
+        base: Currency,
+        /// Keep reading lines appended to a delimited file and print the running totals
+        #[arg(long)]
+        follow: bool,
+        /// Also write the totals, per-account aggregates and anomalies to a `.json` file,
+        /// or as CSV tables into a directory (any other path)
+        #[arg(long, conflicts_with = "follow")]
+        report: Option<String>,
+        /// Print the p50/p95/p99 amounts, from a t-digest in the same pass
+        #[arg(long, conflicts_with = "follow")]
+        quantiles: bool,
+        /// Print the amounts' histogram in this many buckets as `low,high,count` lines
+        #[arg(long, conflicts_with = "follow")]
+        histogram: Option<usize>,
+    },
+    /// Check the checksum sidecar and count malformed lines per category, exits with 1 on either
+    Validate {
+        /// Write rejected lines to `<path>.rejects`
+        #[arg(long)]
+        rejects: bool,
+        /// Read on past reads that fail, skipping to the next line a block further on,
+        /// instead of stopping at the first. Skipped bytes fail the check too
+        #[arg(long)]
+        recover: bool,
+        /// Also check every record against the standard rules (positive amounts, id format,
+        /// no self-transfers, no repeated ids), any violation fails too
+        #[arg(long)]
+        rules: bool,
+        /// Also write the malformed lines and rule violations to a `.json` file, or as
+        /// CSV tables into a directory (any other path)
+        #[arg(long)]
+        report: Option<String>,
+        path: String,
+    },
+    /// Guess a delimited file's delimiter, header and column types from its first lines
+    Sniff { path: String },
+    /// Count the distinct accounts, estimated with a HyperLogLog unless `--exact`
+    Distinct {
+        /// Any format `analyze` reads, delimited ones are streamed in one pass
+        path: String,
+        /// log2 of the sketch's registers (4 to 18), one more doubles the memory
+        /// and takes the error down by about 30%
+        #[arg(long, default_value_t = p01::analysis::cardinality::DEFAULT_PRECISION)]
+        precision: u8,
+        /// Count them in a hash set instead, memory grows with the accounts
+        #[arg(long)]
+        exact: bool,
+    },
+    /// The most active accounts by transactions and by money moved, from a
+    /// Space-Saving sketch unless `--exact`
+    Top {
+        /// Any format `analyze` reads, delimited ones are streamed in one pass
+        path: String,
+        #[arg(short, long, default_value_t = 10)]
+        k: usize,
+        /// Group every account instead, memory grows with the accounts
+        #[arg(long)]
+        exact: bool,
+    },
+    /// Sort a file by a column into a new one, spilling to temp files past the memory budget
+    Sort {
+        path: String,
+        #[arg(long)]
+        out: String,
+        /// Column to sort by: id, from_id, to_id, amount, timestamp or kind
+        #[arg(long, default_value = "id", value_parser = parse_column)]
+        by: Column,
+        /// Where the sorted runs get spilled, next to `--out` by default
+        #[arg(long)]
+        temp_dir: Option<String>,
+        /// e.g. `512mb`
+        #[arg(long, default_value = "256mb", value_parser = parse_size)]
+        memory: usize,
+    },
+    /// Split a file into `--out/<key>=<value>/part-*` directories by a partition key
+    Partition {
+        path: String,
+        #[arg(long)]
+        out: String,
+        #[arg(long, value_enum, default_value = "date")]
+        by: PartitionKeyArg,
+        /// Format of the part files, csv by default
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+        /// Records per part file before the next one starts
+        #[arg(long, default_value_t = p01::partition::DEFAULT_RECORDS_PER_FILE)]
+        records_per_file: usize,
+    },
+    /// Analyze only the partitions of a partitioned directory with values in
+    /// `--from..=--to` (dates compare as they should), without opening the others
+    Scan {
+        path: String,
+        #[arg(long)]
+        from: Option<String>,
+        #[arg(long)]
+        to: Option<String>,
+        #[arg(long, value_enum, default_value = "functional")]
+        approach: Approach,
+    },
+    /// Clean a file into a new one: normalize ids, clamp amounts, fill in missing
+    /// values and drop columns, one record at a time
+    Transform {
+        path: String,
+        #[arg(long)]
+        out: String,
+        /// Trim and upper-case the ids
+        #[arg(long)]
+        normalize_ids: bool,
+        #[arg(long, allow_hyphen_values = true)]
+        min_amount: Option<f64>,
+        #[arg(long, allow_hyphen_values = true)]
+        max_amount: Option<f64>,
+        /// Currency of records without one
+        #[arg(long, value_parser = parse_currency)]
+        fill_currency: Option<Currency>,
+        /// Unix seconds of records without a timestamp
+        #[arg(long, allow_hyphen_values = true)]
+        fill_timestamp: Option<i64>,
+        /// Optional columns to leave out: timestamp, kind or currency
+        #[arg(long, value_delimiter = ',', value_parser = parse_column)]
+        drop: Vec<Column>,
+        /// Format of the output, by its extension if not given
+        #[arg(long, value_enum)]
+        format: Option<FormatArg>,
+    },
+    /// Join transactions with the accounts table and total them per country, partitioned
+    /// on disk (a grace hash join) when the accounts don't fit in the memory budget
+    Join {
+        path: String,
+        /// What `generate-accounts` wrote
+        #[arg(long)]
+        accounts: String,
+        /// Whose account's country a transaction counts for
+        #[arg(long, value_enum, default_value = "sender")]
+        on: JoinSideArg,
+        /// e.g. `512mb`
+        #[arg(long, default_value = "256mb", value_parser = parse_size)]
+        memory: usize,
+        /// Partition into this many pairs of files whatever the budget
+        #[arg(long)]
+        partitions: Option<usize>,
+        /// Where the partitions go, the system temp dir by default
+        #[arg(long)]
+        temp_dir: Option<String>,
+    },
+    /// Records added, removed and changed (by transaction id) from one file to another,
+    /// whatever order they're in. Fails when they differ
+    Diff {
+        a: String,
+        b: String,
+        /// e.g. `512mb`, shared by both files
+        #[arg(long, default_value = "256mb", value_parser = parse_size)]
+        memory: usize,
+        /// Where the sorted runs get spilled, the system temp dir by default
+        #[arg(long)]
+        temp_dir: Option<String>,
+        /// Differences to print
+        #[arg(long, default_value_t = p01::diff::DEFAULT_DIFF_SAMPLES)]
+        samples: usize,
+        /// Amounts closer than this count as the same
+        #[arg(long, default_value_t = 0.0)]
+        tolerance: f64,
+    },
+    /// Totals per value of a column, within a memory budget (sorted runs spill to temp files)
+    GroupBy {
+        path: String,
+        /// Column to group by: id, from_id, to_id, amount, timestamp or kind
+        #[arg(long, default_value = "from_id", value_parser = parse_column)]
+        by: Column,
+        /// Where the sorted runs get spilled, the system temp dir by default
+        #[arg(long)]
+        temp_dir: Option<String>,
+        /// e.g. `512mb`
+        #[arg(long, default_value = "256mb", value_parser = parse_size)]
+        memory: usize,
+        /// Groups to print, the ones with the largest sums
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Copy a file's records into the `transactions` table of a SQLite database (needs the sqlite feature)
+    Export {
+        path: String,
+        /// Database file, created if it isn't there. An old `transactions` table is replaced
+        #[arg(long)]
+        sqlite: String,
+        /// Rows per transaction
+        #[arg(long, default_value_t = p01::export::DEFAULT_BATCH_ROWS)]
+        batch_rows: usize,
+        /// Rows per INSERT statement
+        #[arg(long, default_value_t = 1)]
+        rows_per_insert: usize,
+        /// Leave the table without indexes
+        #[arg(long)]
+        no_indexes: bool,
+    },
+    /// Replay a `generate --ledger` file and check no money appeared, vanished or overdrew
+    VerifyLedger {
+        path: String,
+        #[arg(long, default_value_t = 10_000.0)]
+        opening_balance: f64,
+    },
+    /// Generate records into a Kafka topic, one JSON record per message (needs the kafka feature)
+    Publish {
+        #[arg(long, default_value_t = 1_000_000)]
+        records: usize,
+        /// Records per second at most, as fast as the brokers take them otherwise
+        #[arg(long)]
+        rate: Option<f64>,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[command(flatten)]
+        kafka: KafkaArgs,
+    },
+    /// Read a Kafka topic and print the running totals after every batch (needs the kafka feature)
+    Consume {
+        /// Stop after this many seconds without a message
+        #[arg(long)]
+        idle: Option<u64>,
+        #[command(flatten)]
+        kafka: KafkaArgs,
+    },
+    /// Generate or ingest with live progress, throughput and totals on screen (needs the tui feature)
+    Dashboard {
+        #[command(subcommand)]
+        job: DashboardJob,
+    },
+    /// Run SQL over a csv/parquet file, it's the `txns` table (needs the datafusion feature)
+    Sql {
+        path: String,
+        /// e.g. "SELECT from_id, SUM(amount) FROM txns GROUP BY from_id"
+        query: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DashboardJob {
+    /// Same as `generate`, for the common options
+    Generate {
+        #[arg(long, default_value = "1gb", value_parser = parse_size, conflicts_with = "records")]
+        size: usize,
+        #[arg(long)]
+        records: Option<usize>,
+        #[arg(long, default_value = "data.csv")]
+        out: String,
+        #[arg(long)]
+        corruption_rate: Option<f64>,
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Records per second
+        #[arg(long)]
+        rate: Option<f64>,
+    },
+    /// Read a file in one pass, keeping the totals like `analyze -` does
+    Ingest { path: String },
+}
+
+#[derive(Args)]
+struct KafkaArgs {
+    /// `host:port`, comma separated
+    #[arg(long, default_value = "localhost:9092", value_delimiter = ',')]
+    brokers: Vec<String>,
+    #[arg(long, default_value = "transactions")]
+    topic: String,
+    /// Consumer group, where `consume` picks up again
+    #[arg(long, default_value = "p01")]
+    group: String,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaArgs {
+    fn config(&self) -> p01::streaming::KafkaConfig {
+        p01::streaming::KafkaConfig::new()
+            .brokers(self.brokers.clone())
+            .topic(&self.topic)
+            .group(&self.group)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Csv,
+    Jsonl,
+    Parquet,
+}
+
+impl From<FormatArg> for Format {
+    fn from(format: FormatArg) -> Self {
+        match format {
+            FormatArg::Csv => Format::Delimited,
+            FormatArg::Jsonl => Format::JsonLines,
+            FormatArg::Parquet => Format::Parquet,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PartitionKeyArg {
+    Date,
+    Kind,
+    Currency,
+}
+
+impl From<PartitionKeyArg> for PartitionKey {
+    fn from(key: PartitionKeyArg) -> Self {
+        match key {
+            PartitionKeyArg::Date => PartitionKey::Date,
+            PartitionKeyArg::Kind => PartitionKey::Kind,
+            PartitionKeyArg::Currency => PartitionKey::Currency,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum JoinSideArg {
+    Sender,
+    Receiver,
+}
+
+impl From<JoinSideArg> for JoinSide {
+    fn from(side: JoinSideArg) -> Self {
+        match side {
+            JoinSideArg::Sender => JoinSide::Sender,
+            JoinSideArg::Receiver => JoinSide::Receiver,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum WriteStrategyArg {
+    /// a new buffer per line
+    PerLine,
+    /// one line buffer, formatted into over and over
+    Reused,
+    /// `--batch-lines` lines per `write_vectored`
+    Vectored,
+}
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    generate_mock_data("data.csv", 1)?;
-    let data = open_file("data.csv")?;
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Approach {
+    Greedy,
+    Functional,
+    Rayon,
+    /// std::thread::scope, one chunk per core
+    Threads,
+    Polars,
+    /// polars reads the file itself, no `ClientData` in between
+    PolarsLazy,
+    /// the amounts only, read straight into integer cents and summed exactly
+    Cents,
+}
+
+/// Records between progress bar updates, indicatif limits the redraws itself
+#[cfg(feature = "indicatif")]
+const PROGRESS_BAR_EVERY: usize = 50_000;
+
+fn eta(progress: &Progress) -> String {
+    match progress.eta() {
+        Some(eta) => format!("{}s", eta.as_secs()),
+        None => "-".to_string(),
+    }
+}
 
+/// A token Ctrl-C cancels, with the `signal` feature. Without it Ctrl-C ends
+/// the process like it always did
+fn interrupt() -> std::io::Result<Option<CancellationToken>> {
+    #[cfg(feature = "signal")]
+    return p01::cancel::ctrl_c().map(Some);
+    #[cfg(not(feature = "signal"))]
+    Ok(None)
+}
+
+/// 130 after a Ctrl-C, what a shell reports for a process SIGINT ended
+fn exit_code(cancelled: bool) -> ExitCode {
+    if !cancelled {
+        return ExitCode::SUCCESS;
+    }
+    eprintln!("Interrupted, stopped early with what was done by then");
+    ExitCode::from(130)
+}
+
+fn parse_column(name: &str) -> Result<Column, String> {
+    Column::from_name(name).ok_or_else(|| format!("unknown column `{name}`"))
+}
+
+/// What `--quantiles` and `--histogram` print
+fn print_distribution(amounts: &mut StreamingStats, quantiles: bool, histogram: Option<usize>) {
+    if quantiles && let Some(stats) = amounts.finish(&DEFAULT_PERCENTILES) {
+        let [p50, p95, p99] = DEFAULT_PERCENTILES.map(|p| stats.percentile(p).unwrap_or(f64::NAN));
+        println!("p50={p50:.2}, p95={p95:.2}, p99={p99:.2} (t-digest estimates)");
+    }
+    if let Some(buckets) = histogram {
+        println!("low,high,count");
+        for bucket in amounts.histogram(buckets) {
+            println!("{:.2},{:.2},{:.0}", bucket.low, bucket.high, bucket.count);
+        }
+    }
+}
+
+/// `--report`, the format comes from the path
+fn save_report(report: &Report, path: &str) -> std::io::Result<()> {
+    let format = ReportFormat::from_path(path);
+    report.save(path, format)?;
+    match format {
+        ReportFormat::Json => println!("report written to {path}"),
+        ReportFormat::Csv => println!("report tables written into {path}/"),
+    }
     Ok(())
 }
+
+/// A record's value in `column`, as it would read in a file
+fn column_value(record: &ClientData, column: Column) -> String {
+    match column {
+        Column::Id => record.id.clone(),
+        Column::FromId => record.from_id.clone(),
+        Column::ToId => record.to_id.clone(),
+        Column::Amount => format!("{:.2}", record.amount),
+        Column::Timestamp => record
+            .timestamp
+            .map(|ts| ts.to_string())
+            .unwrap_or_default(),
+        Column::Kind => record.kind.to_string(),
+        Column::Currency => record.currency.map(String::from).unwrap_or_default(),
+    }
+}
+
+fn parse_currency(code: &str) -> Result<Currency, String> {
+    code.parse().map_err(|e: IngestError| e.to_string())
+}
+
+fn parse_rate(rate: &str) -> Result<(Currency, f64), String> {
+    let (code, rate) = rate
+        .split_once('=')
+        .ok_or_else(|| format!("expected CODE=rate, got `{rate}`"))?;
+    let rate = rate
+        .parse()
+        .map_err(|_| format!("invalid exchange rate `{rate}`"))?;
+    Ok((parse_currency(code)?, rate))
+}
+
+fn load(path: &str) -> Result<Vec<ClientData>, IngestError> {
+    // a directory of shards from `generate --shards`
+    if std::path::Path::new(path).is_dir() {
+        return open_dir(path);
+    }
+
+    match Format::from_path(path) {
+        #[cfg(feature = "parquet")]
+        Format::Parquet => open_file_parquet(path),
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => Err(IngestError::Schema(
+            "parquet support is not compiled in, enable the `parquet` feature".into(),
+        )),
+        Format::Delimited | Format::JsonLines => {
+            open_file_with(path, IngestOptions::new().on_error(warn)).map(|(records, _)| records)
+        }
+    }
+}
+
+// the library only counts broken lines, telling about them is up to us
+fn warn(e: &IngestError) {
+    eprintln!("Warning: Failed to parse {e}");
+}
+
+// and the readers that count them into a report instead of taking options
+fn warn_dirty(dirty: &DirtyDataReport) {
+    if !dirty.is_empty() {
+        eprint!("Warning: {} lines failed to parse\n{dirty}", dirty.total());
+    }
+}
+
+// min_len only means something to rayon
+#[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+fn analyze(
+    approach: Approach,
+    records: &[ClientData],
+    min_len: Option<usize>,
+    cancel: Option<&CancellationToken>,
+) -> Result<AnalysisResult, String> {
+    match approach {
+        Approach::Greedy => Ok(analyze_greedy(records)),
+        Approach::Functional => Ok(analyze_functional(records)),
+        #[cfg(feature = "rayon")]
+        Approach::Rayon => Ok(match (min_len, cancel) {
+            (Some(min_len), _) => {
+                analyze_rayon_with(records, &AnalysisOptions::new().min_len(min_len))
+            }
+            (None, Some(token)) => analyze_rayon_cancellable(records, token),
+            (None, None) => analyze_rayon(records),
+        }),
+        Approach::Threads => {
+            let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+            Ok(match cancel {
+                Some(token) => analyze_threads_cancellable(records, threads, token),
+                None => analyze_threads(records, threads),
+            })
+        }
+        #[cfg(feature = "polars")]
+        Approach::Polars => analyze_polars(records).map_err(|e| e.to_string()),
+        Approach::Cents => {
+            Err("cents reads the amounts from the file itself, `analyze` only".into())
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err("approach not compiled in, enable its feature (or --features all)".into()),
+    }
+}
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
+    match Cli::parse().command {
+        Command::Generate {
+            size,
+            records,
+            out,
+            dest,
+            format,
+            corruption_rate,
+            corrupt_all,
+            seed,
+            crlf,
+            timestamps,
+            kinds,
+            currencies,
+            zipf,
+            hot_accounts,
+            accounts,
+            lognormal,
+            ledger,
+            duplicates,
+            shuffle,
+            shards,
+            partition_by,
+            append,
+            rate,
+            buffer_size,
+            write_strategy,
+            batch_lines,
+            compress_threads,
+            sample_dataset,
+        } => {
+            let mut config = match records {
+                _ if sample_dataset => GeneratorConfig::sample_dataset(),
+                Some(records) => GeneratorConfig::new().target_records(records),
+                None => GeneratorConfig::new().target_bytes(size),
+            };
+            config.format = format.map(Format::from);
+            if let Some(seed) = seed {
+                config = config.seed(seed);
+            }
+            config.append = append;
+            config.crlf = crlf;
+            config.records_per_second = rate;
+            if let Some(bytes) = buffer_size {
+                config = config.buffer_capacity(bytes);
+            }
+            config = config.write_strategy(match write_strategy {
+                WriteStrategyArg::PerLine => WriteStrategy::PerLine,
+                WriteStrategyArg::Reused => WriteStrategy::ReusedBuffer,
+                WriteStrategyArg::Vectored => WriteStrategy::Vectored(batch_lines),
+            });
+            if let Some(rate) = corruption_rate {
+                config = config.corruption_probability(rate);
+            }
+            if let Some(rate) = corrupt_all {
+                config = config.corruption(CorruptionProfile::all(rate));
+            }
+            if timestamps {
+                config = config.timestamps(Timestamps::default());
+            }
+            if kinds {
+                config = config.kinds(KindMix::default());
+            }
+            if currencies {
+                config = config.currencies(CurrencyMix::default());
+            }
+            if ledger {
+                config = config.ledger(Ledger::default());
+            }
+            if duplicates.is_some() || shuffle.is_some() {
+                config = config.disorder(Disorder {
+                    duplicate: duplicates.unwrap_or(0.0),
+                    shuffle: shuffle.unwrap_or(0.0),
+                    ..Disorder::default()
+                });
+            }
+            if let Some(exponent) = zipf {
+                config = config.account_distribution(AccountDistribution::Zipf { exponent });
+            }
+            if hot_accounts {
+                config = config.hot_accounts(HotAccounts::default());
+            }
+            if let Some(accounts) = accounts {
+                config = config.accounts(accounts);
+            }
+            if lognormal {
+                config = config.amounts(AmountDistribution::LogNormal {
+                    median: 250.0,
+                    sigma: 1.2,
+                });
+            }
+
+            if let Some(token) = interrupt()? {
+                config = config.cancellation(token);
+            }
+
+            let out = dest.unwrap_or(out);
+            if out == "-" {
+                if shards.is_some() {
+                    return Err("--shards needs a directory to write to, not stdout".into());
+                }
+                // stdout is the data, everything else goes to stderr
+                let report = match generate_mock_data_to(std::io::stdout(), &config, |progress| {
+                    eprintln!(
+                        "Generated {} million records, ~{:.2} GB ({:.0}%, eta {})",
+                        progress.records / 1_000_000,
+                        progress.bytes as f64 / 1e9,
+                        progress.fraction() * 100.0,
+                        eta(&progress)
+                    );
+                }) {
+                    Ok(report) => report,
+                    // `| head` and friends closing the pipe is how a run gets cut short
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => {
+                        return Ok(ExitCode::SUCCESS);
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                eprintln!(
+                    "Generated {} records, {} malformed, in {:?}",
+                    report.records, report.malformed_records, report.elapsed
+                );
+                return Ok(exit_code(report.cancelled));
+            }
+
+            if let Some(key) = partition_by {
+                let start = Instant::now();
+                let format = config.format.unwrap_or_default();
+                let mut writer =
+                    PartitionedWriter::create(&out, key.into(), format)?.layout(config.layout());
+                let generated = generate_mock_records(&config, |record| writer.write(&record))?;
+                let report = writer.finish()?;
+                println!(
+                    "Generated {} records into {} partitions ({} files) under {out} in {:?}",
+                    report.records,
+                    report.partitions,
+                    report.files,
+                    start.elapsed()
+                );
+                return Ok(exit_code(generated.cancelled));
+            }
+
+            if let Some(shards) = shards {
+                let report = generate_mock_data_sharded_with(&out, &config, shards)?;
+                println!(
+                    "Generated {} records in {shards} shards under {out}, total size: {:.2} GB, {} malformed, in {:?}",
+                    report.records,
+                    report.bytes as f64 / 1e9,
+                    report.malformed_records,
+                    report.elapsed
+                );
+                return Ok(exit_code(report.cancelled));
+            }
+
+            // a bar when someone is watching, a line per million records for logs
+            #[cfg(feature = "indicatif")]
+            let bar = std::io::IsTerminal::is_terminal(&std::io::stderr())
+                .then(|| p01::generator::IndicatifProgress::new(config.target));
+            #[cfg(feature = "indicatif")]
+            if bar.is_some() {
+                config = config.progress_every(PROGRESS_BAR_EVERY);
+            }
+            let on_progress = |progress: Progress| {
+                #[cfg(feature = "indicatif")]
+                if let Some(bar) = &bar {
+                    bar.update(&progress);
+                    return;
+                }
+                println!(
+                    "Generated {} million records, ~{:.2} GB ({:.0}%, {:.1} MB/s, eta {})\nMalformed records: {}",
+                    progress.records / 1_000_000,
+                    progress.bytes as f64 / 1e9,
+                    progress.fraction() * 100.0,
+                    progress.bytes_per_second / 1e6,
+                    eta(&progress),
+                    progress.malformed_records
+                );
+            };
+            let report = match compress_threads {
+                Some(threads) => generate_mock_data_pipelined_with_progress(
+                    &out,
+                    &config,
+                    &ParallelCompression::new().threads(threads),
+                    on_progress,
+                )?,
+                None => generate_mock_data_with_progress(&out, &config, on_progress)?,
+            };
+            #[cfg(feature = "indicatif")]
+            if let Some(bar) = &bar {
+                bar.finish();
+            }
+            println!(
+                "Generated {} records, total size: {:.2} GB, {} malformed, in {:?}",
+                report.records,
+                report.bytes as f64 / 1e9,
+                report.malformed_records,
+                report.elapsed
+            );
+            if report.duplicates > 0 {
+                println!("{} duplicated records", report.duplicates);
+            }
+            return Ok(exit_code(report.cancelled));
+        }
+
+        Command::Run { config } => {
+            let run = p01::Pipeline::from_config(&config)?.run()?;
+            if let Some(generated) = &run.generated {
+                println!(
+                    "Generated {} records, {} malformed, in {:?}",
+                    generated.records, generated.malformed_records, generated.elapsed
+                );
+            }
+            println!(
+                "Read {} records from {} ({} malformed), kept {} ({} duplicates, {} invalid)",
+                run.records,
+                run.source,
+                run.dirty.total(),
+                run.kept,
+                run.duplicates,
+                run.invalid
+            );
+            if let Some(summary) = &run.summary {
+                println!("sum={:.2}, count={}", summary.sum, summary.count);
+            }
+            for approach in &run.approaches {
+                println!(
+                    "{}: sum={:.2}, count={} in {:?}",
+                    approach.name, approach.result.sum, approach.result.count, approach.elapsed
+                );
+            }
+            println!("in {:?}", run.elapsed);
+        }
+
+        Command::Analyze {
+            path,
+            report,
+            quantiles,
+            histogram,
+            ..
+        } if path == "-" => {
+            let start = Instant::now();
+            // gzip/zstd compressed input is spotted the same way as in a file
+            let stdin = std::io::BufReader::with_capacity(1 << 16, std::io::stdin());
+            let stats = match quantiles || histogram.is_some() {
+                true => StreamStats::with_quantiles(),
+                false => StreamStats::default(),
+            };
+            let stdin = p01::compression::decompress(Box::new(stdin))?;
+            let mut stats = match interrupt()? {
+                Some(token) => analyze_streaming_cancellable(stdin, stats, &token)?,
+                None => analyze_streaming_into(stdin, stats)?,
+            };
+            println!("sum={:.2}, count={}", stats.sum, stats.count);
+            if let (Some(min), Some(max)) = (stats.min, stats.max) {
+                println!("min={min:.2}, max={max:.2}");
+            }
+            println!(
+                "accounts: {}, malformed: {}",
+                stats.accounts.len(),
+                stats.dirty.total()
+            );
+            if let Some(amounts) = &mut stats.amounts {
+                print_distribution(amounts, quantiles, histogram);
+            }
+            println!("load + analysis: {:?}", start.elapsed());
+            if let Some(out) = report {
+                let report = Report::new()
+                    .source("-")
+                    .summary(&stats.result())
+                    .accounts(&stats.accounts)
+                    .dirty(&stats.dirty);
+                save_report(&report, &out)?;
+            }
+            return Ok(exit_code(stats.cancelled));
+        }
+
+        Command::Analyze {
+            path, follow: true, ..
+        } => {
+            let mut live = LiveStats::new(Follower::new(&path));
+            live.watch(|update, totals| {
+                println!(
+                    "+{} records, sum={:.2} | total sum={:.2}, count={}, accounts: {}, malformed: {}",
+                    update.count,
+                    update.sum,
+                    totals.sum,
+                    totals.count,
+                    totals.accounts.len(),
+                    totals.dirty.total()
+                );
+            })?;
+        }
+
+        Command::Analyze {
+            approach: Approach::Cents,
+            path,
+            report,
+            ..
+        } => {
+            let start = Instant::now();
+            let totals = analyze_file_cents(&path)?;
+            warn_dirty(&totals.dirty);
+            println!("{totals}");
+            println!("malformed: {}", totals.dirty.total());
+            println!("load + analysis: {:?}", start.elapsed());
+            if let Some(out) = report {
+                let report = Report::new()
+                    .source(path)
+                    .summary(&totals.result())
+                    .dirty(&totals.dirty);
+                save_report(&report, &out)?;
+            }
+        }
+
+        Command::Analyze {
+            approach: Approach::PolarsLazy,
+            path,
+            report,
+            ..
+        } => {
+            #[cfg(feature = "polars")]
+            {
+                let start = Instant::now();
+                let result = analyze_polars_lazy(&path)?;
+                println!("sum={:.2}, count={}", result.sum, result.count);
+                println!("load + analysis: {:?}", start.elapsed());
+                // no records come out of polars to aggregate further
+                if let Some(out) = report {
+                    save_report(&Report::new().source(path).summary(&result), &out)?;
+                }
+            }
+            #[cfg(not(feature = "polars"))]
+            {
+                let _ = (path, report);
+                return Err(
+                    "approach not compiled in, enable its feature (or --features all)".into(),
+                );
+            }
+        }
+
+        Command::Analyze {
+            approach,
+            path,
+            min_len,
+            autotune,
+            rates,
+            base,
+            follow: _,
+            report,
+            quantiles,
+            histogram,
+        } => {
+            let start = Instant::now();
+            let mut records = load(&path)?;
+            let loaded = start.elapsed();
+
+            let by_currency = totals_by_currency(&records);
+            if by_currency.len() > 1 {
+                for (currency, (sum, count)) in &by_currency {
+                    let currency = currency.map_or("(base)".to_string(), String::from);
+                    println!("  {currency}: sum={sum:.2}, count={count}");
+                }
+            }
+            if !rates.is_empty() {
+                let table = rates
+                    .into_iter()
+                    .fold(RateTable::new(base), |table, (currency, rate)| {
+                        table.rate(currency, rate)
+                    });
+                table.convert_all(&mut records)?;
+                println!("converted to {base}");
+            } else if by_currency.len() > 1 {
+                println!("mixed currencies, the totals below add them up as is (see --rates)");
+            }
+
+            #[cfg(feature = "rayon")]
+            let min_len = if autotune && approach == Approach::Rayon {
+                let tuned = p01::analysis::autotune(&records, 1_000_000);
+                for (min_len, elapsed) in &tuned.timings {
+                    println!("  min_len {min_len}: {elapsed:?}");
+                }
+                println!("autotuned min_len: {}", tuned.options.min_len);
+                Some(tuned.options.min_len)
+            } else {
+                min_len
+            };
+            #[cfg(not(feature = "rayon"))]
+            let _ = autotune;
+
+            // Ctrl-C while loading still ends the process right away
+            let cancel = interrupt()?;
+            let start = Instant::now();
+            let result = analyze(approach, &records, min_len, cancel.as_ref())?;
+            let analyzed = start.elapsed();
+
+            println!("sum={:.2}, count={}", result.sum, result.count);
+            if let (Some(min), Some(max), Some(mean)) = (result.min, result.max, result.mean) {
+                println!("min={min:.2}, max={max:.2}, mean={mean:.2}");
+            }
+            // the rest goes over every record again, not what a Ctrl-C asked for
+            if result.count < records.len() {
+                return Ok(exit_code(true));
+            }
+            let by_kind = totals_by_kind(&records);
+            if by_kind.len() > 1 {
+                for (kind, (sum, count)) in by_kind {
+                    println!("  {kind}: sum={sum:.2}, count={count}");
+                }
+            }
+            if quantiles || histogram.is_some() {
+                let mut amounts = StreamingStats::new();
+                amounts.extend(records.iter().map(|r| r.amount));
+                print_distribution(&mut amounts, quantiles, histogram);
+            }
+            println!("load: {loaded:?}, analysis: {analyzed:?}");
+
+            if let Some(out) = report {
+                let report = Report::new()
+                    .source(path)
+                    .summary(&result.with_distinct_accounts(&records))
+                    .accounts(&aggregate_by_account(&records))
+                    .anomalies(&detect_anomalies(&records, AnomalyConfig::default()));
+                save_report(&report, &out)?;
+            }
+        }
+
+        Command::Validate {
+            rejects,
+            recover,
+            rules,
+            report: report_path,
+            path,
+        } => {
+            // files from elsewhere have no sidecar, that's not a failure
+            match verify(&path) {
+                Ok(()) => println!("checksum: ok"),
+                Err(IngestError::MissingChecksum(_)) => println!("checksum: no sidecar"),
+                Err(e) => {
+                    println!("checksum: {e}");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+
+            let options = IngestOptions::new()
+                .skip()
+                .write_rejects(rejects)
+                .recover(recover);
+            let (_, report) = open_file_with(&path, options)?;
+
+            println!("lines: {}, records: {}", report.lines, report.records);
+            print!("{}", report.dirty);
+            for range in &report.skipped_ranges {
+                println!("skipped {range}");
+            }
+            if let Some(rejects_path) = &report.rejects_path {
+                println!("rejected lines written to {rejects_path}");
+            }
+
+            let mut clean = report.malformed_total() == 0 && report.skipped_ranges.is_empty();
+            let mut findings = Report::new().source(path.as_str()).dirty(&report.dirty);
+            if rules {
+                let report = Validator::standard().validate_file(&path)?;
+                print!("{report}");
+                clean &= report.violations() == 0;
+                findings = findings.validation(&report);
+            }
+            if let Some(out) = report_path {
+                save_report(&findings, &out)?;
+            }
+
+            if !clean {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        Command::Sniff { path } => {
+            let schema = sniff(&path)?;
+            print!("{schema}");
+            match schema.layout() {
+                Ok(layout) => println!("reads as: {}", layout.header(layout.delimiter())),
+                Err(e) => println!("doesn't read as transactions: {e}"),
+            }
+        }
+
+        Command::Distinct {
+            path,
+            precision,
+            exact,
+        } => {
+            use p01::analysis::cardinality::{distinct_accounts, estimate_streaming, sketch};
+
+            if exact {
+                println!("distinct accounts: {}", distinct_accounts(&load(&path)?));
+                return Ok(ExitCode::SUCCESS);
+            }
+            let estimate = match Format::from_path(&path) {
+                Format::Delimited if !std::path::Path::new(&path).is_dir() => {
+                    let (estimate, dirty) =
+                        estimate_streaming(p01::compression::open_reader(&path)?, precision)?;
+                    warn_dirty(&dirty);
+                    estimate
+                }
+                _ => sketch(&load(&path)?, precision).estimate(),
+            };
+            let (low, high) = estimate.bounds(2.0);
+            println!(
+                "distinct accounts: ~{:.0} (95% between {low:.0} and {high:.0})",
+                estimate.estimate
+            );
+        }
+
+        Command::Top { path, k, exact } => {
+            if exact {
+                let accounts = aggregate_by_account(&load(&path)?);
+                println!("by transactions:");
+                for (id, stats) in top_n_active(&accounts, k) {
+                    println!("  {id:<20} {}", stats.transaction_count());
+                }
+                let mut by_amount: Vec<_> = accounts
+                    .iter()
+                    .map(|(id, stats)| (id, stats.total_sent.abs() + stats.total_received.abs()))
+                    .collect();
+                by_amount.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+                println!("by amount:");
+                for (id, volume) in by_amount.into_iter().take(k) {
+                    println!("  {id:<20} {volume:.2}");
+                }
+                return Ok(ExitCode::SUCCESS);
+            }
+            let top = match Format::from_path(&path) {
+                Format::Delimited if !std::path::Path::new(&path).is_dir() => {
+                    let (top, dirty) = top_k_accounts(p01::compression::open_reader(&path)?, k)?;
+                    warn_dirty(&dirty);
+                    top
+                }
+                _ => {
+                    let mut top = TopAccounts::new(k);
+                    load(&path)?.iter().for_each(|r| top.add(r));
+                    top.result()
+                }
+            };
+            // the real value is between estimate - error and estimate
+            println!("by transactions:");
+            for hitter in &top.by_count {
+                println!(
+                    "  {:<20} ~{:.0} (at least {:.0})",
+                    hitter.account,
+                    hitter.estimate,
+                    hitter.guaranteed()
+                );
+            }
+            println!("by amount:");
+            for hitter in &top.by_amount {
+                println!(
+                    "  {:<20} ~{:.2} (at least {:.2})",
+                    hitter.account,
+                    hitter.estimate,
+                    hitter.guaranteed()
+                );
+            }
+        }
+
+        Command::Sort {
+            path,
+            out,
+            by,
+            temp_dir,
+            memory,
+        } => {
+            let start = Instant::now();
+            let mut options = SortOptions::new().key(by).memory_budget(memory);
+            if let Some(dir) = temp_dir {
+                options = options.temp_dir(dir);
+            }
+            let report = sort_file(&path, &out, &options)?;
+            warn_dirty(&report.dirty);
+            println!(
+                "Sorted {} records into {out} ({} runs, {} skipped) in {:?}",
+                report.records,
+                report.runs,
+                report.dirty.total(),
+                start.elapsed()
+            );
+        }
+
+        Command::GenerateAccounts {
+            accounts,
+            out,
+            seed,
+        } => {
+            let start = Instant::now();
+            let mut config = AccountsConfig::new().accounts(accounts);
+            if let Some(seed) = seed {
+                config = config.seed(seed);
+            }
+            let written = generate_accounts(&out, &config)?;
+            println!("Wrote {written} accounts to {out} in {:?}", start.elapsed());
+        }
+
+        Command::Partition {
+            path,
+            out,
+            by,
+            format,
+            records_per_file,
+        } => {
+            let start = Instant::now();
+            let format = format.map_or(Format::Delimited, Format::from);
+            let writer = PartitionedWriter::create(&out, by.into(), format)?
+                .records_per_file(records_per_file);
+            let report = partition_file(&path, writer)?;
+            warn_dirty(&report.dirty);
+            println!(
+                "Wrote {} records into {} partitions ({} files) under {out} in {:?}",
+                report.records,
+                report.partitions,
+                report.files,
+                start.elapsed()
+            );
+        }
+
+        Command::Scan {
+            path,
+            from,
+            to,
+            approach,
+        } => {
+            let start = Instant::now();
+            // a range says nothing about records without a value
+            let keep = |partition: &Partition| {
+                if from.is_none() && to.is_none() {
+                    return true;
+                }
+                !partition.is_default()
+                    && from.as_ref().is_none_or(|from| partition.value >= *from)
+                    && to.as_ref().is_none_or(|to| partition.value <= *to)
+            };
+            let partitions = p01::partition::partitions(&path)?;
+            let kept = partitions.iter().filter(|p| keep(p)).count();
+            let records = open_partitioned(&path, keep)?;
+            let result = analyze(approach, &records, None, None)?;
+            println!(
+                "Read {kept} of {} partitions, {} records in {:?}",
+                partitions.len(),
+                records.len(),
+                start.elapsed()
+            );
+            println!("sum={:.2}, count={}", result.sum, result.count);
+        }
+
+        Command::Transform {
+            path,
+            out,
+            normalize_ids,
+            min_amount,
+            max_amount,
+            fill_currency,
+            fill_timestamp,
+            drop,
+            format,
+        } => {
+            let start = Instant::now();
+            let mut transform = Transform::new();
+            if normalize_ids {
+                transform = transform.normalize_ids();
+            }
+            if min_amount.is_some() || max_amount.is_some() {
+                transform = transform.clamp_amounts(
+                    min_amount.unwrap_or(f64::NEG_INFINITY),
+                    max_amount.unwrap_or(f64::INFINITY),
+                );
+            }
+            if let Some(currency) = fill_currency {
+                transform = transform.fill_currency(currency);
+            }
+            if let Some(timestamp) = fill_timestamp {
+                transform = transform.fill_timestamp(timestamp);
+            }
+            transform = transform.drop_columns(&drop);
+
+            let format = format.map_or_else(|| Format::from_path(&out), Format::from);
+            let report = transform.run_with(&path, &out, format)?;
+            warn_dirty(&report.dirty);
+            println!(
+                "Wrote {} of {} records to {out} in {:?} ({} dropped, {} skipped)",
+                report.written,
+                report.records,
+                start.elapsed(),
+                report.dropped,
+                report.dirty.total()
+            );
+            println!(
+                "normalized={}, clamped={}, filled={}",
+                report.normalized, report.clamped, report.filled
+            );
+        }
+
+        Command::Join {
+            path,
+            accounts,
+            on,
+            memory,
+            partitions,
+            temp_dir,
+        } => {
+            let start = Instant::now();
+            let mut options = JoinOptions::new().side(on.into()).memory_budget(memory);
+            if let Some(partitions) = partitions {
+                options = options.partitions(partitions);
+            }
+            if let Some(dir) = temp_dir {
+                options = options.temp_dir(dir);
+            }
+            let totals = totals_by_country_files(&path, &accounts, &options)?;
+            warn_dirty(&totals.dirty_accounts);
+            warn_dirty(&totals.dirty_transactions);
+            println!(
+                "{:<8} {:>12} {:>20} {:>12}",
+                "country", "transactions", "amount", "mean"
+            );
+            for (country, stats) in totals.by_amount() {
+                println!(
+                    "{country:<8} {:>12} {:>20.2} {:>12.2}",
+                    stats.transactions,
+                    stats.amount,
+                    stats.mean()
+                );
+            }
+            println!(
+                "{} unmatched, {} partition(s), {:?}",
+                totals.unmatched,
+                totals.partitions,
+                start.elapsed()
+            );
+        }
+
+        Command::Diff {
+            a,
+            b,
+            memory,
+            temp_dir,
+            samples,
+            tolerance,
+        } => {
+            let start = Instant::now();
+            let mut options = DiffOptions::new()
+                .memory_budget(memory)
+                .samples(samples)
+                .tolerance(tolerance);
+            if let Some(dir) = temp_dir {
+                options = options.temp_dir(dir);
+            }
+            let report = compare_with(&a, &b, &options)?;
+            print!("{report}");
+            println!("compared in {:?}", start.elapsed());
+            if !report.is_identical() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        Command::GroupBy {
+            path,
+            by,
+            temp_dir,
+            memory,
+            top,
+        } => {
+            let start = Instant::now();
+            let mut options = SortOptions::new().key(by).memory_budget(memory);
+            if let Some(dir) = temp_dir {
+                options = options.temp_dir(dir);
+            }
+            let spilled = open_file_spilled(&path, &options)?;
+            warn_dirty(&spilled.report.dirty);
+
+            // (value, sum, count) of the largest groups so far, trimmed now and then
+            let mut largest: Vec<(String, f64, usize)> = Vec::new();
+            let mut groups = 0;
+            for group in spilled.groups()? {
+                let group = group?;
+                let sum = group.iter().map(|r| r.amount).sum();
+                largest.push((column_value(&group[0], by), sum, group.len()));
+                groups += 1;
+                if largest.len() >= 2 * top.max(1) {
+                    largest.sort_by(|a, b| b.1.total_cmp(&a.1));
+                    largest.truncate(top);
+                }
+            }
+            largest.sort_by(|a, b| b.1.total_cmp(&a.1));
+            largest.truncate(top);
+
+            for (value, sum, count) in &largest {
+                println!("  {value}: sum={sum:.2}, count={count}");
+            }
+            println!(
+                "{groups} groups of {} records by {} ({} runs, {} skipped) in {:?}",
+                spilled.report.records,
+                by.name(),
+                spilled.report.runs,
+                spilled.report.dirty.total(),
+                start.elapsed()
+            );
+        }
+
+        Command::Export {
+            path,
+            sqlite,
+            batch_rows,
+            rows_per_insert,
+            no_indexes,
+        } => {
+            let start = Instant::now();
+            let options = p01::export::SqliteOptions::new()
+                .batch_rows(batch_rows)
+                .rows_per_insert(rows_per_insert)
+                .indexes(!no_indexes);
+            let report = p01::export::to_sqlite_with(path.as_str(), &sqlite, &options)?;
+            warn_dirty(&report.dirty);
+            println!(
+                "Exported {} records into {sqlite} ({} transactions, {} skipped) in {:?}",
+                report.records,
+                report.batches,
+                report.dirty.total(),
+                start.elapsed()
+            );
+        }
+
+        Command::VerifyLedger {
+            path,
+            opening_balance,
+        } => {
+            let report = verify_conservation(&load(&path)?, opening_balance);
+            let cents = |cents: i64| cents as f64 / 100.0;
+
+            println!("accounts: {}", report.accounts);
+            println!(
+                "opening: {:.2}, deposits: {:.2}, outflows: {:.2}, closing: {:.2}",
+                cents(report.opening_cents),
+                cents(report.deposit_cents),
+                cents(report.outflow_cents),
+                cents(report.closing_cents)
+            );
+            println!(
+                "discrepancy: {:.2}, overdrafts: {}, negative amounts: {}",
+                cents(report.discrepancy_cents()),
+                report.overdrafts.len(),
+                report.negative_amounts
+            );
+            if let Some(first) = report.overdrafts.first() {
+                println!("first overdraft: {first}");
+            }
+
+            if !report.is_conserved() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+
+        Command::Publish {
+            records,
+            rate,
+            seed,
+            kafka,
+        } => {
+            #[cfg(feature = "kafka")]
+            {
+                let mut config = GeneratorConfig::new().target_records(records);
+                config.seed = seed;
+                config.records_per_second = rate;
+                let report = p01::streaming::publish(&kafka.config(), &config)?;
+                println!(
+                    "Published {} records to {}, {} malformed, in {:?}",
+                    report.records, kafka.topic, report.malformed_records, report.elapsed
+                );
+            }
+            #[cfg(not(feature = "kafka"))]
+            {
+                let _ = (records, rate, seed, kafka);
+                return Err("publish needs the kafka feature (or --features all)".into());
+            }
+        }
+
+        Command::Consume { idle, kafka } => {
+            #[cfg(feature = "kafka")]
+            {
+                let mut config = kafka.config();
+                if let Some(idle) = idle {
+                    config = config.idle_timeout(std::time::Duration::from_secs(idle));
+                }
+                let mut totals = StreamStats::default();
+                let report = p01::streaming::consume(&config, |records| {
+                    records.iter().for_each(|record| totals.add(record));
+                    println!(
+                        "+{} records | total sum={:.2}, count={}, accounts: {}",
+                        records.len(),
+                        totals.sum,
+                        totals.count,
+                        totals.accounts.len()
+                    );
+                    Ok(())
+                })?;
+                warn_dirty(&report.dirty);
+                println!(
+                    "messages: {}, records: {}, malformed: {}",
+                    report.messages,
+                    report.records,
+                    report.dirty.total()
+                );
+            }
+            #[cfg(not(feature = "kafka"))]
+            {
+                let _ = (idle, kafka);
+                return Err("consume needs the kafka feature (or --features all)".into());
+            }
+        }
+
+        Command::Dashboard { job } => {
+            #[cfg(feature = "tui")]
+            match job {
+                DashboardJob::Generate {
+                    size,
+                    records,
+                    out,
+                    corruption_rate,
+                    seed,
+                    rate,
+                } => {
+                    let mut config = match records {
+                        Some(records) => GeneratorConfig::new().target_records(records),
+                        None => GeneratorConfig::new().target_bytes(size),
+                    };
+                    config.seed = seed;
+                    config.records_per_second = rate;
+                    if let Some(rate) = corruption_rate {
+                        config = config.corruption_probability(rate);
+                    }
+                    let Some(report) = p01::dashboard::generate(&out, &config)? else {
+                        println!("Stopped, {out} is incomplete");
+                        return Ok(ExitCode::FAILURE);
+                    };
+                    println!(
+                        "Generated {} records, total size: {:.2} GB, {} malformed, in {:?}",
+                        report.records,
+                        report.bytes as f64 / 1e9,
+                        report.malformed_records,
+                        report.elapsed
+                    );
+                }
+                DashboardJob::Ingest { path } => {
+                    let Some(stats) = p01::dashboard::ingest(&path)? else {
+                        println!("Stopped");
+                        return Ok(ExitCode::FAILURE);
+                    };
+                    println!("sum={:.2}, count={}", stats.sum, stats.count);
+                    if let (Some(min), Some(max)) = (stats.min, stats.max) {
+                        println!("min={min:.2}, max={max:.2}");
+                    }
+                    println!(
+                        "accounts: {}, malformed: {}",
+                        stats.accounts.len(),
+                        stats.dirty.total()
+                    );
+                }
+            }
+            #[cfg(not(feature = "tui"))]
+            {
+                let _ = job;
+                return Err("dashboard needs the tui feature (or --features all)".into());
+            }
+        }
+
+        Command::Sql { path, query } => {
+            #[cfg(feature = "datafusion")]
+            {
+                let batches = p01::analysis::sql::query(&path, &query)?;
+                println!(
+                    "{}",
+                    datafusion::arrow::util::pretty::pretty_format_batches(&batches)?
+                );
+            }
+            #[cfg(not(feature = "datafusion"))]
+            {
+                let _ = (path, query);
+                return Err("sql needs the datafusion feature (or --features all)".into());
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,636 @@
+//! Generate, ingest, clean, analyze and report as one chain:
+//!
+//! `Pipeline::new().source(csv("data.csv")).clean(dedup().validate(rules)).analyze(group_by_account()).sink(json_report("out.json")).run()`
+//!
+//! Every record goes through every stage before the next one is read, so
+//! nothing is held but what the analyses keep. [`approach`] is the exception,
+//! the [`Analyzer`]s take a slice and the cleaned records get collected for them
+use std::collections::{HashMap, HashSet};
+use std::io::BufRead;
+use std::time::Duration;
+
+use crate::Instant;
+
+use crate::analysis::anomaly::{AnomalyConfig, AnomalyDetector, AnomalyReport};
+use crate::analysis::{AccountStats, AnalysisResult, Analyzer, StreamStats, Totals};
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, IngestOptions, RecordIter};
+use crate::encoding::Encoding;
+use crate::format::Format;
+use crate::generator::{GenerationReport, GeneratorConfig};
+use crate::report::{Report, ReportFormat};
+use crate::validation::{ValidationReport, ValidationRun, Validator};
+
+/// Where the records come from
+pub enum Source {
+    /// read a line at a time, `format: None` goes by the extension
+    File {
+        path: String,
+        format: Option<Format>,
+    },
+    /// straight from the generator, never written anywhere
+    Generated(GeneratorConfig),
+    /// generated into `path` first, then read back like a [`Source::File`]
+    GeneratedFile {
+        path: String,
+        config: GeneratorConfig,
+    },
+    /// an open stream, e.g. an upload held in memory. Plain, gzip or zstd,
+    /// detected from its first bytes
+    Reader {
+        reader: Box<dyn BufRead + Send>,
+        format: Format,
+    },
+    Records(Vec<ClientData>),
+}
+
+impl Source {
+    /// What the report says the findings are about
+    fn name(&self) -> &str {
+        match self {
+            Source::File { path, .. } | Source::GeneratedFile { path, .. } => path,
+            Source::Generated(_) => "(generated)",
+            Source::Reader { .. } => "(reader)",
+            Source::Records(_) => "(records)",
+        }
+    }
+}
+
+/// A delimited file, plain, `.gz` or `.zst`, whatever its extension
+pub fn csv(path: impl Into<String>) -> Source {
+    Source::File {
+        path: path.into(),
+        format: Some(Format::Delimited),
+    }
+}
+
+/// A JSON Lines file, plain, `.gz` or `.zst`, whatever its extension
+pub fn jsonl(path: impl Into<String>) -> Source {
+    Source::File {
+        path: path.into(),
+        format: Some(Format::JsonLines),
+    }
+}
+
+/// A file in the format its extension says, see [`Format::from_path`]
+pub fn file(path: impl Into<String>) -> Source {
+    Source::File {
+        path: path.into(),
+        format: None,
+    }
+}
+
+pub fn generate(config: GeneratorConfig) -> Source {
+    Source::Generated(config)
+}
+
+pub fn generate_to(path: impl Into<String>, config: GeneratorConfig) -> Source {
+    Source::GeneratedFile {
+        path: path.into(),
+        config,
+    }
+}
+
+/// Delimited or JSON Lines, Parquet needs a file
+pub fn reader(reader: Box<dyn BufRead + Send>, format: Format) -> Source {
+    Source::Reader { reader, format }
+}
+
+pub fn records(records: Vec<ClientData>) -> Source {
+    Source::Records(records)
+}
+
+type Records = Box<dyn Iterator<Item = Result<ClientData, IngestError>>>;
+
+type Keep = Box<dyn FnMut(&ClientData) -> bool + Send>;
+
+enum Step {
+    /// keeps the first of every id, or fails on a repeat when `strict`
+    Dedup {
+        strict: bool,
+    },
+    /// drops the records that break any rule
+    Validate(Validator),
+    Filter(Keep),
+}
+
+/// What happens to the records between the source and the analyses, in order
+#[derive(Default)]
+pub struct Clean {
+    steps: Vec<Step>,
+}
+
+impl Clean {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drops records whose id was seen before, like [`crate::data_ingestion::DedupStrategy::KeepFirst`].
+    /// Keeps every id it has seen
+    pub fn dedup(mut self) -> Self {
+        self.steps.push(Step::Dedup { strict: false });
+        self
+    }
+
+    /// Fails the run on the first repeated id instead
+    pub fn dedup_strict(mut self) -> Self {
+        self.steps.push(Step::Dedup { strict: true });
+        self
+    }
+
+    /// Drops the records that break any of `validator`'s rules, the violations
+    /// end up in [`PipelineOutput::validation`]
+    pub fn validate(mut self, validator: Validator) -> Self {
+        self.steps.push(Step::Validate(validator));
+        self
+    }
+
+    /// Keeps only the records `keep` returns true for
+    pub fn filter(mut self, keep: impl FnMut(&ClientData) -> bool + Send + 'static) -> Self {
+        self.steps.push(Step::Filter(Box::new(keep)));
+        self
+    }
+}
+
+pub fn dedup() -> Clean {
+    Clean::new().dedup()
+}
+
+pub fn validate(validator: Validator) -> Clean {
+    Clean::new().validate(validator)
+}
+
+pub fn filter(keep: impl FnMut(&ClientData) -> bool + Send + 'static) -> Clean {
+    Clean::new().filter(keep)
+}
+
+/// What gets worked out from the cleaned records
+#[derive(Default)]
+pub struct Analyze {
+    totals: bool,
+    accounts: bool,
+    anomalies: Option<AnomalyConfig>,
+    approaches: Vec<Box<dyn Analyzer>>,
+}
+
+impl Analyze {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sum, count, min, max and mean, [`PipelineOutput::summary`]
+    pub fn totals(mut self) -> Self {
+        self.totals = true;
+        self
+    }
+
+    /// Per-account totals, [`PipelineOutput::accounts`]
+    pub fn group_by_account(mut self) -> Self {
+        self.accounts = true;
+        self
+    }
+
+    pub fn anomalies(mut self, config: AnomalyConfig) -> Self {
+        self.anomalies = Some(config);
+        self
+    }
+
+    /// Runs `analyzer` over all the cleaned records once they're in, timed.
+    /// Those are kept in memory for it
+    pub fn approach(mut self, analyzer: impl Analyzer + 'static) -> Self {
+        self.approaches.push(Box::new(analyzer));
+        self
+    }
+
+    /// [`Analyze::approach`] for an already boxed one
+    pub fn approach_boxed(mut self, analyzer: Box<dyn Analyzer>) -> Self {
+        self.approaches.push(analyzer);
+        self
+    }
+
+    fn merge(&mut self, other: Analyze) {
+        self.totals |= other.totals;
+        self.accounts |= other.accounts;
+        self.anomalies = other.anomalies.or(self.anomalies.take());
+        self.approaches.extend(other.approaches);
+    }
+}
+
+pub fn totals() -> Analyze {
+    Analyze::new().totals()
+}
+
+pub fn group_by_account() -> Analyze {
+    Analyze::new().group_by_account()
+}
+
+pub fn anomalies(config: AnomalyConfig) -> Analyze {
+    Analyze::new().anomalies(config)
+}
+
+pub fn approach(analyzer: impl Analyzer + 'static) -> Analyze {
+    Analyze::new().approach(analyzer)
+}
+
+/// Where the findings go once the run is over
+pub enum Sink {
+    Report { path: String, format: ReportFormat },
+}
+
+/// A [`Report`] as one JSON document
+pub fn json_report(path: impl Into<String>) -> Sink {
+    Sink::Report {
+        path: path.into(),
+        format: ReportFormat::Json,
+    }
+}
+
+/// A [`Report`] as a directory of CSV tables, see [`Report::write_csv`]
+pub fn csv_report(dir: impl Into<String>) -> Sink {
+    Sink::Report {
+        path: dir.into(),
+        format: ReportFormat::Csv,
+    }
+}
+
+/// JSON or CSV by the path, see [`ReportFormat::from_path`]
+pub fn report(path: impl Into<String>) -> Sink {
+    let path = path.into();
+    let format = ReportFormat::from_path(&path);
+    Sink::Report { path, format }
+}
+
+/// What to do with a line of a file source that doesn't parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// skip it with a warning on stderr
+    #[default]
+    Warn,
+    /// skip it quietly, it's still counted in [`PipelineOutput::dirty`]
+    Skip,
+    /// end the run with its error
+    Fail,
+}
+
+/// One [`Analyze::approach`] of a run
+#[derive(Debug, Clone)]
+pub struct ApproachRun {
+    /// [`Analyzer::name`]
+    pub name: String,
+    pub result: AnalysisResult,
+    pub elapsed: Duration,
+}
+
+/// What a [`Pipeline::run`] did and found, the analyses that weren't asked for are `None`
+#[derive(Debug, Clone, Default)]
+pub struct PipelineOutput {
+    /// what the report calls the source, its path for files
+    pub source: String,
+    /// for generated sources
+    pub generated: Option<GenerationReport>,
+    /// records out of the source, before cleaning
+    pub records: usize,
+    /// lines of a file that didn't parse
+    pub dirty: DirtyDataReport,
+    /// records left after cleaning, what the analyses saw
+    pub kept: usize,
+    pub duplicates: usize,
+    /// dropped by [`Clean::filter`]
+    pub filtered: usize,
+    /// dropped by [`Clean::validate`]
+    pub invalid: usize,
+    /// one per [`Clean::validate`]
+    pub validation: Vec<ValidationReport>,
+    pub summary: Option<AnalysisResult>,
+    pub accounts: Option<HashMap<String, AccountStats>>,
+    pub anomalies: Option<AnomalyReport>,
+    /// in the order they were added
+    pub approaches: Vec<ApproachRun>,
+    pub elapsed: Duration,
+}
+
+impl PipelineOutput {
+    /// Everything found, in the layout [`crate::report`] writes. The summary
+    /// is [`Analyze::totals`]' or else the first approach's
+    pub fn report(&self) -> Report {
+        let mut report = Report::new()
+            .source(self.source.as_str())
+            .dirty(&self.dirty);
+        let summary = self
+            .summary
+            .or_else(|| self.approaches.first().map(|a| a.result));
+        if let Some(mut summary) = summary {
+            if let Some(accounts) = &self.accounts {
+                summary.distinct_accounts = Some(accounts.len());
+            }
+            report = report.summary(&summary);
+        }
+        if let Some(accounts) = &self.accounts {
+            report = report.accounts(accounts);
+        }
+        // the rules of every validator, in the order they ran
+        let rules = self
+            .validation
+            .iter()
+            .fold(ValidationReport::default(), |mut all, v| {
+                all.rules.extend(v.rules.iter().cloned());
+                all
+            });
+        report = report.validation(&rules);
+        if let Some(anomalies) = &self.anomalies {
+            report = report.anomalies(anomalies);
+        }
+        report
+    }
+}
+
+/// A source, what cleans its records, what's worked out from them and where
+/// that goes. Built up with the methods below, then [`Pipeline::run`]
+#[derive(Default)]
+pub struct Pipeline {
+    source: Option<Source>,
+    clean: Clean,
+    analyze: Analyze,
+    sinks: Vec<Sink>,
+    on_error: OnError,
+    max_error_rate: Option<f64>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn source(mut self, source: Source) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Steps run in the order they're added, across calls too
+    pub fn clean(mut self, clean: Clean) -> Self {
+        self.clean.steps.extend(clean.steps);
+        self
+    }
+
+    pub fn analyze(mut self, analyze: Analyze) -> Self {
+        self.analyze.merge(analyze);
+        self
+    }
+
+    pub fn sink(mut self, sink: Sink) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn on_error(mut self, on_error: OnError) -> Self {
+        self.on_error = on_error;
+        self
+    }
+
+    /// Ends the run once this fraction of a file's lines failed, checked
+    /// after the same number of lines as [`IngestOptions::min_lines_for_rate`]
+    pub fn max_error_rate(mut self, rate: f64) -> Self {
+        self.max_error_rate = Some(rate);
+        self
+    }
+
+    /// Streams the source through everything, then writes the sinks
+    pub fn run(self) -> Result<PipelineOutput, IngestError> {
+        let start = Instant::now();
+        let Some(source) = self.source else {
+            return Err(IngestError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "the pipeline has no source",
+            )));
+        };
+        let mut clean = self.clean;
+        let mut flow = Flow {
+            steps: clean
+                .steps
+                .iter_mut()
+                .map(|step| match step {
+                    Step::Dedup { strict } => StepRun::Dedup {
+                        strict: *strict,
+                        seen: HashSet::new(),
+                    },
+                    Step::Validate(validator) => StepRun::Validate(validator.start()),
+                    Step::Filter(keep) => StepRun::Filter(keep),
+                })
+                .collect(),
+            totals: self.analyze.totals.then(Totals::default),
+            accounts: self.analyze.accounts.then(StreamStats::default),
+            anomalies: self.analyze.anomalies.map(AnomalyDetector::new),
+            collected: (!self.analyze.approaches.is_empty()).then(Vec::new),
+            output: PipelineOutput {
+                source: source.name().to_string(),
+                ..PipelineOutput::default()
+            },
+        };
+
+        match source {
+            Source::File { path, format } => {
+                flow.read(&path, format, self.on_error, self.max_error_rate)?
+            }
+            Source::Generated(config) => {
+                // the generator calls back with every record, a failing stage stops it
+                let mut failed = None;
+                let generated = crate::generate_mock_records(&config, |record| {
+                    flow.push(record).map_err(|e| {
+                        failed = Some(e);
+                        std::io::Error::other("pipeline stage failed")
+                    })
+                });
+                if let Some(e) = failed {
+                    return Err(e);
+                }
+                flow.output.generated = Some(generated?);
+            }
+            Source::GeneratedFile { path, config } => {
+                flow.output.generated = Some(crate::generate_mock_data_with(&path, &config)?);
+                flow.read(&path, None, self.on_error, self.max_error_rate)?
+            }
+            Source::Reader { reader, format } => {
+                let reader = crate::compression::decompress(reader)?;
+                let records = RecordIter::from_reader(reader, format, Encoding::Auto)?;
+                flow.stream(Box::new(records), self.on_error, self.max_error_rate)?
+            }
+            Source::Records(records) => {
+                for record in records {
+                    flow.push(record)?;
+                }
+            }
+        }
+
+        let mut output = flow.finish(&self.analyze.approaches);
+        output.elapsed = start.elapsed();
+        for sink in &self.sinks {
+            match sink {
+                Sink::Report { path, format } => output.report().save(path, *format)?,
+            }
+        }
+        Ok(output)
+    }
+}
+
+enum StepRun<'p> {
+    Dedup { strict: bool, seen: HashSet<String> },
+    Validate(ValidationRun<'p>),
+    Filter(&'p mut Keep),
+}
+
+/// A run in progress: the steps with their state, and the analyses so far
+struct Flow<'p> {
+    steps: Vec<StepRun<'p>>,
+    totals: Option<Totals>,
+    accounts: Option<StreamStats>,
+    anomalies: Option<AnomalyDetector>,
+    /// for the approaches, `None` without any
+    collected: Option<Vec<ClientData>>,
+    output: PipelineOutput,
+}
+
+impl Flow<'_> {
+    fn push(&mut self, record: ClientData) -> Result<(), IngestError> {
+        self.output.records += 1;
+        for step in &mut self.steps {
+            match step {
+                StepRun::Dedup { strict, seen } => {
+                    // contains first, so only new ids get cloned
+                    if !seen.contains(&record.id) {
+                        seen.insert(record.id.clone());
+                    } else if *strict {
+                        return Err(IngestError::DuplicateId { id: record.id });
+                    } else {
+                        self.output.duplicates += 1;
+                        return Ok(());
+                    }
+                }
+                StepRun::Validate(run) => {
+                    if !run.check(&record) {
+                        self.output.invalid += 1;
+                        return Ok(());
+                    }
+                }
+                StepRun::Filter(keep) => {
+                    if !keep(&record) {
+                        self.output.filtered += 1;
+                        return Ok(());
+                    }
+                }
+            }
+        }
+
+        self.output.kept += 1;
+        if let Some(totals) = &mut self.totals {
+            *totals = totals.push(record.amount);
+        }
+        if let Some(accounts) = &mut self.accounts {
+            accounts.add(&record);
+        }
+        if let Some(detector) = &mut self.anomalies {
+            detector.push(&record);
+        }
+        if let Some(collected) = &mut self.collected {
+            collected.push(record);
+        }
+        Ok(())
+    }
+
+    fn read(
+        &mut self,
+        path: &str,
+        format: Option<Format>,
+        on_error: OnError,
+        max_error_rate: Option<f64>,
+    ) -> Result<(), IngestError> {
+        let records: Records = match format.unwrap_or_else(|| Format::from_path(path)) {
+            // not line-based, nothing to skip: it's all read, then streamed through
+            #[cfg(feature = "parquet")]
+            Format::Parquet => Box::new(
+                crate::data_ingestion::open_file_parquet(path)?
+                    .into_iter()
+                    .map(Ok),
+            ),
+            #[cfg(not(feature = "parquet"))]
+            Format::Parquet => {
+                return Err(IngestError::Schema(
+                    "parquet support is not compiled in, enable the `parquet` feature".into(),
+                ));
+            }
+            format => Box::new(RecordIter::from_reader(
+                crate::compression::open_reader(path)?,
+                format,
+                Encoding::Auto,
+            )?),
+        };
+        self.stream(records, on_error, max_error_rate)
+    }
+
+    fn stream(
+        &mut self,
+        records: Records,
+        on_error: OnError,
+        max_error_rate: Option<f64>,
+    ) -> Result<(), IngestError> {
+        let min_lines = IngestOptions::new().min_lines_for_rate;
+
+        for record in records {
+            match record {
+                Ok(record) => self.push(record)?,
+                Err(e @ IngestError::Line { .. }) => {
+                    match on_error {
+                        OnError::Fail => return Err(e),
+                        OnError::Warn => eprintln!("Warning: Failed to parse {e}"),
+                        OnError::Skip => {}
+                    }
+                    self.output.dirty.record(&e);
+                }
+                Err(e) => return Err(e),
+            }
+
+            let lines = self.output.records + self.output.dirty.total();
+            if let Some(threshold) = max_error_rate
+                && lines >= min_lines
+            {
+                let rate = self.output.dirty.total() as f64 / lines as f64;
+                if rate > threshold {
+                    return Err(IngestError::ErrorRateExceeded {
+                        rate,
+                        threshold,
+                        lines,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self, approaches: &[Box<dyn Analyzer>]) -> PipelineOutput {
+        let mut output = self.output;
+        for step in self.steps {
+            if let StepRun::Validate(run) = step {
+                output.validation.push(run.finish());
+            }
+        }
+        output.summary = self
+            .totals
+            .map(|totals| AnalysisResult::new(totals.sum, totals.count, totals.min, totals.max));
+        output.accounts = self.accounts.map(|stats| stats.accounts);
+        output.anomalies = self.anomalies.map(AnomalyDetector::finish);
+        if let Some(records) = &self.collected {
+            output.approaches = approaches
+                .iter()
+                .map(|analyzer| {
+                    let start = Instant::now();
+                    let result = analyzer.analyze(records);
+                    ApproachRun {
+                        name: analyzer.name().to_string(),
+                        result,
+                        elapsed: start.elapsed(),
+                    }
+                })
+                .collect();
+        }
+        output
+    }
+}
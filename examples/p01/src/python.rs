@@ -0,0 +1,363 @@
+//! The `p01` Python module, built with `maturin build --release` (see
+//! `pyproject.toml`). Same generator, parser and analyses as the Rust side, so
+//! numbers from a notebook line up with the benches. Tables come back as a
+//! dict of equal-length lists, one per column, which is what
+//! `pandas.DataFrame(...)` takes and `numpy.asarray(d["amount"])` turns into
+//! an array. The GIL is released while Rust does the work
+
+// pyo3 0.21's macros predate edition 2024, their expansions call unsafe fns bare
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use pyo3::exceptions::{PyIOError, PyStopIteration, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyString};
+
+use crate::analysis::anomaly::AnomalyConfig;
+use crate::config::{GenerateConfig, analyzer};
+use crate::data_ingestion::{ClientData, DirtyDataReport, IngestError, RecordIter};
+use crate::pipeline::{
+    Analyze, OnError, Pipeline, PipelineOutput, anomalies, file, group_by_account, totals,
+};
+use crate::report::Report;
+
+/// Rows per [`Batches`] item unless asked otherwise
+const DEFAULT_BATCH_ROWS: usize = 100_000;
+
+fn ingest_error(e: IngestError) -> PyErr {
+    match e {
+        IngestError::Io(e) => PyIOError::new_err(e.to_string()),
+        e => PyValueError::new_err(e.to_string()),
+    }
+}
+
+fn parse_on_error(on_error: &str) -> PyResult<OnError> {
+    match on_error {
+        "warn" => Ok(OnError::Warn),
+        "skip" => Ok(OnError::Skip),
+        "fail" => Ok(OnError::Fail),
+        _ => Err(PyValueError::new_err(format!(
+            "unknown on_error {on_error:?}, expected warn, skip or fail"
+        ))),
+    }
+}
+
+/// A keyword argument as the TOML value `[generate]` would have for it
+fn toml_value(value: &Bound<'_, PyAny>) -> PyResult<toml::Value> {
+    // bool before int, True is an int to Python
+    if value.is_instance_of::<PyBool>() {
+        Ok(toml::Value::Boolean(value.extract()?))
+    } else if value.is_instance_of::<PyInt>() {
+        Ok(toml::Value::Integer(value.extract()?))
+    } else if value.is_instance_of::<PyFloat>() {
+        Ok(toml::Value::Float(value.extract()?))
+    } else if value.is_instance_of::<PyString>() {
+        Ok(toml::Value::String(value.extract()?))
+    } else {
+        Err(PyValueError::new_err(format!(
+            "unsupported value {value}, expected a bool, int, float or str"
+        )))
+    }
+}
+
+/// Writes mock records to `path`. Takes the keys of a `p01.toml`
+/// `[generate]` section as keyword arguments, e.g. `size="100mb", seed=42,
+/// timestamps=True`, and returns what the generator reports
+#[pyfunction]
+#[pyo3(signature = (path, **options))]
+fn generate_mock_data<'py>(
+    py: Python<'py>,
+    path: &str,
+    options: Option<&Bound<'py, PyDict>>,
+) -> PyResult<Bound<'py, PyDict>> {
+    let mut table = toml::Table::new();
+    for (key, value) in options.into_iter().flatten() {
+        table.insert(key.extract()?, toml_value(&value)?);
+    }
+    table.insert("out".to_string(), toml::Value::String(path.to_string()));
+    let config = toml::Value::Table(table)
+        .try_into::<GenerateConfig>()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?
+        .generator_config()
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+
+    let report = py
+        .allow_threads(|| crate::generate_mock_data_with(path, &config))
+        .map_err(|e| PyIOError::new_err(e.to_string()))?;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("records", report.records)?;
+    dict.set_item("bytes", report.bytes)?;
+    dict.set_item("malformed_records", report.malformed_records)?;
+    dict.set_item("duplicates", report.duplicates)?;
+    dict.set_item("elapsed", report.elapsed.as_secs_f64())?;
+    Ok(dict)
+}
+
+/// Records as columns, `None` where a file has no timestamps or currencies
+#[derive(Default)]
+struct Columns {
+    ids: Vec<String>,
+    from_ids: Vec<String>,
+    to_ids: Vec<String>,
+    amounts: Vec<f64>,
+    timestamps: Vec<Option<i64>>,
+    kinds: Vec<&'static str>,
+    currencies: Vec<Option<String>>,
+}
+
+impl Columns {
+    fn push(&mut self, record: ClientData) {
+        self.ids.push(record.id);
+        self.from_ids.push(record.from_id);
+        self.to_ids.push(record.to_id);
+        self.amounts.push(record.amount);
+        self.timestamps.push(record.timestamp);
+        self.kinds.push(record.kind.as_str());
+        self.currencies
+            .push(record.currency.map(|c| c.as_str().to_string()));
+    }
+
+    fn into_dict(self, py: Python<'_>) -> PyResult<Bound<'_, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        dict.set_item("id", self.ids)?;
+        dict.set_item("from_id", self.from_ids)?;
+        dict.set_item("to_id", self.to_ids)?;
+        dict.set_item("amount", self.amounts)?;
+        dict.set_item("timestamp", self.timestamps)?;
+        dict.set_item("kind", self.kinds)?;
+        dict.set_item("currency", self.currencies)?;
+        Ok(dict)
+    }
+}
+
+/// A file read `batch_rows` records at a time, each batch a dict of columns.
+/// Lines that don't parse are handled by `on_error` and counted in `dirty`
+#[pyclass(module = "p01")]
+struct Batches {
+    records: RecordIter,
+    batch_rows: usize,
+    on_error: OnError,
+    dirty: DirtyDataReport,
+    done: bool,
+}
+
+impl Batches {
+    /// Up to `batch_rows` records, fewer only at the end of the file
+    fn next_batch(&mut self) -> Result<Columns, IngestError> {
+        let mut columns = Columns::default();
+        while columns.amounts.len() < self.batch_rows {
+            match self.records.next() {
+                Some(Ok(record)) => columns.push(record),
+                Some(Err(e @ IngestError::Line { .. })) => {
+                    self.dirty.record(&e);
+                    match self.on_error {
+                        OnError::Warn => eprintln!("Warning: Failed to parse {e}"),
+                        OnError::Skip => {}
+                        OnError::Fail => return Err(e),
+                    }
+                }
+                Some(Err(e)) => return Err(e),
+                None => {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+        Ok(columns)
+    }
+}
+
+#[pymethods]
+impl Batches {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        if self.done {
+            return Err(PyStopIteration::new_err(()));
+        }
+        let columns = py
+            .allow_threads(|| self.next_batch())
+            .map_err(ingest_error)?;
+        // the last batch comes back empty when the file ends on a full one
+        if columns.amounts.is_empty() && self.done {
+            return Err(PyStopIteration::new_err(()));
+        }
+        columns.into_dict(py)
+    }
+
+    /// Lines that didn't parse so far, by failure kind
+    #[getter]
+    fn dirty<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new_bound(py);
+        for (kind, category) in &self.dirty.categories {
+            dict.set_item(format!("{kind:?}"), category.count)?;
+        }
+        Ok(dict)
+    }
+}
+
+/// Streams `path` (csv or jsonl, plain, `.gz` or `.zst`) in batches of
+/// `batch_rows` records, see [`Batches`]
+#[pyfunction]
+#[pyo3(signature = (path, batch_rows = DEFAULT_BATCH_ROWS, on_error = "warn"))]
+fn read_batches(path: &str, batch_rows: usize, on_error: &str) -> PyResult<Batches> {
+    if batch_rows == 0 {
+        return Err(PyValueError::new_err("batch_rows has to be at least 1"));
+    }
+    Ok(Batches {
+        records: RecordIter::open(path).map_err(|e| PyIOError::new_err(e.to_string()))?,
+        batch_rows,
+        on_error: parse_on_error(on_error)?,
+        dirty: DirtyDataReport::default(),
+        done: false,
+    })
+}
+
+/// All of `path` as one dict of columns
+#[pyfunction]
+#[pyo3(signature = (path, on_error = "warn"))]
+fn read<'py>(py: Python<'py>, path: &str, on_error: &str) -> PyResult<Bound<'py, PyDict>> {
+    let mut batches = read_batches(path, usize::MAX, on_error)?;
+    let columns = py
+        .allow_threads(|| batches.next_batch())
+        .map_err(ingest_error)?;
+    columns.into_dict(py)
+}
+
+/// Runs `pipeline` over `path` with the GIL released
+fn run(
+    py: Python<'_>,
+    path: &str,
+    on_error: &str,
+    pipeline: Pipeline,
+) -> PyResult<(PipelineOutput, Report)> {
+    let pipeline = pipeline
+        .source(file(path))
+        .on_error(parse_on_error(on_error)?);
+    let output = py.allow_threads(|| pipeline.run()).map_err(ingest_error)?;
+    let report = output.report();
+    Ok((output, report))
+}
+
+/// Sum, count, min, max and mean of the amounts in `path`. Streamed unless
+/// `approach` names one of `p01 analyze`'s, which loads the file first
+#[pyfunction]
+#[pyo3(signature = (path, approach = None, on_error = "warn"))]
+fn analyze<'py>(
+    py: Python<'py>,
+    path: &str,
+    approach: Option<&str>,
+    on_error: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let analyze = match approach {
+        Some(name) => Analyze::new()
+            .approach_boxed(analyzer(name).map_err(|e| PyValueError::new_err(e.to_string()))?),
+        None => totals(),
+    };
+    let (output, report) = run(py, path, on_error, Pipeline::new().analyze(analyze))?;
+    let dict = PyDict::new_bound(py);
+    if let Some(summary) = report.summary {
+        dict.set_item("count", summary.count)?;
+        dict.set_item("sum", summary.sum)?;
+        dict.set_item("min", summary.min)?;
+        dict.set_item("max", summary.max)?;
+        dict.set_item("mean", summary.mean)?;
+    }
+    dict.set_item("malformed", output.dirty.total())?;
+    let elapsed = match output.approaches.first() {
+        Some(approach) => approach.elapsed,
+        None => output.elapsed,
+    };
+    dict.set_item("elapsed", elapsed.as_secs_f64())?;
+    Ok(dict)
+}
+
+/// Per-account totals of `path` as columns, sorted by account
+#[pyfunction]
+#[pyo3(signature = (path, on_error = "warn"))]
+fn aggregate_by_account<'py>(
+    py: Python<'py>,
+    path: &str,
+    on_error: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let (_, report) = run(
+        py,
+        path,
+        on_error,
+        Pipeline::new().analyze(group_by_account()),
+    )?;
+    let rows = &report.accounts;
+    let dict = PyDict::new_bound(py);
+    dict.set_item(
+        "account",
+        rows.iter().map(|r| &r.account).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "total_sent",
+        rows.iter().map(|r| r.total_sent).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "total_received",
+        rows.iter().map(|r| r.total_received).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "sent_count",
+        rows.iter().map(|r| r.sent_count).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "received_count",
+        rows.iter().map(|r| r.received_count).collect::<Vec<_>>(),
+    )?;
+    dict.set_item("net", rows.iter().map(|r| r.net).collect::<Vec<_>>())?;
+    Ok(dict)
+}
+
+/// Flagged records of `path` as columns, with the default thresholds of
+/// `p01 anomalies`. The columns a reason doesn't have are `None`
+#[pyfunction]
+#[pyo3(signature = (path, stddev_threshold = None, on_error = "warn"))]
+fn detect_anomalies<'py>(
+    py: Python<'py>,
+    path: &str,
+    stddev_threshold: Option<f64>,
+    on_error: &str,
+) -> PyResult<Bound<'py, PyDict>> {
+    let mut config = AnomalyConfig::default();
+    if let Some(threshold) = stddev_threshold {
+        config.stddev_threshold = threshold;
+    }
+    let pipeline = Pipeline::new().analyze(anomalies(config));
+    let (_, report) = run(py, path, on_error, pipeline)?;
+    let rows = &report.anomalies;
+    let dict = PyDict::new_bound(py);
+    dict.set_item("id", rows.iter().map(|r| &r.id).collect::<Vec<_>>())?;
+    dict.set_item("reason", rows.iter().map(|r| r.reason).collect::<Vec<_>>())?;
+    dict.set_item("zscore", rows.iter().map(|r| r.zscore).collect::<Vec<_>>())?;
+    dict.set_item(
+        "account",
+        rows.iter().map(|r| &r.account).collect::<Vec<_>>(),
+    )?;
+    dict.set_item(
+        "window_start",
+        rows.iter().map(|r| r.window_start).collect::<Vec<_>>(),
+    )?;
+    dict.set_item("count", rows.iter().map(|r| r.count).collect::<Vec<_>>())?;
+    dict.set_item(
+        "baseline",
+        rows.iter().map(|r| r.baseline).collect::<Vec<_>>(),
+    )?;
+    Ok(dict)
+}
+
+#[pymodule]
+fn p01(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(generate_mock_data, m)?)?;
+    m.add_function(wrap_pyfunction!(read_batches, m)?)?;
+    m.add_function(wrap_pyfunction!(read, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze, m)?)?;
+    m.add_function(wrap_pyfunction!(aggregate_by_account, m)?)?;
+    m.add_function(wrap_pyfunction!(detect_anomalies, m)?)?;
+    m.add_class::<Batches>()?;
+    Ok(())
+}
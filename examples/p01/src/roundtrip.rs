@@ -0,0 +1,148 @@
+//! Records written out in one of the formats and read back with the regular
+//! readers, the other half of [`ClientData::to_line`]. The tests check that
+//! whatever goes out comes back the same, over the records
+//! [`crate::arbitrary`] comes up with (`cargo test --features proptest`)
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::data_ingestion::{ClientData, IngestError, IngestOptions, Layout, open_file_with};
+use crate::format::Format;
+
+/// Writes `records` to `path` in `format`: delimited under a header of
+/// `layout`'s columns, parquet with them as its schema. JSON Lines get every
+/// field whatever the layout, like [`ClientData::to_json_line`]
+pub fn write_file(
+    path: &str,
+    records: &[ClientData],
+    layout: &Layout,
+    format: Format,
+) -> Result<(), IngestError> {
+    match format {
+        Format::Delimited => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            writeln!(writer, "{}", layout.header(layout.delimiter()))?;
+            for record in records {
+                writeln!(writer, "{}", layout.to_line(record))?;
+            }
+            writer.flush()?;
+        }
+        Format::JsonLines => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            for record in records {
+                writeln!(writer, "{}", record.to_json_line())?;
+            }
+            writer.flush()?;
+        }
+        #[cfg(feature = "parquet")]
+        Format::Parquet => {
+            let mut batch = crate::data_ingestion::BatchBuilder::new(layout);
+            let mut writer =
+                parquet::arrow::ArrowWriter::try_new(File::create(path)?, batch.schema(), None)?;
+            for record in records {
+                batch.push(record);
+            }
+            writer.write(&batch.finish()?)?;
+            writer.close()?;
+        }
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => return Err(not_compiled_in()),
+    }
+    Ok(())
+}
+
+/// Reads back what [`write_file`] wrote, the first broken line is an error
+/// instead of a warning
+pub fn read_file(path: &str, format: Format) -> Result<Vec<ClientData>, IngestError> {
+    match format {
+        Format::Delimited | Format::JsonLines => {
+            open_file_with(path, IngestOptions::new().fail_fast()).map(|(records, _)| records)
+        }
+        #[cfg(feature = "parquet")]
+        Format::Parquet => crate::data_ingestion::open_file_parquet(path),
+        #[cfg(not(feature = "parquet"))]
+        Format::Parquet => Err(not_compiled_in()),
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+fn not_compiled_in() -> IngestError {
+    IngestError::Schema("parquet support is not compiled in, enable the `parquet` feature".into())
+}
+
+/// [`write_file`] then [`read_file`] through a scratch file in `dir`, which is
+/// gone again afterwards
+pub fn round_trip(
+    records: &[ClientData],
+    layout: &Layout,
+    format: Format,
+    dir: &Path,
+) -> Result<Vec<ClientData>, IngestError> {
+    // the readers go by extension, so the file needs the right one
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    let extension = match format {
+        Format::Delimited => "csv",
+        Format::JsonLines => "jsonl",
+        Format::Parquet => "parquet",
+    };
+    let name = format!(
+        "p01-roundtrip-{}-{}.{extension}",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = dir.join(name).to_string_lossy().into_owned();
+
+    let result = write_file(&path, records, layout, format).and_then(|()| read_file(&path, format));
+    match std::fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => result,
+    }
+}
+
+#[cfg(all(test, feature = "proptest"))]
+mod tests {
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::arbitrary::{client_data_for, layout};
+
+    /// A layout and up to 32 records that fit it
+    fn file(format: Format) -> impl Strategy<Value = (Layout, Vec<ClientData>)> {
+        layout().prop_flat_map(move |layout| {
+            let records = vec(client_data_for(format, &layout), 0..=32);
+            (Just(layout), records)
+        })
+    }
+
+    fn check(format: Format, layout: &Layout, written: &[ClientData]) -> Result<(), TestCaseError> {
+        let read = round_trip(written, layout, format, &std::env::temp_dir())
+            .map_err(|e| TestCaseError::fail(e.to_string()))?;
+        prop_assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(written) {
+            prop_assert_eq!(read, written);
+            // 0.0 == -0.0, the bits tell them apart
+            prop_assert_eq!(read.amount.to_bits(), written.amount.to_bits());
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn delimited((layout, written) in file(Format::Delimited)) {
+            check(Format::Delimited, &layout, &written)?;
+        }
+
+        #[test]
+        fn json_lines((layout, written) in file(Format::JsonLines)) {
+            check(Format::JsonLines, &layout, &written)?;
+        }
+
+        #[cfg(feature = "parquet")]
+        #[test]
+        fn parquet((layout, written) in file(Format::Parquet)) {
+            check(Format::Parquet, &layout, &written)?;
+        }
+    }
+}
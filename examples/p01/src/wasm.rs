@@ -0,0 +1,30 @@
+//! wasm-bindgen exports for the browser demo in `web/`, `web/build.sh` builds
+//! them. The upload never leaves the page: it's parsed and totalled here,
+//! with the same parser and the same report layout as `p01 analyze --report`
+use std::io::Cursor;
+
+use wasm_bindgen::prelude::*;
+
+use crate::format::Format;
+use crate::pipeline::{OnError, Pipeline, reader, totals};
+
+/// Parses an uploaded file (delimited or JSON Lines by `name`'s extension,
+/// plain, gzip or zstd by its first bytes) and returns the [`crate::report::Report`]
+/// as JSON. Broken lines are skipped and counted in its `malformed` section,
+/// `accounts` only has the `top_accounts` with the most volume
+#[wasm_bindgen]
+pub fn analyze(bytes: Vec<u8>, name: &str, top_accounts: usize) -> Result<String, JsError> {
+    let source = reader(Box::new(Cursor::new(bytes)), Format::from_path(name));
+    let output = Pipeline::new()
+        .source(source)
+        .on_error(OnError::Skip)
+        .analyze(totals().group_by_account())
+        .run()?;
+
+    let mut report = output.report().source(name);
+    report.accounts.sort_by(|a, b| {
+        (b.total_sent + b.total_received).total_cmp(&(a.total_sent + a.total_received))
+    });
+    report.accounts.truncate(top_accounts);
+    Ok(serde_json::to_string(&report)?)
+}
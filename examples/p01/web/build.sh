@@ -0,0 +1,10 @@
+#!/bin/sh
+# Builds the wasm module into web/pkg, then serve web/ with any static server,
+# e.g. `python3 -m http.server -d web`, and open http://localhost:8000.
+# Needs `rustup target add wasm32-unknown-unknown` and a wasm-bindgen CLI of
+# the same version as the wasm-bindgen in Cargo.lock
+set -e
+cd "$(dirname "$0")/.."
+
+cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm,gzip --crate-type cdylib
+wasm-bindgen --target web --out-dir web/pkg ../../target/wasm32-unknown-unknown/release/p01.wasm